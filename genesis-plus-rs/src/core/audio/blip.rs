@@ -0,0 +1,284 @@
+//! Síntese e reamostragem limitadas em banda ("blip buffer").
+//! Baseado em `sound/blip_buf.c` do Genesis Plus GX (blip_buf 1.1.0 de
+//! Shay Green, com suporte a estéreo).
+//!
+//! Uma fonte descreve a sua saída por variações de nível no seu próprio
+//! clock. Cada variação entra no buffer como um degrau limitado em banda já
+//! na taxa de saída; a leitura integra o buffer e remove o nível contínuo
+//! com um passa-altas leve.
+
+/// Bits de fração extra no cálculo de tempo
+const PRE_SHIFT: u32 = 32;
+const TIME_BITS: u32 = PRE_SHIFT + 20;
+const TIME_UNIT: u64 = 1 << TIME_BITS;
+
+/// Ponto de corte do passa-altas
+const BASS_SHIFT: u32 = 9;
+/// Permite variações um pouco além do fim do quadro
+const END_FRAME_EXTRA: usize = 2;
+
+const HALF_WIDTH: usize = 8;
+const BUF_EXTRA: usize = HALF_WIDTH * 2 + END_FRAME_EXTRA;
+const PHASE_BITS: u32 = 5;
+const PHASE_COUNT: usize = 1 << PHASE_BITS;
+const DELTA_BITS: u32 = 15;
+const DELTA_UNIT: i32 = 1 << DELTA_BITS;
+const FRAC_BITS: u32 = TIME_BITS - PRE_SHIFT;
+const PHASE_SHIFT: u32 = FRAC_BITS - PHASE_BITS;
+
+/// Maior razão suportada entre o clock de entrada e a taxa de saída
+const MAX_RATIO: u64 = 1 << 20;
+
+/// Metade do degrau limitado em banda para cada fase
+const BL_STEP: [[i32; HALF_WIDTH]; PHASE_COUNT + 1] = [
+    [   43,  -115,   350,  -488,  1136,  -914,  5861, 21022],
+    [   44,  -118,   348,  -473,  1076,  -799,  5274, 21001],
+    [   45,  -121,   344,  -454,  1011,  -677,  4706, 20936],
+    [   46,  -122,   336,  -431,   942,  -549,  4156, 20829],
+    [   47,  -123,   327,  -404,   868,  -418,  3629, 20679],
+    [   47,  -122,   316,  -375,   792,  -285,  3124, 20488],
+    [   47,  -120,   303,  -344,   714,  -151,  2644, 20256],
+    [   46,  -117,   289,  -310,   634,   -17,  2188, 19985],
+    [   46,  -114,   273,  -275,   553,   117,  1758, 19675],
+    [   44,  -108,   255,  -237,   471,   247,  1356, 19327],
+    [   43,  -103,   237,  -199,   390,   373,   981, 18944],
+    [   42,   -98,   218,  -160,   310,   495,   633, 18527],
+    [   40,   -91,   198,  -121,   231,   611,   314, 18078],
+    [   38,   -84,   178,   -81,   153,   722,    22, 17599],
+    [   36,   -76,   157,   -43,    80,   824,  -241, 17092],
+    [   34,   -68,   135,    -3,     8,   919,  -476, 16558],
+    [   32,   -61,   115,    34,   -60,  1006,  -683, 16001],
+    [   29,   -52,    94,    70,  -123,  1083,  -862, 15422],
+    [   27,   -44,    73,   106,  -184,  1152, -1015, 14824],
+    [   25,   -36,    53,   139,  -239,  1211, -1142, 14210],
+    [   22,   -27,    34,   170,  -290,  1261, -1244, 13582],
+    [   20,   -20,    16,   199,  -335,  1301, -1322, 12942],
+    [   18,   -12,    -3,   226,  -375,  1331, -1376, 12293],
+    [   15,    -4,   -19,   250,  -410,  1351, -1408, 11638],
+    [   13,     3,   -35,   272,  -439,  1361, -1419, 10979],
+    [   11,     9,   -49,   292,  -464,  1362, -1410, 10319],
+    [    9,    16,   -63,   309,  -483,  1354, -1383,  9660],
+    [    7,    22,   -75,   322,  -496,  1337, -1339,  9005],
+    [    6,    26,   -85,   333,  -504,  1312, -1280,  8355],
+    [    4,    31,   -94,   341,  -507,  1278, -1205,  7713],
+    [    3,    35,  -102,   347,  -506,  1238, -1119,  7082],
+    [    1,    40,  -110,   350,  -499,  1190, -1021,  6464],
+    [    0,    43,  -115,   350,  -488,  1136,  -914,  5861],
+];
+
+/// Buffer estéreo de síntese
+pub struct Blip {
+    /// Amostras de saída por clock de entrada, em ponto fixo
+    factor: u64,
+    /// Posição do início do quadro corrente no buffer, em ponto fixo
+    offset: u64,
+    size: usize,
+    /// Integradores da leitura (esquerda, direita)
+    integrator: [i32; 2],
+    buffer: [Vec<i32>; 2],
+}
+
+impl Blip {
+    /// Buffer com espaço para `size` amostras de saída
+    pub fn new(size: usize) -> Self {
+        let mut blip = Self {
+            factor: TIME_UNIT / MAX_RATIO,
+            offset: 0,
+            size,
+            integrator: [0; 2],
+            buffer: [vec![0; size + BUF_EXTRA], vec![0; size + BUF_EXTRA]],
+        };
+        blip.clear();
+        blip
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Define o clock de entrada e a taxa de saída
+    pub fn set_rates(&mut self, clock_rate: f64, sample_rate: f64) {
+        let factor = TIME_UNIT as f64 * sample_rate / clock_rate;
+        self.factor = factor.ceil() as u64;
+    }
+
+    /// Descarta as amostras e as variações pendentes
+    pub fn clear(&mut self) {
+        // factor/2 acomoda o arredondamento do fator em qualquer direção
+        self.offset = self.factor / 2;
+        self.integrator = [0; 2];
+        for buffer in &mut self.buffer {
+            buffer.fill(0);
+        }
+    }
+
+    /// Clocks de entrada necessários para ter `samples` amostras disponíveis
+    pub fn clocks_needed(&self, samples: usize) -> u64 {
+        let needed = samples as u64 * TIME_UNIT;
+        if needed < self.offset {
+            0
+        } else {
+            (needed - self.offset).div_ceil(self.factor)
+        }
+    }
+
+    /// Fecha o quadro corrente após `time` clocks de entrada; as amostras
+    /// até esse ponto ficam disponíveis e o tempo volta a contar do zero
+    pub fn end_frame(&mut self, time: u64) {
+        self.offset += time * self.factor;
+        debug_assert!(self.samples_avail() <= self.size, "blip buffer overflow");
+    }
+
+    /// Amostras prontas para a leitura
+    pub fn samples_avail(&self) -> usize {
+        (self.offset >> TIME_BITS) as usize
+    }
+
+    /// Posição no buffer (com fração) do clock `time` do quadro corrente
+    fn fixed(&self, time: u64) -> usize {
+        ((time * self.factor + self.offset) >> PRE_SHIFT) as usize
+    }
+
+    /// Soma uma variação de nível no clock `time` do quadro corrente
+    pub fn add_delta(&mut self, time: u64, left: i32, right: i32) {
+        if left | right == 0 {
+            return;
+        }
+        let fixed = self.fixed(time);
+        let phase = (fixed >> PHASE_SHIFT) & (PHASE_COUNT - 1);
+        let interp = (fixed >> (PHASE_SHIFT - DELTA_BITS)) as i32 & (DELTA_UNIT - 1);
+        let pos = fixed >> FRAC_BITS;
+        debug_assert!(pos <= self.size + END_FRAME_EXTRA, "blip buffer overflow");
+
+        // Metade inicial a partir da fase e metade final espelhada, cada uma
+        // interpolada com a fase seguinte
+        let (step, next) = (&BL_STEP[phase], &BL_STEP[phase + 1]);
+        let (rev, rev_next) = (&BL_STEP[PHASE_COUNT - phase], &BL_STEP[PHASE_COUNT - phase - 1]);
+        for (buffer, level) in self.buffer.iter_mut().zip([left, right]) {
+            let delta = level.wrapping_mul(interp) >> DELTA_BITS;
+            let level = level - delta;
+            let out = &mut buffer[pos..pos + HALF_WIDTH * 2];
+            for i in 0..HALF_WIDTH {
+                let head = step[i].wrapping_mul(level).wrapping_add(next[i].wrapping_mul(delta));
+                out[i] = out[i].wrapping_add(head);
+                let j = HALF_WIDTH - 1 - i;
+                let tail = rev[j].wrapping_mul(level).wrapping_add(rev_next[j].wrapping_mul(delta));
+                out[HALF_WIDTH + i] = out[HALF_WIDTH + i].wrapping_add(tail);
+            }
+        }
+    }
+
+    /// Como `add_delta`, com interpolação linear no lugar do degrau limitado
+    /// em banda (mais rápido, para fontes já amostradas)
+    pub fn add_delta_fast(&mut self, time: u64, left: i32, right: i32) {
+        if left | right == 0 {
+            return;
+        }
+        let fixed = self.fixed(time);
+        let interp = (fixed >> (FRAC_BITS - DELTA_BITS)) as i32 & (DELTA_UNIT - 1);
+        let pos = fixed >> FRAC_BITS;
+        debug_assert!(pos <= self.size + END_FRAME_EXTRA, "blip buffer overflow");
+
+        for (buffer, level) in self.buffer.iter_mut().zip([left, right]) {
+            let delta = level.wrapping_mul(interp);
+            let out = &mut buffer[pos + HALF_WIDTH - 1..pos + HALF_WIDTH + 1];
+            out[0] = out[0].wrapping_add(level.wrapping_mul(DELTA_UNIT).wrapping_sub(delta));
+            out[1] = out[1].wrapping_add(delta);
+        }
+    }
+
+    /// Lê até `count` amostras estéreo intercaladas para `out`; devolve
+    /// quantas foram lidas
+    pub fn read_samples(&mut self, out: &mut Vec<i16>, count: usize) -> usize {
+        self.mix_samples(&mut [], out, count)
+    }
+
+    /// Lê até `count` amostras somando as dos buffers `others`, que devem
+    /// ter a mesma taxa de saída. Os integradores deste buffer valem para a
+    /// mistura toda.
+    pub fn mix_samples(&mut self, others: &mut [&mut Blip], out: &mut Vec<i16>, count: usize) -> usize {
+        let count = others.iter().fold(count.min(self.samples_avail()), |count, blip| {
+            count.min(blip.samples_avail())
+        });
+
+        let [mut sum_l, mut sum_r] = self.integrator;
+        for i in 0..count {
+            let (in_l, in_r) = others.iter().fold((self.buffer[0][i], self.buffer[1][i]), |(l, r), blip| {
+                (l.wrapping_add(blip.buffer[0][i]), r.wrapping_add(blip.buffer[1][i]))
+            });
+            for (sum, input) in [(&mut sum_l, in_l), (&mut sum_r, in_r)] {
+                // Elimina a fração
+                let sample = (*sum >> DELTA_BITS).clamp(i16::MIN as i32, i16::MAX as i32);
+                *sum = sum.wrapping_add(input);
+                out.push(sample as i16);
+                // Passa-altas
+                *sum = sum.wrapping_sub(sample << (DELTA_BITS - BASS_SHIFT));
+            }
+        }
+        self.integrator = [sum_l, sum_r];
+
+        self.remove_samples(count);
+        for blip in others.iter_mut() {
+            blip.remove_samples(count);
+        }
+        count
+    }
+
+    /// Como `read_samples`, mas entrega as amostras em 32 bits, para que a
+    /// mistura aplique ganhos e filtros antes da saturação final
+    pub fn read_levels(&mut self, out: &mut Vec<[i32; 2]>, count: usize) -> usize {
+        let count = count.min(self.samples_avail());
+        for i in 0..count {
+            let mut sample = [0; 2];
+            for (side, sum) in self.integrator.iter_mut().enumerate() {
+                sample[side] = (*sum >> DELTA_BITS).clamp(i16::MIN as i32, i16::MAX as i32);
+                *sum = sum.wrapping_add(self.buffer[side][i]);
+                *sum = sum.wrapping_sub(sample[side] << (DELTA_BITS - BASS_SHIFT));
+            }
+            out.push(sample);
+        }
+        self.remove_samples(count);
+        count
+    }
+
+    /// Descarta as `count` primeiras amostras do buffer
+    fn remove_samples(&mut self, count: usize) {
+        let remain = self.samples_avail() + BUF_EXTRA - count;
+        self.offset -= count as u64 * TIME_UNIT;
+        for buffer in &mut self.buffer {
+            buffer.copy_within(count..count + remain, 0);
+            buffer[remain..remain + count].fill(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_settles_at_level() {
+        // Clock mestre NTSC para 44,1 kHz
+        let mut blip = Blip::new(4410);
+        blip.set_rates(53_693_175.0, 44_100.0);
+        let clocks = blip.clocks_needed(735);
+        assert!(clocks.abs_diff(262 * 3420) < 1218, "{}", clocks);
+
+        blip.add_delta(1000, 8000, -8000);
+        blip.add_delta_fast(1000, 1000, 1000);
+        blip.end_frame(clocks - 1);
+        assert_eq!(blip.samples_avail(), 734);
+        blip.end_frame(1);
+        assert_eq!(blip.samples_avail(), 735);
+
+        let mut out = Vec::new();
+        assert_eq!(blip.read_samples(&mut out, 1000), 735);
+        assert_eq!(out.len(), 735 * 2);
+        // O degrau chega ao nível e decai lentamente pelo passa-altas
+        let (l, r) = (out[40] as i32, out[41] as i32);
+        assert!((8500..=9000).contains(&l), "{}", l);
+        assert!((-7000..=-6500).contains(&r), "{}", r);
+        assert!(out[1468].abs() < out[40].abs());
+        assert_eq!(blip.samples_avail(), 0);
+    }
+}
@@ -0,0 +1,107 @@
+//! Equalizador de 3 bandas.
+//! Baseado em `sound/eq.c` do Genesis Plus GX (Neil C / Etanza Systems,
+//! filtros de Paul Kellet).
+//!
+//! Dois passa-baixas de quarta ordem separam graves e agudos; os médios são
+//! o que sobra do sinal. Com os três ganhos unitários a saída é a entrada.
+
+use std::f64::consts::PI;
+
+/// Evita números desnormalizados nos filtros
+const VSA: f64 = 1.0 / 4294967295.0;
+
+/// Bandas e ganhos do equalizador
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EqSettings {
+    /// Frequências de corte entre graves e médios e entre médios e agudos (Hz)
+    pub low_freq: u32,
+    pub high_freq: u32,
+    /// Ganho de cada banda, em porcentagem
+    pub low_gain: u32,
+    pub mid_gain: u32,
+    pub high_gain: u32,
+}
+
+impl Default for EqSettings {
+    /// Frequências recomendadas e ganhos unitários
+    fn default() -> Self {
+        Self {
+            low_freq: 880,
+            high_freq: 5000,
+            low_gain: 100,
+            mid_gain: 100,
+            high_gain: 100,
+        }
+    }
+}
+
+/// Estado do equalizador de um canal
+#[derive(Debug, Clone, Default)]
+pub struct Eq3Band {
+    /// Passa-baixas da banda grave: coeficiente e polos
+    lf: f64,
+    f1p: [f64; 4],
+    /// Passa-baixas da banda aguda
+    hf: f64,
+    f2p: [f64; 4],
+    /// Últimas três entradas
+    sdm: [f64; 3],
+    /// Ganhos das bandas
+    pub lg: f64,
+    pub mg: f64,
+    pub hg: f64,
+}
+
+impl Eq3Band {
+    /// Equalizador zerado, com ganhos unitários, para a taxa `mix_freq`
+    pub fn new(low_freq: u32, high_freq: u32, mix_freq: u32) -> Self {
+        let mut eq = Self {
+            lg: 1.0,
+            mg: 1.0,
+            hg: 1.0,
+            ..Default::default()
+        };
+        eq.set_bands(low_freq, high_freq, mix_freq);
+        eq
+    }
+
+    /// Recalcula as frequências de corte sem apagar o estado dos filtros
+    pub fn set_bands(&mut self, low_freq: u32, high_freq: u32, mix_freq: u32) {
+        self.lf = 2.0 * (PI * low_freq as f64 / mix_freq as f64).sin();
+        self.hf = 2.0 * (PI * high_freq as f64 / mix_freq as f64).sin();
+    }
+
+    /// Apaga o estado dos filtros
+    pub fn clear(&mut self) {
+        self.f1p = [0.0; 4];
+        self.f2p = [0.0; 4];
+        self.sdm = [0.0; 3];
+    }
+
+    /// Equaliza uma amostra; a saída pode precisar de saturação
+    pub fn process(&mut self, sample: i32) -> i32 {
+        let sample = sample as f64;
+
+        // Filtro 1 (passa-baixas)
+        let f = &mut self.f1p;
+        f[0] += self.lf * (sample - f[0]) + VSA;
+        f[1] += self.lf * (f[0] - f[1]);
+        f[2] += self.lf * (f[1] - f[2]);
+        f[3] += self.lf * (f[2] - f[3]);
+        let l = f[3];
+
+        // Filtro 2 (passa-altas)
+        let f = &mut self.f2p;
+        f[0] += self.hf * (sample - f[0]) + VSA;
+        f[1] += self.hf * (f[0] - f[1]);
+        f[2] += self.hf * (f[1] - f[2]);
+        f[3] += self.hf * (f[2] - f[3]);
+        let h = self.sdm[2] - f[3];
+
+        // Médios: o sinal menos graves e agudos
+        let m = sample - (h + l);
+
+        self.sdm = [sample, self.sdm[0], self.sdm[1]];
+        (l * self.lg + m * self.mg + h * self.hg) as i32
+    }
+}
@@ -0,0 +1,243 @@
+//! Chip FM visto pelo sistema: núcleo de emulação selecionável,
+//! sincronização com o clock mestre e flag BUSY.
+//! Baseado em `sound/sound.c` do Genesis Plus GX.
+//!
+//! Cada acesso, seja do 68000 ou do Z80, gera antes as amostras devidas até
+//! o clock mestre informado, de modo que escritas no meio de uma linha são
+//! ouvidas no instante certo.
+
+use super::{YM2612, YM3438};
+use crate::utils::clock::{M68K_DIVIDER, YM2612_DIVIDER};
+
+/// Clock interno do chip: clock do 68000 / 6
+const INTERNAL_DIVIDER: u64 = M68K_DIVIDER * 6;
+
+/// Ciclos internos em que o chip permanece ocupado após uma escrita de dados
+const BUSY_CYCLES: u64 = 32;
+
+/// Ciclos internos por amostra no núcleo ciclo a ciclo
+const YM3438_CYCLES: u64 = YM2612_DIVIDER / INTERNAL_DIVIDER;
+
+/// Núcleo de emulação do chip FM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FmBackend {
+    /// Rápido, uma amostra por vez (`ym2612.c`)
+    #[default]
+    Ym2612,
+    /// Ciclo a ciclo, a partir do die shot (`ym3438.c`)
+    Ym3438,
+}
+
+enum Core {
+    Ym2612(Box<YM2612>),
+    Ym3438 {
+        chip: Box<YM3438>,
+        /// Soma das saídas dos ciclos da amostra em andamento
+        accm: (i32, i32),
+    },
+}
+
+/// Chip FM ligado ao barramento
+pub struct FmChip {
+    core: Core,
+    backend: FmBackend,
+    /// YM2612 discreto (modelo 1) em vez do YM3438 integrado
+    ladder_effect: bool,
+    /// Clock mestre do próximo passo a gerar
+    clock: u64,
+    /// Clock mestre em que o flag BUSY cai (núcleo rápido)
+    busy_until: u64,
+    /// Amostras geradas ainda não consumidas e o clock da primeira
+    samples: Vec<(i32, i32)>,
+    samples_start: u64,
+}
+
+impl FmChip {
+    pub fn new(backend: FmBackend, ladder_effect: bool) -> Self {
+        Self {
+            core: Self::build(backend, ladder_effect),
+            backend,
+            ladder_effect,
+            clock: 0,
+            busy_until: 0,
+            samples: Vec::new(),
+            samples_start: 0,
+        }
+    }
+
+    fn build(backend: FmBackend, ladder_effect: bool) -> Core {
+        match backend {
+            FmBackend::Ym2612 => {
+                let mut chip = YM2612::new();
+                chip.set_ladder_effect(ladder_effect);
+                Core::Ym2612(Box::new(chip))
+            }
+            FmBackend::Ym3438 => Core::Ym3438 {
+                chip: Box::new(YM3438::new(ladder_effect)),
+                accm: (0, 0),
+            },
+        }
+    }
+
+    pub fn backend(&self) -> FmBackend {
+        self.backend
+    }
+
+    pub fn ladder_effect(&self) -> bool {
+        self.ladder_effect
+    }
+
+    /// Troca o núcleo e o modelo do chip no clock mestre `mcycles`. Como em
+    /// `sound_init`, o chip novo começa resetado.
+    pub fn configure(&mut self, mcycles: u64, backend: FmBackend, ladder_effect: bool) {
+        self.sync(mcycles);
+        self.backend = backend;
+        self.ladder_effect = ladder_effect;
+        self.core = Self::build(backend, ladder_effect);
+        self.busy_until = 0;
+    }
+
+    /// Reseta o chip no clock mestre `mcycles`
+    pub fn reset(&mut self, mcycles: u64) {
+        self.sync(mcycles);
+        match &mut self.core {
+            Core::Ym2612(chip) => chip.reset(),
+            Core::Ym3438 { chip, accm } => {
+                chip.reset();
+                *accm = (0, 0);
+            }
+        }
+        self.busy_until = 0;
+    }
+
+    /// Gera as amostras devidas até o clock mestre `mcycles`
+    pub fn sync(&mut self, mcycles: u64) {
+        if mcycles <= self.clock {
+            return;
+        }
+        match &mut self.core {
+            Core::Ym2612(chip) => {
+                if self.samples.is_empty() {
+                    self.samples_start = self.clock;
+                }
+                let count = (mcycles - self.clock).div_ceil(YM2612_DIVIDER);
+                for _ in 0..count {
+                    self.samples.push(chip.update());
+                }
+                self.clock += count * YM2612_DIVIDER;
+            }
+            Core::Ym3438 { chip, accm } => {
+                let count = (mcycles - self.clock).div_ceil(INTERNAL_DIVIDER);
+                for _ in 0..count {
+                    let (l, r) = chip.clock();
+                    accm.0 += l as i32;
+                    accm.1 += r as i32;
+                    self.clock += INTERNAL_DIVIDER;
+                    // Amostra completa a cada volta do pipeline
+                    if chip.cycle() == 0 {
+                        if self.samples.is_empty() {
+                            self.samples_start = self.clock - YM3438_CYCLES * INTERNAL_DIVIDER;
+                        }
+                        self.samples.push((accm.0 * 11, accm.1 * 11));
+                        *accm = (0, 0);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Consome as amostras geradas; devolve o clock mestre da primeira
+    pub fn drain(&mut self) -> (u64, std::vec::Drain<'_, (i32, i32)>) {
+        (self.samples_start, self.samples.drain(..))
+    }
+
+    /// Escrita nas portas do chip (A1:A0)
+    pub fn write(&mut self, mcycles: u64, port: u8, value: u8) {
+        match &mut self.core {
+            Core::Ym2612(_) if port & 1 == 0 => {}
+            Core::Ym2612(_) => {
+                self.sync(mcycles);
+                self.busy_until = (mcycles.div_ceil(INTERNAL_DIVIDER) + BUSY_CYCLES) * INTERNAL_DIVIDER;
+            }
+            Core::Ym3438 { .. } => self.sync(mcycles),
+        }
+        match &mut self.core {
+            Core::Ym2612(chip) => chip.write(port, value),
+            Core::Ym3438 { chip, .. } => chip.write(port, value),
+        }
+    }
+
+    /// Leitura do status. O YM2612 discreto só responde em $4000; o YM3438
+    /// responde nas quatro portas.
+    pub fn read(&mut self, mcycles: u64, port: u8) -> u8 {
+        self.sync(mcycles);
+        match &mut self.core {
+            Core::Ym2612(chip) => {
+                if port & 3 != 0 && self.ladder_effect {
+                    return 0;
+                }
+                if mcycles < self.busy_until {
+                    chip.status() | 0x80
+                } else {
+                    chip.status()
+                }
+            }
+            Core::Ym3438 { chip, .. } => chip.read(port),
+        }
+    }
+}
+
+impl Default for FmChip {
+    fn default() -> Self {
+        Self::new(FmBackend::default(), true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_reg(fm: &mut FmChip, mcycles: u64, reg: u8, value: u8) {
+        fm.write(mcycles, 0, reg);
+        fm.write(mcycles, 1, value);
+    }
+
+    #[test]
+    fn test_busy_and_status_ports() {
+        let mut fm = FmChip::default();
+
+        // Timer A = 1023: estoura a cada amostra
+        write_reg(&mut fm, 0, 0x24, 0xFF);
+        write_reg(&mut fm, 0, 0x25, 0x03);
+        write_reg(&mut fm, 0, 0x27, 0x05);
+        assert_eq!(fm.read(0, 0), 0x80);
+        // As amostras devidas são geradas antes da leitura
+        assert_eq!(fm.read(BUSY_CYCLES * INTERNAL_DIVIDER - 1, 0), 0x81);
+        assert_eq!(fm.read(BUSY_CYCLES * INTERNAL_DIVIDER, 0), 0x01);
+        // Só $4000 devolve o status no YM2612 discreto
+        assert_eq!(fm.read(YM2612_DIVIDER * 2, 2), 0x00);
+        fm.configure(YM2612_DIVIDER * 2, FmBackend::Ym2612, false);
+        write_reg(&mut fm, YM2612_DIVIDER * 2, 0x24, 0xFF);
+        write_reg(&mut fm, YM2612_DIVIDER * 2, 0x25, 0x03);
+        write_reg(&mut fm, YM2612_DIVIDER * 2, 0x27, 0x05);
+        assert_eq!(fm.read(YM2612_DIVIDER * 4, 2), 0x01);
+    }
+
+    #[test]
+    fn test_backends_share_timing() {
+        for backend in [FmBackend::Ym2612, FmBackend::Ym3438] {
+            let mut fm = FmChip::new(backend, false);
+            // O núcleo ciclo a ciclo só trava uma escrita por ciclo interno
+            for (i, (port, value)) in [(0, 0x2B), (1, 0x80), (0, 0x2A), (1, 0xFF)].into_iter().enumerate() {
+                fm.write(i as u64 * YM2612_DIVIDER, port, value);
+            }
+            fm.sync(YM2612_DIVIDER * 10);
+            let (start, samples) = fm.drain();
+            let samples: Vec<_> = samples.collect();
+            assert_eq!(start, 0, "{:?}", backend);
+            assert_eq!(samples.len(), 10, "{:?}", backend);
+            // O DAC chega à saída nos dois núcleos
+            assert!(samples[9].0 > 0x7F << 5, "{:?} {:?}", backend, samples[9]);
+        }
+    }
+}
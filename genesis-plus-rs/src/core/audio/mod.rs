@@ -0,0 +1,394 @@
+//! Chips de som: FM (YM2612/YM3438), PSG (SN76489) e a FM Sound Unit do
+//! Master System (YM2413), e a mistura das fontes na saída.
+//! Baseado em `sound/sound.c` e `system.c` (`audio_*`) do Genesis Plus GX.
+//!
+//! Cada fonte soma variações de nível no seu próprio clock a um buffer de
+//! síntese (`Blip`); os buffers são reamostrados para a taxa do frontend e
+//! misturados num único fluxo estéreo, com ganho por fonte, passa-baixas e
+//! equalizador opcionais. Os ajustes mudam gradualmente ao longo de algumas
+//! amostras, de modo que podem ser feitos durante o jogo sem estalos.
+//!
+//! Dos chips de áudio de cartucho, só o tocador MP3 YX5200 está ligado ao
+//! mixer (`BLIP_CART`). O ASIC do Paprium (`PapriumAsic::process_audio`) e o
+//! CD-DA do MegaSD (`MegaSD::update_cdda`, em `BLIP_CDDA`) ficam pendentes
+//! até os seus módulos em `core::cartridge` compilarem.
+
+pub mod blip;
+pub mod eq;
+pub mod fm;
+pub mod sn76489;
+pub mod ym2413;
+pub mod ym2612;
+pub mod ym3438;
+
+pub use blip::Blip;
+pub use eq::{Eq3Band, EqSettings};
+pub use fm::{FmBackend, FmChip};
+pub use sn76489::{PsgType, SN76489};
+pub use ym2413::{FmUnit, YM2413};
+pub use ym2612::YM2612;
+pub use ym3438::YM3438;
+
+/// Buffer do chip FM (e do YM2413 no Master System), no clock mestre
+pub const BLIP_FM: usize = 0;
+/// Buffer do PSG, no clock mestre
+pub const BLIP_PSG: usize = 1;
+/// Buffers do Mega-CD: PCM (RF5C164) e CD-DA (também o do MegaSD, ainda
+/// sem produtor)
+pub const BLIP_PCM: usize = 2;
+pub const BLIP_CDDA: usize = 3;
+/// Buffer do chip de som do cartucho (tocador MP3 YX5200)
+pub const BLIP_CART: usize = 4;
+
+const BLIP_COUNT: usize = 5;
+
+/// Ganho unitário em ponto fixo 16.16
+const UNITY: i32 = 0x10000;
+
+/// Variação máxima de um ajuste por amostra (de 0 a 100% em 256 amostras)
+const RAMP_STEP: i32 = UNITY / 256;
+
+/// Parâmetro em ponto fixo 16.16 que segue o valor pedido aos poucos
+#[derive(Debug, Clone, Copy)]
+struct Ramp {
+    current: i32,
+    target: i32,
+}
+
+impl Ramp {
+    fn new(value: i32) -> Self {
+        Self { current: value, target: value }
+    }
+
+    fn settled(&self) -> bool {
+        self.current == self.target
+    }
+
+    /// Valor para a próxima amostra
+    fn next(&mut self) -> i32 {
+        self.current += (self.target - self.current).clamp(-RAMP_STEP, RAMP_STEP);
+        self.current
+    }
+}
+
+/// Porcentagem para ponto fixo 16.16
+fn percent(value: u32) -> i32 {
+    (value.min(1000) as i64 * UNITY as i64 / 100) as i32
+}
+
+/// Buffers de síntese e mistura das fontes de áudio
+pub struct Sound {
+    /// Um buffer por fonte, indexado por `BLIP_*`. As fontes externas
+    /// definem o próprio clock com `Blip::set_rates` e fecham seus quadros
+    /// com o mesmo número de amostras dos chips do console.
+    pub blips: Vec<Blip>,
+    /// Buffers com fonte ligada; FM e PSG estão sempre presentes
+    enabled: [bool; BLIP_COUNT],
+    sample_rate: u32,
+    clock_rate: u32,
+    /// Clock mestre do início do quadro de áudio corrente
+    frame_start: u64,
+
+    /// Ganho de cada fonte
+    gain: [Ramp; BLIP_COUNT],
+    /// Passa-baixas de primeira ordem: corte em Hz, peso da saída anterior
+    /// (zero quando desligado) e última saída de cada lado
+    low_pass: Option<u32>,
+    lp_factor: Ramp,
+    lp_last: [i32; 2],
+    /// Equalizador: bandas, ganhos (unitários quando desligado) e estado
+    equalizer: Option<EqSettings>,
+    eq_gain: [Ramp; 3],
+    eq: [Eq3Band; 2],
+
+    /// Amostras de uma fonte e soma das fontes do quadro sendo lido
+    levels: Vec<[i32; 2]>,
+    mix: Vec<[i32; 2]>,
+}
+
+impl Sound {
+    /// Saída em `sample_rate` Hz a partir do clock mestre `clock_rate`
+    pub fn new(sample_rate: u32, clock_rate: u32) -> Self {
+        let bands = EqSettings::default();
+        let mut sound = Self {
+            blips: Vec::new(),
+            enabled: [true, true, false, false, false],
+            sample_rate: 0,
+            clock_rate: 0,
+            frame_start: 0,
+            gain: [Ramp::new(UNITY); BLIP_COUNT],
+            low_pass: None,
+            lp_factor: Ramp::new(0),
+            lp_last: [0; 2],
+            equalizer: None,
+            eq_gain: [Ramp::new(UNITY); 3],
+            eq: std::array::from_fn(|_| Eq3Band::new(bands.low_freq, bands.high_freq, sample_rate)),
+            levels: Vec::new(),
+            mix: Vec::new(),
+        };
+        sound.set_rate(sample_rate, clock_rate);
+        sound
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Troca a taxa de saída ou o clock mestre. Os buffers (com espaço
+    /// para 100 ms) são recriados vazios; as fontes externas precisam
+    /// definir de novo o seu clock.
+    pub fn set_rate(&mut self, sample_rate: u32, clock_rate: u32) {
+        self.blips = (0..BLIP_COUNT).map(|_| Blip::new(sample_rate as usize / 10)).collect();
+        for blip in [BLIP_FM, BLIP_PSG] {
+            self.blips[blip].set_rates(clock_rate as f64, sample_rate as f64);
+        }
+        self.sample_rate = sample_rate;
+        self.clock_rate = clock_rate;
+        // Os filtros dependem da taxa de saída
+        self.set_low_pass(self.low_pass);
+        self.lp_factor.current = self.lp_factor.target;
+        let bands = self.equalizer.unwrap_or_default();
+        for eq in &mut self.eq {
+            eq.set_bands(bands.low_freq, bands.high_freq, sample_rate);
+        }
+    }
+
+    /// Liga ou desliga a mistura de uma fonte externa (PCM, CD-DA, cartucho)
+    pub fn enable(&mut self, blip: usize, enabled: bool) {
+        if blip >= BLIP_PCM {
+            self.enabled[blip] = enabled;
+            self.blips[blip].clear();
+        }
+    }
+
+    /// Ganho de uma fonte (`BLIP_*`), em porcentagem
+    pub fn set_gain(&mut self, blip: usize, gain: u32) {
+        self.gain[blip].target = percent(gain);
+    }
+
+    pub fn gain(&self, blip: usize) -> u32 {
+        (self.gain[blip].target as i64 * 100 / UNITY as i64) as u32
+    }
+
+    /// Passa-baixas de primeira ordem com corte em `cutoff` Hz, ou
+    /// desligado. Cerca de 3600 Hz a 44,1 kHz imita o som abafado do
+    /// Mega Drive modelo 1.
+    pub fn set_low_pass(&mut self, cutoff: Option<u32>) {
+        self.low_pass = cutoff;
+        self.lp_factor.target = match cutoff {
+            Some(cutoff) => {
+                let decay = (-2.0 * std::f64::consts::PI * cutoff as f64 / self.sample_rate as f64).exp();
+                (decay * UNITY as f64) as i32
+            }
+            None => 0,
+        };
+    }
+
+    pub fn low_pass(&self) -> Option<u32> {
+        self.low_pass
+    }
+
+    /// Equalizador de 3 bandas, ou desligado
+    pub fn set_equalizer(&mut self, equalizer: Option<EqSettings>) {
+        let settings = equalizer.unwrap_or_default();
+        if self.equalizer.is_none() && equalizer.is_some() && self.eq_gain.iter().all(Ramp::settled) {
+            for eq in &mut self.eq {
+                eq.clear();
+            }
+        }
+        for eq in &mut self.eq {
+            eq.set_bands(settings.low_freq, settings.high_freq, self.sample_rate);
+        }
+        let gains = [settings.low_gain, settings.mid_gain, settings.high_gain];
+        for (ramp, gain) in self.eq_gain.iter_mut().zip(gains) {
+            ramp.target = percent(gain);
+        }
+        self.equalizer = equalizer;
+    }
+
+    pub fn equalizer(&self) -> Option<EqSettings> {
+        self.equalizer
+    }
+
+    /// Descarta o áudio pendente e o estado dos filtros; o quadro seguinte
+    /// começa em `mcycles`
+    pub fn reset(&mut self, mcycles: u64) {
+        for blip in &mut self.blips {
+            blip.clear();
+        }
+        self.lp_last = [0; 2];
+        for eq in &mut self.eq {
+            eq.clear();
+        }
+        self.frame_start = mcycles;
+    }
+
+    /// Variação de nível de um chip do console (`BLIP_FM` ou `BLIP_PSG`)
+    /// no clock mestre `mcycles`
+    pub fn add_delta(&mut self, blip: usize, mcycles: u64, left: i32, right: i32) {
+        let time = mcycles.saturating_sub(self.frame_start);
+        self.blips[blip].add_delta(time, left, right);
+    }
+
+    /// Fecha o quadro dos chips do console no clock mestre `mcycles`;
+    /// devolve as amostras de saída disponíveis
+    pub fn end_frame(&mut self, mcycles: u64) -> usize {
+        for blip in [BLIP_FM, BLIP_PSG] {
+            self.blips[blip].end_frame(mcycles - self.frame_start);
+        }
+        self.frame_start = mcycles;
+        self.blips[BLIP_FM].samples_avail()
+    }
+
+    /// Mistura as amostras disponíveis em `out` (estéreo intercalado)
+    pub fn read(&mut self, out: &mut Vec<i16>) -> usize {
+        let count = (0..BLIP_COUNT)
+            .filter(|&blip| self.enabled[blip])
+            .map(|blip| self.blips[blip].samples_avail())
+            .min()
+            .unwrap_or(0);
+
+        // Ganho de cada fonte
+        self.mix.clear();
+        self.mix.resize(count, [0; 2]);
+        for blip in (0..BLIP_COUNT).filter(|&blip| self.enabled[blip]) {
+            self.levels.clear();
+            self.blips[blip].read_levels(&mut self.levels, count);
+            let gain = &mut self.gain[blip];
+            for (mix, level) in self.mix.iter_mut().zip(&self.levels) {
+                let gain = gain.next() as i64;
+                for side in 0..2 {
+                    mix[side] += ((level[side] as i64 * gain) >> 16) as i32;
+                }
+            }
+        }
+
+        let eq_bypass = self.equalizer.is_none() && self.eq_gain.iter().all(Ramp::settled);
+        for mut sample in self.mix.drain(..) {
+            // Passa-baixas
+            let factor = self.lp_factor.next() as i64;
+            for (side, last) in self.lp_last.iter_mut().enumerate() {
+                *last = ((*last as i64 * factor + sample[side] as i64 * (UNITY as i64 - factor)) >> 16) as i32;
+                sample[side] = *last;
+            }
+
+            // Equalizador
+            if !eq_bypass {
+                let [low, mid, high] = self.eq_gain.each_mut().map(|ramp| ramp.next() as f64 / UNITY as f64);
+                for (side, eq) in self.eq.iter_mut().enumerate() {
+                    (eq.lg, eq.mg, eq.hg) = (low, mid, high);
+                    sample[side] = eq.process(sample[side]);
+                }
+            }
+
+            for value in sample {
+                out.push(value.clamp(i16::MIN as i32, i16::MAX as i32) as i16);
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix_streams_at_host_rates() {
+        for rate in [44_100, 48_000, 96_000] {
+            let mut sound = Sound::new(rate, 53_693_175);
+            // Quadro NTSC: 262 linhas de 3420 clocks mestres
+            sound.add_delta(BLIP_FM, 1000, 4000, 4000);
+            let count = sound.end_frame(262 * 3420);
+            assert_eq!(count, (262 * 3420 * rate as u64 / 53_693_175) as usize);
+
+            // Fonte externa a 44,1 kHz (CD-DA), só à direita
+            sound.enable(BLIP_CDDA, true);
+            let cdda = &mut sound.blips[BLIP_CDDA];
+            cdda.set_rates(44_100.0, rate as f64);
+            let clocks = cdda.clocks_needed(count);
+            cdda.add_delta_fast(0, 0, 2000);
+            cdda.end_frame(clocks);
+
+            let mut out = Vec::new();
+            assert_eq!(sound.read(&mut out), count);
+            // O degrau limitado em banda é centrado 8 amostras adiante
+            let (l, r) = (out[48] as i32, out[49] as i32);
+            assert!(l > 3000 && l < 4000, "{} {}", rate, l);
+            assert!(r > l + 1500, "{} {} {}", rate, l, r);
+        }
+    }
+
+    #[test]
+    fn test_gain_and_filters_ramp_without_steps() {
+        let frame = 262 * 3420;
+        let tone = |sound: &mut Sound| {
+            for i in 0..20 {
+                let level = if i % 2 == 0 { 8000 } else { -8000 };
+                sound.add_delta(BLIP_FM, i * frame / 20, level, level);
+            }
+        };
+
+        // Com o ganho unitário e os filtros desligados a mistura é a saída
+        // do próprio buffer
+        let mut sound = Sound::new(44_100, 53_693_175);
+        let mut blip = Blip::new(4410);
+        blip.set_rates(53_693_175.0, 44_100.0);
+        tone(&mut sound);
+        for i in 0..20 {
+            let level = if i % 2 == 0 { 8000 } else { -8000 };
+            blip.add_delta(i * frame / 20, level, level);
+        }
+        sound.end_frame(frame);
+        blip.end_frame(frame);
+        let (mut out, mut expected) = (Vec::new(), Vec::new());
+        sound.read(&mut out);
+        blip.read_samples(&mut expected, 1000);
+        assert_eq!(out, expected);
+
+        // Ganho zerado e filtros ligados: o nível cai aos poucos, sem saltos
+        sound.set_gain(BLIP_FM, 0);
+        sound.set_low_pass(Some(3600));
+        sound.set_equalizer(Some(EqSettings { low_gain: 50, ..Default::default() }));
+        assert_eq!(sound.gain(BLIP_FM), 0);
+        sound.end_frame(frame * 2);
+        out.clear();
+        sound.read(&mut out);
+        let left: Vec<i32> = out.iter().step_by(2).map(|&s| s as i32).collect();
+        assert!(left.windows(2).all(|w| (w[0] - w[1]).abs() < 300));
+        assert!(left.last().unwrap().abs() <= 1);
+    }
+
+    #[test]
+    fn test_cart_source_gain_ramps() {
+        let mut sound = Sound::new(44_100, 53_693_175);
+        sound.enable(BLIP_CART, true);
+        sound.set_gain(BLIP_CART, 50);
+        let count = sound.end_frame(262 * 3420);
+
+        // Tocador MP3 do cartucho a 48 kHz, comparado a um buffer igual
+        // lido sem ganho
+        let mut reference = Blip::new(4410);
+        for blip in [&mut sound.blips[BLIP_CART], &mut reference] {
+            blip.set_rates(48_000.0, 44_100.0);
+            let clocks = blip.clocks_needed(count);
+            blip.add_delta_fast(0, 8000, -8000);
+            blip.end_frame(clocks);
+        }
+        let (mut out, mut expected) = (Vec::new(), Vec::new());
+        reference.read_samples(&mut expected, count);
+        assert_eq!(sound.read(&mut out), count);
+        assert_eq!(sound.gain(BLIP_CART), 50);
+
+        // O ganho desce de 100% a 50% em 128 amostras, sem saltos; o degrau
+        // interpolado aparece 8 amostras adiante
+        for (i, (out, expected)) in out.chunks(2).zip(expected.chunks(2)).enumerate() {
+            let gain = 1.0 - (i + 1).min(128) as f64 / 256.0;
+            for side in 0..2 {
+                let level = expected[side] as f64 * gain;
+                assert!((out[side] as f64 - level).abs() <= 2.0, "{} {} {}", i, out[side], level);
+            }
+        }
+        assert!(out[16] > 7000 && out[17] < -7000);
+    }
+}
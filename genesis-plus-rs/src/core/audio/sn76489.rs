@@ -0,0 +1,313 @@
+//! Gerador de som programável SN76489 (PSG).
+//! Baseado em `sound/psg.c` do Genesis Plus GX.
+//!
+//! Três canais de onda quadrada e um de ruído, com atenuação de 2 dB por
+//! passo. O chip é integrado ao VDP no Master System, no Game Gear e no Mega
+//! Drive; o SG-1000 usa o SN76489AN discreto da TI, cujo gerador de ruído
+//! tem um registrador de deslocamento menor e outras derivações.
+//!
+//! A saída é descrita por variações de nível com o clock mestre em que
+//! ocorrem, de modo que escritas nos registradores de volume no meio de um
+//! período (reprodução de amostras pelo PSG) são ouvidas no instante certo.
+
+use crate::utils::clock::PSG_DIVIDER;
+
+/// Saída máxima de um canal (equilíbrio com o FM de um MD1 VA4)
+const MAX_VOLUME: i32 = 2800;
+
+/// Pré-amplificação padrão, em porcentagem
+const PREAMP: i32 = 150;
+
+/// Volume de cada valor de atenuação (-2 dB por passo, 15 = mudo)
+const CHAN_VOLUME: [i32; 16] = [
+    MAX_VOLUME,
+    (MAX_VOLUME as f64 * 0.794328234) as i32,
+    (MAX_VOLUME as f64 * 0.630957344) as i32,
+    (MAX_VOLUME as f64 * 0.501187233) as i32,
+    (MAX_VOLUME as f64 * 0.398107170) as i32,
+    (MAX_VOLUME as f64 * 0.316227766) as i32,
+    (MAX_VOLUME as f64 * 0.251188643) as i32,
+    (MAX_VOLUME as f64 * 0.199526231) as i32,
+    (MAX_VOLUME as f64 * 0.158489319) as i32,
+    (MAX_VOLUME as f64 * 0.125892541) as i32,
+    (MAX_VOLUME as f64 * 0.1) as i32,
+    (MAX_VOLUME as f64 * 0.079432823) as i32,
+    (MAX_VOLUME as f64 * 0.063095734) as i32,
+    (MAX_VOLUME as f64 * 0.050118723) as i32,
+    (MAX_VOLUME as f64 * 0.039810717) as i32,
+    0,
+];
+
+/// Realimentação do ruído branco: paridade dos bits derivados
+const NOISE_FEEDBACK: [u32; 10] = [0, 1, 1, 0, 1, 0, 0, 1, 1, 0];
+
+/// Revisão do chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PsgType {
+    /// SN76489AN discreto (SG-1000): ruído de 15 bits, derivações nos bits
+    /// 1 e 2, e frequência zero equivale a $400
+    Discrete,
+    /// Clone integrado ao VDP da Sega (SMS, GG, MD): ruído de 16 bits,
+    /// derivações nos bits 0 e 3, e frequência zero equivale a 1
+    Integrated,
+}
+
+impl PsgType {
+    /// Posição do bit realimentado no registrador de ruído
+    fn noise_shift_width(self) -> u32 {
+        match self {
+            PsgType::Discrete => 14,
+            PsgType::Integrated => 15,
+        }
+    }
+
+    fn noise_bit_mask(self) -> u32 {
+        match self {
+            PsgType::Discrete => 0x6,
+            PsgType::Integrated => 0x9,
+        }
+    }
+
+    fn zero_freq_inc(self) -> u64 {
+        match self {
+            PsgType::Discrete => 0x400 * PSG_DIVIDER,
+            PsgType::Integrated => PSG_DIVIDER,
+        }
+    }
+}
+
+/// Chip PSG
+pub struct SN76489 {
+    psg_type: PsgType,
+    /// Clock mestre até onde o chip foi executado
+    clock: u64,
+    /// Registrador selecionado pelo último byte de latch
+    latch: usize,
+    /// Frequências dos tons (10 bits) e controle do ruído
+    tone: [u16; 3],
+    noise: u8,
+    /// Volume de cada canal, já convertido da atenuação
+    volume: [i32; 4],
+    noise_shift: u32,
+    /// Período de meia onda e instante da próxima transição de cada canal
+    freq_inc: [u64; 4],
+    freq_counter: [u64; 4],
+    polarity: [i32; 4],
+    /// Variações de volume pendentes, aplicadas no próximo ciclo interno
+    chan_delta: [[i32; 2]; 4],
+    /// Saída de cada canal em nível alto, por lado
+    chan_out: [[i32; 2]; 4],
+    /// Amplificação de cada canal por lado (porcentagem)
+    chan_amp: [[i32; 2]; 4],
+    /// Variações de nível (clock mestre, esquerda, direita) não consumidas
+    deltas: Vec<(u64, i32, i32)>,
+}
+
+impl SN76489 {
+    /// Chip no estado de power-on (verificado nas versões integradas)
+    pub fn new(psg_type: PsgType) -> Self {
+        let zero = psg_type.zero_freq_inc();
+        Self {
+            psg_type,
+            clock: 0,
+            // A atenuação do tom 2 fica selecionada no power-on
+            latch: 3,
+            tone: [0; 3],
+            noise: 0,
+            volume: [0; 4],
+            noise_shift: 1 << psg_type.noise_shift_width(),
+            freq_inc: [zero, zero, zero, 16 * PSG_DIVIDER],
+            freq_counter: [0; 4],
+            polarity: [-1; 4],
+            chan_delta: [[0; 2]; 4],
+            chan_out: [[0; 2]; 4],
+            chan_amp: [[PREAMP; 2]; 4],
+            deltas: Vec::new(),
+        }
+    }
+
+    pub fn psg_type(&self) -> PsgType {
+        self.psg_type
+    }
+
+    /// Executa o chip até o clock mestre `mcycles`, arredondado para cima
+    /// até o próximo ciclo interno
+    pub fn sync(&mut self, mcycles: u64) {
+        if mcycles > self.clock {
+            self.update(mcycles);
+            self.clock += (mcycles - self.clock).div_ceil(PSG_DIVIDER) * PSG_DIVIDER;
+        }
+    }
+
+    /// Consome as variações de nível geradas, em ordem de tempo
+    pub fn drain(&mut self) -> std::vec::Drain<'_, (u64, i32, i32)> {
+        self.deltas.sort_by_key(|&(time, _, _)| time);
+        self.deltas.drain(..)
+    }
+
+    /// Escrita no registrador de dados (byte de latch 1rrrdddd ou de dados 0-dddddd)
+    pub fn write(&mut self, mcycles: u64, data: u8) {
+        self.sync(mcycles);
+
+        if data & 0x80 != 0 {
+            self.latch = ((data >> 4) & 0x07) as usize;
+        }
+
+        match self.latch {
+            // Frequência dos tons
+            0 | 2 | 4 => {
+                let ch = self.latch >> 1;
+                let value = if data & 0x80 != 0 {
+                    (self.tone[ch] & 0x3F0) | (data as u16 & 0x0F)
+                } else {
+                    (self.tone[ch] & 0x00F) | ((data as u16 & 0x3F) << 4)
+                };
+                self.tone[ch] = value;
+                self.freq_inc[ch] = if value != 0 {
+                    value as u64 * PSG_DIVIDER
+                } else {
+                    self.psg_type.zero_freq_inc()
+                };
+                // O ruído pode seguir o tom 3
+                if ch == 2 && self.noise & 0x03 == 0x03 {
+                    self.freq_inc[3] = self.freq_inc[2];
+                }
+            }
+            // Controle do ruído
+            6 => {
+                let rate = data & 0x03;
+                if rate == 0x03 {
+                    self.freq_inc[3] = self.freq_inc[2];
+                    self.freq_counter[3] = self.freq_counter[2];
+                } else {
+                    self.freq_inc[3] = (0x10 << rate) * PSG_DIVIDER;
+                }
+                // O registrador é recarregado e a saída forçada para baixo
+                if self.noise_shift & 1 != 0 {
+                    self.chan_delta[3][0] -= self.chan_out[3][0];
+                    self.chan_delta[3][1] -= self.chan_out[3][1];
+                }
+                self.noise_shift = 1 << self.psg_type.noise_shift_width();
+                self.noise = data;
+            }
+            // Atenuação dos tons e do ruído
+            _ => {
+                let ch = self.latch >> 1;
+                self.volume[ch] = CHAN_VOLUME[(data & 0x0F) as usize];
+                self.update_output(ch);
+            }
+        }
+    }
+
+    /// Registrador de estéreo do Game Gear (porta $06): bits 4-7 ligam os
+    /// canais à esquerda e bits 0-3 à direita
+    pub fn write_stereo(&mut self, mcycles: u64, panning: u8) {
+        self.sync(mcycles);
+        for ch in 0..4 {
+            self.chan_amp[ch][0] = PREAMP * ((panning >> (ch + 4)) & 1) as i32;
+            self.chan_amp[ch][1] = PREAMP * ((panning >> ch) & 1) as i32;
+            self.update_output(ch);
+        }
+    }
+
+    /// Recalcula a saída do canal; se ele está em nível alto, a variação é
+    /// aplicada no próximo ciclo interno
+    fn update_output(&mut self, ch: usize) {
+        let high = if ch < 3 { self.polarity[ch] > 0 } else { self.noise_shift & 1 != 0 };
+        for side in 0..2 {
+            let out = self.volume[ch] * self.chan_amp[ch][side] / 100;
+            if high {
+                self.chan_delta[ch][side] += out - self.chan_out[ch][side];
+            }
+            self.chan_out[ch][side] = out;
+        }
+    }
+
+    /// Gera as transições de todos os canais até o clock mestre `mcycles`
+    fn update(&mut self, mcycles: u64) {
+        let width = self.psg_type.noise_shift_width();
+        let mask = self.psg_type.noise_bit_mask();
+
+        for ch in 0..4 {
+            let [dl, dr] = self.chan_delta[ch];
+            if dl | dr != 0 {
+                self.deltas.push((self.clock, dl, dr));
+                self.chan_delta[ch] = [0; 2];
+            }
+
+            let mut timestamp = self.freq_counter[ch];
+            let mut polarity = self.polarity[ch];
+            let [out_l, out_r] = self.chan_out[ch];
+
+            if ch < 3 {
+                while timestamp < mcycles {
+                    polarity = -polarity;
+                    self.deltas.push((timestamp, polarity * out_l, polarity * out_r));
+                    timestamp += self.freq_inc[ch];
+                }
+            } else {
+                let mut shift = self.noise_shift;
+                while timestamp < mcycles {
+                    polarity = -polarity;
+                    // O registrador desloca apenas na borda de subida
+                    if polarity > 0 {
+                        let output = (shift & 1) as i32;
+                        let feedback = if self.noise & 0x04 != 0 {
+                            // Ruído branco
+                            NOISE_FEEDBACK[(shift & mask) as usize]
+                        } else {
+                            // Ruído periódico
+                            shift & 1
+                        };
+                        shift = (shift >> 1) | (feedback << width);
+                        let variation = (shift & 1) as i32 - output;
+                        if variation != 0 {
+                            self.deltas.push((timestamp, variation * out_l, variation * out_r));
+                        }
+                    }
+                    timestamp += self.freq_inc[3];
+                }
+                self.noise_shift = shift;
+            }
+
+            self.freq_counter[ch] = timestamp;
+            self.polarity[ch] = polarity;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tone_and_stereo() {
+        let mut psg = SN76489::new(PsgType::Integrated);
+        // Tom 1 com período 2 e volume máximo, só à esquerda
+        psg.write_stereo(0, 0x10);
+        psg.write(0, 0x82);
+        psg.write(0, 0x00);
+        psg.write(0, 0x90);
+        psg.sync(PSG_DIVIDER * 8);
+        let deltas: Vec<_> = psg.drain().filter(|&(_, l, r)| l != 0 || r != 0).collect();
+        let high = MAX_VOLUME * PREAMP / 100;
+        assert_eq!(
+            deltas,
+            vec![(0, high, 0), (PSG_DIVIDER * 2, -high, 0), (PSG_DIVIDER * 4, high, 0), (PSG_DIVIDER * 6, -high, 0)]
+        );
+    }
+
+    #[test]
+    fn test_noise_lfsr_variants() {
+        // Ruído periódico: um pulso a cada volta do registrador
+        let rising = |psg_type| {
+            let mut psg = SN76489::new(psg_type);
+            psg.write(0, 0xE0);
+            psg.write(0, 0xF0);
+            psg.sync(240 * 2 * 16 * PSG_DIVIDER);
+            psg.drain().filter(|&(_, l, _)| l > 0).count()
+        };
+        assert_eq!(rising(PsgType::Integrated), 240 / 16);
+        assert_eq!(rising(PsgType::Discrete), 240 / 15);
+    }
+}
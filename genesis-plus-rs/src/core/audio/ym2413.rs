@@ -0,0 +1,1031 @@
+//! Sintetizador FM Yamaha YM2413 (OPLL).
+//! Baseado em `sound/ym2413.c` do Genesis Plus GX (derivado do `ym2413.c` do MAME).
+//!
+//! Nove canais de dois operadores. Cada canal toca um dos 15 instrumentos
+//! gravados na ROM do chip ou o instrumento do usuário ($00-$07); no modo
+//! rítmico os canais 7 a 9 dão lugar a cinco instrumentos de percussão
+//! (bumbo, chimbal, caixa, tom-tom e prato). O chip é a FM Sound Unit do
+//! Mark III e vem embutido no Master System japonês.
+//!
+//! Uma amostra mono é gerada a cada 72 ciclos do Z80 (~49,7 kHz); como o
+//! PSG, o chip acompanha o clock mestre e gera as amostras devidas antes de
+//! cada escrita.
+
+use std::sync::OnceLock;
+
+use crate::utils::clock::YM2413_DIVIDER;
+
+/// Contador de fase em ponto fixo 16.16
+const FREQ_SH: u32 = 16;
+const FREQ_MASK: u32 = (1 << FREQ_SH) - 1;
+
+/// Gerador de envelope: atenuação de 7 bits
+const ENV_BITS: u32 = 10;
+const MAX_ATT_INDEX: i32 = (1 << (ENV_BITS - 3)) - 1;
+const MIN_ATT_INDEX: i32 = 0;
+
+/// Tabela de seno logarítmica (duas formas de onda)
+const SIN_BITS: u32 = 10;
+const SIN_LEN: usize = 1 << SIN_BITS;
+const SIN_MASK: usize = SIN_LEN - 1;
+
+/// Tabela de potência: 11 bits de amplitude, sinal e 256 passos de resolução
+const TL_RES_LEN: usize = 256;
+const TL_TAB_LEN: usize = 11 * 2 * TL_RES_LEN;
+const ENV_QUIET: u32 = (TL_TAB_LEN >> 5) as u32;
+
+/// LFO em ponto fixo 8.24: o AM troca de nível a cada 64 amostras e o PM a
+/// cada 1024
+const LFO_SH: u32 = 24;
+const LFO_AM_INC: u32 = (1 << LFO_SH) / 64;
+const LFO_PM_INC: u32 = (1 << LFO_SH) / 1024;
+const LFO_AM_TAB_ELEMENTS: u32 = 210;
+
+/// Passos por taxa do gerador de envelope
+const RATE_STEPS: u16 = 16;
+
+/// Incrementos do envelope nas fases de decay, sustain, release e dump
+const EG_INC: [u8; 14 * RATE_STEPS as usize] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 0: taxas infinitas
+    0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, // 1: taxas 01..12 0
+    0, 1, 1, 1, 0, 1, 0, 1, 0, 1, 1, 1, 0, 1, 0, 1, // 2: taxas 01..12 1
+    0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, // 3: taxas 01..12 2
+    0, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, // 4: taxas 01..12 3
+    0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, // 5: taxa 13 0
+    0, 1, 0, 1, 1, 1, 1, 1, 0, 1, 0, 1, 0, 1, 0, 1, // 6: taxa 13 1
+    0, 1, 0, 1, 1, 1, 1, 1, 0, 1, 0, 1, 1, 1, 1, 1, // 7: taxa 13 2
+    0, 1, 0, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 8: taxa 13 3
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 9: taxa 14 0
+    1, 1, 1, 1, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, // 10: taxa 14 1
+    1, 1, 1, 1, 2, 2, 2, 2, 1, 1, 1, 1, 2, 2, 2, 2, // 11: taxa 14 2
+    1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // 12: taxa 14 3
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // 13: taxas 15 x
+];
+
+/// Multiplicadores do ataque (a atenuação cai proporcionalmente ao nível)
+const EG_MUL: [u8; 17 * RATE_STEPS as usize] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // 0: taxas infinitas
+    0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, // 1: taxas 01..11 0
+    0, 1, 1, 1, 0, 1, 0, 1, 0, 1, 1, 1, 0, 1, 0, 1, // 2: taxas 01..11 1
+    0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, 0, 1, 1, 1, // 3: taxas 01..11 2
+    0, 1, 1, 1, 1, 1, 1, 1, 0, 1, 1, 1, 1, 1, 1, 1, // 4: taxas 01..11 3
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, // 5: taxa 12 0
+    1, 1, 1, 1, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, // 6: taxa 12 1
+    1, 1, 1, 1, 2, 2, 2, 2, 1, 1, 1, 1, 2, 2, 2, 2, // 7: taxa 12 2
+    1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // 8: taxa 12 3
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, // 9: taxa 13 0
+    2, 2, 2, 2, 4, 4, 4, 4, 2, 2, 2, 2, 2, 2, 2, 2, // 10: taxa 13 1
+    2, 2, 2, 2, 4, 4, 4, 4, 2, 2, 2, 2, 4, 4, 4, 4, // 11: taxa 13 2
+    2, 2, 2, 2, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, // 12: taxa 13 3
+    4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, 4, // 13: taxa 14 0
+    4, 4, 4, 4, 8, 8, 8, 8, 4, 4, 4, 4, 4, 4, 4, 4, // 14: taxa 14 1
+    4, 4, 4, 4, 8, 8, 8, 8, 4, 4, 4, 4, 8, 8, 8, 8, // 15: taxa 14 2
+    4, 4, 4, 4, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, // 16: taxa 14 3
+];
+
+/// Key scale level (3 dB/oitava) em passos de 0,1875 dB, por bloco e F-number
+const KSL_TAB: [u32; 8 * 16] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 6, 8, 10, 12, 14, 16,
+    0, 0, 0, 0, 0, 6, 10, 14, 16, 20, 22, 24, 26, 28, 30, 32,
+    0, 0, 0, 10, 16, 22, 26, 30, 32, 36, 38, 40, 42, 44, 46, 48,
+    0, 0, 16, 26, 32, 38, 42, 46, 48, 52, 54, 56, 58, 60, 62, 64,
+    0, 16, 32, 42, 48, 54, 58, 62, 64, 68, 70, 72, 74, 76, 78, 80,
+    0, 32, 48, 58, 64, 70, 74, 78, 80, 84, 86, 88, 90, 92, 94, 96,
+    0, 48, 64, 74, 80, 86, 90, 94, 96, 100, 102, 104, 106, 108, 110, 112,
+];
+
+/// Multiplicador de frequência (x2): 1/2, 1, 2, ..., 10, 10, 12, 12, 15, 15
+const MUL_TAB: [u32; 16] = [1, 2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 20, 24, 24, 30, 30];
+
+/// Desvio do F-number pelo vibrato, por 3 bits altos do F-number e passo do LFO
+const LFO_PM_TABLE: [i8; 8 * 8] = [
+    0, 0, 0, 0, 0, 0, 0, 0,
+    1, 0, 0, 0, -1, 0, 0, 0,
+    2, 1, 0, -1, -2, -1, 0, 1,
+    3, 1, 0, -1, -3, -1, 0, 1,
+    4, 2, 0, -2, -4, -2, 0, 2,
+    5, 2, 0, -2, -5, -2, 0, 2,
+    6, 3, 0, -3, -6, -3, 0, 3,
+    7, 3, 0, -3, -7, -3, 0, 3,
+];
+
+/// Instrumentos da ROM (lida do die do YM2413B): 0 = usuário, 1-15 melódicos,
+/// 16-18 bumbo, chimbal/caixa e tom-tom/prato
+const INSTRUMENTS: [[u8; 8]; 19] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    [0x71, 0x61, 0x1E, 0x17, 0xD0, 0x78, 0x00, 0x17],
+    [0x13, 0x41, 0x1A, 0x0D, 0xD8, 0xF7, 0x23, 0x13],
+    [0x13, 0x01, 0x99, 0x00, 0xF2, 0xC4, 0x11, 0x23],
+    [0x31, 0x61, 0x0E, 0x07, 0xA8, 0x64, 0x70, 0x27],
+    [0x32, 0x21, 0x1E, 0x06, 0xE0, 0x76, 0x00, 0x28],
+    [0x31, 0x22, 0x16, 0x05, 0xE0, 0x71, 0x00, 0x18],
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x10, 0x07],
+    [0x23, 0x21, 0x2D, 0x14, 0xA2, 0x72, 0x00, 0x07],
+    [0x61, 0x61, 0x1B, 0x06, 0x64, 0x65, 0x10, 0x17],
+    [0x41, 0x61, 0x0B, 0x18, 0x85, 0xF7, 0x71, 0x07],
+    [0x13, 0x01, 0x83, 0x11, 0xFA, 0xE4, 0x10, 0x04],
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12],
+    [0x61, 0x50, 0x0C, 0x05, 0xC2, 0xF5, 0x20, 0x42],
+    [0x01, 0x01, 0x55, 0x03, 0xC9, 0x95, 0x03, 0x02],
+    [0x61, 0x41, 0x89, 0x03, 0xF1, 0xE4, 0x40, 0x13],
+    [0x01, 0x01, 0x18, 0x0F, 0xDF, 0xF8, 0x6A, 0x6D],
+    [0x01, 0x01, 0x00, 0x00, 0xC8, 0xD8, 0xA7, 0x48],
+    [0x05, 0x01, 0x00, 0x00, 0xF8, 0xAA, 0x59, 0x55],
+];
+
+/// Operadores de cada canal
+const SLOT1: usize = 0;
+const SLOT2: usize = 1;
+
+/// Tabelas geradas na primeira instância do chip
+struct Tables {
+    /// Potência: atenuação -> amplitude linear com sinal
+    tl: Vec<i32>,
+    /// Seno completo e meia onda retificada, no formato da tabela de potência
+    sin: Vec<u32>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(Tables::new)
+}
+
+impl Tables {
+    fn new() -> Self {
+        const ENV_STEP: f64 = 128.0 / (1 << ENV_BITS) as f64;
+
+        let mut tl = vec![0; TL_TAB_LEN];
+        for x in 0..TL_RES_LEN {
+            let m = ((1 << 16) as f64 / 2f64.powf((x + 1) as f64 * (ENV_STEP / 4.0) / 8.0)).floor();
+            // 16 bits -> 12 bits -> 11 bits arredondados
+            let mut n = (m as i32) >> 4;
+            n = if n & 1 != 0 { (n >> 1) + 1 } else { n >> 1 };
+            for i in 0..11 {
+                tl[x * 2 + i * 2 * TL_RES_LEN] = n >> i;
+                tl[x * 2 + 1 + i * 2 * TL_RES_LEN] = -(n >> i);
+            }
+        }
+
+        let mut sin = vec![0; SIN_LEN * 2];
+        for i in 0..SIN_LEN {
+            // Seno não padrão, conferido no chip real
+            let m = (((i * 2) + 1) as f64 * std::f64::consts::PI / SIN_LEN as f64).sin();
+            let o = 8.0 * (1.0 / m.abs()).log2() / (ENV_STEP / 4.0);
+            let mut n = (2.0 * o) as u32;
+            n = if n & 1 != 0 { (n >> 1) + 1 } else { n >> 1 };
+            sin[i] = n * 2 + (m < 0.0) as u32;
+            // Onda 1: só a metade positiva do seno
+            sin[SIN_LEN + i] = if i & (1 << (SIN_BITS - 1)) != 0 { TL_TAB_LEN as u32 } else { sin[i] };
+        }
+
+        Self { tl, sin }
+    }
+}
+
+/// Incremento de fase para um F-number de 10 bits no bloco 7
+fn fn_tab(fnum: u32) -> u32 {
+    // O chip trabalha em 10.10; o contador aqui é 16.16
+    fnum * 64 * (1 << (FREQ_SH - 10))
+}
+
+/// Nível do AM do LFO: triângulo de 27 níveis em 210 passos
+fn lfo_am_level(step: u32) -> u32 {
+    match step {
+        0..=6 => 0,
+        7..=106 => (step - 3) / 4,
+        107..=109 => 26,
+        _ => (213 - step) / 4,
+    }
+}
+
+/// Deslocamento do contador e seleção em `EG_INC` para uma taxa efetiva
+/// (16 taxas infinitas + 64 taxas + 16 de key scale)
+fn eg_rate(rate: u32) -> (u8, u16) {
+    match rate {
+        0..=19 => (13, 0),
+        20..=67 => (12 - ((rate - 20) / 4) as u8, (1 + (rate & 3) as u16) * RATE_STEPS),
+        68..=75 => (0, (rate - 63) as u16 * RATE_STEPS),
+        _ => (0, 13 * RATE_STEPS),
+    }
+}
+
+/// Fase do gerador de envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EgState {
+    Off,
+    Release,
+    Sustain,
+    Decay,
+    Attack,
+    /// Descida rápida ao silêncio antes do ataque
+    Dump,
+}
+
+/// Um operador: gerador de fase e de envelope
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// Taxas de ataque, decay e release (taxa << 2, com 16 de deslocamento)
+    ar: u32,
+    dr: u32,
+    rr: u32,
+    /// Deslocamento do key scale rate (0 ou 2)
+    ksr_shift: u8,
+    /// Deslocamento do key scale level (31 = desligado)
+    ksl: u8,
+    /// Key scale rate efetivo (kcode >> ksr_shift)
+    ksr: u8,
+    mul: u32,
+
+    phase: u32,
+    freq: u32,
+    fb_shift: u8,
+    /// Duas últimas saídas do modulador, para a realimentação
+    op1_out: [i32; 2],
+
+    /// Tom sustentado (true) ou percussivo
+    eg_type: bool,
+    state: EgState,
+    tl: u32,
+    tll: i32,
+    volume: i32,
+    sl: i32,
+
+    eg_sh_dp: u8,
+    eg_sel_dp: u16,
+    eg_sh_ar: u8,
+    eg_sel_ar: u16,
+    eg_sh_dr: u8,
+    eg_sel_dr: u16,
+    eg_sh_rr: u8,
+    eg_sel_rr: u16,
+    eg_sh_rs: u8,
+    eg_sel_rs: u16,
+
+    /// Bit 0: key on do canal, bit 1: key on do modo rítmico
+    key: u8,
+    am_mask: u32,
+    vib: bool,
+    /// Início da forma de onda na tabela de seno
+    wavetable: usize,
+}
+
+impl Slot {
+    fn new() -> Self {
+        Self {
+            ar: 0,
+            dr: 0,
+            rr: 0,
+            ksr_shift: 0,
+            ksl: 0,
+            ksr: 0,
+            mul: 0,
+            phase: 0,
+            freq: 0,
+            fb_shift: 0,
+            op1_out: [0; 2],
+            eg_type: false,
+            state: EgState::Off,
+            tl: 0,
+            tll: 0,
+            volume: 0,
+            sl: 0,
+            eg_sh_dp: 0,
+            eg_sel_dp: 0,
+            eg_sh_ar: 0,
+            eg_sel_ar: 0,
+            eg_sh_dr: 0,
+            eg_sel_dr: 0,
+            eg_sh_rr: 0,
+            eg_sel_rr: 0,
+            eg_sh_rs: 0,
+            eg_sel_rs: 0,
+            key: 0,
+            am_mask: 0,
+            vib: false,
+            wavetable: 0,
+        }
+    }
+
+    fn key_on(&mut self, key_set: u8) {
+        // A fase não é reiniciada (verificado no YM2413 real)
+        if self.key == 0 {
+            self.state = EgState::Dump;
+        }
+        self.key |= key_set;
+    }
+
+    fn key_off(&mut self, key_clr: u8) {
+        if self.key != 0 {
+            self.key &= key_clr;
+            if self.key == 0 {
+                self.state = if self.at_max_attenuation() { EgState::Off } else { EgState::Release };
+            }
+        }
+    }
+
+    /// O comparador ignora os 2 bits baixos do nível do envelope
+    fn at_max_attenuation(&self) -> bool {
+        (self.volume & !3) == (MAX_ATT_INDEX & !3)
+    }
+
+    /// Recalcula as taxas de ataque com o key scale atual
+    fn update_attack_rate(&mut self) {
+        let rate = self.ar + self.ksr as u32;
+        (self.eg_sh_ar, self.eg_sel_ar) = if rate >= 16 + 60 {
+            // Ataque 15.x é pulado ou bloqueado (notas de engenharia reversa
+            // do YM2413 na SMS Power!, 2017-01-26)
+            (13, 0)
+        } else if rate >= 16 + 48 {
+            // Ataques 12.0 a 14.3 avançam a cada amostra
+            (0, eg_rate(rate).1 + 4 * RATE_STEPS)
+        } else {
+            eg_rate(rate)
+        };
+    }
+
+    /// Atualiza incremento de fase e taxas de envelope do operador
+    fn calc_fc(&mut self, fc: u32, kcode: u8, sus: bool) {
+        self.freq = fc * self.mul;
+        let ksr = kcode >> self.ksr_shift;
+        if self.ksr != ksr {
+            self.ksr = ksr;
+            self.update_attack_rate();
+            (self.eg_sh_dr, self.eg_sel_dr) = eg_rate(self.dr + ksr as u32);
+            (self.eg_sh_rr, self.eg_sel_rr) = eg_rate(self.rr + ksr as u32);
+        }
+        let rs = if sus { 16 + (5 << 2) } else { 16 + (7 << 2) };
+        (self.eg_sh_rs, self.eg_sel_rs) = eg_rate(rs + self.ksr as u32);
+        (self.eg_sh_dp, self.eg_sel_dp) = eg_rate(16 + (12 << 2) + self.ksr as u32);
+    }
+
+    /// Incremento do envelope se o contador global cair no passo da taxa
+    fn eg_step(eg_cnt: u32, shift: u8, select: u16) -> Option<i32> {
+        if eg_cnt & ((1 << shift) - 1) != 0 {
+            return None;
+        }
+        Some(EG_INC[select as usize + ((eg_cnt >> shift) & 15) as usize] as i32)
+    }
+
+    /// Soma um passo de decay/release e desliga o envelope no silêncio
+    fn eg_fall(&mut self, eg_cnt: u32, shift: u8, select: u16) {
+        if let Some(inc) = Self::eg_step(eg_cnt, shift, select) {
+            self.volume += inc;
+            if self.at_max_attenuation() {
+                self.state = EgState::Off;
+            }
+        }
+    }
+
+    /// Avança o envelope um passo. `release` indica se o operador pode
+    /// executar a fase de release; devolve true quando o dump termina.
+    fn advance_eg(&mut self, eg_cnt: u32, sus: bool, release: bool) -> bool {
+        match self.state {
+            EgState::Dump => {
+                if self.at_max_attenuation() {
+                    self.state = EgState::Attack;
+                    // Ataque 15.x leva o envelope direto a zero
+                    if self.ar + self.ksr as u32 >= 16 + 60 {
+                        self.volume = MIN_ATT_INDEX;
+                    }
+                    return true;
+                }
+                if let Some(inc) = Self::eg_step(eg_cnt, self.eg_sh_dp, self.eg_sel_dp) {
+                    self.volume += inc;
+                }
+            }
+            EgState::Attack => {
+                if self.volume == MIN_ATT_INDEX {
+                    self.state = EgState::Decay;
+                } else if eg_cnt & (((1 << self.eg_sh_ar) - 1) & !3) == 0 {
+                    let step = ((eg_cnt >> self.eg_sh_ar) & 15) as usize;
+                    self.volume += (!self.volume * EG_MUL[self.eg_sel_ar as usize + step] as i32) >> 4;
+                }
+            }
+            EgState::Decay => {
+                // O comparador ignora os 3 bits baixos
+                if (self.volume & !7) == self.sl {
+                    self.state = EgState::Sustain;
+                } else {
+                    self.eg_fall(eg_cnt, self.eg_sh_dr, self.eg_sel_dr);
+                }
+            }
+            EgState::Sustain => {
+                // No modo percussivo o release continua durante o sustain
+                if !self.eg_type {
+                    self.eg_fall(eg_cnt, self.eg_sh_rr, self.eg_sel_rr);
+                }
+            }
+            EgState::Release => {
+                // Moduladores de canais melódicos não executam o release
+                if release {
+                    if self.eg_type && !sus {
+                        self.eg_fall(eg_cnt, self.eg_sh_rr, self.eg_sel_rr);
+                    } else {
+                        self.eg_fall(eg_cnt, self.eg_sh_rs, self.eg_sel_rs);
+                    }
+                }
+            }
+            EgState::Off => self.volume = MAX_ATT_INDEX,
+        }
+        false
+    }
+
+    /// Atenuação total do operador
+    fn volume(&self, lfo_am: u32) -> u32 {
+        if self.state != EgState::Off {
+            (self.tll + self.volume) as u32 + (lfo_am & self.am_mask)
+        } else {
+            ENV_QUIET
+        }
+    }
+
+    /// Fase de 10 bits somada à modulação de fase (já em 16.16)
+    fn op_calc(t: &Tables, phase: u32, env: u32, pm: i32, wavetable: usize) -> i32 {
+        let index = ((phase & !FREQ_MASK).wrapping_add(pm as u32) >> FREQ_SH) as usize & SIN_MASK;
+        let p = (env << 5) as usize + t.sin[wavetable + index] as usize;
+        if p >= TL_TAB_LEN {
+            0
+        } else {
+            t.tl[p]
+        }
+    }
+}
+
+/// Um canal: modulador (SLOT1) e portador (SLOT2)
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    slots: [Slot; 2],
+    block_fnum: u32,
+    fc: u32,
+    ksl_base: u32,
+    kcode: u8,
+    /// Sustain ligado: release mais lento após o key off
+    sus: bool,
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            slots: [Slot::new(); 2],
+            block_fnum: 0,
+            fc: 0,
+            ksl_base: 0,
+            kcode: 0,
+            sus: false,
+        }
+    }
+
+    fn refresh_tll(&mut self, slot: usize) {
+        let s = &mut self.slots[slot];
+        s.tll = (s.tl + (self.ksl_base >> s.ksl)) as i32;
+    }
+
+    fn calc_fc(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.calc_fc(self.fc, self.kcode, self.sus);
+        }
+    }
+
+    /// Saída do canal: modulador com realimentação -> portador
+    fn calc(&mut self, t: &Tables, lfo_am: u32) -> i32 {
+        let m = &mut self.slots[SLOT1];
+        let env = m.volume(lfo_am);
+        let mut out = m.op1_out[0] + m.op1_out[1];
+        m.op1_out[0] = m.op1_out[1];
+        let phase_modulation = m.op1_out[0];
+        m.op1_out[1] = 0;
+        if env < ENV_QUIET {
+            if m.fb_shift == 0 {
+                out = 0;
+            }
+            m.op1_out[1] = Slot::op_calc(t, m.phase, env, out << m.fb_shift, m.wavetable);
+        }
+
+        let c = &self.slots[SLOT2];
+        let env = c.volume(lfo_am);
+        if env < ENV_QUIET {
+            Slot::op_calc(t, c.phase, env, phase_modulation << 17, c.wavetable)
+        } else {
+            0
+        }
+    }
+}
+
+/// Modo de uso da FM Sound Unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FmUnit {
+    /// Sem chip: as portas $F0-$F2 ficam desconectadas
+    Off,
+    /// Chip sempre presente
+    On,
+    /// Presente apenas nos jogos com suporte a FM
+    #[default]
+    Auto,
+}
+
+/// Chip YM2413
+pub struct YM2413 {
+    channels: [Channel; 9],
+    /// Instrumento e volume de cada canal ($30-$38)
+    instvol_r: [u8; 9],
+    eg_cnt: u32,
+    /// Modo rítmico e key on das percussões ($0E)
+    rhythm: u8,
+    lfo_am_cnt: u32,
+    lfo_pm_cnt: u32,
+    lfo_am: u32,
+    lfo_pm: u32,
+    /// Registrador de ruído de 23 bits
+    noise_rng: u32,
+    /// Instrumentos: o do usuário seguido da ROM
+    inst_tab: [[u8; 8]; 19],
+    address: u8,
+    /// Saída FM habilitada (bit 0 do controle de áudio do Master System)
+    status: u8,
+
+    /// Clock mestre da próxima amostra a gerar
+    clock: u64,
+    /// Amostras geradas ainda não consumidas e o clock da primeira
+    samples: Vec<i32>,
+    samples_start: u64,
+}
+
+impl YM2413 {
+    pub fn new() -> Self {
+        let mut chip = Self {
+            channels: [Channel::new(); 9],
+            instvol_r: [0; 9],
+            eg_cnt: 0,
+            rhythm: 0,
+            lfo_am_cnt: 0,
+            lfo_pm_cnt: 0,
+            lfo_am: 0,
+            lfo_pm: 0,
+            noise_rng: 1,
+            inst_tab: INSTRUMENTS,
+            address: 0,
+            status: 0,
+            clock: 0,
+            samples: Vec::new(),
+            samples_start: 0,
+        };
+        chip.reset_chip();
+        chip
+    }
+
+    /// Reseta o chip no clock mestre `mcycles`
+    pub fn reset(&mut self, mcycles: u64) {
+        self.sync(mcycles);
+        self.reset_chip();
+    }
+
+    fn reset_chip(&mut self) {
+        self.eg_cnt = 0;
+        self.noise_rng = 1;
+        self.inst_tab = INSTRUMENTS;
+
+        self.write_reg(0x0F, 0);
+        for reg in (0x10..=0x3F).rev() {
+            self.write_reg(reg, 0);
+        }
+
+        for ch in self.channels.iter_mut() {
+            for slot in ch.slots.iter_mut() {
+                slot.wavetable = 0;
+                slot.state = EgState::Off;
+                slot.volume = MAX_ATT_INDEX;
+            }
+        }
+    }
+
+    /// Gera as amostras devidas até o clock mestre `mcycles`
+    pub fn sync(&mut self, mcycles: u64) {
+        if mcycles <= self.clock {
+            return;
+        }
+        if self.samples.is_empty() {
+            self.samples_start = self.clock;
+        }
+        let count = (mcycles - self.clock).div_ceil(YM2413_DIVIDER);
+        for _ in 0..count {
+            let sample = self.update();
+            self.samples.push(sample);
+        }
+        self.clock += count * YM2413_DIVIDER;
+    }
+
+    /// Consome as amostras geradas; devolve o clock mestre da primeira
+    pub fn drain(&mut self) -> (u64, std::vec::Drain<'_, i32>) {
+        (self.samples_start, self.samples.drain(..))
+    }
+
+    /// Escrita nas portas do chip: endereço (A0 = 0), dados (A0 = 1) e, com
+    /// A1, o bit de habilitação da saída FM
+    pub fn write(&mut self, mcycles: u64, port: u8, value: u8) {
+        if port & 3 != 0 {
+            self.sync(mcycles);
+        }
+        if port & 2 != 0 {
+            self.status = value & 0x01;
+        } else if port & 1 == 0 {
+            self.address = value;
+        } else {
+            self.write_reg(self.address, value);
+        }
+    }
+
+    /// Leitura: bit 0 devolve a habilitação da saída FM, bits 1-2 em zero
+    pub fn read(&self) -> u8 {
+        0xF8 | self.status
+    }
+
+    fn set_mul(&mut self, slot: usize, v: u8) {
+        let ch = &mut self.channels[slot / 2];
+        let s = &mut ch.slots[slot & 1];
+        s.mul = MUL_TAB[(v & 0x0F) as usize];
+        s.ksr_shift = if v & 0x10 != 0 { 0 } else { 2 };
+        s.eg_type = v & 0x20 != 0;
+        s.vib = v & 0x40 != 0;
+        s.am_mask = if v & 0x80 != 0 { !0 } else { 0 };
+        s.calc_fc(ch.fc, ch.kcode, ch.sus);
+    }
+
+    fn set_ksl_tl(&mut self, chan: usize, v: u8) {
+        let ch = &mut self.channels[chan];
+        let s = &mut ch.slots[SLOT1];
+        let ksl = v >> 6;
+        s.ksl = if ksl != 0 { 3 - ksl } else { 31 };
+        s.tl = ((v & 0x3F) as u32) << (ENV_BITS - 2 - 7);
+        ch.refresh_tll(SLOT1);
+    }
+
+    fn set_ksl_wave_fb(&mut self, chan: usize, v: u8) {
+        let ch = &mut self.channels[chan];
+        let m = &mut ch.slots[SLOT1];
+        m.wavetable = ((v & 0x08) >> 3) as usize * SIN_LEN;
+        m.fb_shift = if v & 7 != 0 { (v & 7) + 8 } else { 0 };
+
+        let c = &mut ch.slots[SLOT2];
+        c.wavetable = ((v & 0x10) >> 4) as usize * SIN_LEN;
+        let ksl = v >> 6;
+        c.ksl = if ksl != 0 { 3 - ksl } else { 31 };
+        ch.refresh_tll(SLOT2);
+    }
+
+    fn set_ar_dr(&mut self, slot: usize, v: u8) {
+        let s = &mut self.channels[slot / 2].slots[slot & 1];
+        s.ar = if v >> 4 != 0 { 16 + (((v >> 4) as u32) << 2) } else { 0 };
+        s.update_attack_rate();
+        s.dr = if v & 0x0F != 0 { 16 + (((v & 0x0F) as u32) << 2) } else { 0 };
+        (s.eg_sh_dr, s.eg_sel_dr) = eg_rate(s.dr + s.ksr as u32);
+    }
+
+    fn set_sl_rr(&mut self, slot: usize, v: u8) {
+        let s = &mut self.channels[slot / 2].slots[slot & 1];
+        // 3 dB por passo
+        s.sl = ((v >> 4) as i32) * 8;
+        s.rr = if v & 0x0F != 0 { 16 + (((v & 0x0F) as u32) << 2) } else { 0 };
+        (s.eg_sh_rr, s.eg_sel_rr) = eg_rate(s.rr + s.ksr as u32);
+    }
+
+    fn load_instrument(&mut self, chan: usize, inst: usize) {
+        let data = self.inst_tab[inst];
+        let slot = chan * 2;
+        self.set_mul(slot, data[0]);
+        self.set_mul(slot + 1, data[1]);
+        self.set_ksl_tl(chan, data[2]);
+        self.set_ksl_wave_fb(chan, data[3]);
+        self.set_ar_dr(slot, data[4]);
+        self.set_ar_dr(slot + 1, data[5]);
+        self.set_sl_rr(slot, data[6]);
+        self.set_sl_rr(slot + 1, data[7]);
+    }
+
+    /// Propaga a escrita no instrumento do usuário aos canais que o usam
+    fn update_instrument_zero(&mut self, r: u8) {
+        let inst = self.inst_tab[0];
+        let chan_max = if self.rhythm & 0x20 != 0 { 6 } else { 9 };
+        for chan in 0..chan_max {
+            if self.instvol_r[chan] & 0xF0 != 0 {
+                continue;
+            }
+            match r & 7 {
+                0 => self.set_mul(chan * 2, inst[0]),
+                1 => self.set_mul(chan * 2 + 1, inst[1]),
+                2 => self.set_ksl_tl(chan, inst[2]),
+                3 => self.set_ksl_wave_fb(chan, inst[3]),
+                4 => self.set_ar_dr(chan * 2, inst[4]),
+                5 => self.set_ar_dr(chan * 2 + 1, inst[5]),
+                6 => self.set_sl_rr(chan * 2, inst[6]),
+                _ => self.set_sl_rr(chan * 2 + 1, inst[7]),
+            }
+        }
+    }
+
+    /// Volume do modulador de HH (canal 8) e TOM (canal 9) no modo rítmico
+    fn set_rhythm_volume(&mut self, chan: usize, v: u8) {
+        let ch = &mut self.channels[chan];
+        ch.slots[SLOT1].tl = (((v >> 4) as u32) << 2) << (ENV_BITS - 2 - 7);
+        ch.refresh_tll(SLOT1);
+    }
+
+    fn write_reg(&mut self, r: u8, v: u8) {
+        match r & 0xF0 {
+            0x00 => match r & 0x0F {
+                // Instrumento do usuário
+                0x00..=0x07 => {
+                    self.inst_tab[0][r as usize] = v;
+                    self.update_instrument_zero(r);
+                }
+                // x, x, R, BD, SD, TOM, TC, HH
+                0x0E => self.write_rhythm(v),
+                _ => {}
+            },
+            0x10 | 0x20 => {
+                // Canais 9-15 espelham 0-6 (verificado no YM2413 real)
+                let chan = (r & 0x0F) as usize % 9;
+                let ch = &mut self.channels[chan];
+                let block_fnum = if r & 0x10 != 0 {
+                    // $10-$18: F-number 0-7
+                    (ch.block_fnum & 0x0F00) | v as u32
+                } else {
+                    // $20-$28: sustain, key on, bloco, F-number 8
+                    for slot in ch.slots.iter_mut() {
+                        if v & 0x10 != 0 {
+                            slot.key_on(1);
+                        } else {
+                            slot.key_off(!1);
+                        }
+                    }
+                    ch.sus = v & 0x20 != 0;
+                    (((v & 0x0F) as u32) << 8) | (ch.block_fnum & 0xFF)
+                };
+
+                if ch.block_fnum != block_fnum {
+                    ch.block_fnum = block_fnum;
+                    // Bloco nos bits 3-1 do key code, F-number 8 no bit 0
+                    ch.kcode = ((block_fnum & 0x0F00) >> 8) as u8;
+                    ch.ksl_base = KSL_TAB[(block_fnum >> 5) as usize];
+                    let block_fnum = block_fnum * 2;
+                    let block = (block_fnum & 0x1C00) >> 10;
+                    ch.fc = fn_tab(block_fnum & 0x03FF) >> (7 - block);
+                    ch.refresh_tll(SLOT1);
+                    ch.refresh_tll(SLOT2);
+                    ch.calc_fc();
+                }
+            }
+            0x30 => {
+                // Instrumento (4 bits altos) e volume (4 bits baixos)
+                let chan = (r & 0x0F) as usize % 9;
+                let ch = &mut self.channels[chan];
+                ch.slots[SLOT2].tl = (((v & 0x0F) as u32) << 2) << (ENV_BITS - 2 - 7);
+                ch.refresh_tll(SLOT2);
+
+                if chan >= 6 && self.rhythm & 0x20 != 0 {
+                    // No modo rítmico os 4 bits altos são o volume do HH ou do TOM
+                    if chan >= 7 {
+                        self.set_rhythm_volume(chan, v);
+                    }
+                } else if self.instvol_r[chan] & 0xF0 != v & 0xF0 {
+                    self.instvol_r[chan] = v;
+                    self.load_instrument(chan, (v >> 4) as usize);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn write_rhythm(&mut self, v: u8) {
+        if v & 0x20 != 0 {
+            if self.rhythm & 0x20 == 0 {
+                // Percussões nos canais 7-9
+                self.load_instrument(6, 16);
+                self.load_instrument(7, 17);
+                self.set_rhythm_volume(7, self.instvol_r[7]);
+                self.load_instrument(8, 18);
+                self.set_rhythm_volume(8, self.instvol_r[8]);
+            }
+            // BD nos dois operadores do canal 7; HH, SD, TOM e TC em um cada
+            let keys = [
+                (6, SLOT1, 0x10),
+                (6, SLOT2, 0x10),
+                (7, SLOT1, 0x01),
+                (7, SLOT2, 0x08),
+                (8, SLOT1, 0x04),
+                (8, SLOT2, 0x02),
+            ];
+            for (chan, slot, bit) in keys {
+                let s = &mut self.channels[chan].slots[slot];
+                if v & bit != 0 {
+                    s.key_on(2);
+                } else {
+                    s.key_off(!2);
+                }
+            }
+        } else {
+            if self.rhythm & 0x20 != 0 {
+                // Volta aos instrumentos melódicos
+                for chan in 6..9 {
+                    self.load_instrument(chan, (self.instvol_r[chan] >> 4) as usize);
+                }
+            }
+            for ch in self.channels[6..].iter_mut() {
+                for slot in ch.slots.iter_mut() {
+                    slot.key_off(!2);
+                }
+            }
+        }
+        self.rhythm = v & 0x3F;
+    }
+
+    /// Gera uma amostra e avança LFO, envelopes, fases e ruído
+    pub fn update(&mut self) -> i32 {
+        let t = tables();
+        self.advance_lfo();
+
+        let mut melody = 0;
+        let mut rhythm = 0;
+        for ch in self.channels[..6].iter_mut() {
+            melody += ch.calc(t, self.lfo_am);
+        }
+        if self.rhythm & 0x20 == 0 {
+            for ch in self.channels[6..].iter_mut() {
+                melody += ch.calc(t, self.lfo_am);
+            }
+        } else {
+            rhythm = self.rhythm_calc(t, self.noise_rng & 1 != 0);
+        }
+
+        // Saídas melódica (MO) e rítmica (RO), mudas sem o bit de habilitação
+        let out = (melody + rhythm * 2) * 2 * self.status as i32;
+
+        self.advance();
+        out
+    }
+
+    fn advance_lfo(&mut self) {
+        self.lfo_am_cnt += LFO_AM_INC;
+        if self.lfo_am_cnt >= LFO_AM_TAB_ELEMENTS << LFO_SH {
+            self.lfo_am_cnt -= LFO_AM_TAB_ELEMENTS << LFO_SH;
+        }
+        self.lfo_am = lfo_am_level(self.lfo_am_cnt >> LFO_SH) >> 1;
+        self.lfo_pm_cnt = self.lfo_pm_cnt.wrapping_add(LFO_PM_INC);
+        self.lfo_pm = (self.lfo_pm_cnt >> LFO_SH) & 7;
+    }
+
+    fn advance(&mut self) {
+        // O envelope avança uma vez por amostra
+        self.eg_cnt = self.eg_cnt.wrapping_add(1);
+        let rhythm = self.rhythm & 0x20 != 0;
+        for (c, ch) in self.channels.iter_mut().enumerate() {
+            for s in 0..2 {
+                // Release: portadores sempre, moduladores só nas percussões
+                let release = s == SLOT2 || (rhythm && c >= 6);
+                // O fim do dump do portador zera a fase dos dois operadores
+                if ch.slots[s].advance_eg(self.eg_cnt, ch.sus, release) && s == SLOT2 {
+                    ch.slots[SLOT1].phase = 0;
+                    ch.slots[SLOT2].phase = 0;
+                }
+            }
+        }
+
+        for ch in self.channels.iter_mut() {
+            for slot in ch.slots.iter_mut() {
+                let offset = if slot.vib {
+                    let fnum_lfo = 8 * ((ch.block_fnum & 0x01C0) >> 6);
+                    LFO_PM_TABLE[(self.lfo_pm + fnum_lfo) as usize] as i32
+                } else {
+                    0
+                };
+                if offset != 0 {
+                    let block_fnum = (ch.block_fnum * 2).wrapping_add_signed(offset);
+                    let block = (block_fnum & 0x1C00) >> 10;
+                    let incr = (fn_tab(block_fnum & 0x03FF) >> (7 - block)) * slot.mul;
+                    slot.phase = slot.phase.wrapping_add(incr);
+                } else {
+                    slot.phase = slot.phase.wrapping_add(slot.freq);
+                }
+            }
+        }
+
+        // Ruído: registrador de 23 bits deslocado uma vez por amostra, com
+        // a saída no bit 0 (um passo adiantada em relação ao chip)
+        if self.noise_rng & 1 != 0 {
+            self.noise_rng ^= 0x800302;
+        }
+        self.noise_rng >>= 1;
+    }
+
+    /// Percussões do modo rítmico (verificadas no YM3812 e no YM2413 real)
+    fn rhythm_calc(&mut self, t: &Tables, noise: bool) -> i32 {
+        let lfo_am = self.lfo_am;
+
+        // Bumbo: igual a um canal melódico
+        let mut out = self.channels[6].calc(t, lfo_am);
+
+        // Fases derivadas do modulador do canal 8 e do portador do canal 9
+        let ch7 = &self.channels[7];
+        let ch8 = &self.channels[8];
+        let p7 = ch7.slots[SLOT1].phase >> FREQ_SH;
+        let p8 = ch8.slots[SLOT2].phase >> FREQ_SH;
+        let res1 = ((p7 >> 2) ^ (p7 >> 7)) & 1 != 0 || (p7 >> 3) & 1 != 0;
+        let res2 = (p8 >> 3) & 1 != 0 || (p8 >> 5) & 1 != 0;
+
+        // Chimbal
+        let hh = &ch7.slots[SLOT1];
+        let env = hh.volume(lfo_am);
+        if env < ENV_QUIET {
+            let mut phase = if res1 || res2 { 0x200 | (0xD0 >> 2) } else { 0xD0 };
+            if noise {
+                phase = if phase & 0x200 != 0 { 0x200 | 0xD0 } else { 0xD0 >> 2 };
+            }
+            out += Slot::op_calc(t, phase << FREQ_SH, env, 0, hh.wavetable);
+        }
+
+        // Caixa
+        let sd = &ch7.slots[SLOT2];
+        let env = sd.volume(lfo_am);
+        if env < ENV_QUIET {
+            let mut phase = if (p7 >> 8) & 1 != 0 { 0x200 } else { 0x100 };
+            if noise {
+                phase ^= 0x100;
+            }
+            out += Slot::op_calc(t, phase << FREQ_SH, env, 0, sd.wavetable);
+        }
+
+        // Tom-tom
+        let tom = &ch8.slots[SLOT1];
+        let env = tom.volume(lfo_am);
+        if env < ENV_QUIET {
+            out += Slot::op_calc(t, tom.phase, env, 0, tom.wavetable);
+        }
+
+        // Prato
+        let tc = &ch8.slots[SLOT2];
+        let env = tc.volume(lfo_am);
+        if env < ENV_QUIET {
+            let phase = if res1 || res2 { 0x300 } else { 0x100 };
+            out += Slot::op_calc(t, phase << FREQ_SH, env, 0, tc.wavetable);
+        }
+
+        out
+    }
+}
+
+impl Default for YM2413 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_reg(chip: &mut YM2413, reg: u8, value: u8) {
+        chip.write(0, 0, reg);
+        chip.write(0, 1, value);
+    }
+
+    fn peak(chip: &mut YM2413, samples: usize) -> i32 {
+        (0..samples).map(|_| chip.update().abs()).max().unwrap()
+    }
+
+    #[test]
+    fn test_rom_instrument_and_output_enable() {
+        let mut chip = YM2413::new();
+        // Canal 1: piano (instrumento 3), volume máximo, bloco 4, key on
+        write_reg(&mut chip, 0x30, 0x30);
+        write_reg(&mut chip, 0x10, 0xAC);
+        write_reg(&mut chip, 0x20, 0x18);
+        // Saída muda até o bit de habilitação ser escrito
+        assert_eq!(peak(&mut chip, 256), 0);
+        assert_eq!(chip.read(), 0xF8);
+        chip.write(0, 2, 0x01);
+        assert_eq!(chip.read(), 0xF9);
+        assert!(peak(&mut chip, 256) > 1000);
+
+        // Key off: o portador entra em release e chega ao silêncio
+        write_reg(&mut chip, 0x20, 0x08);
+        peak(&mut chip, 50_000);
+        assert_eq!(peak(&mut chip, 256), 0);
+    }
+
+    #[test]
+    fn test_rhythm_mode() {
+        let mut chip = YM2413::new();
+        chip.write(0, 2, 0x01);
+        // Bumbo: volume máximo no canal 7, bloco 2
+        write_reg(&mut chip, 0x36, 0x00);
+        write_reg(&mut chip, 0x16, 0x20);
+        write_reg(&mut chip, 0x26, 0x05);
+        write_reg(&mut chip, 0x0E, 0x30);
+        assert!(peak(&mut chip, 1024) > 1000);
+
+        // Sem percussões ligadas e sem key on melódico, nada soa
+        write_reg(&mut chip, 0x0E, 0x20);
+        peak(&mut chip, 50_000);
+        assert_eq!(peak(&mut chip, 256), 0);
+    }
+}
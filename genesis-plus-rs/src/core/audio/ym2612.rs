@@ -0,0 +1,1274 @@
+//! Sintetizador FM Yamaha YM2612 (OPN2).
+//! Baseado em `ym2612.c` do Genesis Plus GX (derivado do `fm.c` do MAME).
+//!
+//! Seis canais de quatro operadores com oito algoritmos, SSG-EG, LFO,
+//! modo especial do canal 3 (frequência por operador e CSM), timers A/B e
+//! DAC no canal 6. Cada chamada a `update` gera uma amostra estéreo
+//! (144 ciclos do 68000, ~53 kHz); a sincronização com o clock mestre e o
+//! flag BUSY ficam em [`FmChip`](super::FmChip).
+//!
+//! As saídas dos operadores portadores são truncadas para os 9 bits do DAC;
+//! o "ladder effect" do YM2612 discreto (Mega Drive modelo 1) é opcional.
+
+use std::sync::OnceLock;
+
+/// Gerador de envelope: atenuação de 10 bits
+const ENV_BITS: u32 = 10;
+const MAX_ATT_INDEX: i32 = (1 << ENV_BITS) - 1;
+const MIN_ATT_INDEX: i32 = 0;
+
+/// Incremento de fase de 17 bits (máscara do detune)
+const DT_MASK: u32 = (1 << 17) - 1;
+
+/// Tabela de seno logarítmica
+const SIN_BITS: u32 = 10;
+const SIN_LEN: usize = 1 << SIN_BITS;
+const SIN_MASK: i32 = SIN_LEN as i32 - 1;
+
+/// Tabela de potência: 13 bits de amplitude, sinal e 256 passos de resolução
+const TL_RES_LEN: usize = 256;
+const TL_TAB_LEN: usize = 13 * 2 * TL_RES_LEN;
+const ENV_QUIET: u32 = (TL_TAB_LEN >> 3) as u32;
+
+/// Passos por taxa do gerador de envelope e seleção das taxas "infinitas"
+const RATE_STEPS: u8 = 8;
+const RATE_INFINITE: u8 = 18 * RATE_STEPS;
+
+/// Incrementos do envelope para cada uma das 8 fases do contador
+const EG_INC: [u8; 19 * RATE_STEPS as usize] = [
+    0, 1, 0, 1, 0, 1, 0, 1, // 0: taxas 00..11 0
+    0, 1, 0, 1, 1, 1, 0, 1, // 1: taxas 00..11 1
+    0, 1, 1, 1, 0, 1, 1, 1, // 2: taxas 00..11 2
+    0, 1, 1, 1, 1, 1, 1, 1, // 3: taxas 00..11 3
+    1, 1, 1, 1, 1, 1, 1, 1, // 4: taxa 12 0
+    1, 1, 1, 2, 1, 1, 1, 2, // 5: taxa 12 1
+    1, 2, 1, 2, 1, 2, 1, 2, // 6: taxa 12 2
+    1, 2, 2, 2, 1, 2, 2, 2, // 7: taxa 12 3
+    2, 2, 2, 2, 2, 2, 2, 2, // 8: taxa 13 0
+    2, 2, 2, 4, 2, 2, 2, 4, // 9: taxa 13 1
+    2, 4, 2, 4, 2, 4, 2, 4, // 10: taxa 13 2
+    2, 4, 4, 4, 2, 4, 4, 4, // 11: taxa 13 3
+    4, 4, 4, 4, 4, 4, 4, 4, // 12: taxa 14 0
+    4, 4, 4, 8, 4, 4, 4, 8, // 13: taxa 14 1
+    4, 8, 4, 8, 4, 8, 4, 8, // 14: taxa 14 2
+    4, 8, 8, 8, 4, 8, 8, 8, // 15: taxa 14 3
+    8, 8, 8, 8, 8, 8, 8, 8, // 16: taxas 15 x
+    16, 16, 16, 16, 16, 16, 16, 16, // 17: taxas 15 2 e 15 3 no ataque
+    0, 0, 0, 0, 0, 0, 0, 0, // 18: taxas infinitas
+];
+
+/// Detune em formato 10.10 para FD=0..3 e os 32 key codes
+const DT_TAB: [u8; 4 * 32] = [
+    // FD=0
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    // FD=1
+    0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2,
+    2, 3, 3, 3, 4, 4, 4, 5, 5, 6, 6, 7, 8, 8, 8, 8,
+    // FD=2
+    1, 1, 1, 1, 2, 2, 2, 2, 2, 3, 3, 3, 4, 4, 4, 5,
+    5, 6, 6, 7, 8, 8, 9, 10, 11, 12, 13, 14, 16, 16, 16, 16,
+    // FD=3
+    2, 2, 2, 2, 2, 3, 3, 3, 4, 4, 4, 5, 5, 6, 6, 7,
+    8, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 20, 22, 22, 22, 22,
+];
+
+/// Bits 7-10 do F-number -> 2 bits baixos do key code
+const OPN_FKTABLE: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 3, 3, 3, 3, 3, 3];
+
+/// Amostras que cada passo do LFO dura, por frequência
+const LFO_SAMPLES_PER_STEP: [u32; 8] = [108, 77, 71, 67, 62, 44, 8, 5];
+
+/// Deslocamento da saída AM do LFO para 0, 1.4, 5.9 e 11.8 dB
+const LFO_AMS_DEPTH_SHIFT: [u8; 4] = [8, 3, 1, 0];
+
+/// Nível de sustain (3 dB por passo, 93 dB no último)
+const SL_TABLE: [i32; 16] = [
+    0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 992,
+];
+
+/// Desvio do F-number pelo PM do LFO: para cada bit 4-10 do F-number,
+/// 8 profundidades e 8 níveis (primeiro quarto da onda)
+const LFO_PM_OUTPUT: [[u8; 8]; 7 * 8] = [
+    // bit 4
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1],
+    // bit 5
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1],
+    [0, 0, 1, 1, 2, 2, 2, 3],
+    // bit 6
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 1],
+    [0, 0, 0, 0, 1, 1, 1, 1],
+    [0, 0, 1, 1, 2, 2, 2, 3],
+    [0, 0, 2, 3, 4, 4, 5, 6],
+    // bit 7
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 1, 1],
+    [0, 0, 0, 0, 1, 1, 1, 1],
+    [0, 0, 0, 1, 1, 1, 1, 2],
+    [0, 0, 1, 1, 2, 2, 2, 3],
+    [0, 0, 2, 3, 4, 4, 5, 6],
+    [0, 0, 4, 6, 8, 8, 0xA, 0xC],
+    // bit 8
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 1, 1, 1, 1],
+    [0, 0, 0, 1, 1, 1, 2, 2],
+    [0, 0, 1, 1, 2, 2, 3, 3],
+    [0, 0, 1, 2, 2, 2, 3, 4],
+    [0, 0, 2, 3, 4, 4, 5, 6],
+    [0, 0, 4, 6, 8, 8, 0xA, 0xC],
+    [0, 0, 8, 0xC, 0x10, 0x10, 0x14, 0x18],
+    // bit 9
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 2, 2, 2, 2],
+    [0, 0, 0, 2, 2, 2, 4, 4],
+    [0, 0, 2, 2, 4, 4, 6, 6],
+    [0, 0, 2, 4, 4, 4, 6, 8],
+    [0, 0, 4, 6, 8, 8, 0xA, 0xC],
+    [0, 0, 8, 0xC, 0x10, 0x10, 0x14, 0x18],
+    [0, 0, 0x10, 0x18, 0x20, 0x20, 0x28, 0x30],
+    // bit 10
+    [0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 4, 4, 4, 4],
+    [0, 0, 0, 4, 4, 4, 8, 8],
+    [0, 0, 4, 4, 8, 8, 0xC, 0xC],
+    [0, 0, 4, 8, 8, 8, 0xC, 0x10],
+    [0, 0, 8, 0xC, 0x10, 0x10, 0x14, 0x18],
+    [0, 0, 0x10, 0x18, 0x20, 0x20, 0x28, 0x30],
+    [0, 0, 0x20, 0x30, 0x40, 0x40, 0x50, 0x60],
+];
+
+/// Índices dos operadores no vetor de slots (ordem dos registradores)
+const SLOT1: usize = 0;
+const SLOT2: usize = 2;
+const SLOT3: usize = 1;
+const SLOT4: usize = 3;
+
+/// Tabelas geradas na primeira instância do chip
+struct Tables {
+    /// Potência: atenuação -> amplitude linear com sinal
+    tl: Vec<i32>,
+    /// Seno em escala logarítmica, no formato da tabela de potência
+    sin: Vec<u32>,
+    /// Desvio do F-number: 128 F-numbers x 8 profundidades x 32 passos
+    lfo_pm: Vec<i32>,
+    /// Detune com sinal para DT=0..7
+    dt: [[i32; 32]; 8],
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(Tables::new)
+}
+
+impl Tables {
+    fn new() -> Self {
+        const ENV_STEP: f64 = 128.0 / (1 << ENV_BITS) as f64;
+
+        let mut tl = vec![0; TL_TAB_LEN];
+        for x in 0..TL_RES_LEN {
+            let m = ((1 << 16) as f64 / 2f64.powf((x + 1) as f64 * (ENV_STEP / 4.0) / 8.0)).floor();
+            // 16 bits -> 12 bits -> 11 bits arredondados -> 13 bits como no chip
+            let mut n = (m as i32) >> 4;
+            n = if n & 1 != 0 { (n >> 1) + 1 } else { n >> 1 };
+            n <<= 2;
+            for i in 0..13 {
+                tl[x * 2 + i * 2 * TL_RES_LEN] = n >> i;
+                tl[x * 2 + 1 + i * 2 * TL_RES_LEN] = -(n >> i);
+            }
+        }
+
+        let mut sin = vec![0; SIN_LEN];
+        for (i, entry) in sin.iter_mut().enumerate() {
+            // Seno não padrão, conferido no chip real
+            let m = (((i * 2) + 1) as f64 * std::f64::consts::PI / SIN_LEN as f64).sin();
+            let o = 8.0 * (1.0 / m.abs()).log2() / (ENV_STEP / 4.0);
+            let mut n = (2.0 * o) as u32;
+            n = if n & 1 != 0 { (n >> 1) + 1 } else { n >> 1 };
+            *entry = n * 2 + (m < 0.0) as u32;
+        }
+
+        let mut lfo_pm = vec![0; 128 * 8 * 32];
+        for depth in 0..8 {
+            for fnum in 0..128 {
+                for step in 0..8 {
+                    let value: i32 = (0..7)
+                        .filter(|bit| fnum & (1 << bit) != 0)
+                        .map(|bit| LFO_PM_OUTPUT[bit * 8 + depth][step] as i32)
+                        .sum();
+                    let base = fnum * 32 * 8 + depth * 32;
+                    lfo_pm[base + step] = value;
+                    lfo_pm[base + (step ^ 7) + 8] = value;
+                    lfo_pm[base + step + 16] = -value;
+                    lfo_pm[base + (step ^ 7) + 24] = -value;
+                }
+            }
+        }
+
+        let mut dt = [[0; 32]; 8];
+        for d in 0..4 {
+            for kc in 0..32 {
+                dt[d][kc] = DT_TAB[d * 32 + kc] as i32;
+                dt[d + 4][kc] = -dt[d][kc];
+            }
+        }
+
+        Self { tl, sin, lfo_pm, dt }
+    }
+}
+
+/// Deslocamento do contador e seleção em `EG_INC` para uma taxa efetiva
+/// (32 taxas infinitas + 64 taxas + 32 de key scale)
+fn eg_rate(rate: u32) -> (u8, u8) {
+    match rate {
+        // Taxas 0 e 1 não avançam (testes de Nemesis no YM2612 real)
+        0..=33 => (11, RATE_INFINITE),
+        34..=79 => (11 - ((rate - 32) / 4) as u8, (rate & 3) as u8 * RATE_STEPS),
+        80..=91 => (0, (4 + rate - 80) as u8 * RATE_STEPS),
+        _ => (0, 16 * RATE_STEPS),
+    }
+}
+
+/// Fase do gerador de envelope (a ordem importa nas comparações)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum EgState {
+    Off,
+    Release,
+    Sustain,
+    Decay,
+    Attack,
+}
+
+/// Um operador: gerador de fase e de envelope
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// Índice da tabela de detune (0-7)
+    dt: usize,
+    /// Deslocamento do key scale (3 - KS)
+    ks_shift: u8,
+    ar: u32,
+    d1r: u32,
+    d2r: u32,
+    rr: u32,
+    /// Key scale efetivo (kcode >> ks_shift)
+    ksr: u8,
+    mul: u32,
+
+    phase: u32,
+    incr: u32,
+
+    state: EgState,
+    tl: u32,
+    volume: i32,
+    sl: i32,
+    /// Saída do envelope sem o AM do LFO
+    vol_out: u32,
+
+    eg_sh_ar: u8,
+    eg_sel_ar: u8,
+    eg_sh_d1r: u8,
+    eg_sel_d1r: u8,
+    eg_sh_d2r: u8,
+    eg_sel_d2r: u8,
+    eg_sh_rr: u8,
+    eg_sel_rr: u8,
+
+    /// Forma do SSG-EG e flag de inversão da saída
+    ssg: u8,
+    ssgn: u8,
+
+    key: bool,
+    am_mask: u32,
+}
+
+impl Slot {
+    fn new() -> Self {
+        Self {
+            dt: 0,
+            ks_shift: 3,
+            ar: 0,
+            d1r: 0,
+            d2r: 0,
+            rr: 0,
+            ksr: 0,
+            mul: 1,
+            phase: 0,
+            incr: 0,
+            state: EgState::Off,
+            tl: 0,
+            volume: MAX_ATT_INDEX,
+            sl: 0,
+            vol_out: MAX_ATT_INDEX as u32,
+            eg_sh_ar: 0,
+            eg_sel_ar: RATE_INFINITE,
+            eg_sh_d1r: 0,
+            eg_sel_d1r: RATE_INFINITE,
+            eg_sh_d2r: 0,
+            eg_sel_d2r: RATE_INFINITE,
+            eg_sh_rr: 0,
+            eg_sel_rr: RATE_INFINITE,
+            ssg: 0,
+            ssgn: 0,
+            key: false,
+            am_mask: 0,
+        }
+    }
+
+    /// Saída do SSG-EG invertida
+    fn inverted(&self) -> bool {
+        self.ssgn ^ (self.ssg & 0x04) != 0
+    }
+
+    /// Recalcula a saída do envelope, com a inversão do SSG-EG
+    fn update_vol_out(&mut self) {
+        self.vol_out = if self.ssg & 0x08 != 0 && self.inverted() {
+            ((0x200 - self.volume) & MAX_ATT_INDEX) as u32 + self.tl
+        } else {
+            self.volume as u32 + self.tl
+        };
+    }
+
+    /// Recalcula as taxas de ataque com o key scale atual
+    fn update_attack_rate(&mut self) {
+        // Taxa de ataque máxima bloqueia o ataque (verificado por Nemesis)
+        (self.eg_sh_ar, self.eg_sel_ar) = if self.ar + (self.ksr as u32) < 32 + 62 {
+            eg_rate(self.ar + self.ksr as u32)
+        } else {
+            (0, RATE_INFINITE)
+        };
+    }
+
+    /// Inicia o envelope como no key on
+    fn start(&mut self) {
+        self.phase = 0;
+        self.ssgn = 0;
+        self.restart_envelope();
+        self.update_vol_out();
+    }
+
+    /// Volta ao ataque, ou direto ao decay quando a taxa de ataque é máxima
+    fn restart_envelope(&mut self) {
+        let decay = if self.sl == MIN_ATT_INDEX { EgState::Sustain } else { EgState::Decay };
+        if self.ar + (self.ksr as u32) < 32 + 62 {
+            self.state = if self.volume <= MIN_ATT_INDEX { decay } else { EgState::Attack };
+        } else {
+            self.volume = MIN_ATT_INDEX;
+            self.state = decay;
+        }
+    }
+
+    /// Passa à fase de release
+    fn release(&mut self) {
+        if self.state <= EgState::Release {
+            return;
+        }
+        self.state = EgState::Release;
+        if self.ssg & 0x08 != 0 {
+            // Converte o nível de atenuação invertido
+            if self.inverted() {
+                self.volume = (0x200 - self.volume) & MAX_ATT_INDEX;
+            }
+            if self.volume >= 0x200 {
+                self.volume = MAX_ATT_INDEX;
+                self.state = EgState::Off;
+            }
+            self.vol_out = self.volume as u32 + self.tl;
+        }
+    }
+
+    fn key_on(&mut self, csm: bool) {
+        if !self.key && !csm {
+            self.start();
+        }
+        self.key = true;
+    }
+
+    fn key_off(&mut self, csm: bool) {
+        if self.key && !csm {
+            self.release();
+        }
+        self.key = false;
+    }
+
+    /// Atualiza incremento de fase e taxas de envelope a partir de F-number e key code
+    fn refresh(&mut self, fc: u32, kc: u8, dt: &[[i32; 32]; 8]) {
+        let fc = (fc as i32 + dt[self.dt][kc as usize]) as u32 & DT_MASK;
+        self.incr = (fc * self.mul) >> 1;
+
+        let ksr = kc >> self.ks_shift;
+        if self.ksr != ksr {
+            self.ksr = ksr;
+            self.update_attack_rate();
+            (self.eg_sh_d1r, self.eg_sel_d1r) = eg_rate(self.d1r + ksr as u32);
+            (self.eg_sh_d2r, self.eg_sel_d2r) = eg_rate(self.d2r + ksr as u32);
+            (self.eg_sh_rr, self.eg_sel_rr) = eg_rate(self.rr + ksr as u32);
+        }
+    }
+
+    /// Avança a fase com o PM do LFO aplicado ao F-number
+    fn advance_phase_lfo(&mut self, offset: i32, block_fnum: u32, kc: u8, dt: &[[i32; 32]; 8]) {
+        if offset == 0 {
+            self.phase = self.phase.wrapping_add(self.incr);
+            return;
+        }
+        let blk = block_fnum >> 11;
+        // O LFO trabalha com um bit a mais de precisão (12 bits)
+        let fn12 = (((block_fnum << 1) as i32 + offset) & 0xFFF) as u32;
+        let fc = (((fn12 << blk) >> 2) as i32 + dt[self.dt][kc as usize]) as u32 & DT_MASK;
+        self.phase = self.phase.wrapping_add((fc * self.mul) >> 1);
+    }
+
+    /// Um passo do gerador de envelope
+    fn advance_eg(&mut self, eg_cnt: u32) {
+        let step = |sh: u8, sel: u8| -> Option<i32> {
+            if eg_cnt & ((1 << sh) - 1) != 0 {
+                None
+            } else {
+                Some(EG_INC[(sel + ((eg_cnt >> sh) & 7) as u8) as usize] as i32)
+            }
+        };
+        match self.state {
+            EgState::Attack => {
+                if let Some(inc) = step(self.eg_sh_ar, self.eg_sel_ar) {
+                    self.volume += (!self.volume * inc) >> 4;
+                    if self.volume <= MIN_ATT_INDEX {
+                        self.volume = MIN_ATT_INDEX;
+                        self.state = if self.sl == MIN_ATT_INDEX { EgState::Sustain } else { EgState::Decay };
+                    }
+                    self.update_vol_out();
+                }
+            }
+            EgState::Decay => {
+                if let Some(inc) = step(self.eg_sh_d1r, self.eg_sel_d1r) {
+                    if self.ssg & 0x08 != 0 {
+                        // SSG-EG: passos 4 vezes maiores
+                        if self.volume < 0x200 {
+                            self.volume += 4 * inc;
+                            self.update_vol_out();
+                        }
+                    } else {
+                        self.volume += inc;
+                        self.update_vol_out();
+                    }
+                    if self.volume >= self.sl {
+                        self.state = EgState::Sustain;
+                    }
+                }
+            }
+            EgState::Sustain => {
+                if let Some(inc) = step(self.eg_sh_d2r, self.eg_sel_d2r) {
+                    if self.ssg & 0x08 != 0 {
+                        if self.volume < 0x200 {
+                            self.volume += 4 * inc;
+                            self.update_vol_out();
+                        }
+                    } else {
+                        // Permanece em sustain (verificado no chip real)
+                        self.volume = (self.volume + inc).min(MAX_ATT_INDEX);
+                        self.update_vol_out();
+                    }
+                }
+            }
+            EgState::Release => {
+                if let Some(inc) = step(self.eg_sh_rr, self.eg_sel_rr) {
+                    if self.ssg & 0x08 != 0 {
+                        if self.volume < 0x200 {
+                            self.volume += 4 * inc;
+                        }
+                        if self.volume >= 0x200 {
+                            self.volume = MAX_ATT_INDEX;
+                            self.state = EgState::Off;
+                        }
+                    } else {
+                        self.volume += inc;
+                        if self.volume >= MAX_ATT_INDEX {
+                            self.volume = MAX_ATT_INDEX;
+                            self.state = EgState::Off;
+                        }
+                    }
+                    self.vol_out = self.volume as u32 + self.tl;
+                }
+            }
+            EgState::Off => {}
+        }
+    }
+
+    /// Transições do SSG-EG, avaliadas antes de cada amostra (testes de Nemesis)
+    fn update_ssg_eg(&mut self) {
+        if self.ssg & 0x08 == 0 || self.volume < 0x200 || self.state <= EgState::Release {
+            return;
+        }
+        if self.ssg & 0x01 != 0 {
+            // Hold
+            if self.ssg & 0x02 != 0 {
+                self.ssgn = 4;
+            }
+            if self.state != EgState::Attack && !self.inverted() {
+                self.volume = MAX_ATT_INDEX;
+            }
+        } else {
+            // Loop: inverte a saída ou reinicia a fase
+            if self.ssg & 0x02 != 0 {
+                self.ssgn ^= 4;
+            } else {
+                self.phase = 0;
+            }
+            if self.state != EgState::Attack {
+                self.restart_envelope();
+            }
+        }
+        self.update_vol_out();
+    }
+}
+
+/// Destinos da saída de um operador dentro do algoritmo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Route {
+    M2,
+    C1,
+    C2,
+    Mem,
+    Out,
+}
+
+/// Ligações de cada algoritmo: saída de M1, de M2 e de C1, e para onde vai
+/// a amostra atrasada (MEM). C2 sempre vai para a saída.
+const CONNECTIONS: [(Option<Route>, Route, Route, Route); 8] = [
+    (Some(Route::C1), Route::C2, Route::Mem, Route::M2),
+    (Some(Route::Mem), Route::C2, Route::Mem, Route::M2),
+    (Some(Route::C2), Route::C2, Route::Mem, Route::M2),
+    (Some(Route::C1), Route::C2, Route::Mem, Route::C2),
+    (Some(Route::C1), Route::C2, Route::Out, Route::Mem),
+    // M1 modula C1, M2 e C2 (marcado como `None`)
+    (None, Route::Out, Route::Out, Route::M2),
+    (Some(Route::C1), Route::Out, Route::Out, Route::Mem),
+    (Some(Route::Out), Route::Out, Route::Out, Route::Mem),
+];
+
+/// Operadores portadores de cada algoritmo (SLOT1 a SLOT4)
+const CARRIERS: [[bool; 4]; 8] = [
+    [false, false, false, true],
+    [false, false, false, true],
+    [false, false, false, true],
+    [false, false, false, true],
+    [false, true, false, true],
+    [false, true, true, true],
+    [false, true, true, true],
+    [true, true, true, true],
+];
+
+/// Quantização do DAC de 9 bits sobre a saída de 14 bits dos portadores
+const DAC_MASK: i32 = !0x1F;
+
+/// Um canal FM de quatro operadores
+#[derive(Debug, Clone, Copy)]
+struct Channel {
+    slots: [Slot; 4],
+    algo: u8,
+    /// Deslocamento do feedback (SIN_BITS = desligado)
+    fb: u32,
+    op1_out: [i32; 2],
+    mem_value: i32,
+    /// Profundidade do PM * 32 (índice na tabela do LFO)
+    pms: u32,
+    /// Deslocamento do AM
+    ams: u8,
+    fc: u32,
+    kcode: u8,
+    /// Bloco e F-number para o PM do LFO
+    block_fnum: u32,
+    /// Incrementos de fase precisam ser recalculados
+    refresh: bool,
+    pan: [bool; 2],
+}
+
+impl Channel {
+    fn new() -> Self {
+        Self {
+            slots: [Slot::new(); 4],
+            algo: 0,
+            fb: SIN_BITS,
+            op1_out: [0; 2],
+            mem_value: 0,
+            pms: 0,
+            ams: LFO_AMS_DEPTH_SHIFT[0],
+            fc: 0,
+            kcode: 0,
+            block_fnum: 0,
+            refresh: true,
+            pan: [false; 2],
+        }
+    }
+
+    /// Saída de um operador: seno logarítmico + atenuação -> amplitude
+    fn op_calc(t: &Tables, phase: u32, env: u32, pm: i32, mask: i32) -> i32 {
+        let index = (((phase >> SIN_BITS) as i32).wrapping_add(pm) & SIN_MASK) as usize;
+        let p = ((env << 3) + t.sin[index]) as usize;
+        if p >= TL_TAB_LEN {
+            0
+        } else {
+            t.tl[p] & mask
+        }
+    }
+
+    /// Calcula uma amostra do canal
+    fn calc(&mut self, t: &Tables, lfo_am: u32) -> i32 {
+        let am = lfo_am >> self.ams;
+        let (m1_route, m2_route, c1_route, mem_route) = CONNECTIONS[self.algo as usize];
+        let volume = |slot: &Slot| slot.vol_out + (am & slot.am_mask);
+        let carriers = CARRIERS[self.algo as usize];
+        let mask = |n: usize| if carriers[n] { DAC_MASK } else { !0 };
+
+        // m2, c1, c2, mem e saída
+        let mut bus = [0i32; 5];
+        bus[mem_route as usize] = self.mem_value;
+
+        let mut out = 0;
+        let eg_out = volume(&self.slots[SLOT1]);
+        if eg_out < ENV_QUIET {
+            let feedback = if self.fb < SIN_BITS {
+                (self.op1_out[0] + self.op1_out[1]) >> self.fb
+            } else {
+                0
+            };
+            out = Self::op_calc(t, self.slots[SLOT1].phase, eg_out, feedback, mask(0));
+        }
+        self.op1_out = [self.op1_out[1], out];
+
+        match m1_route {
+            Some(route) => bus[route as usize] = out,
+            None => {
+                bus[Route::Mem as usize] = out;
+                bus[Route::C1 as usize] = out;
+                bus[Route::C2 as usize] = out;
+            }
+        }
+
+        let eg_out = volume(&self.slots[SLOT3]);
+        if eg_out < ENV_QUIET {
+            let pm = bus[Route::M2 as usize] >> 1;
+            bus[m2_route as usize] += Self::op_calc(t, self.slots[SLOT3].phase, eg_out, pm, mask(2));
+        }
+        let eg_out = volume(&self.slots[SLOT2]);
+        if eg_out < ENV_QUIET {
+            let pm = bus[Route::C1 as usize] >> 1;
+            bus[c1_route as usize] += Self::op_calc(t, self.slots[SLOT2].phase, eg_out, pm, mask(1));
+        }
+        let eg_out = volume(&self.slots[SLOT4]);
+        if eg_out < ENV_QUIET {
+            let pm = bus[Route::C2 as usize] >> 1;
+            bus[Route::Out as usize] += Self::op_calc(t, self.slots[SLOT4].phase, eg_out, pm, mask(3));
+        }
+
+        self.mem_value = bus[Route::Mem as usize];
+        bus[Route::Out as usize]
+    }
+
+    /// Avança a fase dos quatro operadores, com o PM do LFO se ativo
+    fn advance_phase(&mut self, t: &Tables, lfo_pm: u32) {
+        if self.pms == 0 {
+            for slot in self.slots.iter_mut() {
+                slot.phase = slot.phase.wrapping_add(slot.incr);
+            }
+            return;
+        }
+        let offset = t.lfo_pm[(((self.block_fnum & 0x7F0) << 4) + self.pms + lfo_pm) as usize];
+        for slot in self.slots.iter_mut() {
+            slot.advance_phase_lfo(offset, self.block_fnum, self.kcode, &t.dt);
+        }
+    }
+}
+
+/// Estado do modo especial do canal 3
+#[derive(Debug, Clone, Copy, Default)]
+struct Ch3Special {
+    fc: [u32; 3],
+    fn_h: u8,
+    kcode: [u8; 3],
+    block_fnum: [u32; 3],
+    /// Key on do CSM (deslocado a cada amostra)
+    key_csm: u8,
+}
+
+/// Chip YM2612
+pub struct YM2612 {
+    channels: [Channel; 6],
+    ch3: Ch3Special,
+    dac_enabled: bool,
+    dac_out: i32,
+
+    address: u16,
+    status: u8,
+    /// Modo CSM / 3 slots e controle dos timers ($27)
+    mode: u8,
+    fn_h: u8,
+    ta: u32,
+    tal: i32,
+    tac: i32,
+    tb: u32,
+    tbl: i32,
+    tbc: i32,
+
+    eg_cnt: u32,
+    eg_timer: u32,
+    lfo_cnt: u8,
+    lfo_timer: u32,
+    lfo_timer_overflow: u32,
+    lfo_am: u32,
+    lfo_pm: u32,
+
+    /// Distorção do DAC do YM2612 discreto
+    ladder_effect: bool,
+}
+
+impl YM2612 {
+    pub fn new() -> Self {
+        let mut chip = Self {
+            channels: [Channel::new(); 6],
+            ch3: Ch3Special::default(),
+            dac_enabled: false,
+            dac_out: 0,
+            address: 0,
+            status: 0,
+            mode: 0,
+            fn_h: 0,
+            ta: 0,
+            tal: 1024,
+            tac: 0,
+            tb: 0,
+            tbl: 256 << 4,
+            tbc: 0,
+            eg_cnt: 0,
+            eg_timer: 0,
+            lfo_cnt: 0,
+            lfo_timer: 0,
+            lfo_timer_overflow: 0,
+            lfo_am: 126,
+            lfo_pm: 0,
+            ladder_effect: true,
+        };
+        chip.reset();
+        chip
+    }
+
+    /// Liga ou desliga o "ladder effect" do DAC discreto
+    pub fn set_ladder_effect(&mut self, enabled: bool) {
+        self.ladder_effect = enabled;
+    }
+
+    /// Reseta o chip (pino /IC)
+    pub fn reset(&mut self) {
+        self.eg_timer = 0;
+        self.eg_cnt = 0;
+        self.lfo_timer_overflow = 0;
+        self.lfo_timer = 0;
+        self.lfo_cnt = 0;
+        self.lfo_am = 126;
+        self.lfo_pm = 0;
+        self.tac = 0;
+        self.tbc = 0;
+        self.ch3.key_csm = 0;
+        self.dac_enabled = false;
+        self.dac_out = 0;
+
+        self.set_timers(0x30);
+        self.tb = 0;
+        self.tbl = 256 << 4;
+        self.ta = 0;
+        self.tal = 1024;
+
+        for ch in self.channels.iter_mut() {
+            ch.mem_value = 0;
+            ch.op1_out = [0; 2];
+            ch.refresh = true;
+            for slot in ch.slots.iter_mut() {
+                slot.key = false;
+                slot.phase = 0;
+                slot.ssgn = 0;
+                slot.state = EgState::Off;
+                slot.volume = MAX_ATT_INDEX;
+                slot.vol_out = MAX_ATT_INDEX as u32;
+            }
+        }
+
+        for reg in (0xB4..=0xB6).rev() {
+            self.write_reg(reg, 0xC0);
+            self.write_reg(reg | 0x100, 0xC0);
+        }
+        for reg in (0x30..=0xB2).rev() {
+            self.write_reg(reg, 0);
+            self.write_reg(reg | 0x100, 0);
+        }
+    }
+
+    /// Escrita nas portas do chip (A1:A0): endereço e dados das partes 1 e 2
+    pub fn write(&mut self, port: u8, value: u8) {
+        if port & 1 == 0 {
+            self.address = value as u16 | ((port as u16 & 2) << 7);
+            return;
+        }
+
+        let addr = self.address;
+        match addr {
+            // DAC (canal 6)
+            0x2A => self.dac_out = (value as i32 - 0x80) << 6,
+            0x2B => self.dac_enabled = value & 0x80 != 0,
+            0x20..=0x2F => self.write_mode(addr as u8, value),
+            _ => self.write_reg(addr, value),
+        }
+    }
+
+    /// Flags dos timers (o BUSY é tratado por quem sincroniza o chip)
+    pub fn status(&self) -> u8 {
+        self.status
+    }
+
+    /// Registradores $20-$2F
+    fn write_mode(&mut self, reg: u8, value: u8) {
+        match reg {
+            // LFO
+            0x22 => {
+                if value & 0x08 != 0 {
+                    self.lfo_timer_overflow = LFO_SAMPLES_PER_STEP[(value & 7) as usize];
+                } else {
+                    // Mantém o LFO em reset
+                    self.lfo_timer_overflow = 0;
+                    self.lfo_timer = 0;
+                    self.lfo_cnt = 0;
+                    self.lfo_pm = 0;
+                    self.lfo_am = 126;
+                }
+            }
+            0x24 => {
+                self.ta = (self.ta & 0x03) | ((value as u32) << 2);
+                self.tal = 1024 - self.ta as i32;
+            }
+            0x25 => {
+                self.ta = (self.ta & 0x3FC) | (value as u32 & 3);
+                self.tal = 1024 - self.ta as i32;
+            }
+            0x26 => {
+                self.tb = value as u32;
+                self.tbl = (256 - value as i32) << 4;
+            }
+            0x27 => self.set_timers(value),
+            // Key on/off
+            0x28 => {
+                let mut c = (value & 3) as usize;
+                if c == 3 {
+                    return;
+                }
+                if value & 0x04 != 0 {
+                    c += 3;
+                }
+                let csm = self.ch3.key_csm != 0;
+                let ch = &mut self.channels[c];
+                for (bit, slot) in [(0x10, SLOT1), (0x20, SLOT2), (0x40, SLOT3), (0x80, SLOT4)] {
+                    if value & bit != 0 {
+                        ch.slots[slot].key_on(csm);
+                    } else {
+                        ch.slots[slot].key_off(csm);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Registrador $27: modo do canal 3 e controle dos timers
+    fn set_timers(&mut self, value: u8) {
+        if (self.mode ^ value) & 0xC0 != 0 {
+            self.channels[2].refresh = true;
+            // Saída do modo CSM com key on ativo: key off (verificado por Nemesis)
+            if value & 0xC0 != 0x80 && self.ch3.key_csm != 0 {
+                self.csm_key_off();
+            }
+        }
+
+        if value & 1 != 0 && self.mode & 1 == 0 {
+            self.tac = self.tal;
+        }
+        if value & 2 != 0 && self.mode & 2 == 0 {
+            self.tbc = self.tbl;
+        }
+        // Bits 4-5 limpam os flags dos timers
+        self.status &= !(value >> 4);
+        self.mode = value;
+    }
+
+    /// Key on automático do canal 3 no estouro do timer A em modo CSM
+    fn csm_key_on(&mut self) {
+        let csm = self.ch3.key_csm != 0;
+        for slot in self.channels[2].slots.iter_mut() {
+            if !slot.key && !csm {
+                slot.start();
+            }
+        }
+        self.ch3.key_csm = 1;
+    }
+
+    fn csm_key_off(&mut self) {
+        for slot in self.channels[2].slots.iter_mut() {
+            if !slot.key {
+                slot.release();
+            }
+        }
+        self.ch3.key_csm = 0;
+    }
+
+    /// Registradores $30-$B6 das duas partes
+    fn write_reg(&mut self, reg: u16, value: u8) {
+        let mut c = (reg & 3) as usize;
+        if c == 3 {
+            return;
+        }
+        if reg >= 0x100 {
+            c += 3;
+        }
+        let fn_h = self.fn_h;
+        let ch = &mut self.channels[c];
+        let slot = &mut ch.slots[((reg >> 2) & 3) as usize];
+
+        match reg & 0xF0 {
+            // DT, MUL
+            0x30 => {
+                slot.mul = if value & 0x0F != 0 { (value as u32 & 0x0F) * 2 } else { 1 };
+                slot.dt = ((value >> 4) & 7) as usize;
+                ch.refresh = true;
+            }
+            // TL
+            0x40 => {
+                slot.tl = (value as u32 & 0x7F) << (ENV_BITS - 7);
+                if slot.state > EgState::Release {
+                    slot.update_vol_out();
+                } else {
+                    slot.vol_out = slot.volume as u32 + slot.tl;
+                }
+            }
+            // KS, AR
+            0x50 => {
+                let ks_shift = 3 - (value >> 6);
+                slot.ar = if value & 0x1F != 0 { 32 + ((value as u32 & 0x1F) << 1) } else { 0 };
+                if slot.ks_shift != ks_shift {
+                    slot.ks_shift = ks_shift;
+                    ch.refresh = true;
+                }
+                // Mesmo sem mudar o key scale efetivo a taxa de ataque muda
+                // (introdução de The Adventures of Batman & Robin)
+                slot.update_attack_rate();
+            }
+            // AM, D1R
+            0x60 => {
+                slot.d1r = if value & 0x1F != 0 { 32 + ((value as u32 & 0x1F) << 1) } else { 0 };
+                (slot.eg_sh_d1r, slot.eg_sel_d1r) = eg_rate(slot.d1r + slot.ksr as u32);
+                slot.am_mask = if value & 0x80 != 0 { !0 } else { 0 };
+            }
+            // D2R
+            0x70 => {
+                slot.d2r = if value & 0x1F != 0 { 32 + ((value as u32 & 0x1F) << 1) } else { 0 };
+                (slot.eg_sh_d2r, slot.eg_sel_d2r) = eg_rate(slot.d2r + slot.ksr as u32);
+            }
+            // SL, RR
+            0x80 => {
+                slot.sl = SL_TABLE[(value >> 4) as usize];
+                if slot.state == EgState::Decay && slot.volume >= slot.sl {
+                    slot.state = EgState::Sustain;
+                }
+                slot.rr = 34 + ((value as u32 & 0x0F) << 2);
+                (slot.eg_sh_rr, slot.eg_sel_rr) = eg_rate(slot.rr + slot.ksr as u32);
+            }
+            // SSG-EG
+            0x90 => {
+                slot.ssg = value & 0x0F;
+                if slot.state > EgState::Release {
+                    slot.update_vol_out();
+                }
+            }
+            0xA0 => match (reg >> 2) & 3 {
+                // F-number baixo: usa o latch do bloco/F-number alto
+                0 => {
+                    let fnum = ((fn_h as u32 & 7) << 8) | value as u32;
+                    let blk = (fn_h >> 3) as u32;
+                    ch.kcode = ((blk << 2) as u8) | OPN_FKTABLE[(fnum >> 7) as usize];
+                    ch.fc = (fnum << blk) >> 1;
+                    ch.block_fnum = (blk << 11) | fnum;
+                    ch.refresh = true;
+                }
+                1 => self.fn_h = value & 0x3F,
+                // F-number por operador do canal 3
+                2 => {
+                    if reg < 0x100 {
+                        let fnum = ((self.ch3.fn_h as u32 & 7) << 8) | value as u32;
+                        let blk = (self.ch3.fn_h >> 3) as u32;
+                        self.ch3.kcode[c] = ((blk << 2) as u8) | OPN_FKTABLE[(fnum >> 7) as usize];
+                        self.ch3.fc[c] = (fnum << blk) >> 1;
+                        self.ch3.block_fnum[c] = (blk << 11) | fnum;
+                        self.channels[2].refresh = true;
+                    }
+                }
+                _ => {
+                    if reg < 0x100 {
+                        self.ch3.fn_h = value & 0x3F;
+                    }
+                }
+            },
+            0xB0 => match (reg >> 2) & 3 {
+                // Feedback, algoritmo
+                0 => {
+                    ch.algo = value & 7;
+                    ch.fb = SIN_BITS - ((value as u32 >> 3) & 7);
+                }
+                // L, R, AMS, PMS
+                1 => {
+                    ch.pms = (value as u32 & 7) * 32;
+                    ch.ams = LFO_AMS_DEPTH_SHIFT[((value >> 4) & 3) as usize];
+                    ch.pan = [value & 0x80 != 0, value & 0x40 != 0];
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Recalcula incrementos de fase pendentes
+    fn refresh_channels(&mut self) {
+        let dt = &tables().dt;
+        for (c, ch) in self.channels.iter_mut().enumerate() {
+            if !ch.refresh {
+                continue;
+            }
+            ch.refresh = false;
+            if c == 2 && self.mode & 0xC0 != 0 {
+                // Modo de 3 slots: ordem dos operadores é 1, 3, 2, canal
+                let ch3 = &self.ch3;
+                ch.slots[SLOT1].refresh(ch3.fc[1], ch3.kcode[1], dt);
+                ch.slots[SLOT2].refresh(ch3.fc[2], ch3.kcode[2], dt);
+                ch.slots[SLOT3].refresh(ch3.fc[0], ch3.kcode[0], dt);
+                ch.slots[SLOT4].refresh(ch.fc, ch.kcode, dt);
+            } else {
+                for slot in ch.slots.iter_mut() {
+                    slot.refresh(ch.fc, ch.kcode, dt);
+                }
+            }
+        }
+    }
+
+    /// Gera uma amostra estéreo e avança LFO, envelopes e timers
+    pub fn update(&mut self) -> (i32, i32) {
+        let t = tables();
+        self.refresh_channels();
+
+        for ch in self.channels.iter_mut() {
+            for slot in ch.slots.iter_mut() {
+                slot.update_ssg_eg();
+            }
+        }
+
+        let mut out = [0i32; 6];
+        let fm_channels = if self.dac_enabled { 5 } else { 6 };
+        for (c, ch) in self.channels.iter_mut().enumerate().take(fm_channels) {
+            out[c] = ch.calc(t, self.lfo_am);
+
+            // Fases avançam depois do cálculo da saída
+            if c == 2 && ch.pms != 0 && self.mode & 0xC0 != 0 {
+                let pm = ch.pms + self.lfo_pm;
+                let kc = ch.kcode;
+                let fnums = [self.ch3.block_fnum[1], self.ch3.block_fnum[2], self.ch3.block_fnum[0], ch.block_fnum];
+                for (slot, fnum) in [SLOT1, SLOT2, SLOT3, SLOT4].into_iter().zip(fnums) {
+                    let offset = t.lfo_pm[(((fnum & 0x7F0) << 4) + pm) as usize];
+                    ch.slots[slot].advance_phase_lfo(offset, fnum, kc, &t.dt);
+                }
+            } else {
+                ch.advance_phase(t, self.lfo_pm);
+            }
+        }
+        if self.dac_enabled {
+            out[5] = self.dac_out;
+        }
+
+        self.advance_lfo();
+
+        // O envelope avança a cada 3 amostras; o contador de 12 bits pula o zero
+        self.eg_timer += 1;
+        if self.eg_timer >= 3 {
+            self.eg_timer = 0;
+            self.eg_cnt += 1;
+            if self.eg_cnt == 4096 {
+                self.eg_cnt = 1;
+            }
+            for ch in self.channels.iter_mut() {
+                for slot in ch.slots.iter_mut() {
+                    slot.advance_eg(self.eg_cnt);
+                }
+            }
+        }
+
+        // Acumulador de cada canal limitado a 14 bits
+        let (mut left, mut right) = (0, 0);
+        for (ch, &sample) in self.channels.iter().zip(out.iter()) {
+            let sample = sample.clamp(-8192, 8191);
+            if ch.pan[0] {
+                left += sample;
+            }
+            if ch.pan[1] {
+                right += sample;
+            }
+            if self.ladder_effect {
+                // Degrau de -4 (-3 se o lado não estiver mudo) nas saídas
+                // negativas e de +4 nas positivas, em unidades de 9 bits
+                if sample < 0 {
+                    left -= (4 - ch.pan[0] as i32) << 5;
+                    right -= (4 - ch.pan[1] as i32) << 5;
+                } else {
+                    left += 4 << 5;
+                    right += 4 << 5;
+                }
+            }
+        }
+
+        // Key off do CSM se o timer A não estourar de novo nesta amostra
+        self.ch3.key_csm <<= 1;
+        self.timer_a();
+        if self.ch3.key_csm & 2 != 0 {
+            self.csm_key_off();
+        }
+        self.timer_b();
+
+        (left, right)
+    }
+
+    fn advance_lfo(&mut self) {
+        if self.lfo_timer_overflow == 0 {
+            return;
+        }
+        self.lfo_timer += 1;
+        if self.lfo_timer >= self.lfo_timer_overflow {
+            self.lfo_timer = 0;
+            self.lfo_cnt = (self.lfo_cnt + 1) & 127;
+            // AM: triângulo invertido de 126 a 0 e de volta
+            self.lfo_am = if self.lfo_cnt < 64 {
+                ((self.lfo_cnt ^ 63) as u32) << 1
+            } else {
+                ((self.lfo_cnt & 63) as u32) << 1
+            };
+            // PM avança 4 vezes mais devagar
+            self.lfo_pm = (self.lfo_cnt >> 2) as u32;
+        }
+    }
+
+    /// Timer A: conta amostras
+    fn timer_a(&mut self) {
+        if self.mode & 0x01 == 0 {
+            return;
+        }
+        self.tac -= 1;
+        if self.tac <= 0 {
+            if self.mode & 0x04 != 0 {
+                self.status |= 0x01;
+            }
+            self.tac = self.tal;
+            if self.mode & 0xC0 == 0x80 {
+                self.csm_key_on();
+            }
+        }
+    }
+
+    /// Timer B: conta 16 amostras por unidade
+    fn timer_b(&mut self) {
+        if self.mode & 0x02 == 0 {
+            return;
+        }
+        self.tbc -= 1;
+        if self.tbc <= 0 {
+            if self.mode & 0x08 != 0 {
+                self.status |= 0x02;
+            }
+            self.tbc += self.tbl;
+        }
+    }
+}
+
+impl Default for YM2612 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_reg(chip: &mut YM2612, reg: u16, value: u8) {
+        let part = ((reg >> 7) & 2) as u8;
+        chip.write(part, reg as u8);
+        chip.write(part | 1, value);
+    }
+
+    #[test]
+    fn test_timers() {
+        let mut chip = YM2612::new();
+
+        // Timer A = 1023: estoura a cada amostra
+        write_reg(&mut chip, 0x24, 0xFF);
+        write_reg(&mut chip, 0x25, 0x03);
+        write_reg(&mut chip, 0x27, 0x05);
+        assert_eq!(chip.status(), 0x00);
+        chip.update();
+        assert_eq!(chip.status(), 0x01);
+
+        // Timer B = 255: 16 amostras
+        write_reg(&mut chip, 0x26, 0xFF);
+        write_reg(&mut chip, 0x27, 0x3A);
+        for _ in 0..15 {
+            chip.update();
+        }
+        assert_eq!(chip.status(), 0x00);
+        chip.update();
+        assert_eq!(chip.status(), 0x02);
+    }
+
+    #[test]
+    fn test_dac_and_fm_output() {
+        let mut chip = YM2612::new();
+        chip.set_ladder_effect(false);
+        write_reg(&mut chip, 0x2B, 0x80);
+        write_reg(&mut chip, 0x2A, 0xFF);
+        assert_eq!(chip.update(), (0x7F << 6, 0x7F << 6));
+
+        // Ladder effect: +4 (9 bits) em cada um dos 5 canais FM parados e no DAC
+        chip.set_ladder_effect(true);
+        assert_eq!(chip.update(), ((0x7F << 6) + 6 * (4 << 5), (0x7F << 6) + 6 * (4 << 5)));
+
+        // Algoritmo 7 com um único operador audível no canal 1
+        chip.set_ladder_effect(false);
+        write_reg(&mut chip, 0x2B, 0x00);
+        write_reg(&mut chip, 0xB0, 0x07);
+        write_reg(&mut chip, 0x30, 0x01);
+        write_reg(&mut chip, 0x50, 0x1F);
+        write_reg(&mut chip, 0x80, 0x0F);
+        write_reg(&mut chip, 0xA4, 0x22);
+        write_reg(&mut chip, 0xA0, 0x69);
+        write_reg(&mut chip, 0x28, 0x10);
+        let samples: Vec<_> = (0..200).map(|_| chip.update()).collect();
+        assert!(samples.iter().all(|&(l, _)| l & 0x1F == 0), "saída fora da grade de 9 bits");
+        let peak = samples.iter().map(|&(l, _)| l.abs()).max().unwrap();
+        assert!(peak > 4000, "pico {}", peak);
+    }
+}
@@ -0,0 +1,1229 @@
+//! Emulação ciclo a ciclo do YM3438 (OPN2C) e do YM2612.
+//! Baseado em `ym3438.c` do Genesis Plus GX (Nuked OPN2, de Alexey Khokholov,
+//! a partir do die shot do chip).
+//!
+//! O chip processa um operador por ciclo interno (clock do 68000 / 6); uma
+//! amostra completa leva 24 ciclos. Escritas são apenas travadas pela porta
+//! e aplicadas pelo pipeline nos ciclos seguintes, como no hardware.
+
+/// Estados do gerador de envelope
+const EG_ATTACK: u8 = 0;
+const EG_DECAY: u8 = 1;
+const EG_SUSTAIN: u8 = 2;
+const EG_RELEASE: u8 = 3;
+
+/// Tabela log-seno (um quarto de onda)
+const LOGSIN_ROM: [u16; 256] = [
+    0x859, 0x6c3, 0x607, 0x58b, 0x52e, 0x4e4, 0x4a6, 0x471, 0x443, 0x41a, 0x3f5, 0x3d3, 0x3b5, 0x398, 0x37e, 0x365,
+    0x34e, 0x339, 0x324, 0x311, 0x2ff, 0x2ed, 0x2dc, 0x2cd, 0x2bd, 0x2af, 0x2a0, 0x293, 0x286, 0x279, 0x26d, 0x261,
+    0x256, 0x24b, 0x240, 0x236, 0x22c, 0x222, 0x218, 0x20f, 0x206, 0x1fd, 0x1f5, 0x1ec, 0x1e4, 0x1dc, 0x1d4, 0x1cd,
+    0x1c5, 0x1be, 0x1b7, 0x1b0, 0x1a9, 0x1a2, 0x19b, 0x195, 0x18f, 0x188, 0x182, 0x17c, 0x177, 0x171, 0x16b, 0x166,
+    0x160, 0x15b, 0x155, 0x150, 0x14b, 0x146, 0x141, 0x13c, 0x137, 0x133, 0x12e, 0x129, 0x125, 0x121, 0x11c, 0x118,
+    0x114, 0x10f, 0x10b, 0x107, 0x103, 0x0ff, 0x0fb, 0x0f8, 0x0f4, 0x0f0, 0x0ec, 0x0e9, 0x0e5, 0x0e2, 0x0de, 0x0db,
+    0x0d7, 0x0d4, 0x0d1, 0x0cd, 0x0ca, 0x0c7, 0x0c4, 0x0c1, 0x0be, 0x0bb, 0x0b8, 0x0b5, 0x0b2, 0x0af, 0x0ac, 0x0a9,
+    0x0a7, 0x0a4, 0x0a1, 0x09f, 0x09c, 0x099, 0x097, 0x094, 0x092, 0x08f, 0x08d, 0x08a, 0x088, 0x086, 0x083, 0x081,
+    0x07f, 0x07d, 0x07a, 0x078, 0x076, 0x074, 0x072, 0x070, 0x06e, 0x06c, 0x06a, 0x068, 0x066, 0x064, 0x062, 0x060,
+    0x05e, 0x05c, 0x05b, 0x059, 0x057, 0x055, 0x053, 0x052, 0x050, 0x04e, 0x04d, 0x04b, 0x04a, 0x048, 0x046, 0x045,
+    0x043, 0x042, 0x040, 0x03f, 0x03e, 0x03c, 0x03b, 0x039, 0x038, 0x037, 0x035, 0x034, 0x033, 0x031, 0x030, 0x02f,
+    0x02e, 0x02d, 0x02b, 0x02a, 0x029, 0x028, 0x027, 0x026, 0x025, 0x024, 0x023, 0x022, 0x021, 0x020, 0x01f, 0x01e,
+    0x01d, 0x01c, 0x01b, 0x01a, 0x019, 0x018, 0x017, 0x017, 0x016, 0x015, 0x014, 0x014, 0x013, 0x012, 0x011, 0x011,
+    0x010, 0x00f, 0x00f, 0x00e, 0x00d, 0x00d, 0x00c, 0x00c, 0x00b, 0x00a, 0x00a, 0x009, 0x009, 0x008, 0x008, 0x007,
+    0x007, 0x007, 0x006, 0x006, 0x005, 0x005, 0x005, 0x004, 0x004, 0x004, 0x003, 0x003, 0x003, 0x002, 0x002, 0x002,
+    0x002, 0x001, 0x001, 0x001, 0x001, 0x001, 0x001, 0x001, 0x000, 0x000, 0x000, 0x000, 0x000, 0x000, 0x000, 0x000,
+];
+
+/// Tabela exponencial
+const EXP_ROM: [u16; 256] = [
+    0x000, 0x003, 0x006, 0x008, 0x00b, 0x00e, 0x011, 0x014, 0x016, 0x019, 0x01c, 0x01f, 0x022, 0x025, 0x028, 0x02a,
+    0x02d, 0x030, 0x033, 0x036, 0x039, 0x03c, 0x03f, 0x042, 0x045, 0x048, 0x04b, 0x04e, 0x051, 0x054, 0x057, 0x05a,
+    0x05d, 0x060, 0x063, 0x066, 0x069, 0x06c, 0x06f, 0x072, 0x075, 0x078, 0x07b, 0x07e, 0x082, 0x085, 0x088, 0x08b,
+    0x08e, 0x091, 0x094, 0x098, 0x09b, 0x09e, 0x0a1, 0x0a4, 0x0a8, 0x0ab, 0x0ae, 0x0b1, 0x0b5, 0x0b8, 0x0bb, 0x0be,
+    0x0c2, 0x0c5, 0x0c8, 0x0cc, 0x0cf, 0x0d2, 0x0d6, 0x0d9, 0x0dc, 0x0e0, 0x0e3, 0x0e7, 0x0ea, 0x0ed, 0x0f1, 0x0f4,
+    0x0f8, 0x0fb, 0x0ff, 0x102, 0x106, 0x109, 0x10c, 0x110, 0x114, 0x117, 0x11b, 0x11e, 0x122, 0x125, 0x129, 0x12c,
+    0x130, 0x134, 0x137, 0x13b, 0x13e, 0x142, 0x146, 0x149, 0x14d, 0x151, 0x154, 0x158, 0x15c, 0x160, 0x163, 0x167,
+    0x16b, 0x16f, 0x172, 0x176, 0x17a, 0x17e, 0x181, 0x185, 0x189, 0x18d, 0x191, 0x195, 0x199, 0x19c, 0x1a0, 0x1a4,
+    0x1a8, 0x1ac, 0x1b0, 0x1b4, 0x1b8, 0x1bc, 0x1c0, 0x1c4, 0x1c8, 0x1cc, 0x1d0, 0x1d4, 0x1d8, 0x1dc, 0x1e0, 0x1e4,
+    0x1e8, 0x1ec, 0x1f0, 0x1f5, 0x1f9, 0x1fd, 0x201, 0x205, 0x209, 0x20e, 0x212, 0x216, 0x21a, 0x21e, 0x223, 0x227,
+    0x22b, 0x230, 0x234, 0x238, 0x23c, 0x241, 0x245, 0x249, 0x24e, 0x252, 0x257, 0x25b, 0x25f, 0x264, 0x268, 0x26d,
+    0x271, 0x276, 0x27a, 0x27f, 0x283, 0x288, 0x28c, 0x291, 0x295, 0x29a, 0x29e, 0x2a3, 0x2a8, 0x2ac, 0x2b1, 0x2b5,
+    0x2ba, 0x2bf, 0x2c4, 0x2c8, 0x2cd, 0x2d2, 0x2d6, 0x2db, 0x2e0, 0x2e5, 0x2e9, 0x2ee, 0x2f3, 0x2f8, 0x2fd, 0x302,
+    0x306, 0x30b, 0x310, 0x315, 0x31a, 0x31f, 0x324, 0x329, 0x32e, 0x333, 0x338, 0x33d, 0x342, 0x347, 0x34c, 0x351,
+    0x356, 0x35b, 0x360, 0x365, 0x36a, 0x370, 0x375, 0x37a, 0x37f, 0x384, 0x38a, 0x38f, 0x394, 0x399, 0x39f, 0x3a4,
+    0x3a9, 0x3ae, 0x3b4, 0x3b9, 0x3bf, 0x3c4, 0x3c9, 0x3cf, 0x3d4, 0x3da, 0x3df, 0x3e4, 0x3ea, 0x3ef, 0x3f5, 0x3fa,
+];
+
+/// Bits 7-10 do F-number -> 2 bits baixos do key code
+const FN_NOTE: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 3, 3, 3, 3, 3, 3];
+
+/// Passos extras do envelope nas taxas altas
+const EG_STEPHI: [[u8; 4]; 4] = [[0, 0, 0, 0], [1, 0, 0, 0], [1, 0, 1, 0], [1, 1, 1, 0]];
+
+/// Deslocamento do AM do LFO por AMS
+const EG_AM_SHIFT: [u8; 4] = [7, 3, 1, 0];
+
+const PG_DETUNE: [u32; 8] = [16, 17, 19, 20, 22, 24, 27, 29];
+
+/// Deslocamentos do PM do LFO por PMS e passo
+const PG_LFO_SH1: [[u32; 8]; 8] = [
+    [7, 7, 7, 7, 7, 7, 7, 7],
+    [7, 7, 7, 7, 7, 7, 7, 7],
+    [7, 7, 7, 7, 7, 7, 1, 1],
+    [7, 7, 7, 7, 1, 1, 1, 1],
+    [7, 7, 7, 1, 1, 1, 1, 0],
+    [7, 7, 1, 1, 0, 0, 0, 0],
+    [7, 7, 1, 1, 0, 0, 0, 0],
+    [7, 7, 1, 1, 0, 0, 0, 0],
+];
+const PG_LFO_SH2: [[u32; 8]; 8] = [
+    [7, 7, 7, 7, 7, 7, 7, 7],
+    [7, 7, 7, 7, 2, 2, 2, 2],
+    [7, 7, 7, 2, 2, 2, 7, 7],
+    [7, 7, 2, 2, 7, 7, 2, 2],
+    [7, 7, 2, 7, 7, 7, 2, 7],
+    [7, 7, 7, 2, 7, 7, 2, 1],
+    [7, 7, 7, 2, 7, 7, 2, 1],
+    [7, 7, 7, 2, 7, 7, 2, 1],
+];
+
+/// Decodificador de endereço: operadores e canais de cada slot
+const OP_OFFSET: [u16; 12] = [
+    0x000, 0x001, 0x002, 0x100, 0x101, 0x102, 0x004, 0x005, 0x006, 0x104, 0x105, 0x106,
+];
+const CH_OFFSET: [u16; 6] = [0x000, 0x001, 0x002, 0x100, 0x101, 0x102];
+
+/// Duração de cada passo do LFO
+const LFO_CYCLES: [u8; 8] = [108, 77, 71, 67, 62, 44, 8, 5];
+
+/// Ligações dos algoritmos por operador: entradas OP1 (atual e anterior),
+/// OP2, último operador (duas entradas) e saída
+const FM_ALGORITHM: [[[bool; 8]; 6]; 4] = {
+    const O: bool = false;
+    const I: bool = true;
+    [
+        [
+            [I, I, I, I, I, I, I, I],
+            [I, I, I, I, I, I, I, I],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, O, O, I],
+        ],
+        [
+            [O, I, O, O, O, I, O, O],
+            [O, O, O, O, O, O, O, O],
+            [I, I, I, O, O, O, O, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, I, I, I],
+        ],
+        [
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, O, O, O, O],
+            [I, O, O, I, I, I, I, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, O, I, I, I, I],
+        ],
+        [
+            [O, O, I, O, O, I, O, O],
+            [O, O, O, O, O, O, O, O],
+            [O, O, O, I, O, O, O, O],
+            [I, I, O, I, I, O, O, O],
+            [O, O, I, O, O, O, O, O],
+            [I, I, I, I, I, I, I, I],
+        ],
+    ]
+};
+
+/// Estende o sinal do bit `bit` de `value`
+fn sign_extend(bit: u32, value: i32) -> i16 {
+    ((value & ((1 << bit) - 1)) - (value & (1 << bit))) as i16
+}
+
+/// Estado completo do chip
+#[derive(Clone)]
+pub struct YM3438 {
+    /// Emula o DAC e a leitura de status do YM2612 discreto
+    ym2612_mode: bool,
+
+    cycles: u32,
+    channel: u32,
+    mol: i16,
+    mor: i16,
+    // E/S
+    write_data: u16,
+    write_a: u8,
+    write_d: u8,
+    write_a_en: bool,
+    write_d_en: bool,
+    write_busy: bool,
+    write_busy_cnt: u8,
+    write_fm_address: bool,
+    write_fm_data: bool,
+    write_fm_mode_a: u16,
+    address: u16,
+    data: u8,
+    busy: bool,
+    // LFO
+    lfo_en: u8,
+    lfo_freq: u8,
+    lfo_pm: u8,
+    lfo_am: u8,
+    lfo_cnt: u8,
+    lfo_inc: u8,
+    lfo_quotient: u8,
+    // Gerador de fase
+    pg_fnum: u16,
+    pg_block: u8,
+    pg_kcode: u8,
+    pg_inc: [u32; 24],
+    pg_phase: [u32; 24],
+    pg_reset: [bool; 24],
+    pg_read: u32,
+    // Gerador de envelope
+    eg_cycle: u8,
+    eg_cycle_stop: bool,
+    eg_shift: u8,
+    eg_shift_lock: u8,
+    eg_timer_low_lock: u8,
+    eg_timer: u16,
+    eg_timer_inc: u16,
+    eg_quotient: u16,
+    eg_rate: u8,
+    eg_ksv: u8,
+    eg_inc: u8,
+    eg_ratemax: bool,
+    eg_sl: [u8; 2],
+    eg_lfo_am: u8,
+    eg_tl: [u8; 2],
+    eg_state: [u8; 24],
+    eg_level: [u16; 24],
+    eg_out: [u16; 24],
+    eg_kon: [bool; 24],
+    eg_kon_csm: [bool; 24],
+    eg_kon_latch: [bool; 24],
+    eg_ssg_enable: [bool; 24],
+    eg_ssg_pgrst_latch: [bool; 24],
+    eg_ssg_repeat_latch: [bool; 24],
+    eg_ssg_hold_up_latch: [bool; 24],
+    eg_ssg_dir: [bool; 24],
+    eg_ssg_inv: [bool; 24],
+    eg_read: [u32; 2],
+    eg_read_inc: u32,
+    // FM
+    fm_op1: [[i16; 2]; 6],
+    fm_op2: [i16; 6],
+    fm_out: [i16; 24],
+    fm_mod: [u16; 24],
+    // Canais
+    ch_acc: [i16; 6],
+    ch_out: [i16; 6],
+    ch_lock: i16,
+    ch_lock_l: bool,
+    ch_lock_r: bool,
+    ch_read: i16,
+    // Timers
+    timer_a_cnt: u16,
+    timer_a_reg: u16,
+    timer_a_load_lock: bool,
+    timer_a_load: bool,
+    timer_a_enable: bool,
+    timer_a_reset: bool,
+    timer_a_load_latch: bool,
+    timer_a_overflow_flag: bool,
+    timer_a_overflow: bool,
+
+    timer_b_cnt: u16,
+    timer_b_subcnt: u8,
+    timer_b_reg: u16,
+    timer_b_load_lock: bool,
+    timer_b_load: bool,
+    timer_b_enable: bool,
+    timer_b_reset: bool,
+    timer_b_load_latch: bool,
+    timer_b_overflow_flag: bool,
+    timer_b_overflow: bool,
+
+    // Registradores
+    mode_test_21: [bool; 8],
+    mode_test_2c: [bool; 8],
+    mode_ch3: u8,
+    mode_kon_channel: u8,
+    mode_kon_operator: [bool; 4],
+    mode_kon: [bool; 24],
+    mode_csm: bool,
+    mode_kon_csm: bool,
+    dacen: bool,
+    dacdata: i16,
+
+    ks: [u8; 24],
+    ar: [u8; 24],
+    sr: [u8; 24],
+    dt: [u8; 24],
+    multi: [u8; 24],
+    sl: [u8; 24],
+    rr: [u8; 24],
+    dr: [u8; 24],
+    am: [bool; 24],
+    tl: [u8; 24],
+    ssg_eg: [u8; 24],
+
+    fnum: [u16; 6],
+    block: [u8; 6],
+    kcode: [u8; 6],
+    fnum_3ch: [u16; 6],
+    block_3ch: [u8; 6],
+    kcode_3ch: [u8; 6],
+    reg_a4: u8,
+    reg_ac: u8,
+    connect: [u8; 6],
+    fb: [u8; 6],
+    pan_l: [bool; 6],
+    pan_r: [bool; 6],
+    ams: [u8; 6],
+    pms: [u8; 6],
+    status: u8,
+    status_time: u32,
+}
+
+impl YM3438 {
+    /// Chip resetado; `ym2612_mode` seleciona o YM2612 discreto em vez do YM3438
+    pub fn new(ym2612_mode: bool) -> Self {
+        Self {
+            ym2612_mode,
+            cycles: 0,
+            channel: 0,
+            mol: 0,
+            mor: 0,
+            write_data: 0,
+            write_a: 0,
+            write_d: 0,
+            write_a_en: false,
+            write_d_en: false,
+            write_busy: false,
+            write_busy_cnt: 0,
+            write_fm_address: false,
+            write_fm_data: false,
+            write_fm_mode_a: 0,
+            address: 0,
+            data: 0,
+            busy: false,
+            lfo_en: 0,
+            lfo_freq: 0,
+            lfo_pm: 0,
+            lfo_am: 0,
+            lfo_cnt: 0,
+            lfo_inc: 0,
+            lfo_quotient: 0,
+            pg_fnum: 0,
+            pg_block: 0,
+            pg_kcode: 0,
+            pg_inc: [0; 24],
+            pg_phase: [0; 24],
+            pg_reset: [false; 24],
+            pg_read: 0,
+            eg_cycle: 0,
+            eg_cycle_stop: false,
+            eg_shift: 0,
+            eg_shift_lock: 0,
+            eg_timer_low_lock: 0,
+            eg_timer: 0,
+            eg_timer_inc: 0,
+            eg_quotient: 0,
+            eg_rate: 0,
+            eg_ksv: 0,
+            eg_inc: 0,
+            eg_ratemax: false,
+            eg_sl: [0; 2],
+            eg_lfo_am: 0,
+            eg_tl: [0; 2],
+            eg_state: [EG_RELEASE; 24],
+            eg_level: [0x3FF; 24],
+            eg_out: [0x3FF; 24],
+            eg_kon: [false; 24],
+            eg_kon_csm: [false; 24],
+            eg_kon_latch: [false; 24],
+            eg_ssg_enable: [false; 24],
+            eg_ssg_pgrst_latch: [false; 24],
+            eg_ssg_repeat_latch: [false; 24],
+            eg_ssg_hold_up_latch: [false; 24],
+            eg_ssg_dir: [false; 24],
+            eg_ssg_inv: [false; 24],
+            eg_read: [0; 2],
+            eg_read_inc: 0,
+            fm_op1: [[0; 2]; 6],
+            fm_op2: [0; 6],
+            fm_out: [0; 24],
+            fm_mod: [0; 24],
+            ch_acc: [0; 6],
+            ch_out: [0; 6],
+            ch_lock: 0,
+            ch_lock_l: false,
+            ch_lock_r: false,
+            ch_read: 0,
+            timer_a_cnt: 0,
+            timer_a_reg: 0,
+            timer_a_load_lock: false,
+            timer_a_load: false,
+            timer_a_enable: false,
+            timer_a_reset: false,
+            timer_a_load_latch: false,
+            timer_a_overflow_flag: false,
+            timer_a_overflow: false,
+            timer_b_cnt: 0,
+            timer_b_subcnt: 0,
+            timer_b_reg: 0,
+            timer_b_load_lock: false,
+            timer_b_load: false,
+            timer_b_enable: false,
+            timer_b_reset: false,
+            timer_b_load_latch: false,
+            timer_b_overflow_flag: false,
+            timer_b_overflow: false,
+            mode_test_21: [false; 8],
+            mode_test_2c: [false; 8],
+            mode_ch3: 0,
+            mode_kon_channel: 0,
+            mode_kon_operator: [false; 4],
+            mode_kon: [false; 24],
+            mode_csm: false,
+            mode_kon_csm: false,
+            dacen: false,
+            dacdata: 0,
+            ks: [0; 24],
+            ar: [0; 24],
+            sr: [0; 24],
+            dt: [0; 24],
+            multi: [1; 24],
+            sl: [0; 24],
+            rr: [0; 24],
+            dr: [0; 24],
+            am: [false; 24],
+            tl: [0; 24],
+            ssg_eg: [0; 24],
+            fnum: [0; 6],
+            block: [0; 6],
+            kcode: [0; 6],
+            fnum_3ch: [0; 6],
+            block_3ch: [0; 6],
+            kcode_3ch: [0; 6],
+            reg_a4: 0,
+            reg_ac: 0,
+            connect: [0; 6],
+            fb: [0; 6],
+            pan_l: [true; 6],
+            pan_r: [true; 6],
+            ams: [0; 6],
+            pms: [0; 6],
+            status: 0,
+            status_time: 0,
+        }
+    }
+
+    /// Pino /IC: volta ao estado de power-on mantendo o modelo
+    pub fn reset(&mut self) {
+        *self = Self::new(self.ym2612_mode);
+    }
+
+    /// Ciclo atual do pipeline (0-23); uma amostra termina a cada volta
+    pub fn cycle(&self) -> u32 {
+        self.cycles
+    }
+
+    /// Trava uma escrita na porta `port` (A1:A0)
+    pub fn write(&mut self, port: u8, data: u8) {
+        let port = port & 3;
+        self.write_data = ((port as u16) << 7 & 0x100) | data as u16;
+        if port & 1 != 0 {
+            self.write_d |= 1;
+        } else {
+            self.write_a |= 1;
+        }
+    }
+
+    /// Leitura do status. O YM2612 só responde em A1:A0 = 0 e mantém o
+    /// valor no barramento por pouco tempo.
+    pub fn read(&mut self, port: u8) -> u8 {
+        if port & 3 == 0 || !self.ym2612_mode {
+            if self.mode_test_21[6] {
+                // Dados de teste
+                let slot = ((self.cycles + 18) % 24) as usize;
+                let mut testdata = ((self.pg_read & 0x01) << 15) as u16
+                    | ((self.eg_read[self.mode_test_21[0] as usize] & 0x01) << 14) as u16;
+                if self.mode_test_2c[4] {
+                    testdata |= self.ch_read as u16 & 0x1FF;
+                } else {
+                    testdata |= self.fm_out[slot] as u16 & 0x3FFF;
+                }
+                self.status = if self.mode_test_21[7] { testdata as u8 } else { (testdata >> 8) as u8 };
+            } else {
+                self.status = (self.busy as u8) << 7
+                    | (self.timer_b_overflow_flag as u8) << 1
+                    | self.timer_a_overflow_flag as u8;
+            }
+            self.status_time = if self.ym2612_mode { 300_000 } else { 40_000_000 };
+        }
+        if self.status_time != 0 {
+            self.status
+        } else {
+            0
+        }
+    }
+
+    /// Linha /IRQ (ativa com qualquer flag de timer)
+    pub fn irq(&self) -> bool {
+        self.timer_a_overflow_flag || self.timer_b_overflow_flag
+    }
+
+    /// Executa um ciclo interno e devolve a saída analógica (esquerda, direita)
+    pub fn clock(&mut self) -> (i16, i16) {
+        let slot = self.cycles;
+        self.lfo_inc = self.mode_test_21[1] as u8;
+        self.pg_read >>= 1;
+        self.eg_read[1] >>= 1;
+        self.eg_cycle = self.eg_cycle.wrapping_add(1);
+
+        // Trava o valor do timer do envelope
+        if self.cycles == 1 && self.eg_quotient == 2 {
+            self.eg_shift_lock = if self.eg_cycle_stop { 0 } else { self.eg_shift + 1 };
+            self.eg_timer_low_lock = (self.eg_timer & 0x03) as u8;
+        }
+
+        match self.cycles {
+            0 => {
+                self.lfo_pm = self.lfo_cnt >> 2;
+                self.lfo_am = if self.lfo_cnt & 0x40 != 0 { self.lfo_cnt & 0x3F } else { self.lfo_cnt ^ 0x3F };
+                self.lfo_am <<= 1;
+            }
+            1 => {
+                self.eg_quotient = (self.eg_quotient + 1) % 3;
+                self.eg_cycle = 0;
+                self.eg_cycle_stop = true;
+                self.eg_shift = 0;
+                self.eg_timer_inc |= self.eg_quotient >> 1;
+                self.eg_timer += self.eg_timer_inc;
+                self.eg_timer_inc = self.eg_timer >> 12;
+                self.eg_timer &= 0xFFF;
+            }
+            2 => {
+                self.pg_read = self.pg_phase[21] & 0x3FF;
+                self.eg_read[1] = self.eg_out[0] as u32;
+            }
+            13 => {
+                self.eg_cycle = 0;
+                self.eg_cycle_stop = true;
+                self.eg_shift = 0;
+                self.eg_timer += self.eg_timer_inc;
+                self.eg_timer_inc = self.eg_timer >> 12;
+                self.eg_timer &= 0xFFF;
+            }
+            23 => self.lfo_inc |= 1,
+            _ => {}
+        }
+        if self.mode_test_21[5] {
+            self.eg_timer &= !(1u32 << self.eg_cycle) as u16;
+        }
+        if self.eg_cycle_stop && (self.eg_timer as u32 >> self.eg_cycle) & 1 != 0 {
+            self.eg_shift = self.eg_cycle;
+            self.eg_cycle_stop = false;
+        }
+
+        self.do_io();
+
+        self.do_timer_a();
+        self.do_timer_b();
+        self.key_on();
+
+        self.ch_output();
+        self.ch_generate();
+
+        self.fm_prepare();
+        self.fm_generate();
+
+        self.phase_generate();
+        self.phase_calc_increment();
+
+        self.envelope_adsr();
+        self.envelope_generate();
+        self.envelope_ssg_eg();
+        self.envelope_prepare();
+
+        // Prepara F-number e bloco do próximo slot
+        let next = ((self.channel + 1) % 6) as usize;
+        let (fnum, block, kcode) = match (self.mode_ch3 != 0, slot) {
+            // Modo especial do canal 3: OP1, OP3 e OP2 têm frequência própria
+            (true, 1) => (self.fnum_3ch[1], self.block_3ch[1], self.kcode_3ch[1]),
+            (true, 7) => (self.fnum_3ch[0], self.block_3ch[0], self.kcode_3ch[0]),
+            (true, 13) => (self.fnum_3ch[2], self.block_3ch[2], self.kcode_3ch[2]),
+            _ => (self.fnum[next], self.block[next], self.kcode[next]),
+        };
+        self.pg_fnum = fnum;
+        self.pg_block = block;
+        self.pg_kcode = kcode;
+
+        self.update_lfo();
+        self.do_reg_write();
+        self.cycles = (self.cycles + 1) % 24;
+        self.channel = self.cycles % 6;
+
+        self.status_time = self.status_time.saturating_sub(1);
+        (self.mol, self.mor)
+    }
+
+    fn do_io(&mut self) {
+        // Detecção dos pulsos de escrita
+        self.write_a_en = self.write_a & 0x03 == 0x01;
+        self.write_d_en = self.write_d & 0x03 == 0x01;
+        self.write_a <<= 1;
+        self.write_d <<= 1;
+        // Contador do BUSY
+        self.busy = self.write_busy;
+        self.write_busy_cnt += self.write_busy as u8;
+        self.write_busy = (self.write_busy && self.write_busy_cnt >> 5 == 0) || self.write_d_en;
+        self.write_busy_cnt &= 0x1F;
+    }
+
+    fn do_reg_write(&mut self) {
+        let mut slot = (self.cycles % 12) as usize;
+        let channel = self.channel as usize;
+
+        if self.write_fm_data {
+            // Registradores de operador
+            if OP_OFFSET[slot] == self.address & 0x107 {
+                if self.address & 0x08 != 0 {
+                    // OP2, OP4
+                    slot += 12;
+                }
+                let data = self.data;
+                match self.address & 0xF0 {
+                    0x30 => {
+                        self.multi[slot] = if data & 0x0F == 0 { 1 } else { (data & 0x0F) << 1 };
+                        self.dt[slot] = (data >> 4) & 0x07;
+                    }
+                    0x40 => self.tl[slot] = data & 0x7F,
+                    0x50 => {
+                        self.ar[slot] = data & 0x1F;
+                        self.ks[slot] = (data >> 6) & 0x03;
+                    }
+                    0x60 => {
+                        self.dr[slot] = data & 0x1F;
+                        self.am[slot] = data & 0x80 != 0;
+                    }
+                    0x70 => self.sr[slot] = data & 0x1F,
+                    0x80 => {
+                        self.rr[slot] = data & 0x0F;
+                        let sl = (data >> 4) & 0x0F;
+                        self.sl[slot] = sl | ((sl + 1) & 0x10);
+                    }
+                    0x90 => self.ssg_eg[slot] = data & 0x0F,
+                    _ => {}
+                }
+            }
+
+            // Registradores de canal
+            if CH_OFFSET[channel] == self.address & 0x103 {
+                let data = self.data;
+                match self.address & 0xFC {
+                    0xA0 => {
+                        self.fnum[channel] = data as u16 | ((self.reg_a4 as u16 & 0x07) << 8);
+                        self.block[channel] = (self.reg_a4 >> 3) & 0x07;
+                        self.kcode[channel] = (self.block[channel] << 2) | FN_NOTE[(self.fnum[channel] >> 7) as usize];
+                    }
+                    0xA4 => self.reg_a4 = data,
+                    0xA8 => {
+                        self.fnum_3ch[channel] = data as u16 | ((self.reg_ac as u16 & 0x07) << 8);
+                        self.block_3ch[channel] = (self.reg_ac >> 3) & 0x07;
+                        self.kcode_3ch[channel] =
+                            (self.block_3ch[channel] << 2) | FN_NOTE[(self.fnum_3ch[channel] >> 7) as usize];
+                    }
+                    0xAC => self.reg_ac = data,
+                    0xB0 => {
+                        self.connect[channel] = data & 0x07;
+                        self.fb[channel] = (data >> 3) & 0x07;
+                    }
+                    0xB4 => {
+                        self.pms[channel] = data & 0x07;
+                        self.ams[channel] = (data >> 4) & 0x03;
+                        self.pan_l[channel] = data & 0x80 != 0;
+                        self.pan_r[channel] = data & 0x40 != 0;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if self.write_a_en || self.write_d_en {
+            if self.write_a_en {
+                self.write_fm_data = false;
+            }
+            if self.write_fm_address && self.write_d_en {
+                self.write_fm_data = true;
+            }
+
+            // Endereço: $00-$0F pertencem ao SSG (ausente no OPN2)
+            if self.write_a_en {
+                if self.write_data & 0xF0 != 0 {
+                    self.address = self.write_data;
+                    self.write_fm_address = true;
+                } else {
+                    self.write_fm_address = false;
+                }
+            }
+
+            // Registradores de modo ($21-$2C, apenas na parte 1)
+            if self.write_d_en && self.write_data & 0x100 == 0 {
+                let data = self.write_data as u8;
+                match self.write_fm_mode_a {
+                    0x21 => {
+                        for i in 0..8 {
+                            self.mode_test_21[i] = (data >> i) & 1 != 0;
+                        }
+                    }
+                    0x22 => {
+                        self.lfo_en = if data & 0x08 != 0 { 0x7F } else { 0 };
+                        self.lfo_freq = data & 0x07;
+                    }
+                    0x24 => self.timer_a_reg = (self.timer_a_reg & 0x03) | ((data as u16) << 2),
+                    0x25 => self.timer_a_reg = (self.timer_a_reg & 0x3FC) | (data as u16 & 0x03),
+                    0x26 => self.timer_b_reg = data as u16,
+                    0x27 => {
+                        self.mode_ch3 = (data & 0xC0) >> 6;
+                        self.mode_csm = self.mode_ch3 == 2;
+                        self.timer_a_load = data & 0x01 != 0;
+                        self.timer_a_enable = data & 0x04 != 0;
+                        self.timer_a_reset = data & 0x10 != 0;
+                        self.timer_b_load = data & 0x02 != 0;
+                        self.timer_b_enable = data & 0x08 != 0;
+                        self.timer_b_reset = data & 0x20 != 0;
+                    }
+                    0x28 => {
+                        for i in 0..4 {
+                            self.mode_kon_operator[i] = (data >> (4 + i)) & 1 != 0;
+                        }
+                        self.mode_kon_channel = if data & 0x03 == 0x03 {
+                            // Endereço inválido
+                            0xFF
+                        } else {
+                            (data & 0x03) + ((data >> 2) & 1) * 3
+                        };
+                    }
+                    0x2A => {
+                        self.dacdata &= 0x01;
+                        self.dacdata |= ((data ^ 0x80) as i16) << 1;
+                    }
+                    0x2B => self.dacen = data & 0x80 != 0,
+                    0x2C => {
+                        for i in 0..8 {
+                            self.mode_test_2c[i] = (data >> i) & 1 != 0;
+                        }
+                        self.dacdata &= 0x1FE;
+                        self.dacdata |= self.mode_test_2c[3] as i16;
+                    }
+                    _ => {}
+                }
+            }
+
+            if self.write_a_en {
+                self.write_fm_mode_a = self.write_data & 0x1FF;
+            }
+        }
+
+        if self.write_fm_data {
+            self.data = self.write_data as u8;
+        }
+    }
+
+    fn phase_calc_increment(&mut self) {
+        let chan = self.channel as usize;
+        let slot = self.cycles as usize;
+        let mut fnum = self.pg_fnum as u32;
+        let fnum_h = fnum >> 4;
+        let lfo = self.lfo_pm;
+        let mut lfo_l = (lfo & 0x0F) as usize;
+        let pms = self.pms[chan] as usize;
+        let dt = self.dt[slot];
+        let dt_l = dt & 0x03;
+        let mut detune = 0;
+        let mut kcode = self.pg_kcode;
+
+        fnum <<= 1;
+        // PM do LFO
+        if lfo_l & 0x08 != 0 {
+            lfo_l ^= 0x0F;
+        }
+        let mut fm = (fnum_h >> PG_LFO_SH1[pms][lfo_l]) + (fnum_h >> PG_LFO_SH2[pms][lfo_l]);
+        if pms > 5 {
+            fm <<= pms - 5;
+        }
+        fm >>= 2;
+        fnum = if lfo & 0x10 != 0 { fnum.wrapping_sub(fm) } else { fnum + fm };
+        fnum &= 0xFFF;
+
+        let mut basefreq = (fnum << self.pg_block) >> 2;
+
+        // Detune
+        if dt_l != 0 {
+            kcode = kcode.min(0x1C);
+            let block = kcode >> 2;
+            let note = kcode & 0x03;
+            let sum = block + 9 + ((dt_l == 3) as u8 | (dt_l & 0x02));
+            let sum_h = sum >> 1;
+            let sum_l = sum & 0x01;
+            detune = PG_DETUNE[((sum_l << 2) | note) as usize] >> (9 - sum_h);
+        }
+        basefreq = if dt & 0x04 != 0 { basefreq.wrapping_sub(detune) } else { basefreq + detune };
+        basefreq &= 0x1FFFF;
+        self.pg_inc[slot] = ((basefreq * self.multi[slot] as u32) >> 1) & 0xFFFFF;
+    }
+
+    fn phase_generate(&mut self) {
+        let slot = ((self.cycles + 20) % 24) as usize;
+        if self.pg_reset[slot] {
+            self.pg_inc[slot] = 0;
+        }
+        let slot = ((self.cycles + 19) % 24) as usize;
+        if self.pg_reset[slot] || self.mode_test_21[3] {
+            self.pg_phase[slot] = 0;
+        }
+        self.pg_phase[slot] = (self.pg_phase[slot] + self.pg_inc[slot]) & 0xFFFFF;
+    }
+
+    fn envelope_ssg_eg(&mut self) {
+        let slot = self.cycles as usize;
+        let ssg = self.ssg_eg[slot];
+        let mut direction = false;
+        self.eg_ssg_pgrst_latch[slot] = false;
+        self.eg_ssg_repeat_latch[slot] = false;
+        self.eg_ssg_hold_up_latch[slot] = false;
+        if ssg & 0x08 != 0 {
+            direction = self.eg_ssg_dir[slot];
+            if self.eg_level[slot] & 0x200 != 0 {
+                // Reset
+                if ssg & 0x03 == 0x00 {
+                    self.eg_ssg_pgrst_latch[slot] = true;
+                }
+                // Repetição
+                if ssg & 0x01 == 0x00 {
+                    self.eg_ssg_repeat_latch[slot] = true;
+                }
+                // Inversão
+                if ssg & 0x03 == 0x02 {
+                    direction = !direction;
+                }
+                if ssg & 0x03 == 0x03 {
+                    direction = true;
+                }
+            }
+            // Hold up
+            if self.eg_kon_latch[slot] && (ssg & 0x07 == 0x05 || ssg & 0x07 == 0x03) {
+                self.eg_ssg_hold_up_latch[slot] = true;
+            }
+            direction &= self.eg_kon[slot];
+        }
+        self.eg_ssg_dir[slot] = direction;
+        self.eg_ssg_enable[slot] = ssg & 0x08 != 0;
+        self.eg_ssg_inv[slot] = (direction ^ (ssg & 0x0C == 0x0C)) & self.eg_kon[slot];
+    }
+
+    fn envelope_adsr(&mut self) {
+        let slot = ((self.cycles + 22) % 24) as usize;
+
+        let nkon = self.eg_kon_latch[slot];
+        let okon = self.eg_kon[slot];
+        let mut nextstate = self.eg_state[slot];
+        let mut inc: i16 = 0;
+        self.eg_read[0] = self.eg_read_inc;
+        self.eg_read_inc = (self.eg_inc > 0) as u32;
+
+        // Reinicia o gerador de fase
+        self.pg_reset[slot] = (nkon && !okon) || self.eg_ssg_pgrst_latch[slot];
+
+        let kon_event = (nkon && !okon) || (okon && self.eg_ssg_repeat_latch[slot]);
+        let koff_event = okon && !nkon;
+
+        let mut level = self.eg_level[slot] as i16;
+        let mut ssg_level = level;
+        if self.eg_ssg_inv[slot] {
+            ssg_level = (512 - level) & 0x3FF;
+        }
+        if koff_event {
+            level = ssg_level;
+        }
+        let eg_off = if self.eg_ssg_enable[slot] { level >> 9 != 0 } else { level & 0x3F0 == 0x3F0 };
+        let mut nextlevel = level;
+        let attack_inc = |level: i16, shift: u8| ((!(level as i32)) << shift >> 5) as i16;
+        if kon_event {
+            nextstate = EG_ATTACK;
+            // Ataque instantâneo
+            if self.eg_ratemax {
+                nextlevel = 0;
+            } else if self.eg_state[slot] == EG_ATTACK && level != 0 && self.eg_inc != 0 && nkon {
+                inc = attack_inc(level, self.eg_inc);
+            }
+        } else {
+            match self.eg_state[slot] {
+                EG_ATTACK => {
+                    if level == 0 {
+                        nextstate = EG_DECAY;
+                    } else if self.eg_inc != 0 && !self.eg_ratemax && nkon {
+                        inc = attack_inc(level, self.eg_inc);
+                    }
+                }
+                EG_DECAY => {
+                    if (level >> 4) == (self.eg_sl[1] as i16) << 1 {
+                        nextstate = EG_SUSTAIN;
+                    } else if !eg_off && self.eg_inc != 0 {
+                        inc = 1 << (self.eg_inc - 1);
+                        if self.eg_ssg_enable[slot] {
+                            inc <<= 2;
+                        }
+                    }
+                }
+                _ => {
+                    if !eg_off && self.eg_inc != 0 {
+                        inc = 1 << (self.eg_inc - 1);
+                        if self.eg_ssg_enable[slot] {
+                            inc <<= 2;
+                        }
+                    }
+                }
+            }
+            if !nkon {
+                nextstate = EG_RELEASE;
+            }
+        }
+        if self.eg_kon_csm[slot] {
+            nextlevel |= (self.eg_tl[1] as i16) << 3;
+        }
+
+        // Envelope desligado
+        if !kon_event && !self.eg_ssg_hold_up_latch[slot] && self.eg_state[slot] != EG_ATTACK && eg_off {
+            nextstate = EG_RELEASE;
+            nextlevel = 0x3FF;
+        }
+
+        nextlevel = nextlevel.wrapping_add(inc);
+
+        self.eg_kon[slot] = self.eg_kon_latch[slot];
+        self.eg_level[slot] = nextlevel as u16 & 0x3FF;
+        self.eg_state[slot] = nextstate;
+    }
+
+    fn envelope_prepare(&mut self) {
+        let slot = self.cycles as usize;
+        let mut inc = 0;
+
+        // Incremento do envelope
+        let rate = ((self.eg_rate << 1) + self.eg_ksv).min(0x3F);
+        let sum = ((rate >> 2) + self.eg_shift_lock) & 0x0F;
+        if self.eg_rate != 0 && self.eg_quotient == 2 {
+            if rate < 48 {
+                inc = match sum {
+                    12 => 1,
+                    13 => (rate >> 1) & 0x01,
+                    14 => rate & 0x01,
+                    _ => 0,
+                };
+            } else {
+                inc = (EG_STEPHI[(rate & 0x03) as usize][self.eg_timer_low_lock as usize] + (rate >> 2) - 11).min(4);
+            }
+        }
+        self.eg_inc = inc;
+        self.eg_ratemax = (rate >> 1) == 0x1F;
+
+        // Taxa e key scale do próximo slot
+        let mut rate_sel = self.eg_state[slot];
+        if (self.eg_kon[slot] && self.eg_ssg_repeat_latch[slot]) || (!self.eg_kon[slot] && self.eg_kon_latch[slot]) {
+            rate_sel = EG_ATTACK;
+        }
+        self.eg_rate = match rate_sel {
+            EG_ATTACK => self.ar[slot],
+            EG_DECAY => self.dr[slot],
+            EG_SUSTAIN => self.sr[slot],
+            _ => (self.rr[slot] << 1) | 0x01,
+        };
+        self.eg_ksv = self.pg_kcode >> (self.ks[slot] ^ 0x03);
+        self.eg_lfo_am = if self.am[slot] {
+            self.lfo_am >> EG_AM_SHIFT[self.ams[self.channel as usize] as usize]
+        } else {
+            0
+        };
+        // TL e SL atrasados
+        self.eg_tl[1] = self.eg_tl[0];
+        self.eg_tl[0] = self.tl[slot];
+        self.eg_sl[1] = self.eg_sl[0];
+        self.eg_sl[0] = self.sl[slot];
+    }
+
+    fn envelope_generate(&mut self) {
+        let slot = ((self.cycles + 23) % 24) as usize;
+        let mut level = self.eg_level[slot];
+
+        if self.eg_ssg_inv[slot] {
+            level = 512u16.wrapping_sub(level);
+        }
+        if self.mode_test_21[5] {
+            level = 0;
+        }
+        level &= 0x3FF;
+
+        // AM do LFO e TL
+        level += self.eg_lfo_am as u16;
+        if !(self.mode_csm && self.channel == 2 + 1) {
+            level += (self.eg_tl[0] as u16) << 3;
+        }
+        self.eg_out[slot] = level.min(0x3FF);
+    }
+
+    fn update_lfo(&mut self) {
+        let period = LFO_CYCLES[self.lfo_freq as usize];
+        if self.lfo_quotient & period == period {
+            self.lfo_quotient = 0;
+            self.lfo_cnt = self.lfo_cnt.wrapping_add(1);
+        } else {
+            self.lfo_quotient = self.lfo_quotient.wrapping_add(self.lfo_inc);
+        }
+        self.lfo_cnt &= self.lfo_en;
+    }
+
+    fn fm_prepare(&mut self) {
+        let slot = ((self.cycles + 6) % 24) as usize;
+        let channel = self.channel as usize;
+        let op = slot / 6;
+        let connect = self.connect[channel] as usize;
+        let prevslot = ((self.cycles + 18) % 24) as usize;
+        let algorithm = &FM_ALGORITHM[op];
+
+        // Modulação
+        let mut mod1: i16 = 0;
+        let mut mod2: i16 = 0;
+        if algorithm[0][connect] {
+            mod2 |= self.fm_op1[channel][0];
+        }
+        if algorithm[1][connect] {
+            mod1 |= self.fm_op1[channel][1];
+        }
+        if algorithm[2][connect] {
+            mod1 |= self.fm_op2[channel];
+        }
+        if algorithm[3][connect] {
+            mod2 |= self.fm_out[prevslot];
+        }
+        if algorithm[4][connect] {
+            mod1 |= self.fm_out[prevslot];
+        }
+        let mut modulation = mod1.wrapping_add(mod2);
+        if op == 0 {
+            // Feedback
+            modulation = if self.fb[channel] == 0 { 0 } else { modulation >> (10 - self.fb[channel]) };
+        } else {
+            modulation >>= 1;
+        }
+        self.fm_mod[slot] = modulation as u16;
+
+        let slot = ((self.cycles + 18) % 24) as usize;
+        match slot / 6 {
+            // OP1
+            0 => {
+                self.fm_op1[channel][1] = self.fm_op1[channel][0];
+                self.fm_op1[channel][0] = self.fm_out[slot];
+            }
+            // OP2
+            2 => self.fm_op2[channel] = self.fm_out[slot],
+            _ => {}
+        }
+    }
+
+    fn ch_generate(&mut self) {
+        let slot = ((self.cycles + 18) % 24) as usize;
+        let channel = self.channel as usize;
+        let op = slot / 6;
+        let test_dac = self.mode_test_2c[5];
+        let mut acc = self.ch_acc[channel];
+        let mut add = test_dac as i16;
+        if op == 0 && !test_dac {
+            acc = 0;
+        }
+        if FM_ALGORITHM[op][5][self.connect[channel] as usize] && !test_dac {
+            add += self.fm_out[slot] >> 5;
+        }
+        let sum = (acc + add).clamp(-256, 255);
+
+        if op == 0 || test_dac {
+            self.ch_out[channel] = self.ch_acc[channel];
+        }
+        self.ch_acc[channel] = sum;
+    }
+
+    fn ch_output(&mut self) {
+        let cycles = self.cycles;
+        let mut channel = self.channel as usize;
+        let test_dac = self.mode_test_2c[5];
+        self.ch_read = self.ch_lock;
+        if cycles < 12 {
+            // Canais 4, 5 e 6
+            channel += 1;
+        }
+        if cycles & 3 == 0 {
+            if !test_dac {
+                // Trava o valor
+                self.ch_lock = self.ch_out[channel];
+            }
+            self.ch_lock_l = self.pan_l[channel];
+            self.ch_lock_r = self.pan_r[channel];
+        }
+        // Canal 6
+        let mut out = if ((cycles >> 2) == 1 && self.dacen) || test_dac {
+            sign_extend(8, self.dacdata as i32)
+        } else {
+            self.ch_lock
+        };
+        self.mol = 0;
+        self.mor = 0;
+
+        if self.ym2612_mode {
+            // DAC do YM2612: saída multiplexada com degrau no cruzamento do zero
+            let out_en = cycles & 3 == 3 || test_dac;
+            let mut sign = out >> 8;
+            if out >= 0 {
+                out += 1;
+                sign += 1;
+            }
+            self.mol = if self.ch_lock_l && out_en { out } else { sign } * 3;
+            self.mor = if self.ch_lock_r && out_en { out } else { sign } * 3;
+        } else {
+            let out_en = cycles & 3 != 0 || test_dac;
+            if self.ch_lock_l && out_en {
+                self.mol = out;
+            }
+            if self.ch_lock_r && out_en {
+                self.mor = out;
+            }
+        }
+    }
+
+    fn fm_generate(&mut self) {
+        let slot = ((self.cycles + 19) % 24) as usize;
+        // Fase
+        let phase = (self.fm_mod[slot] as u32).wrapping_add(self.pg_phase[slot] >> 10) & 0x3FF;
+        let quarter = if phase & 0x100 != 0 { (phase ^ 0xFF) & 0xFF } else { phase & 0xFF };
+        // Envelope
+        let level = (LOGSIN_ROM[quarter as usize] as u32 + ((self.eg_out[slot] as u32) << 2)).min(0x1FFF);
+        // Log -> linear
+        let mut output = (((EXP_ROM[((level & 0xFF) ^ 0xFF) as usize] as i32) | 0x400) << 2) >> (level >> 8);
+        let test = (self.mode_test_21[4] as i32) << 13;
+        if phase & 0x200 != 0 {
+            output = ((!output) ^ test) + 1;
+        } else {
+            output ^= test;
+        }
+        self.fm_out[slot] = sign_extend(13, output);
+    }
+
+    fn do_timer_a(&mut self) {
+        let mut load = self.timer_a_overflow;
+        if self.cycles == 2 {
+            // Trava o valor de carga
+            load |= !self.timer_a_load_lock && self.timer_a_load;
+            self.timer_a_load_lock = self.timer_a_load;
+            // Key on do CSM
+            self.mode_kon_csm = self.mode_csm && load;
+        }
+        let mut time = if self.timer_a_load_latch { self.timer_a_reg } else { self.timer_a_cnt };
+        self.timer_a_load_latch = load;
+        if (self.cycles == 1 && self.timer_a_load_lock) || self.mode_test_21[2] {
+            time += 1;
+        }
+        if self.timer_a_reset {
+            self.timer_a_reset = false;
+            self.timer_a_overflow_flag = false;
+        } else {
+            self.timer_a_overflow_flag |= self.timer_a_overflow && self.timer_a_enable;
+        }
+        self.timer_a_overflow = time >> 10 != 0;
+        self.timer_a_cnt = time & 0x3FF;
+    }
+
+    fn do_timer_b(&mut self) {
+        let mut load = self.timer_b_overflow;
+        if self.cycles == 2 {
+            load |= !self.timer_b_load_lock && self.timer_b_load;
+            self.timer_b_load_lock = self.timer_b_load;
+        }
+        let mut time = if self.timer_b_load_latch { self.timer_b_reg } else { self.timer_b_cnt };
+        self.timer_b_load_latch = load;
+        // Timer B conta a cada 16 amostras
+        if self.cycles == 1 {
+            self.timer_b_subcnt += 1;
+        }
+        if (self.timer_b_subcnt == 0x10 && self.timer_b_load_lock) || self.mode_test_21[2] {
+            time += 1;
+        }
+        self.timer_b_subcnt &= 0x0F;
+        if self.timer_b_reset {
+            self.timer_b_reset = false;
+            self.timer_b_overflow_flag = false;
+        } else {
+            self.timer_b_overflow_flag |= self.timer_b_overflow && self.timer_b_enable;
+        }
+        self.timer_b_overflow = time >> 8 != 0;
+        self.timer_b_cnt = time & 0xFF;
+    }
+
+    fn key_on(&mut self) {
+        let slot = self.cycles as usize;
+        let chan = self.channel as usize;
+        self.eg_kon_latch[slot] = self.mode_kon[slot];
+        self.eg_kon_csm[slot] = false;
+        if self.channel == 2 && self.mode_kon_csm {
+            // Key on do CSM
+            self.eg_kon_latch[slot] = true;
+            self.eg_kon_csm[slot] = true;
+        }
+        if self.cycles == self.mode_kon_channel as u32 {
+            self.mode_kon[chan] = self.mode_kon_operator[0];
+            self.mode_kon[chan + 12] = self.mode_kon_operator[1];
+            self.mode_kon[chan + 6] = self.mode_kon_operator[2];
+            self.mode_kon[chan + 18] = self.mode_kon_operator[3];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Executa ciclos suficientes para uma escrita chegar ao registrador
+    fn write_reg(chip: &mut YM3438, reg: u8, value: u8) {
+        chip.write(0, reg);
+        for _ in 0..24 {
+            chip.clock();
+        }
+        chip.write(1, value);
+        for _ in 0..24 * 2 {
+            chip.clock();
+        }
+    }
+
+    #[test]
+    fn test_timer_a_and_busy() {
+        let mut chip = YM3438::new(false);
+        write_reg(&mut chip, 0x24, 0xFF);
+        write_reg(&mut chip, 0x25, 0x03);
+        chip.write(0, 0x27);
+        chip.clock();
+        chip.write(1, 0x05);
+        chip.clock();
+        chip.clock();
+        // BUSY fica ativo por 32 ciclos após a escrita de dados
+        assert_eq!(chip.read(0) & 0x80, 0x80);
+        for _ in 0..24 * 4 {
+            chip.clock();
+        }
+        assert_eq!(chip.read(0), 0x01);
+        // O YM3438 devolve o status em qualquer porta, o YM2612 só em $4000
+        assert_eq!(chip.read(2), 0x01);
+        let mut chip = YM3438::new(true);
+        assert_eq!(chip.read(2), 0x00);
+    }
+}
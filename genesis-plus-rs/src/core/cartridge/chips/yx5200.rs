@@ -1,585 +1,393 @@
-// yx5200.rs
-// Adaptação para o core RetroArch Genesis Plus GX
-
-use crate::cd_stream::CdStream;
-use crate::snd::{Blip, Sound};
-use crate::shared::SharedState;
-use minimp3::{Decoder, Frame};
-use std::io::{Read, Seek, SeekFrom};
-use std::sync::Arc;
-
-// YX5200 UART interface RX message size
-const YX5200_RX_BUFFER_SIZE: usize = 10;
-
-// YX5200 audio playback limitations
-const YX5200_MAX_TRACK_INDEX: u16 = 2999;
-const YX5200_MAX_VOLUME: u8 = 30;
-
-// MP3 decoder context similar to minimp3_ex
-struct Mp3Decoder {
-    decoder: Option<Decoder<CdStream>>,
-    info: Mp3Info,
-    cur_sample: u64,
-    last_error: i32,
-}
+//! Tocador de MP3 YX5200 da placa KAISER WAVE (The Secret Of The Four Winds).
+//! Baseado em `cart_hw/yx5200.c` do Genesis Plus GX.
+//!
+//! O jogo envia comandos de 10 bytes pela linha RX serial, um bit por
+//! escrita (START, 8 bits com o LSB primeiro e STOP). As faixas são arquivos
+//! `<n>.mp3` na pasta da ROM, decodificados na própria taxa de amostragem e
+//! reamostrados pelo buffer `BLIP_CART`.
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use minimp3::{Decoder, Error};
+
+use crate::core::audio::{Sound, BLIP_CART};
+
+/// Tamanho de uma mensagem recebida pela UART
+const RX_BUFFER_SIZE: usize = 10;
+
+const MAX_TRACK_INDEX: u16 = 2999;
+const MAX_VOLUME: i32 = 30;
 
-struct Mp3Info {
-    channels: u8,
+/// Taxa usada enquanto nenhuma faixa está carregada (a maior do MP3)
+const DEFAULT_RATE: u32 = 48_000;
+
+/// Faixa MP3 aberta, decodificada à medida que é lida
+struct Track {
+    path: PathBuf,
+    decoder: Decoder<File>,
+    channels: usize,
     hz: u32,
-    layer: u8,
-    bitrate_kbps: u32,
+    /// Amostras intercaladas do último quadro MP3 e posição de leitura
+    pending: Vec<i16>,
+    read_pos: usize,
+    /// Amostras (por canal) lidas desde o início do arquivo
+    cur_sample: u64,
+    /// Erro de leitura ou de decodificação
+    error: bool,
 }
 
-impl Mp3Decoder {
-    fn new() -> Self {
-        Self {
-            decoder: None,
-            info: Mp3Info {
-                channels: 0,
-                hz: 0,
-                layer: 0,
-                bitrate_kbps: 0,
-            },
-            cur_sample: 0,
-            last_error: 0,
-        }
-    }
-
-    fn open_cb(&mut self, stream: CdStream) -> i32 {
-        match Decoder::new(stream) {
-            Ok(decoder) => {
-                // Get info from first frame
-                if let Ok(frame) = decoder.next_frame() {
-                    self.info.channels = frame.channels as u8;
-                    self.info.hz = frame.sample_rate as u32;
-                    // For simplicity, we'll use constant values for other fields
-                    self.info.layer = 3;
-                    self.info.bitrate_kbps = 128;
-                }
-                self.decoder = Some(decoder);
-                0
-            }
-            Err(_) => -1,
+impl Track {
+    /// Abre um arquivo MP3 mono ou estéreo
+    fn open(path: PathBuf) -> Option<Self> {
+        let mut decoder = Decoder::new(File::open(&path).ok()?);
+        let frame = decoder.next_frame().ok()?;
+        if !(1..=2).contains(&frame.channels) {
+            return None;
         }
+        Some(Self {
+            path,
+            decoder,
+            channels: frame.channels,
+            hz: frame.sample_rate as u32,
+            pending: frame.data,
+            read_pos: 0,
+            cur_sample: 0,
+            error: false,
+        })
     }
 
-    fn read(&mut self, buffer: &mut [i16], samples: usize) -> usize {
-        if let Some(decoder) = &mut self.decoder {
-            let channels = self.info.channels as usize;
-            let samples_needed = samples / channels;
-            let mut samples_read = 0;
-            
-            while samples_read < samples_needed {
-                match decoder.next_frame() {
-                    Ok(Frame { data, sample_rate, channels: frame_channels, .. }) => {
-                        // Update info if needed
-                        if sample_rate as u32 != self.info.hz {
-                            self.info.hz = sample_rate as u32;
-                        }
-                        
-                        // Copy samples to buffer
-                        let frame_samples = data.len();
-                        let to_copy = std::cmp::min(frame_samples, samples - samples_read * channels);
-                        
-                        for i in 0..to_copy {
-                            if samples_read * channels + i < buffer.len() {
-                                buffer[samples_read * channels + i] = data[i];
-                            }
-                        }
-                        
-                        samples_read += to_copy / channels;
-                        self.cur_sample += to_copy as u64 / channels as u64;
-                        
-                        if to_copy < frame_samples {
-                            // Store remaining samples for next read
-                            // This is simplified - actual implementation would need a buffer
-                            break;
-                        }
+    /// Completa `out` até `len` amostras intercaladas; devolve quantas foram
+    /// lidas (menos no fim do arquivo ou num erro)
+    fn read(&mut self, out: &mut Vec<i16>, len: usize) -> usize {
+        let start = out.len();
+        while out.len() < len {
+            if self.read_pos == self.pending.len() {
+                match self.decoder.next_frame() {
+                    Ok(frame) => {
+                        self.pending = frame.data;
+                        self.read_pos = 0;
+                        continue;
                     }
-                    Err(e) => {
-                        self.last_error = -1;
+                    Err(Error::Eof) => break,
+                    Err(_) => {
+                        self.error = true;
                         break;
                     }
                 }
             }
-            
-            samples_read * channels
-        } else {
-            0
-        }
-    }
-
-    fn seek(&mut self, sample: u64) -> bool {
-        if let Some(decoder) = &mut self.decoder {
-            // Note: minimp3 doesn't support direct seeking by sample
-            // This is a simplified implementation
-            self.cur_sample = sample;
-            true
-        } else {
-            false
+            let count = (len - out.len()).min(self.pending.len() - self.read_pos);
+            out.extend_from_slice(&self.pending[self.read_pos..self.read_pos + count]);
+            self.read_pos += count;
         }
+        let read = out.len() - start;
+        self.cur_sample += (read / self.channels) as u64;
+        read
     }
 
-    fn close(&mut self) {
-        self.decoder = None;
-        self.info = Mp3Info {
-            channels: 0,
-            hz: 0,
-            layer: 0,
-            bitrate_kbps: 0,
+    /// Posiciona a leitura na amostra `sample`. O decodificador não tem
+    /// busca: o arquivo é reaberto e as amostras anteriores são descartadas.
+    fn seek(&mut self, sample: u64) {
+        let Ok(file) = File::open(&self.path) else {
+            self.error = true;
+            return;
         };
+        self.decoder = Decoder::new(file);
+        self.pending.clear();
+        self.read_pos = 0;
         self.cur_sample = 0;
-        self.last_error = 0;
+        self.error = false;
+
+        let mut skipped = Vec::new();
+        let mut left = sample as usize * self.channels;
+        while left > 0 {
+            skipped.clear();
+            let read = self.read(&mut skipped, left.min(4096));
+            if read == 0 {
+                break;
+            }
+            left -= read;
+        }
     }
 }
 
+/// Tocador YX5200
 pub struct Yx5200 {
-    // RX data bits reception cycle (0-9)
+    /// Bit sendo recebido (0 a 9), bytes recebidos e mensagem
     rx_cycle: u8,
-    // RX message byte counter
-    rx_counter: u8,
-    // RX message buffer
-    rx_buffer: [u8; YX5200_RX_BUFFER_SIZE],
-    // audio playback status (false: stopped/paused, true: playing)
+    rx_counter: usize,
+    rx_buffer: [u8; RX_BUFFER_SIZE],
+    /// Reprodução em andamento (não pausada) e em repetição
     playback_enabled: bool,
-    // audio playback loop mode (false: single playback, true: repeated playback)
     playback_loop: bool,
-    // audio playback volume (0-30)
-    playback_volume: u8,
-    // audio output (DAC) status (false: muted, true: enabled)
+    /// Volume de 0 a 30
+    volume: u8,
+    /// Saída do DAC ligada
     audio_enabled: bool,
-    // audio track index (0-2999, with 0 meaning no audio track loaded)
+    /// Faixa carregada (1 a 2999, 0 sem faixa)
     track_index: u16,
-    // left & right audio channels outputs
-    audio: [i16; 2],
-    // MP3 decoder
-    mp3_decoder: Mp3Decoder,
-    // Current MP3 file stream
-    current_stream: Option<CdStream>,
-    // ROM path for MP3 files
-    rom_path: String,
+    track: Option<Track>,
+    /// Últimas saídas, base das próximas variações
+    audio: [i32; 2],
+    /// Pasta dos arquivos das faixas
+    track_dir: PathBuf,
+    buffer: Vec<i16>,
 }
 
 impl Yx5200 {
-    pub fn new(rom_path: &str) -> Self {
+    /// Tocador que busca as faixas em `track_dir` (a pasta da ROM)
+    pub fn new(track_dir: impl Into<PathBuf>) -> Self {
         Self {
             rx_cycle: 0,
             rx_counter: 0,
-            rx_buffer: [0; YX5200_RX_BUFFER_SIZE],
+            rx_buffer: [0; RX_BUFFER_SIZE],
             playback_enabled: false,
             playback_loop: false,
-            playback_volume: 30,
+            volume: MAX_VOLUME as u8,
             audio_enabled: true,
             track_index: 0,
+            track: None,
             audio: [0; 2],
-            mp3_decoder: Mp3Decoder::new(),
-            current_stream: None,
-            rom_path: rom_path.to_string(),
+            track_dir: track_dir.into(),
+            buffer: Vec::new(),
         }
     }
 
-    pub fn init(&mut self, samplerate: u32, sound: &mut Sound) {
-        // YX5200 audio playback rate depends on loaded audio track
-        // Audio stream is resampled to desired rate using Blip Buffer
-        if self.track_index != 0 {
-            // Get blip buffer for channel 3 (assuming YX5200 uses channel 3)
-            if let Some(blip) = sound.blips.get_mut(3) {
-                blip.set_rates(self.mp3_decoder.info.hz as f64, samplerate as f64);
-            }
-        } else {
-            // Set maximal MP3 samplerate when no audio track is loaded
-            if let Some(blip) = sound.blips.get_mut(3) {
-                blip.set_rates(48000.0, samplerate as f64);
-            }
-        }
+    /// Faixa carregada (0 sem faixa)
+    pub fn track_index(&self) -> u16 {
+        self.track_index
     }
 
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Para a reprodução e volta ao estado inicial
     pub fn reset(&mut self) {
         self.unload_track();
-        *self = Yx5200::new(&self.rom_path);
-        self.playback_volume = 30;
-        self.audio_enabled = true;
+        *self = Self::new(std::mem::take(&mut self.track_dir));
     }
 
+    /// Taxa de amostragem da faixa carregada
+    pub fn rate(&self) -> u32 {
+        self.track.as_ref().map_or(DEFAULT_RATE, |track| track.hz)
+    }
+
+    /// Bit recebido na linha RX
     pub fn write(&mut self, rx_data: u8) {
-        // RX data byte transfer not started?
+        let rx_data = rx_data & 1;
         if self.rx_cycle == 0 {
-            // START bit received?
+            // START
             if rx_data == 0 {
-                // Initialize RX data byte reception
-                self.rx_buffer[self.rx_counter as usize] = 0;
+                self.rx_buffer[self.rx_counter] = 0;
                 self.rx_cycle = 1;
             }
-        }
-        // RX data byte transfer reception in progress?
-        else if self.rx_cycle < 9 {
-            // Update RX data byte with RX data line state (LSB first)
-            self.rx_buffer[self.rx_counter as usize] |= rx_data << (self.rx_cycle - 1);
+        } else if self.rx_cycle < 9 {
+            self.rx_buffer[self.rx_counter] |= rx_data << (self.rx_cycle - 1);
             self.rx_cycle += 1;
-        }
-        // RX data byte transfer finished?
-        else {
-            // STOP bit received?
+        } else {
+            // STOP
             if rx_data != 0 {
-                // Increment received byte counter
                 self.rx_counter += 1;
-
-                // RX message buffer filled?
-                if self.rx_counter as usize == YX5200_RX_BUFFER_SIZE {
-                    // Process RX message
+                if self.rx_counter == RX_BUFFER_SIZE {
                     self.process_cmd();
-
-                    // Reinitialize RX message byte counter
                     self.rx_counter = 0;
                 }
             }
-
-            // Reset RX cycle
             self.rx_cycle = 0;
         }
     }
 
-    pub fn update(&mut self, samples: u32, sound: &mut Sound) {
-        // Previous audio outputs
-        let prev_l = self.audio[0];
-        let prev_r = self.audio[1];
-        
-        // Get number of needed YX5200 audio samples
-        // This depends on the blip buffer implementation
-        // For now, we'll use the input samples directly
-        let samples_needed = samples as usize;
-
-        // YX5200 audio playback started?
-        if self.playback_enabled && self.track_index != 0 {
-            let channels = self.mp3_decoder.info.channels as usize;
-            let samples_to_read = samples_needed * channels;
-            let mut audio_buffer = vec![0i16; samples_to_read];
-
-            // Read needed audio samples
-            let mut samples_available = self.mp3_decoder.read(&mut audio_buffer, samples_to_read);
-
-            // Assume either end of audio file has been reached or decoding error occurred
-            // if not all needed audio samples could be read
-            if samples_available < samples_to_read {
-                // Playback loop enabled and no MP3 decoding error?
-                if self.playback_loop && self.mp3_decoder.last_error == 0 {
-                    // Seek back to start of MP3 file
-                    self.mp3_decoder.seek(0);
-                    // Read more samples
-                    let additional = self.mp3_decoder.read(
-                        &mut audio_buffer[samples_available..],
-                        samples_to_read - samples_available
-                    );
-                    samples_available += additional;
-                } else {
-                    // Stop audio playback
-                    self.unload_track();
-                    
-                    // Add silent audio samples
-                    for i in samples_available..samples_to_read {
-                        audio_buffer[i] = 0;
-                    }
-                    samples_available = samples_to_read;
-                }
-            }
-
-            // Check audio is not silent or muted
-            if self.playback_volume > 0 && self.audio_enabled {
-                // Update blip buffer with available audio samples
-                let mut count = 0;
-                for i in (0..samples_available).step_by(channels) {
-                    let l = (audio_buffer[i] as i32 * self.playback_volume as i32) / YX5200_MAX_VOLUME as i32;
-                    let r = if channels > 1 {
-                        (audio_buffer[i + channels - 1] as i32 * self.playback_volume as i32) / YX5200_MAX_VOLUME as i32
-                    } else {
-                        l
-                    };
-                    
-                    if let Some(blip) = sound.blips.get_mut(3) {
-                        blip.add_delta_fast(count, (l - prev_l as i32) as i16, (r - prev_r as i32) as i16);
-                    }
-                    
-                    count += 1;
-                    self.audio[0] = l as i16;
-                    self.audio[1] = r as i16;
-                }
-            } else {
-                // Update blip buffer with silent audio output
-                if let Some(blip) = sound.blips.get_mut(3) {
-                    blip.add_delta_fast(0, -prev_l, -prev_r);
+    /// Gera o áudio para `samples` amostras de saída no buffer `BLIP_CART`
+    pub fn update(&mut self, sound: &mut Sound, samples: usize) {
+        let sample_rate = sound.sample_rate();
+        let blip = &mut sound.blips[BLIP_CART];
+        blip.set_rates(self.rate() as f64, sample_rate as f64);
+        let clocks = blip.clocks_needed(samples);
+        let [mut prev_l, mut prev_r] = self.audio;
+
+        self.buffer.clear();
+        let mut channels = 1;
+        if let Some(track) = self.track.as_mut().filter(|_| self.playback_enabled) {
+            channels = track.channels;
+            let count = clocks as usize * channels;
+            track.read(&mut self.buffer, count);
+            // Fim do arquivo (ou erro): recomeça a faixa ou para
+            while self.buffer.len() < count && self.playback_loop && !track.error {
+                track.seek(0);
+                if track.read(&mut self.buffer, count) == 0 {
+                    break;
                 }
-                self.audio = [0, 0];
             }
-        } else {
-            // Update blip buffer with silent audio output
-            if let Some(blip) = sound.blips.get_mut(3) {
-                blip.add_delta_fast(0, -prev_l, -prev_r);
+            if self.buffer.len() < count {
+                self.buffer.extend(std::iter::repeat_n(0, channels));
+                self.unload_track();
             }
-            self.audio = [0, 0];
         }
 
-        // End of blip buffer timeframe
-        if let Some(blip) = sound.blips.get_mut(3) {
-            blip.end_frame(samples_needed as i32);
+        if !self.buffer.is_empty() && self.volume > 0 && self.audio_enabled {
+            let volume = i32::from(self.volume);
+            for (time, frame) in self.buffer.chunks(channels).enumerate() {
+                let l = i32::from(frame[0]) * volume / MAX_VOLUME;
+                let r = i32::from(frame[channels - 1]) * volume / MAX_VOLUME;
+                blip.add_delta_fast(time as u64, l - prev_l, r - prev_r);
+                (prev_l, prev_r) = (l, r);
+            }
+        } else {
+            blip.add_delta_fast(0, -prev_l, -prev_r);
+            (prev_l, prev_r) = (0, 0);
         }
+        self.audio = [prev_l, prev_r];
+        blip.end_frame(clocks);
     }
 
+    /// Estado para o save state
     pub fn context_save(&self, state: &mut Vec<u8>) {
-        // Save YX5200 state
-        state.extend_from_slice(&self.rx_cycle.to_le_bytes());
-        state.extend_from_slice(&self.rx_counter.to_le_bytes());
+        state.extend_from_slice(&[self.rx_cycle, self.rx_counter as u8]);
         state.extend_from_slice(&self.rx_buffer);
-        state.extend_from_slice(&[self.playback_enabled as u8]);
-        state.extend_from_slice(&[self.playback_loop as u8]);
-        state.extend_from_slice(&[self.playback_volume]);
-        state.extend_from_slice(&[self.audio_enabled as u8]);
+        state.extend_from_slice(&[
+            self.playback_enabled as u8,
+            self.playback_loop as u8,
+            self.volume,
+            self.audio_enabled as u8,
+        ]);
         state.extend_from_slice(&self.track_index.to_le_bytes());
-        state.extend_from_slice(&self.audio[0].to_le_bytes());
-        state.extend_from_slice(&self.audio[1].to_le_bytes());
-        
-        // Save MP3 decoder state
-        state.extend_from_slice(&self.mp3_decoder.cur_sample.to_le_bytes());
+        for level in self.audio {
+            state.extend_from_slice(&level.to_le_bytes());
+        }
+        let cur_sample = self.track.as_ref().map_or(0, |track| track.cur_sample);
+        state.extend_from_slice(&cur_sample.to_le_bytes());
     }
 
-    pub fn context_load(&mut self, state: &[u8]) -> Result<usize, &'static str> {
-        let mut offset = 0;
-        
-        // Load YX5200 state
-        if state.len() < offset + 1 { return Err("Invalid state data"); }
-        self.rx_cycle = state[offset]; offset += 1;
-        
-        if state.len() < offset + 1 { return Err("Invalid state data"); }
-        self.rx_counter = state[offset]; offset += 1;
-        
-        if state.len() < offset + YX5200_RX_BUFFER_SIZE { return Err("Invalid state data"); }
-        self.rx_buffer.copy_from_slice(&state[offset..offset + YX5200_RX_BUFFER_SIZE]);
-        offset += YX5200_RX_BUFFER_SIZE;
-        
-        if state.len() < offset + 1 { return Err("Invalid state data"); }
-        self.playback_enabled = state[offset] != 0; offset += 1;
-        
-        if state.len() < offset + 1 { return Err("Invalid state data"); }
-        self.playback_loop = state[offset] != 0; offset += 1;
-        
-        if state.len() < offset + 1 { return Err("Invalid state data"); }
-        self.playback_volume = state[offset]; offset += 1;
-        
-        if state.len() < offset + 1 { return Err("Invalid state data"); }
-        self.audio_enabled = state[offset] != 0; offset += 1;
-        
-        if state.len() < offset + 2 { return Err("Invalid state data"); }
-        self.track_index = u16::from_le_bytes([state[offset], state[offset + 1]]);
-        offset += 2;
-        
-        if state.len() < offset + 2 { return Err("Invalid state data"); }
-        self.audio[0] = i16::from_le_bytes([state[offset], state[offset + 1]]);
-        offset += 2;
-        
-        if state.len() < offset + 2 { return Err("Invalid state data"); }
-        self.audio[1] = i16::from_le_bytes([state[offset], state[offset + 1]]);
-        offset += 2;
-        
-        // Load MP3 decoder state
-        if state.len() < offset + 8 { return Err("Invalid state data"); }
-        let cur_sample = u64::from_le_bytes([
-            state[offset], state[offset + 1], state[offset + 2], state[offset + 3],
-            state[offset + 4], state[offset + 5], state[offset + 6], state[offset + 7]
-        ]);
-        offset += 8;
-        
-        // Reload track if needed
-        let index = self.track_index;
-        self.track_index = 0;
-        if index > 0 && index <= YX5200_MAX_TRACK_INDEX {
+    /// Restaura o estado salvo, reabrindo a faixa na mesma posição; devolve
+    /// os bytes lidos ou `None` se o estado está incompleto
+    pub fn context_load(&mut self, state: &[u8]) -> Option<usize> {
+        const SIZE: usize = 2 + RX_BUFFER_SIZE + 4 + 2 + 8 + 8;
+        let state = state.get(..SIZE)?;
+        self.unload_track();
+
+        let (rx, rest) = state.split_at(2);
+        let (buffer, rest) = rest.split_at(RX_BUFFER_SIZE);
+        self.rx_cycle = rx[0];
+        self.rx_counter = usize::from(rx[1]) % RX_BUFFER_SIZE;
+        self.rx_buffer.copy_from_slice(buffer);
+        self.playback_enabled = rest[0] != 0;
+        self.playback_loop = rest[1] != 0;
+        self.volume = rest[2].min(MAX_VOLUME as u8);
+        self.audio_enabled = rest[3] != 0;
+        let index = u16::from_le_bytes([rest[4], rest[5]]);
+        self.audio = [
+            i32::from_le_bytes(rest[6..10].try_into().unwrap()),
+            i32::from_le_bytes(rest[10..14].try_into().unwrap()),
+        ];
+        let cur_sample = u64::from_le_bytes(rest[14..22].try_into().unwrap());
+
+        let playback_enabled = self.playback_enabled;
+        self.playback_enabled = false;
+        if index > 0 && index <= MAX_TRACK_INDEX {
             self.load_track(index, self.playback_loop);
-            self.mp3_decoder.seek(cur_sample);
+            if let Some(track) = &mut self.track {
+                track.seek(cur_sample);
+                self.playback_enabled = playback_enabled;
+            }
         }
-        
-        Ok(offset)
+        Some(SIZE)
     }
 
+    /// Executa a mensagem recebida (formato e checksum não são conferidos)
     fn process_cmd(&mut self) {
-        // Process command code (assume message format and checksum are always correct)
+        let param = u16::from_be_bytes([self.rx_buffer[5], self.rx_buffer[6]]);
         match self.rx_buffer[3] {
-            0x01 => {
-                // Play next track (only if there is already a track loaded)
-                if self.track_index > 0 && self.track_index <= YX5200_MAX_TRACK_INDEX {
-                    self.load_track(self.track_index + 1, self.playback_loop);
-                }
-            }
-            0x02 => {
-                // Play previous track
-                if self.track_index > 1 {
-                    self.load_track(self.track_index - 1, self.playback_loop);
-                }
-            }
-            0x03 => {
-                // Play selected track (1-2999)
-                let index = (self.rx_buffer[5] as u16) << 8 | self.rx_buffer[6] as u16;
-                if index > 0 && index <= YX5200_MAX_TRACK_INDEX {
-                    // Playback loop seems to be enabled by default
-                    self.load_track(index, true);
-                }
-            }
-            0x04 => {
-                // Increase playback volume
-                if self.playback_volume < YX5200_MAX_VOLUME {
-                    self.playback_volume += 1;
-                }
-            }
-            0x05 => {
-                // Decrease playback volume
-                if self.playback_volume > 0 {
-                    self.playback_volume -= 1;
-                }
-            }
-            0x06 => {
-                // Set playback volume (0-30)
-                let volume = (self.rx_buffer[5] as u16) << 8 | self.rx_buffer[6] as u16;
-                if volume <= YX5200_MAX_VOLUME as u16 {
-                    self.playback_volume = volume as u8;
-                }
-            }
-            0x08 => {
-                // Single-repeat selected track (1-2999)
-                let index = (self.rx_buffer[5] as u16) << 8 | self.rx_buffer[6] as u16;
-                if index > 0 && index <= YX5200_MAX_TRACK_INDEX {
-                    // Purpose of this command is not clear in available documentation so,
-                    // to differentiate it from command 0x03 which appears to play selected
-                    // audio track in loop, assume 'single repeat' playback means selected
-                    // audio track is played only once (to be confirmed)
-                    self.load_track(index, false);
-                }
-            }
-            0x0c => {
-                // Reset
-                self.reset();
-            }
-            0x0d => {
-                // Resume audio playback
-                if self.track_index != 0 {
-                    self.playback_enabled = true;
-                }
-            }
-            0x0e => {
-                // Pause audio playback
-                self.playback_enabled = false;
-            }
-            0x16 => {
-                // Stop audio playback
-                self.unload_track();
-            }
-            0x19 => {
-                // Enable/disable current audio track loop playback (during playback only)
-                if self.playback_enabled {
-                    self.playback_loop = (self.rx_buffer[6] & 0x01) == 0;
-                }
-            }
-            0x1a => {
-                // Enable/disable DAC output
-                self.audio_enabled = (self.rx_buffer[6] & 0x01) == 0;
-            }
-            _ => {
-                // Unsupported command
+            // Próxima faixa (só com uma faixa carregada) e faixa anterior
+            0x01 if self.track_index > 0 && self.track_index <= MAX_TRACK_INDEX => {
+                self.load_track(self.track_index + 1, self.playback_loop);
             }
+            0x02 if self.track_index > 1 => self.load_track(self.track_index - 1, self.playback_loop),
+            // Toca a faixa escolhida, em repetição
+            0x03 if param > 0 && param <= MAX_TRACK_INDEX => self.load_track(param, true),
+            // Volume +1, -1 e volume escolhido
+            0x04 => self.volume = (self.volume + 1).min(MAX_VOLUME as u8),
+            0x05 => self.volume = self.volume.saturating_sub(1),
+            0x06 if param <= MAX_VOLUME as u16 => self.volume = param as u8,
+            // "Repetição única": a documentação é vaga; assume-se que a faixa
+            // toca uma vez só, para diferenciar do comando 0x03
+            0x08 if param > 0 && param <= MAX_TRACK_INDEX => self.load_track(param, false),
+            0x0C => self.reset(),
+            // Continua e pausa
+            0x0D => self.playback_enabled = self.track_index != 0,
+            0x0E => self.playback_enabled = false,
+            0x16 => self.unload_track(),
+            // Repetição da faixa corrente (só durante a reprodução)
+            0x19 if self.playback_enabled => self.playback_loop = self.rx_buffer[6] & 1 == 0,
+            // Liga e desliga o DAC
+            0x1A => self.audio_enabled = self.rx_buffer[6] & 1 == 0,
+            _ => {}
         }
     }
 
+    /// Abre a faixa `index`: o primeiro de `1.mp3`, `01.mp3`, `001.mp3` e
+    /// `0001.mp3` que existir
     fn load_track(&mut self, index: u16, playback_loop: bool) {
-        // First stop any audio playback
         self.unload_track();
-
-        // Supported filename formats (max 2999 tracks)
-        let formats = ["{:01}.mp3", "{:02}.mp3", "{:03}.mp3", "{:04}.mp3"];
-
-        // Attempt to open MP3 file
-        for fmt in formats.iter() {
-            let filename = fmt.replace("{}", &index.to_string());
-            let full_path = format!("{}/{}", self.rom_path, filename);
-            
-            if let Ok(stream) = CdStream::open(&full_path) {
-                // Attempt to initialize MP3 file decoder
-                if self.mp3_decoder.open_cb(stream) == 0 {
-                    // Valid MP3 file?
-                    if self.mp3_decoder.info.channels > 0 && self.mp3_decoder.info.channels <= 2 {
-                        // Indicate audio track is loaded
-                        self.track_index = index;
-                        
-                        // Start audio playback
-                        self.playback_enabled = true;
-                        
-                        // Set playback loop mode
-                        self.playback_loop = playback_loop;
-                        
-                        // Store current stream
-                        self.current_stream = Some(CdStream::open(&full_path).unwrap());
-                    } else {
-                        // Close stream if not a valid MP3
-                        // Stream will be closed when dropped
-                    }
-                }
-                
-                // Exit loop when MP3 file has been found
-                break;
-            }
+        let Some(path) = (1..=4)
+            .map(|width| self.track_dir.join(format!("{:0width$}.mp3", index, width = width)))
+            .find(|path| path.is_file())
+        else {
+            return;
+        };
+        if let Some(track) = Track::open(path) {
+            self.track = Some(track);
+            self.track_index = index;
+            self.playback_enabled = true;
+            self.playback_loop = playback_loop;
         }
     }
 
     fn unload_track(&mut self) {
-        // Check audio track is loaded
-        if self.track_index != 0 {
-            // Close MP3 file decoder
-            self.mp3_decoder.close();
-            
-            // Close MP3 file stream
-            self.current_stream = None;
-            
-            // Stop audio playback
-            self.playback_enabled = false;
-            
-            // No audio track loaded
-            self.track_index = 0;
-        }
+        self.track = None;
+        self.playback_enabled = false;
+        self.track_index = 0;
     }
 }
 
-// Interface functions for C compatibility (FFI)
-pub mod ffi {
+#[cfg(test)]
+mod tests {
     use super::*;
-    use std::ffi::CStr;
-    use std::os::raw::c_char;
-    
-    #[no_mangle]
-    pub extern "C" fn yx5200_init(yx5200: &mut Yx5200, samplerate: u32, sound: &mut Sound) {
-        yx5200.init(samplerate, sound);
-    }
-    
-    #[no_mangle]
-    pub extern "C" fn yx5200_reset(yx5200: &mut Yx5200) {
-        yx5200.reset();
-    }
-    
-    #[no_mangle]
-    pub extern "C" fn yx5200_write(yx5200: &mut Yx5200, rx_data: u8) {
-        yx5200.write(rx_data);
-    }
-    
-    #[no_mangle]
-    pub extern "C" fn yx5200_update(yx5200: &mut Yx5200, samples: u32, sound: &mut Sound) {
-        yx5200.update(samples, sound);
-    }
-    
-    #[no_mangle]
-    pub extern "C" fn yx5200_create(rom_path: *const c_char) -> *mut Yx5200 {
-        let rom_path_cstr = unsafe { CStr::from_ptr(rom_path) };
-        let rom_path_str = rom_path_cstr.to_str().unwrap_or("");
-        Box::into_raw(Box::new(Yx5200::new(rom_path_str)))
-    }
-    
-    #[no_mangle]
-    pub extern "C" fn yx5200_destroy(yx5200: *mut Yx5200) {
-        if !yx5200.is_null() {
-            unsafe { Box::from_raw(yx5200); }
+
+    /// Envia uma mensagem pela UART, bit a bit
+    fn send(yx: &mut Yx5200, cmd: u8, param: u16) {
+        let [hi, lo] = param.to_be_bytes();
+        for byte in [0x7E, 0xFF, 0x06, cmd, 0x00, hi, lo, 0x00, 0x00, 0xEF] {
+            yx.write(0);
+            for bit in 0..8 {
+                yx.write(byte >> bit);
+            }
+            yx.write(1);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_uart_commands_and_silent_frame() {
+        let mut yx = Yx5200::new(std::env::temp_dir().join("yx5200-sem-faixas"));
+        send(&mut yx, 0x06, 12);
+        assert_eq!(yx.volume(), 12);
+        send(&mut yx, 0x04, 0);
+        assert_eq!(yx.volume(), 13);
+
+        // Faixa inexistente: nada é carregado
+        send(&mut yx, 0x03, 5);
+        assert_eq!(yx.track_index(), 0);
+        send(&mut yx, 0x0C, 0);
+        assert_eq!(yx.volume(), 30);
+
+        // Sem faixa o quadro é fechado em silêncio, a 48 kHz
+        let mut sound = Sound::new(44_100, 53_693_175);
+        yx.update(&mut sound, 735);
+        assert_eq!(sound.blips[BLIP_CART].samples_avail(), 735);
+
+        let mut state = Vec::new();
+        yx.context_save(&mut state);
+        assert_eq!(yx.context_load(&state), Some(state.len()));
+        assert_eq!(yx.context_load(&state[1..]), None);
+    }
+}
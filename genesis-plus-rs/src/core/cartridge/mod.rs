@@ -0,0 +1,10 @@
+//! Hardware extra dos cartuchos.
+//!
+//! Só os chips já portados para a nova estrutura do núcleo são compilados;
+//! os demais arquivos de `chips/` ainda usam a interface antiga.
+
+pub mod chips {
+    pub mod yx5200;
+
+    pub use yx5200::Yx5200;
+}
@@ -0,0 +1,2576 @@
+//! Interpretador do Motorola 68000 com contagem de ciclos por instrução.
+//! Baseado em `m68kcpu.c`, `m68kcpu.h` e `m68kops.h` (Musashi) do Genesis Plus GX.
+//!
+//! O núcleo executa uma instrução por vez a partir do `MemoryBus`, acumulando
+//! o tempo de cada instrução (tempo base + cálculo de endereço efetivo) em
+//! ciclos de CPU do 68000. Cada instrução executada é reportada ao barramento
+//! via `MemoryBus::add_cycles`.
+
+use crate::core::memory::MemoryBus;
+use log::{trace, warn};
+
+/// Vetores de exceção do 68000
+pub const EXCEPTION_RESET: u32 = 0;
+pub const EXCEPTION_BUS_ERROR: u32 = 2;
+pub const EXCEPTION_ADDRESS_ERROR: u32 = 3;
+pub const EXCEPTION_ILLEGAL_INSTRUCTION: u32 = 4;
+pub const EXCEPTION_ZERO_DIVIDE: u32 = 5;
+pub const EXCEPTION_CHK: u32 = 6;
+pub const EXCEPTION_TRAPV: u32 = 7;
+pub const EXCEPTION_PRIVILEGE_VIOLATION: u32 = 8;
+pub const EXCEPTION_TRACE: u32 = 9;
+pub const EXCEPTION_1010: u32 = 10;
+pub const EXCEPTION_1111: u32 = 11;
+pub const EXCEPTION_UNINITIALIZED_INTERRUPT: u32 = 15;
+pub const EXCEPTION_INTERRUPT_AUTOVECTOR: u32 = 24;
+pub const EXCEPTION_TRAP_BASE: u32 = 32;
+
+/// Tempo de processamento das exceções (MC68000 User Manual, apêndice D,
+/// com os valores medidos do Genesis Plus GX para CHK e divisão por zero)
+const CYC_RESET: u32 = 40;
+const CYC_GROUP0: u32 = 50;
+const CYC_GROUP2: u32 = 34;
+const CYC_ZERO_DIVIDE: u32 = 38;
+const CYC_CHK: u32 = 38;
+
+/// Latência do ciclo de reconhecimento de interrupção autovetorada:
+/// 44 ciclos + espera pelo clock E (CPU / 10), ver `m68ki_cycle_interrupts`
+const CYC_INTERRUPT: [u32; 10] = [50, 59, 58, 57, 56, 55, 54, 53, 52, 51];
+
+/// Tamanho de operando
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Size {
+    Byte,
+    Word,
+    Long,
+}
+
+impl Size {
+    /// Decodifica o campo de tamanho padrão (bits 7-6): 00=B, 01=W, 10=L
+    fn from_bits(bits: u16) -> Option<Size> {
+        match bits & 3 {
+            0 => Some(Size::Byte),
+            1 => Some(Size::Word),
+            2 => Some(Size::Long),
+            _ => None,
+        }
+    }
+
+    fn mask(self) -> u32 {
+        match self {
+            Size::Byte => 0xFF,
+            Size::Word => 0xFFFF,
+            Size::Long => 0xFFFF_FFFF,
+        }
+    }
+
+    fn msb(self) -> u32 {
+        match self {
+            Size::Byte => 0x80,
+            Size::Word => 0x8000,
+            Size::Long => 0x8000_0000,
+        }
+    }
+
+    fn bits(self) -> u32 {
+        match self {
+            Size::Byte => 8,
+            Size::Word => 16,
+            Size::Long => 32,
+        }
+    }
+
+    fn is_long(self) -> bool {
+        self == Size::Long
+    }
+}
+
+/// Endereço efetivo já resolvido
+#[derive(Debug, Clone, Copy)]
+enum Ea {
+    DataReg(usize),
+    AddrReg(usize),
+    Memory(u32),
+    Immediate(u32),
+}
+
+/// Exceções do grupo 0 que abortam a instrução em andamento
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    /// Acesso de palavra/longword em endereço ímpar
+    Address { addr: u32, write: bool, instruction: bool },
+    /// Ciclo de barramento terminado com /BERR
+    Bus { addr: u32, write: bool, instruction: bool },
+}
+
+type CpuResult<T> = Result<T, Fault>;
+
+/// Estado de parada da CPU
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunState {
+    /// Executando normalmente
+    Running,
+    /// Parado pela instrução STOP, aguardando interrupção
+    Stopped,
+    /// Linha HALT ativa ou falha dupla (erro de endereço durante exceção de grupo 0)
+    Halted,
+}
+
+/// Classes de modo de endereçamento (MC68000 PRM, tabela 2-4)
+const EA_DATA: u16 = 1 << 0;
+const EA_MEMORY: u16 = 1 << 1;
+const EA_CONTROL: u16 = 1 << 2;
+const EA_ALTERABLE: u16 = 1 << 3;
+
+/// Processador Motorola 68000
+pub struct M68K {
+    /// Registradores de dados D0-D7
+    pub d: [u32; 8],
+    /// Registradores de endereço A0-A7 (A7 é o stack pointer ativo)
+    pub a: [u32; 8],
+    /// Stack pointer inativo (USP em modo supervisor, SSP em modo usuário)
+    pub inactive_sp: u32,
+    /// Program counter
+    pub pc: u32,
+
+    // Status register desmembrado
+    pub flag_t: bool,
+    pub flag_s: bool,
+    pub int_mask: u8,
+    pub flag_x: bool,
+    pub flag_n: bool,
+    pub flag_z: bool,
+    pub flag_v: bool,
+    pub flag_c: bool,
+
+    /// Nível da linha de interrupção (IPL0-2)
+    pub int_level: u8,
+    /// Estado de execução (STOP/HALT)
+    pub run_state: RunState,
+    /// Registrador de instrução (último opcode lido)
+    pub ir: u16,
+    /// Ciclos totais executados pela CPU
+    pub cycles: u64,
+
+    /// Processando exceção de grupo 0 ou reset (erro aqui = HALT)
+    group0_pending: bool,
+    /// Ciclos acumulados na instrução corrente
+    cyc: u32,
+    /// PC do início da instrução corrente
+    instr_pc: u32,
+}
+
+impl M68K {
+    /// Cria uma nova CPU em estado de reset
+    pub fn new() -> Self {
+        Self {
+            d: [0; 8],
+            a: [0; 8],
+            inactive_sp: 0,
+            pc: 0,
+            flag_t: false,
+            flag_s: true,
+            int_mask: 7,
+            flag_x: false,
+            flag_n: false,
+            flag_z: false,
+            flag_v: false,
+            flag_c: false,
+            int_level: 0,
+            run_state: RunState::Running,
+            ir: 0,
+            cycles: 0,
+            group0_pending: false,
+            cyc: 0,
+            instr_pc: 0,
+        }
+    }
+
+    /// Pulso na linha RESET: lê SSP e PC dos vetores 0 e 1
+    pub fn reset(&mut self, bus: &mut MemoryBus) {
+        self.run_state = RunState::Running;
+        self.flag_t = false;
+        self.int_mask = 7;
+        self.int_level = 0;
+        if !self.flag_s {
+            self.swap_sp();
+        }
+        self.flag_s = true;
+
+        self.group0_pending = true;
+        self.a[7] = read_long_raw(bus, 0);
+        self.pc = read_long_raw(bus, 4);
+        self.group0_pending = false;
+
+        self.cycles += CYC_RESET as u64;
+        bus.add_cycles(CYC_RESET);
+    }
+
+    /// Ativa a linha HALT
+    pub fn pulse_halt(&mut self) {
+        self.run_state = RunState::Halted;
+    }
+
+    /// Libera a linha HALT
+    pub fn clear_halt(&mut self) {
+        if self.run_state == RunState::Halted {
+            self.run_state = RunState::Running;
+        }
+    }
+
+    /// Define o nível de interrupção pendente (0 = nenhum, 7 = NMI)
+    pub fn set_irq(&mut self, level: u8) {
+        self.int_level = level & 7;
+    }
+
+    /// Gera um erro de barramento externo (/BERR) no endereço indicado
+    pub fn pulse_bus_error(&mut self, bus: &mut MemoryBus, addr: u32, write: bool) {
+        let fault = Fault::Bus { addr, write, instruction: false };
+        let cycles = self.group0_exception(bus, fault);
+        self.cycles += cycles as u64;
+        bus.add_cycles(cycles);
+    }
+
+    /// Executa instruções até consumir pelo menos `cycles` ciclos do 68000.
+    /// Retorna o número de ciclos efetivamente executados.
+    pub fn execute(&mut self, bus: &mut MemoryBus, cycles: u32) -> u32 {
+        let mut done = 0;
+        while done < cycles {
+            if self.run_state == RunState::Halted {
+                // CPU travada: o tempo passa sem atividade de barramento
+                let idle = cycles - done;
+                self.cycles += idle as u64;
+                bus.add_cycles(idle);
+                return cycles;
+            }
+            let used = self.step(bus);
+            self.cycles += used as u64;
+            bus.add_cycles(used);
+            done += used;
+        }
+        done
+    }
+
+    /// Executa uma única instrução (ou processa uma interrupção pendente)
+    /// e retorna o número de ciclos gastos.
+    pub fn step(&mut self, bus: &mut MemoryBus) -> u32 {
+        // CPU parada pelo barramento (ex.: DMA do VDP em andamento)
+        if bus.m68k_wait > 0 {
+            return std::mem::take(&mut bus.m68k_wait);
+        }
+
+        self.cyc = 0;
+
+        // Interrupção pendente acima da máscara atual (VDP ou externa)
+        let level = self.int_level.max(bus.m68k_irq_level());
+        if level > self.int_mask {
+            if let Err(fault) = self.interrupt(bus, level) {
+                self.cyc += self.group0_exception(bus, fault);
+            }
+            return self.cyc;
+        }
+
+        match self.run_state {
+            RunState::Halted => return 4,
+            RunState::Stopped => return 4,
+            RunState::Running => {}
+        }
+
+        let trace = self.flag_t;
+        self.instr_pc = self.pc;
+        let result = self.fetch_opcode(bus).and_then(|op| {
+            self.ir = op;
+            self.execute_opcode(bus, op)
+        });
+
+        match result {
+            Ok(()) => {
+                if trace && self.run_state == RunState::Running {
+                    if let Err(fault) = self.exception(bus, EXCEPTION_TRACE, self.pc) {
+                        self.cyc = self.group0_exception(bus, fault);
+                    } else {
+                        self.cyc += CYC_GROUP2;
+                    }
+                }
+            }
+            Err(fault) => {
+                self.cyc = self.group0_exception(bus, fault);
+            }
+        }
+
+        // Estados de espera inseridos pelo barramento (ex.: acesso ao Z80)
+        self.cyc += std::mem::take(&mut bus.m68k_wait);
+
+        // Acesso sem /DTACK: a CPU fica travada até o próximo reset
+        if std::mem::take(&mut bus.m68k_lockup) {
+            self.pulse_halt();
+        }
+        self.cyc
+    }
+
+    // --- Status register ---
+
+    /// Monta o status register
+    pub fn sr(&self) -> u16 {
+        (self.flag_t as u16) << 15
+            | (self.flag_s as u16) << 13
+            | (self.int_mask as u16) << 8
+            | self.ccr() as u16
+    }
+
+    /// Monta o condition code register
+    pub fn ccr(&self) -> u8 {
+        (self.flag_x as u8) << 4
+            | (self.flag_n as u8) << 3
+            | (self.flag_z as u8) << 2
+            | (self.flag_v as u8) << 1
+            | self.flag_c as u8
+    }
+
+    /// Carrega o condition code register
+    pub fn set_ccr(&mut self, value: u8) {
+        self.flag_x = value & 0x10 != 0;
+        self.flag_n = value & 0x08 != 0;
+        self.flag_z = value & 0x04 != 0;
+        self.flag_v = value & 0x02 != 0;
+        self.flag_c = value & 0x01 != 0;
+    }
+
+    /// Carrega o status register (troca de stack pointer se o bit S mudar)
+    pub fn set_sr(&mut self, value: u16) {
+        self.flag_t = value & 0x8000 != 0;
+        self.int_mask = ((value >> 8) & 7) as u8;
+        self.set_ccr(value as u8);
+        let s = value & 0x2000 != 0;
+        if s != self.flag_s {
+            self.swap_sp();
+            self.flag_s = s;
+        }
+    }
+
+    /// User stack pointer
+    pub fn usp(&self) -> u32 {
+        if self.flag_s { self.inactive_sp } else { self.a[7] }
+    }
+
+    /// Supervisor stack pointer
+    pub fn ssp(&self) -> u32 {
+        if self.flag_s { self.a[7] } else { self.inactive_sp }
+    }
+
+    fn swap_sp(&mut self) {
+        std::mem::swap(&mut self.a[7], &mut self.inactive_sp);
+    }
+
+    /// Avalia uma condição (campo de 4 bits de Bcc/DBcc/Scc)
+    fn condition(&self, cc: u16) -> bool {
+        match cc & 0xF {
+            0x0 => true,                                        // T
+            0x1 => false,                                       // F
+            0x2 => !self.flag_c && !self.flag_z,                // HI
+            0x3 => self.flag_c || self.flag_z,                  // LS
+            0x4 => !self.flag_c,                                // CC
+            0x5 => self.flag_c,                                 // CS
+            0x6 => !self.flag_z,                                // NE
+            0x7 => self.flag_z,                                 // EQ
+            0x8 => !self.flag_v,                                // VC
+            0x9 => self.flag_v,                                 // VS
+            0xA => !self.flag_n,                                // PL
+            0xB => self.flag_n,                                 // MI
+            0xC => self.flag_n == self.flag_v,                  // GE
+            0xD => self.flag_n != self.flag_v,                  // LT
+            0xE => !self.flag_z && self.flag_n == self.flag_v,  // GT
+            _ => self.flag_z || self.flag_n != self.flag_v,     // LE
+        }
+    }
+
+    /// Atualiza N e Z e limpa V e C (resultado de operações lógicas/MOVE)
+    fn set_logic_flags(&mut self, res: u32, size: Size) {
+        let res = res & size.mask();
+        self.flag_n = res & size.msb() != 0;
+        self.flag_z = res == 0;
+        self.flag_v = false;
+        self.flag_c = false;
+    }
+
+    // --- Acesso à memória ---
+
+    fn read_byte(&mut self, bus: &mut MemoryBus, addr: u32) -> CpuResult<u8> {
+        Ok(bus.read_byte(addr))
+    }
+
+    fn read_word(&mut self, bus: &mut MemoryBus, addr: u32) -> CpuResult<u16> {
+        if addr & 1 != 0 {
+            return Err(Fault::Address { addr, write: false, instruction: false });
+        }
+        Ok(bus.read_word(addr))
+    }
+
+    fn read_long(&mut self, bus: &mut MemoryBus, addr: u32) -> CpuResult<u32> {
+        let high = self.read_word(bus, addr)? as u32;
+        let low = self.read_word(bus, addr.wrapping_add(2))? as u32;
+        Ok(high << 16 | low)
+    }
+
+    fn write_byte(&mut self, bus: &mut MemoryBus, addr: u32, value: u8) -> CpuResult<()> {
+        bus.write_byte(addr, value);
+        Ok(())
+    }
+
+    fn write_word(&mut self, bus: &mut MemoryBus, addr: u32, value: u16) -> CpuResult<()> {
+        if addr & 1 != 0 {
+            return Err(Fault::Address { addr, write: true, instruction: false });
+        }
+        bus.write_word(addr, value);
+        Ok(())
+    }
+
+    fn write_long(&mut self, bus: &mut MemoryBus, addr: u32, value: u32) -> CpuResult<()> {
+        self.write_word(bus, addr, (value >> 16) as u16)?;
+        self.write_word(bus, addr.wrapping_add(2), value as u16)
+    }
+
+    fn read_sized(&mut self, bus: &mut MemoryBus, addr: u32, size: Size) -> CpuResult<u32> {
+        match size {
+            Size::Byte => self.read_byte(bus, addr).map(u32::from),
+            Size::Word => self.read_word(bus, addr).map(u32::from),
+            Size::Long => self.read_long(bus, addr),
+        }
+    }
+
+    fn write_sized(&mut self, bus: &mut MemoryBus, addr: u32, size: Size, value: u32) -> CpuResult<()> {
+        match size {
+            Size::Byte => self.write_byte(bus, addr, value as u8),
+            Size::Word => self.write_word(bus, addr, value as u16),
+            Size::Long => self.write_long(bus, addr, value),
+        }
+    }
+
+    /// Lê o opcode no PC
+    fn fetch_opcode(&mut self, bus: &mut MemoryBus) -> CpuResult<u16> {
+        if self.pc & 1 != 0 {
+            return Err(Fault::Address { addr: self.pc, write: false, instruction: true });
+        }
+        let op = bus.read_word(self.pc);
+        bus.open_bus = op;
+        self.pc = self.pc.wrapping_add(2);
+        Ok(op)
+    }
+
+    /// Lê uma palavra de extensão no PC
+    fn fetch_word(&mut self, bus: &mut MemoryBus) -> CpuResult<u16> {
+        self.fetch_opcode(bus)
+    }
+
+    fn fetch_long(&mut self, bus: &mut MemoryBus) -> CpuResult<u32> {
+        let high = self.fetch_word(bus)? as u32;
+        let low = self.fetch_word(bus)? as u32;
+        Ok(high << 16 | low)
+    }
+
+    fn push_word(&mut self, bus: &mut MemoryBus, value: u16) -> CpuResult<()> {
+        self.a[7] = self.a[7].wrapping_sub(2);
+        self.write_word(bus, self.a[7], value)
+    }
+
+    fn push_long(&mut self, bus: &mut MemoryBus, value: u32) -> CpuResult<()> {
+        self.a[7] = self.a[7].wrapping_sub(4);
+        self.write_long(bus, self.a[7], value)
+    }
+
+    fn pop_word(&mut self, bus: &mut MemoryBus) -> CpuResult<u16> {
+        let value = self.read_word(bus, self.a[7])?;
+        self.a[7] = self.a[7].wrapping_add(2);
+        Ok(value)
+    }
+
+    fn pop_long(&mut self, bus: &mut MemoryBus) -> CpuResult<u32> {
+        let value = self.read_long(bus, self.a[7])?;
+        self.a[7] = self.a[7].wrapping_add(4);
+        Ok(value)
+    }
+
+    /// Desvio com verificação de endereço ímpar (detectado na busca seguinte)
+    fn jump(&mut self, addr: u32) {
+        self.pc = addr;
+    }
+
+    // --- Exceções ---
+
+    /// Processamento comum das exceções de grupo 1/2: empilha PC e SR e
+    /// carrega o novo PC do vetor.
+    fn exception(&mut self, bus: &mut MemoryBus, vector: u32, return_pc: u32) -> CpuResult<()> {
+        let sr = self.sr();
+        self.flag_t = false;
+        if !self.flag_s {
+            self.swap_sp();
+            self.flag_s = true;
+        }
+        self.push_long(bus, return_pc)?;
+        self.push_word(bus, sr)?;
+        let new_pc = self.read_long(bus, vector << 2)?;
+        self.jump(new_pc);
+        Ok(())
+    }
+
+    /// Exceções de armadilha geradas por instruções (TRAP, TRAPV, CHK, divisão por zero)
+    fn trap(&mut self, bus: &mut MemoryBus, vector: u32, cycles: u32) -> CpuResult<()> {
+        self.exception(bus, vector, self.pc)?;
+        self.cyc += cycles;
+        Ok(())
+    }
+
+    /// Instrução ilegal, linha A/F e violação de privilégio: PC aponta para a instrução
+    fn illegal(&mut self, bus: &mut MemoryBus, vector: u32) -> CpuResult<()> {
+        trace!("M68K: exceção {} em {:06X} (opcode {:04X})", vector, self.instr_pc, self.ir);
+        self.exception(bus, vector, self.instr_pc)?;
+        self.cyc = CYC_GROUP2;
+        Ok(())
+    }
+
+    /// Verifica o bit S para instruções privilegiadas
+    fn require_supervisor(&mut self, bus: &mut MemoryBus) -> CpuResult<bool> {
+        if self.flag_s {
+            Ok(true)
+        } else {
+            self.illegal(bus, EXCEPTION_PRIVILEGE_VIOLATION)?;
+            Ok(false)
+        }
+    }
+
+    /// Erro de endereço/barramento: quadro de 7 palavras. Uma falha durante
+    /// o processamento de outra exceção de grupo 0 trava a CPU (double fault).
+    fn group0_exception(&mut self, bus: &mut MemoryBus, fault: Fault) -> u32 {
+        let (vector, addr, write, instruction) = match fault {
+            Fault::Address { addr, write, instruction } => (EXCEPTION_ADDRESS_ERROR, addr, write, instruction),
+            Fault::Bus { addr, write, instruction } => (EXCEPTION_BUS_ERROR, addr, write, instruction),
+        };
+
+        if self.group0_pending {
+            warn!("M68K: falha dupla em {:06X}, CPU travada", addr);
+            self.run_state = RunState::Halted;
+            return 4;
+        }
+        self.group0_pending = true;
+
+        let sr = self.sr();
+        self.flag_t = false;
+        if !self.flag_s {
+            self.swap_sp();
+            self.flag_s = true;
+        }
+
+        // Palavra de status: R/W (bit 4), I/N (bit 3), código de função (bits 2-0)
+        let fc = (if sr & 0x2000 != 0 { 4 } else { 0 }) | (if instruction { 2 } else { 1 });
+        let status = (!write as u16) << 4 | (!instruction as u16) << 3 | fc;
+        let ir = self.ir;
+        let pc = self.pc;
+
+        let frame = (|| -> CpuResult<u32> {
+            self.push_long(bus, pc)?;
+            self.push_word(bus, sr)?;
+            self.push_word(bus, ir)?;
+            self.push_long(bus, addr)?;
+            self.push_word(bus, status)?;
+            self.read_long(bus, vector << 2)
+        })();
+
+        match frame {
+            Ok(new_pc) => {
+                self.jump(new_pc);
+                self.group0_pending = false;
+                CYC_GROUP0
+            }
+            Err(_) => {
+                warn!("M68K: falha dupla em {:06X}, CPU travada", addr);
+                self.run_state = RunState::Halted;
+                4
+            }
+        }
+    }
+
+    /// Atende uma interrupção autovetorada
+    fn interrupt(&mut self, bus: &mut MemoryBus, level: u8) -> CpuResult<()> {
+        // Interrupção libera o estado STOP, mas não HALT
+        match self.run_state {
+            RunState::Halted => {
+                self.cyc = 4;
+                return Ok(());
+            }
+            RunState::Stopped => self.run_state = RunState::Running,
+            RunState::Running => {}
+        }
+
+        let sr = self.sr();
+        self.flag_t = false;
+        if !self.flag_s {
+            self.swap_sp();
+            self.flag_s = true;
+        }
+        self.int_mask = level;
+
+        // Ciclo de reconhecimento: o VDP limpa a interrupção atendida
+        bus.m68k_int_ack(level);
+        if self.int_level <= level {
+            self.int_level = 0;
+        }
+
+        let vector = EXCEPTION_INTERRUPT_AUTOVECTOR + level as u32;
+        let mut new_pc = self.read_long(bus, vector << 2)?;
+        if new_pc == 0 {
+            new_pc = self.read_long(bus, EXCEPTION_UNINITIALIZED_INTERRUPT << 2)?;
+        }
+        self.push_long(bus, self.pc)?;
+        self.push_word(bus, sr)?;
+        self.jump(new_pc);
+
+        self.cyc = CYC_INTERRUPT[(self.cycles % 10) as usize];
+        Ok(())
+    }
+
+    // --- Endereço efetivo ---
+
+    /// Verifica se o modo de endereçamento pertence às classes pedidas
+    fn ea_valid(mode: u16, reg: u16, classes: u16) -> bool {
+        let (data, memory, control, alterable) = match mode {
+            0 => (true, false, false, true),
+            1 => (false, false, false, true),
+            2 => (true, true, true, true),
+            3 | 4 => (true, true, false, true),
+            5 | 6 => (true, true, true, true),
+            7 => match reg {
+                0 | 1 => (true, true, true, true),
+                2 | 3 => (true, true, true, false),
+                4 => (true, true, false, false),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        (classes & EA_DATA == 0 || data)
+            && (classes & EA_MEMORY == 0 || memory)
+            && (classes & EA_CONTROL == 0 || control)
+            && (classes & EA_ALTERABLE == 0 || alterable)
+    }
+
+    /// Tempo de cálculo de endereço efetivo (MC68000 UM, tabela 8-1)
+    fn ea_cycles(mode: u16, reg: u16, size: Size) -> u32 {
+        let long = if size.is_long() { 4 } else { 0 };
+        match mode {
+            0 | 1 => 0,
+            2 | 3 => 4 + long,
+            4 => 6 + long,
+            5 => 8 + long,
+            6 => 10 + long,
+            _ => match reg {
+                0 => 8 + long,
+                1 => 12 + long,
+                2 => 8 + long,
+                3 => 10 + long,
+                4 => 4 + long,
+                _ => 0,
+            },
+        }
+    }
+
+    /// Calcula o endereço do modo d8(An,Xn)/d8(PC,Xn)
+    fn index_address(&mut self, bus: &mut MemoryBus, base: u32) -> CpuResult<u32> {
+        let ext = self.fetch_word(bus)?;
+        let reg = ((ext >> 12) & 7) as usize;
+        let mut index = if ext & 0x8000 != 0 { self.a[reg] } else { self.d[reg] };
+        if ext & 0x0800 == 0 {
+            index = index as u16 as i16 as i32 as u32;
+        }
+        let disp = ext as u8 as i8 as i32 as u32;
+        Ok(base.wrapping_add(disp).wrapping_add(index))
+    }
+
+    /// Resolve um endereço efetivo, aplicando pós-incremento/pré-decremento
+    /// e somando o tempo padrão de cálculo.
+    fn ea(&mut self, bus: &mut MemoryBus, mode: u16, reg: u16, size: Size) -> CpuResult<Ea> {
+        self.cyc += Self::ea_cycles(mode, reg, size);
+        let r = reg as usize;
+        Ok(match mode {
+            0 => Ea::DataReg(r),
+            1 => Ea::AddrReg(r),
+            2 => Ea::Memory(self.a[r]),
+            3 => {
+                let addr = self.a[r];
+                // A7 é sempre mantido alinhado em word
+                let step = if size == Size::Byte && r == 7 { 2 } else { size.bits() / 8 };
+                self.a[r] = addr.wrapping_add(step);
+                Ea::Memory(addr)
+            }
+            4 => {
+                let step = if size == Size::Byte && r == 7 { 2 } else { size.bits() / 8 };
+                self.a[r] = self.a[r].wrapping_sub(step);
+                Ea::Memory(self.a[r])
+            }
+            5 => {
+                let disp = self.fetch_word(bus)? as i16 as i32 as u32;
+                Ea::Memory(self.a[r].wrapping_add(disp))
+            }
+            6 => Ea::Memory(self.index_address(bus, self.a[r])?),
+            _ => match reg {
+                0 => Ea::Memory(self.fetch_word(bus)? as i16 as i32 as u32),
+                1 => Ea::Memory(self.fetch_long(bus)?),
+                2 => {
+                    let base = self.pc;
+                    let disp = self.fetch_word(bus)? as i16 as i32 as u32;
+                    Ea::Memory(base.wrapping_add(disp))
+                }
+                3 => {
+                    let base = self.pc;
+                    Ea::Memory(self.index_address(bus, base)?)
+                }
+                _ => match size {
+                    Size::Byte => Ea::Immediate(self.fetch_word(bus)? as u32 & 0xFF),
+                    Size::Word => Ea::Immediate(self.fetch_word(bus)? as u32),
+                    Size::Long => Ea::Immediate(self.fetch_long(bus)?),
+                },
+            },
+        })
+    }
+
+    /// Endereço de controle (LEA, PEA, JMP, JSR, MOVEM) sem somar ciclos
+    fn control_address(&mut self, bus: &mut MemoryBus, mode: u16, reg: u16) -> CpuResult<u32> {
+        match self.ea(bus, mode, reg, Size::Long)? {
+            Ea::Memory(addr) => Ok(addr),
+            _ => unreachable!("modo de controle sem endereço"),
+        }
+    }
+
+    /// Tempo adicional de LEA/PEA/JMP/JSR por modo (índices: (An), d16, d8+Xn, abs.W, abs.L)
+    fn control_cycles(mode: u16, reg: u16, table: [u32; 5]) -> u32 {
+        match (mode, reg) {
+            (2, _) => table[0],
+            (5, _) | (7, 2) => table[1],
+            (6, _) | (7, 3) => table[2],
+            (7, 0) => table[3],
+            _ => table[4],
+        }
+    }
+
+    fn read_ea(&mut self, bus: &mut MemoryBus, ea: Ea, size: Size) -> CpuResult<u32> {
+        Ok(match ea {
+            Ea::DataReg(r) => self.d[r] & size.mask(),
+            Ea::AddrReg(r) => self.a[r] & size.mask(),
+            Ea::Memory(addr) => self.read_sized(bus, addr, size)?,
+            Ea::Immediate(value) => value & size.mask(),
+        })
+    }
+
+    fn write_ea(&mut self, bus: &mut MemoryBus, ea: Ea, size: Size, value: u32) -> CpuResult<()> {
+        match ea {
+            Ea::DataReg(r) => {
+                self.d[r] = (self.d[r] & !size.mask()) | (value & size.mask());
+            }
+            Ea::AddrReg(r) => {
+                self.a[r] = match size {
+                    Size::Long => value,
+                    _ => value as u16 as i16 as i32 as u32,
+                };
+            }
+            Ea::Memory(addr) => self.write_sized(bus, addr, size, value)?,
+            Ea::Immediate(_) => {}
+        }
+        Ok(())
+    }
+
+    // --- Decodificação ---
+
+    fn execute_opcode(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        match op >> 12 {
+            0x0 => self.op_group0(bus, op),
+            0x1 => self.op_move(bus, op, Size::Byte),
+            0x2 => self.op_move(bus, op, Size::Long),
+            0x3 => self.op_move(bus, op, Size::Word),
+            0x4 => self.op_group4(bus, op),
+            0x5 => self.op_group5(bus, op),
+            0x6 => self.op_branch(bus, op),
+            0x7 => self.op_moveq(op),
+            0x8 => self.op_group8(bus, op),
+            0x9 => self.op_addsub(bus, op, false),
+            0xA => self.illegal(bus, EXCEPTION_1010),
+            0xB => self.op_groupb(bus, op),
+            0xC => self.op_groupc(bus, op),
+            0xD => self.op_addsub(bus, op, true),
+            0xE => self.op_shift(bus, op),
+            _ => self.illegal(bus, EXCEPTION_1111),
+        }
+    }
+
+    /// Grupo 0: imediatos, operações de bit e MOVEP
+    fn op_group0(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+
+        if op & 0x0100 != 0 {
+            if mode == 1 {
+                return self.op_movep(bus, op);
+            }
+            // Operação de bit dinâmica (número do bit em Dn)
+            let bit = self.d[((op >> 9) & 7) as usize];
+            return self.op_bit(bus, op, bit);
+        }
+
+        let kind = (op >> 9) & 7;
+        if kind == 4 {
+            // Operação de bit estática (número do bit na palavra de extensão)
+            if mode == 7 && reg == 4 {
+                return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+            }
+            let bit = self.fetch_word(bus)? as u32;
+            self.cyc += 4;
+            return self.op_bit(bus, op, bit);
+        }
+
+        let size = match Size::from_bits(op >> 6) {
+            Some(size) => size,
+            None => return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        };
+
+        // ORI/ANDI/EORI para CCR e SR
+        if mode == 7 && reg == 4 && matches!(kind, 0 | 1 | 5) {
+            return match size {
+                Size::Byte => {
+                    let imm = self.fetch_word(bus)? as u8;
+                    let ccr = self.ccr();
+                    self.set_ccr(match kind {
+                        0 => ccr | imm,
+                        1 => ccr & imm,
+                        _ => ccr ^ imm,
+                    });
+                    self.cyc += 20;
+                    Ok(())
+                }
+                Size::Word => {
+                    if !self.require_supervisor(bus)? {
+                        return Ok(());
+                    }
+                    let imm = self.fetch_word(bus)?;
+                    let sr = self.sr();
+                    self.set_sr(match kind {
+                        0 => sr | imm,
+                        1 => sr & imm,
+                        _ => sr ^ imm,
+                    });
+                    self.cyc += 20;
+                    Ok(())
+                }
+                Size::Long => self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+            };
+        }
+
+        let classes = if kind == 6 { EA_DATA } else { EA_DATA | EA_ALTERABLE };
+        if kind == 7 || !Self::ea_valid(mode, reg, classes) || kind == 6 && mode == 7 && reg >= 2 {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        let imm = match size {
+            Size::Long => self.fetch_long(bus)?,
+            _ => self.fetch_word(bus)? as u32 & size.mask(),
+        };
+        let ea = self.ea(bus, mode, reg, size)?;
+        let dst = self.read_ea(bus, ea, size)?;
+
+        let is_reg = mode == 0;
+        self.cyc += match (kind, is_reg, size.is_long()) {
+            (6, true, false) => 8,
+            (6, true, true) => 14,
+            (6, false, false) => 8,
+            (6, false, true) => 12,
+            (_, true, false) => 8,
+            (_, true, true) => 16,
+            (_, false, false) => 12,
+            (_, false, true) => 20,
+        };
+
+        let res = match kind {
+            0 => dst | imm,
+            1 => dst & imm,
+            2 => self.sub_flags(dst, imm, size, true),
+            3 => self.add_flags(dst, imm, size, true),
+            5 => dst ^ imm,
+            _ => {
+                self.sub_flags(dst, imm, size, false);
+                return Ok(());
+            }
+        };
+        if matches!(kind, 0 | 1 | 5) {
+            self.set_logic_flags(res, size);
+        }
+        self.write_ea(bus, ea, size, res)
+    }
+
+    /// BTST/BCHG/BCLR/BSET
+    fn op_bit(&mut self, bus: &mut MemoryBus, op: u16, bit: u32) -> CpuResult<()> {
+        let kind = (op >> 6) & 3;
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let classes = if kind == 0 { EA_DATA } else { EA_DATA | EA_ALTERABLE };
+        if !Self::ea_valid(mode, reg, classes) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        if mode == 0 {
+            // Operação longa em registrador
+            let r = reg as usize;
+            let mask = 1u32 << (bit & 31);
+            self.flag_z = self.d[r] & mask == 0;
+            self.cyc += match kind {
+                0 => 6,
+                1 | 3 => if bit & 31 < 16 { 6 } else { 8 },
+                _ => if bit & 31 < 16 { 8 } else { 10 },
+            };
+            match kind {
+                1 => self.d[r] ^= mask,
+                2 => self.d[r] &= !mask,
+                3 => self.d[r] |= mask,
+                _ => {}
+            }
+            return Ok(());
+        }
+
+        // Operação em byte de memória
+        let ea = self.ea(bus, mode, reg, Size::Byte)?;
+        let value = self.read_ea(bus, ea, Size::Byte)?;
+        let mask = 1u32 << (bit & 7);
+        self.flag_z = value & mask == 0;
+        self.cyc += if kind == 0 { 4 } else { 8 };
+        let res = match kind {
+            1 => value ^ mask,
+            2 => value & !mask,
+            3 => value | mask,
+            _ => return Ok(()),
+        };
+        self.write_ea(bus, ea, Size::Byte, res)
+    }
+
+    /// MOVEP: transferência para periféricos de 8 bits em endereços alternados
+    fn op_movep(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let dreg = ((op >> 9) & 7) as usize;
+        let areg = (op & 7) as usize;
+        let disp = self.fetch_word(bus)? as i16 as i32 as u32;
+        let addr = self.a[areg].wrapping_add(disp);
+        let long = op & 0x40 != 0;
+        let count = if long { 4 } else { 2 };
+
+        if op & 0x80 != 0 {
+            // Registrador para memória
+            let value = self.d[dreg];
+            for i in 0..count {
+                let shift = (count - 1 - i) * 8;
+                self.write_byte(bus, addr.wrapping_add(i * 2), (value >> shift) as u8)?;
+            }
+        } else {
+            let mut value = 0u32;
+            for i in 0..count {
+                value = value << 8 | self.read_byte(bus, addr.wrapping_add(i * 2))? as u32;
+            }
+            if long {
+                self.d[dreg] = value;
+            } else {
+                self.d[dreg] = (self.d[dreg] & 0xFFFF_0000) | value;
+            }
+        }
+        self.cyc += if long { 24 } else { 16 };
+        Ok(())
+    }
+
+    /// MOVE e MOVEA
+    fn op_move(&mut self, bus: &mut MemoryBus, op: u16, size: Size) -> CpuResult<()> {
+        let src_mode = (op >> 3) & 7;
+        let src_reg = op & 7;
+        let dst_reg = (op >> 9) & 7;
+        let dst_mode = (op >> 6) & 7;
+
+        let src_valid = Self::ea_valid(src_mode, src_reg, 0) && !(size == Size::Byte && src_mode == 1);
+        let dst_valid = dst_mode == 1 && size != Size::Byte
+            || Self::ea_valid(dst_mode, dst_reg, EA_DATA | EA_ALTERABLE);
+        if !src_valid || !dst_valid {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        let src_ea = self.ea(bus, src_mode, src_reg, size)?;
+        let value = self.read_ea(bus, src_ea, size)?;
+        self.cyc += 4;
+
+        if dst_mode == 1 {
+            // MOVEA não altera flags e estende o sinal de words
+            self.write_ea(bus, Ea::AddrReg(dst_reg as usize), size, value)?;
+            return Ok(());
+        }
+
+        let dst_ea = self.ea(bus, dst_mode, dst_reg, size)?;
+        if dst_mode == 4 {
+            // -(An) como destino não tem os 2 ciclos extras
+            self.cyc -= 2;
+        }
+        self.set_logic_flags(value, size);
+        self.write_ea(bus, dst_ea, size, value)
+    }
+
+    fn op_moveq(&mut self, op: u16) -> CpuResult<()> {
+        let value = op as u8 as i8 as i32 as u32;
+        self.d[((op >> 9) & 7) as usize] = value;
+        self.set_logic_flags(value, Size::Long);
+        self.cyc += 4;
+        Ok(())
+    }
+
+    /// Grupo 4: instruções diversas
+    fn op_group4(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+
+        // LEA e CHK (bits 8-6 = 111 / 110)
+        if op & 0x0100 != 0 {
+            let an = ((op >> 9) & 7) as usize;
+            return match (op >> 6) & 7 {
+                7 => {
+                    if !Self::ea_valid(mode, reg, EA_CONTROL) {
+                        return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                    }
+                    let addr = self.control_address(bus, mode, reg)?;
+                    self.cyc = Self::control_cycles(mode, reg, [4, 8, 12, 8, 12]);
+                    self.a[an] = addr;
+                    Ok(())
+                }
+                6 => self.op_chk(bus, op),
+                _ => self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+            };
+        }
+
+        match (op >> 8) & 0xF {
+            0x0 => {
+                if (op >> 6) & 3 == 3 {
+                    // MOVE from SR (não privilegiada no 68000)
+                    if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+                        return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                    }
+                    let ea = self.ea(bus, mode, reg, Size::Word)?;
+                    if mode != 0 {
+                        // Ciclo de leitura fictício antes da escrita
+                        self.read_ea(bus, ea, Size::Word)?;
+                    }
+                    self.cyc += if mode == 0 { 6 } else { 8 };
+                    let sr = self.sr() as u32;
+                    self.write_ea(bus, ea, Size::Word, sr)
+                } else {
+                    self.op_unary(bus, op, UnaryOp::Negx)
+                }
+            }
+            0x2 => {
+                if (op >> 6) & 3 == 3 {
+                    self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION)
+                } else {
+                    self.op_unary(bus, op, UnaryOp::Clr)
+                }
+            }
+            0x4 => {
+                if (op >> 6) & 3 == 3 {
+                    // MOVE to CCR
+                    if !Self::ea_valid(mode, reg, EA_DATA) {
+                        return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                    }
+                    let ea = self.ea(bus, mode, reg, Size::Word)?;
+                    let value = self.read_ea(bus, ea, Size::Word)?;
+                    self.set_ccr(value as u8);
+                    self.cyc += 12;
+                    Ok(())
+                } else {
+                    self.op_unary(bus, op, UnaryOp::Neg)
+                }
+            }
+            0x6 => {
+                if (op >> 6) & 3 == 3 {
+                    // MOVE to SR
+                    if !Self::ea_valid(mode, reg, EA_DATA) {
+                        return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                    }
+                    if !self.require_supervisor(bus)? {
+                        return Ok(());
+                    }
+                    let ea = self.ea(bus, mode, reg, Size::Word)?;
+                    let value = self.read_ea(bus, ea, Size::Word)?;
+                    self.set_sr(value as u16);
+                    self.cyc += 12;
+                    Ok(())
+                } else {
+                    self.op_unary(bus, op, UnaryOp::Not)
+                }
+            }
+            0x8 => match (op >> 6) & 3 {
+                0 => self.op_nbcd(bus, op),
+                1 => {
+                    if mode == 0 {
+                        // SWAP
+                        let r = reg as usize;
+                        self.d[r] = self.d[r].rotate_left(16);
+                        self.set_logic_flags(self.d[r], Size::Long);
+                        self.cyc += 4;
+                        Ok(())
+                    } else {
+                        // PEA
+                        if !Self::ea_valid(mode, reg, EA_CONTROL) {
+                            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                        }
+                        let addr = self.control_address(bus, mode, reg)?;
+                        self.cyc = Self::control_cycles(mode, reg, [12, 16, 20, 16, 20]);
+                        self.push_long(bus, addr)
+                    }
+                }
+                size_bit => {
+                    if mode == 0 {
+                        // EXT.W / EXT.L
+                        let r = reg as usize;
+                        if size_bit == 2 {
+                            let value = self.d[r] as u8 as i8 as i16 as u16 as u32;
+                            self.d[r] = (self.d[r] & 0xFFFF_0000) | value;
+                            self.set_logic_flags(value, Size::Word);
+                        } else {
+                            self.d[r] = self.d[r] as u16 as i16 as i32 as u32;
+                            self.set_logic_flags(self.d[r], Size::Long);
+                        }
+                        self.cyc += 4;
+                        Ok(())
+                    } else {
+                        self.op_movem(bus, op)
+                    }
+                }
+            },
+            0xA => {
+                if op == 0x4AFC {
+                    return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                }
+                if (op >> 6) & 3 == 3 {
+                    self.op_tas(bus, op)
+                } else {
+                    self.op_tst(bus, op)
+                }
+            }
+            0xC => {
+                if (op >> 7) & 1 == 1 {
+                    self.op_movem(bus, op)
+                } else {
+                    self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION)
+                }
+            }
+            0xE => self.op_group4e(bus, op),
+            _ => self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        }
+    }
+
+    /// $4E00-$4EFF: TRAP, LINK, UNLK, MOVE USP, controle, JSR, JMP
+    fn op_group4e(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+
+        match (op >> 6) & 3 {
+            2 | 3 => {
+                if !Self::ea_valid(mode, reg, EA_CONTROL) {
+                    return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                }
+                let addr = self.control_address(bus, mode, reg)?;
+                if (op >> 6) & 3 == 2 {
+                    // JSR
+                    self.cyc = Self::control_cycles(mode, reg, [16, 18, 22, 18, 20]);
+                    self.push_long(bus, self.pc)?;
+                } else {
+                    // JMP
+                    self.cyc = Self::control_cycles(mode, reg, [8, 10, 14, 10, 12]);
+                }
+                self.jump(addr);
+                return Ok(());
+            }
+            1 => {}
+            _ => return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        }
+
+        let r = reg as usize;
+        match (op >> 3) & 7 {
+            0 | 1 => {
+                // TRAP #n
+                let vector = EXCEPTION_TRAP_BASE + (op & 0xF) as u32;
+                self.trap(bus, vector, CYC_GROUP2)
+            }
+            2 => {
+                // LINK An,#d16
+                let disp = self.fetch_word(bus)? as i16 as i32 as u32;
+                let value = self.a[r];
+                self.push_long(bus, value)?;
+                self.a[r] = self.a[7];
+                self.a[7] = self.a[7].wrapping_add(disp);
+                self.cyc += 16;
+                Ok(())
+            }
+            3 => {
+                // UNLK An
+                self.a[7] = self.a[r];
+                let value = self.pop_long(bus)?;
+                self.a[r] = value;
+                self.cyc += 12;
+                Ok(())
+            }
+            4 => {
+                // MOVE An,USP
+                if self.require_supervisor(bus)? {
+                    self.inactive_sp = self.a[r];
+                    self.cyc += 4;
+                }
+                Ok(())
+            }
+            5 => {
+                // MOVE USP,An
+                if self.require_supervisor(bus)? {
+                    self.a[r] = self.inactive_sp;
+                    self.cyc += 4;
+                }
+                Ok(())
+            }
+            6 => match reg {
+                0 => {
+                    // RESET: pulso na linha de reset dos periféricos
+                    if self.require_supervisor(bus)? {
+                        self.cyc += 132;
+                    }
+                    Ok(())
+                }
+                1 => {
+                    // NOP
+                    self.cyc += 4;
+                    Ok(())
+                }
+                2 => {
+                    // STOP #imm
+                    if self.require_supervisor(bus)? {
+                        let imm = self.fetch_word(bus)?;
+                        self.set_sr(imm);
+                        self.run_state = RunState::Stopped;
+                        self.cyc += 4;
+                    }
+                    Ok(())
+                }
+                3 => {
+                    // RTE
+                    if self.require_supervisor(bus)? {
+                        let sr = self.pop_word(bus)?;
+                        let pc = self.pop_long(bus)?;
+                        self.set_sr(sr);
+                        self.jump(pc);
+                        self.cyc += 20;
+                    }
+                    Ok(())
+                }
+                5 => {
+                    // RTS
+                    let pc = self.pop_long(bus)?;
+                    self.jump(pc);
+                    self.cyc += 16;
+                    Ok(())
+                }
+                6 => {
+                    // TRAPV
+                    if self.flag_v {
+                        self.trap(bus, EXCEPTION_TRAPV, CYC_GROUP2)
+                    } else {
+                        self.cyc += 4;
+                        Ok(())
+                    }
+                }
+                7 => {
+                    // RTR
+                    let ccr = self.pop_word(bus)?;
+                    let pc = self.pop_long(bus)?;
+                    self.set_ccr(ccr as u8);
+                    self.jump(pc);
+                    self.cyc += 20;
+                    Ok(())
+                }
+                _ => self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+            },
+            _ => self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        }
+    }
+
+    /// CHK.W <ea>,Dn
+    fn op_chk(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        if !Self::ea_valid(mode, reg, EA_DATA) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let ea = self.ea(bus, mode, reg, Size::Word)?;
+        let bound = self.read_ea(bus, ea, Size::Word)? as u16 as i16;
+        let value = self.d[((op >> 9) & 7) as usize] as u16 as i16;
+
+        // Flags Z, V e C não documentados (comportamento medido em hardware)
+        self.flag_z = value == 0;
+        self.flag_v = false;
+        self.flag_c = false;
+
+        if value >= 0 && value <= bound {
+            self.cyc += 10;
+            return Ok(());
+        }
+        if value < 0 {
+            self.flag_n = true;
+            self.cyc += 2;
+        } else {
+            self.flag_n = false;
+        }
+        self.trap(bus, EXCEPTION_CHK, CYC_CHK)
+    }
+
+    /// NEGX/CLR/NEG/NOT
+    fn op_unary(&mut self, bus: &mut MemoryBus, op: u16, kind: UnaryOp) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let size = match Size::from_bits(op >> 6) {
+            Some(size) => size,
+            None => return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        };
+        if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        let ea = self.ea(bus, mode, reg, size)?;
+        // O 68000 lê o operando mesmo no CLR
+        let dst = self.read_ea(bus, ea, size)?;
+        self.cyc += match (mode == 0, size.is_long()) {
+            (true, false) => 4,
+            (true, true) => 6,
+            (false, false) => 8,
+            (false, true) => 12,
+        };
+
+        let res = match kind {
+            UnaryOp::Negx => self.subx_flags(0, dst, size),
+            UnaryOp::Clr => {
+                self.set_logic_flags(0, size);
+                0
+            }
+            UnaryOp::Neg => self.sub_flags(0, dst, size, true),
+            UnaryOp::Not => {
+                let res = !dst & size.mask();
+                self.set_logic_flags(res, size);
+                res
+            }
+        };
+        self.write_ea(bus, ea, size, res)
+    }
+
+    /// NBCD
+    fn op_nbcd(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let ea = self.ea(bus, mode, reg, Size::Byte)?;
+        let dst = self.read_ea(bus, ea, Size::Byte)?;
+        self.cyc += if mode == 0 { 6 } else { 8 };
+
+        let mut res = 0u32.wrapping_sub(dst).wrapping_sub(self.flag_x as u32);
+        if res != 0 {
+            let mut v = res;
+            if (res | dst) & 0x0F == 0 {
+                res = (res & 0xF0) + 6;
+            }
+            res = res.wrapping_add(0x9A) & 0xFF;
+            v &= !res;
+            self.flag_v = v & 0x80 != 0;
+            if res != 0 {
+                self.flag_z = false;
+            }
+            self.flag_c = true;
+            self.flag_x = true;
+            self.flag_n = res & 0x80 != 0;
+            self.write_ea(bus, ea, Size::Byte, res)
+        } else {
+            self.flag_v = false;
+            self.flag_c = false;
+            self.flag_x = false;
+            self.flag_n = false;
+            Ok(())
+        }
+    }
+
+    /// TST
+    fn op_tst(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let size = match Size::from_bits(op >> 6) {
+            Some(size) => size,
+            None => return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        };
+        if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let ea = self.ea(bus, mode, reg, size)?;
+        let value = self.read_ea(bus, ea, size)?;
+        self.set_logic_flags(value, size);
+        self.cyc += 4;
+        Ok(())
+    }
+
+    /// TAS: o ciclo read-modify-write não completa a escrita no Mega Drive
+    /// (Gargoyles e Ex-Mutants dependem disso), exceto em registradores.
+    fn op_tas(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let ea = self.ea(bus, mode, reg, Size::Byte)?;
+        let value = self.read_ea(bus, ea, Size::Byte)?;
+        self.set_logic_flags(value, Size::Byte);
+        if let Ea::DataReg(_) = ea {
+            self.write_ea(bus, ea, Size::Byte, value | 0x80)?;
+            self.cyc += 4;
+        } else {
+            self.cyc += 14;
+        }
+        Ok(())
+    }
+
+    /// MOVEM
+    fn op_movem(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let to_memory = op & 0x0400 == 0;
+        let size = if op & 0x40 != 0 { Size::Long } else { Size::Word };
+        let valid = if to_memory {
+            mode == 4 || Self::ea_valid(mode, reg, EA_CONTROL | EA_ALTERABLE)
+        } else {
+            mode == 3 || Self::ea_valid(mode, reg, EA_CONTROL)
+        };
+        if !valid {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        let mask = self.fetch_word(bus)?;
+        let step = if size.is_long() { 4 } else { 2 };
+        let per_reg = if size.is_long() { 8 } else { 4 };
+        let count = mask.count_ones();
+        let r = reg as usize;
+
+        if to_memory {
+            if mode == 4 {
+                // Pré-decremento: máscara invertida (bit 0 = A7)
+                let mut addr = self.a[r];
+                for i in 0..16 {
+                    if mask & (1 << i) != 0 {
+                        addr = addr.wrapping_sub(step);
+                        let value = if i < 8 { self.a[7 - i] } else { self.d[15 - i] };
+                        self.write_sized(bus, addr, size, value)?;
+                    }
+                }
+                self.a[r] = addr;
+                self.cyc = 8 + per_reg * count;
+            } else {
+                let mut addr = self.control_address(bus, mode, reg)?;
+                self.cyc = Self::control_cycles(mode, reg, [8, 12, 14, 12, 16]) + per_reg * count;
+                for i in 0..16 {
+                    if mask & (1 << i) != 0 {
+                        let value = if i < 8 { self.d[i] } else { self.a[i - 8] };
+                        self.write_sized(bus, addr, size, value)?;
+                        addr = addr.wrapping_add(step);
+                    }
+                }
+            }
+        } else {
+            let mut addr = if mode == 3 {
+                self.a[r]
+            } else {
+                self.control_address(bus, mode, reg)?
+            };
+            self.cyc = if mode == 3 {
+                12
+            } else {
+                Self::control_cycles(mode, reg, [12, 16, 18, 16, 20])
+            } + per_reg * count;
+            for i in 0..16 {
+                if mask & (1 << i) != 0 {
+                    // Words são estendidas com sinal, inclusive para Dn
+                    let value = match size {
+                        Size::Word => self.read_word(bus, addr)? as i16 as i32 as u32,
+                        _ => self.read_long(bus, addr)?,
+                    };
+                    if i < 8 {
+                        self.d[i] = value;
+                    } else {
+                        self.a[i - 8] = value;
+                    }
+                    addr = addr.wrapping_add(step);
+                }
+            }
+            // Leitura extra no final da transferência
+            self.read_word(bus, addr)?;
+            if mode == 3 {
+                self.a[r] = addr;
+            }
+        }
+        Ok(())
+    }
+
+    /// Grupo 5: ADDQ/SUBQ, Scc e DBcc
+    fn op_group5(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+
+        let size = match Size::from_bits(op >> 6) {
+            Some(size) => size,
+            None => {
+                let cc = (op >> 8) & 0xF;
+                if mode == 1 {
+                    // DBcc Dn,label
+                    let base = self.pc;
+                    let disp = self.fetch_word(bus)? as i16 as i32 as u32;
+                    if self.condition(cc) {
+                        self.cyc += 12;
+                        return Ok(());
+                    }
+                    let r = reg as usize;
+                    let counter = (self.d[r] as u16).wrapping_sub(1);
+                    self.d[r] = (self.d[r] & 0xFFFF_0000) | counter as u32;
+                    if counter != 0xFFFF {
+                        self.jump(base.wrapping_add(disp));
+                        self.cyc += 10;
+                    } else {
+                        self.cyc += 14;
+                    }
+                    return Ok(());
+                }
+                // Scc <ea>
+                if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+                    return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                }
+                let ea = self.ea(bus, mode, reg, Size::Byte)?;
+                let result = self.condition(cc);
+                if mode == 0 {
+                    self.cyc += if result { 6 } else { 4 };
+                } else {
+                    // Ciclo de leitura antes da escrita
+                    self.read_ea(bus, ea, Size::Byte)?;
+                    self.cyc += 8;
+                }
+                return self.write_ea(bus, ea, Size::Byte, if result { 0xFF } else { 0 });
+            }
+        };
+
+        // ADDQ/SUBQ #1-8,<ea>
+        if !Self::ea_valid(mode, reg, EA_ALTERABLE) || mode == 1 && size == Size::Byte {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let data = match (op >> 9) & 7 {
+            0 => 8,
+            n => n as u32,
+        };
+        let subtract = op & 0x0100 != 0;
+
+        if mode == 1 {
+            // Em An a operação é sempre longa e não afeta flags
+            let r = reg as usize;
+            self.a[r] = if subtract {
+                self.a[r].wrapping_sub(data)
+            } else {
+                self.a[r].wrapping_add(data)
+            };
+            self.cyc += 8;
+            return Ok(());
+        }
+
+        let ea = self.ea(bus, mode, reg, size)?;
+        let dst = self.read_ea(bus, ea, size)?;
+        self.cyc += match (mode == 0, size.is_long()) {
+            (true, false) => 4,
+            (true, true) => 8,
+            (false, false) => 8,
+            (false, true) => 12,
+        };
+        let res = if subtract {
+            self.sub_flags(dst, data, size, true)
+        } else {
+            self.add_flags(dst, data, size, true)
+        };
+        self.write_ea(bus, ea, size, res)
+    }
+
+    /// Bcc/BRA/BSR
+    fn op_branch(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let cc = (op >> 8) & 0xF;
+        let base = self.pc;
+        let (disp, word) = match op as u8 {
+            0 => (self.fetch_word(bus)? as i16 as i32 as u32, true),
+            d => (d as i8 as i32 as u32, false),
+        };
+        let target = base.wrapping_add(disp);
+
+        match cc {
+            0 => {
+                // BRA
+                self.jump(target);
+                self.cyc += 10;
+            }
+            1 => {
+                // BSR
+                self.push_long(bus, self.pc)?;
+                self.jump(target);
+                self.cyc += 18;
+            }
+            _ => {
+                if self.condition(cc) {
+                    self.jump(target);
+                    self.cyc += 10;
+                } else {
+                    self.cyc += if word { 12 } else { 8 };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Grupo 8: OR, DIVU, DIVS, SBCD
+    fn op_group8(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        match (op >> 6) & 7 {
+            3 => self.op_divu(bus, op),
+            7 => self.op_divs(bus, op),
+            4 if (op >> 3) & 6 == 0 => self.op_bcd(bus, op, false),
+            _ => self.op_logic(bus, op, LogicOp::Or),
+        }
+    }
+
+    /// Grupo B: CMP, CMPA, CMPM, EOR
+    fn op_groupb(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let rn = ((op >> 9) & 7) as usize;
+        let opmode = (op >> 6) & 7;
+
+        match opmode {
+            0..=2 => {
+                // CMP <ea>,Dn
+                let size = Size::from_bits(opmode).unwrap_or(Size::Long);
+                if !Self::ea_valid(mode, reg, 0) || mode == 1 && size == Size::Byte {
+                    return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                }
+                let ea = self.ea(bus, mode, reg, size)?;
+                let src = self.read_ea(bus, ea, size)?;
+                let dst = self.d[rn] & size.mask();
+                self.sub_flags(dst, src, size, false);
+                self.cyc += if size.is_long() { 6 } else { 4 };
+                Ok(())
+            }
+            3 | 7 => {
+                // CMPA <ea>,An
+                let size = if opmode == 7 { Size::Long } else { Size::Word };
+                if !Self::ea_valid(mode, reg, 0) {
+                    return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                }
+                let ea = self.ea(bus, mode, reg, size)?;
+                let mut src = self.read_ea(bus, ea, size)?;
+                if size == Size::Word {
+                    src = src as u16 as i16 as i32 as u32;
+                }
+                self.sub_flags(self.a[rn], src, Size::Long, false);
+                self.cyc += 6;
+                Ok(())
+            }
+            _ => {
+                let size = Size::from_bits(opmode & 3).unwrap_or(Size::Long);
+                if mode == 1 {
+                    // CMPM (Ay)+,(Ax)+
+                    let src_ea = self.ea(bus, 3, reg, size)?;
+                    let src = self.read_ea(bus, src_ea, size)?;
+                    let dst_ea = self.ea(bus, 3, rn as u16, size)?;
+                    let dst = self.read_ea(bus, dst_ea, size)?;
+                    self.sub_flags(dst, src, size, false);
+                    self.cyc = if size.is_long() { 20 } else { 12 };
+                    Ok(())
+                } else {
+                    // EOR Dn,<ea>
+                    if !Self::ea_valid(mode, reg, EA_DATA | EA_ALTERABLE) {
+                        return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+                    }
+                    let ea = self.ea(bus, mode, reg, size)?;
+                    let dst = self.read_ea(bus, ea, size)?;
+                    let res = dst ^ self.d[rn];
+                    self.set_logic_flags(res, size);
+                    self.cyc += match (mode == 0, size.is_long()) {
+                        (true, false) => 4,
+                        (true, true) => 8,
+                        (false, false) => 8,
+                        (false, true) => 12,
+                    };
+                    self.write_ea(bus, ea, size, res)
+                }
+            }
+        }
+    }
+
+    /// Grupo C: AND, MULU, MULS, ABCD, EXG
+    fn op_groupc(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let rx = ((op >> 9) & 7) as usize;
+        let ry = (op & 7) as usize;
+        match (op >> 3) & 0x3F {
+            0x28 => {
+                // EXG Dx,Dy
+                self.d.swap(rx, ry);
+                self.cyc += 6;
+                Ok(())
+            }
+            0x29 => {
+                // EXG Ax,Ay
+                self.a.swap(rx, ry);
+                self.cyc += 6;
+                Ok(())
+            }
+            0x31 => {
+                // EXG Dx,Ay
+                std::mem::swap(&mut self.d[rx], &mut self.a[ry]);
+                self.cyc += 6;
+                Ok(())
+            }
+            _ => match (op >> 6) & 7 {
+                3 => self.op_mul(bus, op, false),
+                7 => self.op_mul(bus, op, true),
+                4 if (op >> 3) & 6 == 0 => self.op_bcd(bus, op, true),
+                _ => self.op_logic(bus, op, LogicOp::And),
+            },
+        }
+    }
+
+    /// OR/AND nas duas direções
+    fn op_logic(&mut self, bus: &mut MemoryBus, op: u16, kind: LogicOp) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let dn = ((op >> 9) & 7) as usize;
+        let size = match Size::from_bits(op >> 6) {
+            Some(size) => size,
+            None => return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION),
+        };
+        let to_ea = op & 0x0100 != 0;
+
+        if to_ea {
+            if !Self::ea_valid(mode, reg, EA_MEMORY | EA_ALTERABLE) {
+                return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+            }
+        } else if !Self::ea_valid(mode, reg, EA_DATA) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        let ea = self.ea(bus, mode, reg, size)?;
+        let value = self.read_ea(bus, ea, size)?;
+        let res = match kind {
+            LogicOp::Or => value | self.d[dn],
+            LogicOp::And => value & self.d[dn],
+        } & size.mask();
+        self.set_logic_flags(res, size);
+
+        if to_ea {
+            self.cyc += if size.is_long() { 12 } else { 8 };
+            self.write_ea(bus, ea, size, res)
+        } else {
+            self.cyc += Self::alu_to_reg_cycles(mode, reg, size);
+            self.write_ea(bus, Ea::DataReg(dn), size, res)
+        }
+    }
+
+    /// Tempo de <ea>,Dn para ADD/SUB/AND/OR: longword é 8 com registrador/imediato
+    fn alu_to_reg_cycles(mode: u16, reg: u16, size: Size) -> u32 {
+        if !size.is_long() {
+            4
+        } else if mode <= 1 || mode == 7 && reg == 4 {
+            8
+        } else {
+            6
+        }
+    }
+
+    /// ADD/SUB, ADDA/SUBA, ADDX/SUBX
+    fn op_addsub(&mut self, bus: &mut MemoryBus, op: u16, add: bool) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        let rn = ((op >> 9) & 7) as usize;
+        let opmode = (op >> 6) & 7;
+
+        // ADDA/SUBA
+        if opmode == 3 || opmode == 7 {
+            let size = if opmode == 7 { Size::Long } else { Size::Word };
+            if !Self::ea_valid(mode, reg, 0) {
+                return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+            }
+            let ea = self.ea(bus, mode, reg, size)?;
+            let mut src = self.read_ea(bus, ea, size)?;
+            if size == Size::Word {
+                src = src as u16 as i16 as i32 as u32;
+            }
+            self.a[rn] = if add {
+                self.a[rn].wrapping_add(src)
+            } else {
+                self.a[rn].wrapping_sub(src)
+            };
+            self.cyc += if size == Size::Word || mode <= 1 || mode == 7 && reg == 4 {
+                8
+            } else {
+                6
+            };
+            return Ok(());
+        }
+
+        let size = Size::from_bits(opmode & 3).unwrap_or(Size::Long);
+        let to_ea = opmode & 4 != 0;
+
+        // ADDX/SUBX
+        if to_ea && mode <= 1 {
+            let (src, dst, dst_ea) = if mode == 0 {
+                self.cyc += if size.is_long() { 8 } else { 4 };
+                (self.d[reg as usize] & size.mask(), self.d[rn] & size.mask(), Ea::DataReg(rn))
+            } else {
+                let src_ea = self.ea(bus, 4, reg, size)?;
+                let src = self.read_ea(bus, src_ea, size)?;
+                let dst_ea = self.ea(bus, 4, rn as u16, size)?;
+                let dst = self.read_ea(bus, dst_ea, size)?;
+                self.cyc = if size.is_long() { 30 } else { 18 };
+                (src, dst, dst_ea)
+            };
+            let res = if add {
+                self.addx_flags(dst, src, size)
+            } else {
+                self.subx_flags(dst, src, size)
+            };
+            return self.write_ea(bus, dst_ea, size, res);
+        }
+
+        if to_ea {
+            if !Self::ea_valid(mode, reg, EA_MEMORY | EA_ALTERABLE) {
+                return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+            }
+        } else if !Self::ea_valid(mode, reg, 0) || mode == 1 && size == Size::Byte {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+
+        let ea = self.ea(bus, mode, reg, size)?;
+        let value = self.read_ea(bus, ea, size)?;
+        let dreg = self.d[rn] & size.mask();
+
+        if to_ea {
+            let res = if add {
+                self.add_flags(value, dreg, size, true)
+            } else {
+                self.sub_flags(value, dreg, size, true)
+            };
+            self.cyc += if size.is_long() { 12 } else { 8 };
+            self.write_ea(bus, ea, size, res)
+        } else {
+            let res = if add {
+                self.add_flags(dreg, value, size, true)
+            } else {
+                self.sub_flags(dreg, value, size, true)
+            };
+            self.cyc += Self::alu_to_reg_cycles(mode, reg, size);
+            self.write_ea(bus, Ea::DataReg(rn), size, res)
+        }
+    }
+
+    /// ABCD/SBCD (registrador ou -(An))
+    fn op_bcd(&mut self, bus: &mut MemoryBus, op: u16, add: bool) -> CpuResult<()> {
+        let rx = (op >> 9) & 7;
+        let ry = op & 7;
+        let (src, dst, dst_ea) = if op & 8 == 0 {
+            self.cyc += 6;
+            (self.d[ry as usize] & 0xFF, self.d[rx as usize] & 0xFF, Ea::DataReg(rx as usize))
+        } else {
+            let src_ea = self.ea(bus, 4, ry, Size::Byte)?;
+            let src = self.read_ea(bus, src_ea, Size::Byte)?;
+            let dst_ea = self.ea(bus, 4, rx, Size::Byte)?;
+            let dst = self.read_ea(bus, dst_ea, Size::Byte)?;
+            self.cyc = 18;
+            (src, dst, dst_ea)
+        };
+        let x = self.flag_x as u32;
+
+        let res = if add {
+            let mut res = (src & 0x0F) + (dst & 0x0F) + x;
+            let corf = if res > 9 { 6 } else { 0 };
+            res += (src & 0xF0) + (dst & 0xF0);
+            let mut v = !res;
+            res += corf;
+            self.flag_c = res > 0x9F;
+            if self.flag_c {
+                res -= 0xA0;
+            }
+            v &= res;
+            self.flag_v = v & 0x80 != 0;
+            res
+        } else {
+            let mut res = (dst & 0x0F).wrapping_sub(src & 0x0F).wrapping_sub(x);
+            let corf = if res > 0x0F { 6 } else { 0 };
+            res = res.wrapping_add(dst & 0xF0).wrapping_sub(src & 0xF0);
+            let mut v = res;
+            if res > 0xFF {
+                res = res.wrapping_add(0xA0);
+                self.flag_c = true;
+            } else {
+                self.flag_c = res < corf;
+            }
+            res = res.wrapping_sub(corf) & 0xFF;
+            v &= !res;
+            self.flag_v = v & 0x80 != 0;
+            res
+        } & 0xFF;
+
+        self.flag_x = self.flag_c;
+        self.flag_n = res & 0x80 != 0;
+        if res != 0 {
+            self.flag_z = false;
+        }
+        self.write_ea(bus, dst_ea, Size::Byte, res)
+    }
+
+    /// MULU/MULS: 38 + 2n ciclos (n = bits em 1 ou transições 01/10)
+    fn op_mul(&mut self, bus: &mut MemoryBus, op: u16, signed: bool) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        if !Self::ea_valid(mode, reg, EA_DATA) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let dn = ((op >> 9) & 7) as usize;
+        let ea = self.ea(bus, mode, reg, Size::Word)?;
+        let src = self.read_ea(bus, ea, Size::Word)? as u16;
+
+        let res = if signed {
+            let pattern = ((src as u32) << 1 ^ src as u32) & 0xFFFF;
+            self.cyc += 38 + 2 * pattern.count_ones();
+            (src as i16 as i32).wrapping_mul(self.d[dn] as u16 as i16 as i32) as u32
+        } else {
+            self.cyc += 38 + 2 * src.count_ones();
+            src as u32 * (self.d[dn] & 0xFFFF)
+        };
+        self.d[dn] = res;
+        self.set_logic_flags(res, Size::Long);
+        Ok(())
+    }
+
+    /// DIVU com temporização exata do algoritmo de microcódigo
+    fn op_divu(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        if !Self::ea_valid(mode, reg, EA_DATA) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let dn = ((op >> 9) & 7) as usize;
+        let ea = self.ea(bus, mode, reg, Size::Word)?;
+        let src = self.read_ea(bus, ea, Size::Word)?;
+        let dst = self.d[dn];
+
+        if src == 0 {
+            self.flag_c = false;
+            return self.trap(bus, EXCEPTION_ZERO_DIVIDE, CYC_ZERO_DIVIDE);
+        }
+
+        let quotient = dst / src;
+        let remainder = dst % src;
+        if quotient < 0x10000 {
+            self.cyc += Self::divu_cycles(dst, src);
+            self.flag_z = quotient == 0;
+            self.flag_n = quotient & 0x8000 != 0;
+            self.flag_v = false;
+            self.flag_c = false;
+            self.d[dn] = quotient | remainder << 16;
+        } else {
+            // Overflow: N setado (comportamento não documentado, Blood Shot)
+            self.cyc += 10;
+            self.flag_v = true;
+            self.flag_n = true;
+            self.flag_c = false;
+        }
+        Ok(())
+    }
+
+    fn divu_cycles(mut dst: u32, src: u32) -> u32 {
+        let mut cycles = 76;
+        let src = src << 16;
+        for _ in 0..15 {
+            if (dst as i32) < 0 {
+                dst <<= 1;
+                dst = dst.wrapping_sub(src);
+            } else {
+                dst <<= 1;
+                cycles += 4;
+                if dst >= src {
+                    dst = dst.wrapping_sub(src);
+                    cycles -= 2;
+                }
+            }
+        }
+        cycles
+    }
+
+    /// DIVS com temporização exata
+    fn op_divs(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let mode = (op >> 3) & 7;
+        let reg = op & 7;
+        if !Self::ea_valid(mode, reg, EA_DATA) {
+            return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+        }
+        let dn = ((op >> 9) & 7) as usize;
+        let ea = self.ea(bus, mode, reg, Size::Word)?;
+        let src = self.read_ea(bus, ea, Size::Word)? as u16 as i16 as i32;
+        let dst = self.d[dn] as i32;
+
+        if src == 0 {
+            self.flag_c = false;
+            return self.trap(bus, EXCEPTION_ZERO_DIVIDE, CYC_ZERO_DIVIDE);
+        }
+
+        self.cyc += Self::divs_cycles(dst, src);
+
+        if dst == i32::MIN && src == -1 {
+            self.flag_z = true;
+            self.flag_n = false;
+            self.flag_v = false;
+            self.flag_c = false;
+            self.d[dn] = 0;
+            return Ok(());
+        }
+
+        let quotient = dst / src;
+        let remainder = dst % src;
+        if quotient == quotient as i16 as i32 {
+            self.flag_z = quotient as u16 == 0;
+            self.flag_n = quotient & 0x8000 != 0;
+            self.flag_v = false;
+            self.flag_c = false;
+            self.d[dn] = (quotient as u32 & 0xFFFF) | (remainder as u32) << 16;
+        } else {
+            self.flag_v = true;
+            self.flag_n = true;
+            self.flag_c = false;
+        }
+        Ok(())
+    }
+
+    fn divs_cycles(dst: i32, src: i32) -> u32 {
+        let mut cycles = 12;
+        if dst < 0 {
+            cycles += 2;
+        }
+        let abs_dst = dst.unsigned_abs();
+        let abs_src = src.unsigned_abs();
+        if abs_dst >> 16 < abs_src {
+            let mut quotient = abs_dst / abs_src;
+            cycles += 110;
+            if src >= 0 {
+                if dst >= 0 {
+                    cycles -= 2;
+                } else {
+                    cycles += 2;
+                }
+            }
+            for _ in 0..15 {
+                quotient >>= 1;
+                if quotient & 1 == 0 {
+                    cycles += 2;
+                }
+            }
+        } else {
+            cycles += 4;
+        }
+        cycles
+    }
+
+    /// Deslocamentos e rotações (registrador e memória)
+    fn op_shift(&mut self, bus: &mut MemoryBus, op: u16) -> CpuResult<()> {
+        let left = op & 0x0100 != 0;
+
+        if (op >> 6) & 3 == 3 {
+            // Deslocamento de memória: word, 1 bit
+            let mode = (op >> 3) & 7;
+            let reg = op & 7;
+            if op & 0x0800 != 0 || !Self::ea_valid(mode, reg, EA_MEMORY | EA_ALTERABLE) {
+                return self.illegal(bus, EXCEPTION_ILLEGAL_INSTRUCTION);
+            }
+            let kind = (op >> 9) & 3;
+            let ea = self.ea(bus, mode, reg, Size::Word)?;
+            let value = self.read_ea(bus, ea, Size::Word)?;
+            let res = self.shift(kind, left, value, 1, Size::Word);
+            self.cyc += 8;
+            return self.write_ea(bus, ea, Size::Word, res);
+        }
+
+        let size = Size::from_bits(op >> 6).unwrap_or(Size::Long);
+        let kind = (op >> 3) & 3;
+        let r = (op & 7) as usize;
+        let count_field = ((op >> 9) & 7) as u32;
+        let count = if op & 0x20 != 0 {
+            self.d[count_field as usize] & 63
+        } else if count_field == 0 {
+            8
+        } else {
+            count_field
+        };
+
+        let value = self.d[r] & size.mask();
+        let res = self.shift(kind, left, value, count, size);
+        self.d[r] = (self.d[r] & !size.mask()) | res;
+        self.cyc += if size.is_long() { 8 } else { 6 } + 2 * count;
+        Ok(())
+    }
+
+    /// Executa um deslocamento/rotação e atualiza as flags.
+    /// `kind`: 0 = AS, 1 = LS, 2 = ROX, 3 = RO
+    fn shift(&mut self, kind: u16, left: bool, value: u32, count: u32, size: Size) -> u32 {
+        let bits = size.bits();
+        let mask = size.mask() as u64;
+        let msb = size.msb() as u64;
+        let mut v = value as u64 & mask;
+        let mut carry = false;
+        let mut overflow = false;
+
+        match kind {
+            0 | 1 => {
+                for _ in 0..count {
+                    if left {
+                        carry = v & msb != 0;
+                        let next = (v << 1) & mask;
+                        if kind == 0 && (next & msb != 0) != (v & msb != 0) {
+                            overflow = true;
+                        }
+                        v = next;
+                    } else {
+                        carry = v & 1 != 0;
+                        let sign = if kind == 0 { v & msb } else { 0 };
+                        v = (v >> 1) | sign;
+                    }
+                }
+                if count > 0 {
+                    self.flag_x = carry;
+                    self.flag_c = carry;
+                } else {
+                    self.flag_c = false;
+                }
+            }
+            2 => {
+                // ROXL/ROXR: rotação através de X
+                let mut x = self.flag_x;
+                for _ in 0..count {
+                    if left {
+                        let out = v & msb != 0;
+                        v = ((v << 1) & mask) | x as u64;
+                        x = out;
+                    } else {
+                        let out = v & 1 != 0;
+                        v = (v >> 1) | if x { msb } else { 0 };
+                        x = out;
+                    }
+                }
+                self.flag_x = x;
+                self.flag_c = x;
+            }
+            _ => {
+                let n = count % bits;
+                if n > 0 {
+                    v = if left {
+                        ((v << n) | (v >> (bits - n))) & mask
+                    } else {
+                        ((v >> n) | (v << (bits - n))) & mask
+                    };
+                }
+                self.flag_c = count > 0 && if left { v & 1 != 0 } else { v & msb != 0 };
+            }
+        }
+
+        self.flag_v = overflow;
+        self.flag_n = v & msb != 0;
+        self.flag_z = v == 0;
+        v as u32
+    }
+
+    // --- Aritmética com flags ---
+
+    /// dst + src, atualizando NZVC (e X se `set_x`)
+    fn add_flags(&mut self, dst: u32, src: u32, size: Size, set_x: bool) -> u32 {
+        let mask = size.mask();
+        let (dst, src) = (dst & mask, src & mask);
+        let wide = dst as u64 + src as u64;
+        let res = wide as u32 & mask;
+        self.flag_c = wide > mask as u64;
+        self.flag_v = (src ^ res) & (dst ^ res) & size.msb() != 0;
+        self.flag_n = res & size.msb() != 0;
+        self.flag_z = res == 0;
+        if set_x {
+            self.flag_x = self.flag_c;
+        }
+        res
+    }
+
+    /// dst - src, atualizando NZVC (e X se `set_x`)
+    fn sub_flags(&mut self, dst: u32, src: u32, size: Size, set_x: bool) -> u32 {
+        let mask = size.mask();
+        let (dst, src) = (dst & mask, src & mask);
+        let res = dst.wrapping_sub(src) & mask;
+        self.flag_c = src > dst;
+        self.flag_v = (src ^ dst) & (res ^ dst) & size.msb() != 0;
+        self.flag_n = res & size.msb() != 0;
+        self.flag_z = res == 0;
+        if set_x {
+            self.flag_x = self.flag_c;
+        }
+        res
+    }
+
+    /// dst + src + X; Z só é limpo (nunca setado) por ADDX
+    fn addx_flags(&mut self, dst: u32, src: u32, size: Size) -> u32 {
+        let mask = size.mask();
+        let (dst, src) = (dst & mask, src & mask);
+        let wide = dst as u64 + src as u64 + self.flag_x as u64;
+        let res = wide as u32 & mask;
+        self.flag_c = wide > mask as u64;
+        self.flag_x = self.flag_c;
+        self.flag_v = (src ^ res) & (dst ^ res) & size.msb() != 0;
+        self.flag_n = res & size.msb() != 0;
+        if res != 0 {
+            self.flag_z = false;
+        }
+        res
+    }
+
+    /// dst - src - X; Z só é limpo (nunca setado) por SUBX/NEGX
+    fn subx_flags(&mut self, dst: u32, src: u32, size: Size) -> u32 {
+        let mask = size.mask();
+        let (dst, src) = (dst & mask, src & mask);
+        let x = self.flag_x as u64;
+        let res = (dst as u64).wrapping_sub(src as u64).wrapping_sub(x) as u32 & mask;
+        self.flag_c = src as u64 + x > dst as u64;
+        self.flag_x = self.flag_c;
+        self.flag_v = (src ^ dst) & (res ^ dst) & size.msb() != 0;
+        self.flag_n = res & size.msb() != 0;
+        if res != 0 {
+            self.flag_z = false;
+        }
+        res
+    }
+}
+
+impl Default for M68K {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum UnaryOp {
+    Negx,
+    Clr,
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum LogicOp {
+    Or,
+    And,
+}
+
+/// Leitura de longword sem verificação de alinhamento (vetores de reset)
+fn read_long_raw(bus: &mut MemoryBus, addr: u32) -> u32 {
+    (bus.read_word(addr) as u32) << 16 | bus.read_word(addr.wrapping_add(2)) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::Cartridge;
+
+    /// Monta uma ROM com SSP = $FFFE00, PC = $200 e o código dado em $200.
+    /// Todos os vetores de exceção apontam para $300 (que contém NOP).
+    fn setup(code: &[u16]) -> (M68K, MemoryBus) {
+        let mut rom = vec![0u8; 0x400];
+        let put_long = |rom: &mut Vec<u8>, addr: usize, value: u32| {
+            rom[addr..addr + 4].copy_from_slice(&value.to_be_bytes());
+        };
+        put_long(&mut rom, 0, 0x00FF_FE00);
+        put_long(&mut rom, 4, 0x0000_0200);
+        for vector in 2..64 {
+            put_long(&mut rom, vector * 4, 0x0000_0300);
+        }
+        for (i, word) in code.iter().enumerate() {
+            rom[0x200 + i * 2..0x202 + i * 2].copy_from_slice(&word.to_be_bytes());
+        }
+        rom[0x300..0x302].copy_from_slice(&0x4E71u16.to_be_bytes());
+
+        let mut cart = Cartridge::new();
+        cart.load_from_buffer(&rom).unwrap();
+        let mut bus = MemoryBus::new();
+        bus.init(cart).unwrap();
+
+        let mut cpu = M68K::new();
+        cpu.reset(&mut bus);
+        (cpu, bus)
+    }
+
+    #[test]
+    fn test_reset_vectors() {
+        let (cpu, bus) = setup(&[0x4E71]);
+        assert_eq!(cpu.a[7], 0x00FF_FE00);
+        assert_eq!(cpu.pc, 0x200);
+        assert_eq!(cpu.sr(), 0x2700);
+        assert_eq!(bus.cycles, CYC_RESET as u64);
+    }
+
+    #[test]
+    fn test_moveq_add_flags_and_cycles() {
+        // MOVEQ #-1,D0 ; MOVEQ #1,D1 ; ADD.L D1,D0
+        let (mut cpu, mut bus) = setup(&[0x70FF, 0x7201, 0xD081]);
+        assert_eq!(cpu.step(&mut bus), 4);
+        assert_eq!(cpu.step(&mut bus), 4);
+        assert_eq!(cpu.step(&mut bus), 8);
+        assert_eq!(cpu.d[0], 0);
+        assert!(cpu.flag_z && cpu.flag_c && cpu.flag_x && !cpu.flag_v);
+    }
+
+    #[test]
+    fn test_move_to_work_ram() {
+        // MOVE.W #$1234,($FF0000).L ; MOVE.W ($FF0000).L,D2
+        let (mut cpu, mut bus) = setup(&[0x33FC, 0x1234, 0x00FF, 0x0000, 0x3439, 0x00FF, 0x0000]);
+        assert_eq!(cpu.step(&mut bus), 20);
+        assert_eq!(cpu.step(&mut bus), 16);
+        assert_eq!(bus.wram[0], 0x12);
+        assert_eq!(cpu.d[2] & 0xFFFF, 0x1234);
+    }
+
+    #[test]
+    fn test_address_error_on_odd_word() {
+        // LEA ($FF0001).L,A0 ; MOVE.W (A0),D0
+        let (mut cpu, mut bus) = setup(&[0x41F9, 0x00FF, 0x0001, 0x3010]);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.step(&mut bus), CYC_GROUP0);
+        assert_eq!(cpu.pc, 0x300);
+        // Quadro de 7 palavras: status, endereço, IR, SR, PC
+        assert_eq!(cpu.a[7], 0x00FF_FE00 - 14);
+        assert_eq!(bus.read_word(cpu.a[7] + 2), 0x00FF);
+        assert_eq!(bus.read_word(cpu.a[7] + 4), 0x0001);
+        assert_eq!(bus.read_word(cpu.a[7] + 6), 0x3010);
+    }
+
+    #[test]
+    fn test_trap_and_rte() {
+        // MOVE #$0000,SR (modo usuário) ; TRAP #3
+        let (mut cpu, mut bus) = setup(&[0x46FC, 0x0000, 0x4E43]);
+        cpu.a[7] = 0x00FF_FE00;
+        cpu.step(&mut bus);
+        assert!(!cpu.flag_s);
+        cpu.a[7] = 0x00FF_F000;
+        assert_eq!(cpu.step(&mut bus), 34);
+        assert!(cpu.flag_s);
+        assert_eq!(cpu.pc, 0x300);
+        assert_eq!(cpu.usp(), 0x00FF_F000);
+        assert_eq!(bus.read_word(cpu.a[7]), 0x0000);
+        assert_eq!(bus.read_word(cpu.a[7] + 4), 0x0206);
+    }
+
+    #[test]
+    fn test_interrupt_autovector() {
+        // MOVE #$2300,SR ; NOP
+        let (mut cpu, mut bus) = setup(&[0x46FC, 0x2300, 0x4E71]);
+        cpu.step(&mut bus);
+        cpu.set_irq(6);
+        let cycles = cpu.step(&mut bus);
+        assert!((50..=59).contains(&cycles));
+        assert_eq!(cpu.int_mask, 6);
+        assert_eq!(cpu.pc, 0x300);
+
+        // Nível 4 não é atendido com máscara 6
+        cpu.set_irq(4);
+        assert_eq!(cpu.step(&mut bus), 4);
+        assert_eq!(cpu.pc, 0x302);
+    }
+
+    #[test]
+    fn test_privilege_violation() {
+        // MOVE #$0000,SR ; STOP #$2700 (em modo usuário)
+        let (mut cpu, mut bus) = setup(&[0x46FC, 0x0000, 0x4E72, 0x2700]);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.step(&mut bus), 34);
+        assert!(cpu.flag_s);
+        assert_eq!(cpu.pc, 0x300);
+        assert_eq!(bus.read_word(cpu.a[7] + 4), 0x0204);
+    }
+
+    #[test]
+    fn test_divu_timing_and_zero_divide() {
+        // MOVE.L #100000,D0 ; MOVE.W #7,D1 ; DIVU D1,D0 ; DIVU D2,D0
+        let (mut cpu, mut bus) = setup(&[0x203C, 0x0001, 0x86A0, 0x323C, 0x0007, 0x80C1, 0x80C2]);
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        let cycles = cpu.step(&mut bus);
+        assert_eq!(cpu.d[0], ((100000 % 7) << 16) | (100000 / 7));
+        assert_eq!(cycles, M68K::divu_cycles(100000, 7));
+        assert!((76..=136).contains(&cycles));
+        assert_eq!(cpu.step(&mut bus), CYC_ZERO_DIVIDE);
+        assert_eq!(cpu.pc, 0x300);
+    }
+
+    #[test]
+    fn test_abcd_and_dbra_loop() {
+        // MOVEQ #$19,D0 ; MOVEQ #$28,D1 ; ABCD D1,D0
+        let (mut cpu, mut bus) = setup(&[0x7019, 0x7228, 0xC101]);
+        cpu.flag_z = true;
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        cpu.flag_x = false;
+        assert_eq!(cpu.step(&mut bus), 6);
+        assert_eq!(cpu.d[0] & 0xFF, 0x47);
+        assert!(!cpu.flag_c);
+
+        // MOVEQ #2,D3 ; loop: DBRA D3,loop
+        let (mut cpu, mut bus) = setup(&[0x7602, 0x51CB, 0xFFFE]);
+        cpu.step(&mut bus);
+        let total: u32 = (0..3).map(|_| cpu.step(&mut bus)).sum();
+        assert_eq!(total, 10 + 10 + 14);
+        assert_eq!(cpu.d[3] & 0xFFFF, 0xFFFF);
+        assert_eq!(cpu.pc, 0x206);
+    }
+
+    #[test]
+    fn test_movem_cycles() {
+        // MOVEM.L (A0)+,D0-D3 ; MOVEM.W D0-D1/A0,-(A7) ; MOVEM.L D0-D7,(A0) ;
+        // MOVEM.W ($10,A0),D0-D1
+        let (mut cpu, mut bus) = setup(&[0x4CD8, 0x000F, 0x48A7, 0xC080, 0x48D0, 0x00FF, 0x4CA8, 0x0003, 0x0010]);
+        cpu.a[0] = 0x00FF_0000;
+        assert_eq!(cpu.step(&mut bus), 12 + 4 * 8);
+        assert_eq!(cpu.a[0], 0x00FF_0010);
+        assert_eq!(cpu.step(&mut bus), 8 + 3 * 4);
+        assert_eq!(cpu.a[7], 0x00FF_FE00 - 6);
+        assert_eq!(cpu.step(&mut bus), 8 + 8 * 8);
+        assert_eq!(cpu.step(&mut bus), 16 + 2 * 4);
+    }
+
+    #[test]
+    fn test_mul_div_cycles() {
+        // MULU D1,D0 ; MULS D2,D0 ; DIVU D3,D4 ; DIVS D3,D5 ; DIVS D6,D7 ;
+        // DIVU D1,D7
+        let (mut cpu, mut bus) = setup(&[0xC0C1, 0xC1C2, 0x88C3, 0x8BC3, 0x8FC6, 0x8EC1]);
+        cpu.d[..8].copy_from_slice(&[3, 0x00FF, 0x5555, 7, 100_000, -100_000i32 as u32, 1, 0x7FFF_FFFF]);
+
+        // 38 + 2n: bits em 1 (MULU) ou transições 01/10 (MULS)
+        assert_eq!(cpu.step(&mut bus), 38 + 2 * 8);
+        assert_eq!(cpu.d[0], 0x2FD);
+        assert_eq!(cpu.step(&mut bus), 38 + 2 * 16);
+        assert_eq!(cpu.d[0], 0x2FD * 0x5555);
+
+        // Tempos de UseDivuCycles/UseDivsCycles
+        assert_eq!(cpu.step(&mut bus), 118);
+        assert_eq!(cpu.d[4], ((100_000 % 7) << 16) | (100_000 / 7));
+        assert_eq!(cpu.step(&mut bus), 138);
+        assert_eq!(cpu.d[5], (-5i32 as u32) << 16 | (-14_285i32 as u32 & 0xFFFF));
+        assert!(cpu.flag_n && !cpu.flag_v);
+
+        // Estouro: o destino não muda, V e N setados
+        assert_eq!(cpu.step(&mut bus), 16);
+        assert!(cpu.flag_v && cpu.flag_n && !cpu.flag_c);
+        assert_eq!(cpu.step(&mut bus), 10);
+        assert_eq!(cpu.d[7], 0x7FFF_FFFF);
+    }
+
+    #[test]
+    fn test_shift_by_register_count() {
+        // LSL.L D1,D0 ; ASR.W D1,D2 ; LSL.L D4,D0 ; LSL.L D5,D6
+        let (mut cpu, mut bus) = setup(&[0xE3A8, 0xE262, 0xE9A8, 0xEBAE]);
+        cpu.d[..7].copy_from_slice(&[0x1234_5678, 40, 0x8000, 0, 64, 32, 1]);
+
+        // Contagem módulo 64, 2 ciclos por bit
+        assert_eq!(cpu.step(&mut bus), 8 + 2 * 40);
+        assert_eq!(cpu.d[0], 0);
+        assert!(cpu.flag_z && !cpu.flag_c && !cpu.flag_x);
+        assert_eq!(cpu.step(&mut bus), 6 + 2 * 40);
+        assert_eq!(cpu.d[2], 0xFFFF);
+        assert!(cpu.flag_n && cpu.flag_c && cpu.flag_x);
+
+        // Contagem zero: C limpo, X preservado
+        assert_eq!(cpu.step(&mut bus), 8);
+        assert!(!cpu.flag_c && cpu.flag_x);
+
+        // 32 bits: o último bit a sair vai para C e X
+        assert_eq!(cpu.step(&mut bus), 8 + 2 * 32);
+        assert_eq!(cpu.d[6], 0);
+        assert!(cpu.flag_z && cpu.flag_c && cpu.flag_x);
+    }
+
+    #[test]
+    fn test_exception_cycles() {
+        // Latência da interrupção conforme o alinhamento com o clock E
+        const INTERRUPT: [u32; 10] = [50, 59, 58, 57, 56, 55, 54, 53, 52, 51];
+        for (phase, &expected) in INTERRUPT.iter().enumerate() {
+            let (mut cpu, mut bus) = setup(&[0x4E71]);
+            cpu.int_mask = 0;
+            cpu.cycles = 1000 + phase as u64;
+            cpu.set_irq(4);
+            assert_eq!(cpu.step(&mut bus), expected);
+        }
+
+        // (opcode, D0, D1, V, ciclos): ILLEGAL, linha A, linha F, TRAPV e
+        // CHK D1,D0 dentro do limite, acima dele e negativo
+        let cases = [
+            (0x4AFC, 0, 0, false, 34),
+            (0xA000, 0, 0, false, 34),
+            (0xF000, 0, 0, false, 34),
+            (0x4E76, 0, 0, true, 34),
+            (0x4181, 5, 10, false, 10),
+            (0x4181, 11, 10, false, 38),
+            (0x4181, -1i32 as u32, 10, false, 40),
+        ];
+        for (op, d0, d1, v, expected) in cases {
+            let (mut cpu, mut bus) = setup(&[op]);
+            (cpu.d[0], cpu.d[1], cpu.flag_v) = (d0, d1, v);
+            assert_eq!(cpu.step(&mut bus), expected, "{:04X}", op);
+            assert_eq!(cpu.pc, if expected == 10 { 0x202 } else { 0x300 });
+        }
+    }
+
+    #[test]
+    fn test_bcd_and_extend_flags() {
+        // (opcode, D0, D1, X, Z antes, resultado, C/X, V, N, Z depois, ciclos)
+        // ABCD D1,D0 ; SBCD D1,D0 ; NBCD D0 ; ADDX.L D1,D0 ; SUBX.L D1,D0.
+        // V e N seguem o comportamento não documentado do Genesis Plus GX.
+        let cases = [
+            (0xC101, 0x99, 0x01, false, true, 0x00, true, false, false, true, 6),
+            (0xC101, 0x99, 0x01, false, false, 0x00, true, false, false, false, 6),
+            (0xC101, 0x45, 0x38, true, true, 0x84, false, true, true, false, 6),
+            (0x8101, 0x00, 0x01, false, true, 0x99, true, false, true, false, 6),
+            (0x8101, 0x91, 0x25, true, true, 0x65, false, false, false, false, 6),
+            (0x4800, 0x00, 0x00, false, true, 0x00, false, false, false, true, 6),
+            (0x4800, 0x45, 0x00, true, true, 0x54, true, true, false, false, 6),
+            (0x4800, 0x01, 0x00, false, false, 0x99, true, false, true, false, 6),
+            (0xD181, 0xFFFF_FFFF, 0, true, true, 0, true, false, false, true, 8),
+            (0xD181, 0xFFFF_FFFF, 0, true, false, 0, true, false, false, false, 8),
+            (0x9181, 0, 0, true, true, 0xFFFF_FFFF, true, false, true, false, 8),
+        ];
+        for (op, d0, d1, x, z, res, c, v, n, z_after, cycles) in cases {
+            let (mut cpu, mut bus) = setup(&[op]);
+            (cpu.d[0], cpu.d[1], cpu.flag_x, cpu.flag_z) = (d0, d1, x, z);
+            assert_eq!(cpu.step(&mut bus), cycles, "{:04X}", op);
+            let mask = if op & 0x00C0 == 0x0080 { u32::MAX } else { 0xFF };
+            assert_eq!(cpu.d[0] & mask, res, "{:04X} {:X}", op, d0);
+            let flags = (cpu.flag_c, cpu.flag_x, cpu.flag_v, cpu.flag_n, cpu.flag_z);
+            assert_eq!(flags, (c, c, v, n, z_after), "{:04X} {:X}", op, d0);
+        }
+
+        // Forma com memória: ABCD -(A1),-(A0) e ADDX.L -(A2),-(A3)
+        let (mut cpu, mut bus) = setup(&[0xC109, 0xD78A]);
+        cpu.a[..4].copy_from_slice(&[0x00FF_0010, 0x00FF_0020, 0x00FF_0030, 0x00FF_0040]);
+        assert_eq!(cpu.step(&mut bus), 18);
+        assert_eq!(cpu.step(&mut bus), 30);
+    }
+
+    #[test]
+    fn test_execute_reports_cycles_to_bus() {
+        let (mut cpu, mut bus) = setup(&[0x4E71; 16]);
+        let start = bus.cycles;
+        let done = cpu.execute(&mut bus, 20);
+        assert_eq!(done, 20);
+        assert_eq!(bus.cycles - start, 20);
+    }
+}
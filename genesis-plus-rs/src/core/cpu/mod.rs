@@ -0,0 +1,8 @@
+//! Processadores do Genesis/Mega Drive.
+//! Motorola 68000 (CPU principal) e Zilog Z80 (coprocessador de som).
+
+pub mod m68k;
+pub mod z80;
+
+pub use m68k::M68K;
+pub use z80::Z80;
@@ -0,0 +1,1539 @@
+//! Interpretador do Zilog Z80 com contagem de ciclos por instrução.
+//! Baseado em `z80.c` (MAME) do Genesis Plus GX.
+//!
+//! Implementa o conjunto completo de instruções, incluindo os opcodes e flags
+//! não documentados: bits X/Y (3 e 5) do registrador F, meias partes de IX/IY,
+//! SLL, cópia do resultado para registradores em DDCB/FDCB e o registrador
+//! interno WZ (MEMPTR), visível pelas flags de `BIT n,(HL)`.
+//!
+//! O tempo é contado em T-states do Z80. No Mega Drive o Z80 só executa
+//! enquanto o 68000 não segura /BUSREQ nem /RESET (ver `MemoryBus::z80_running`).
+
+use crate::core::memory::MemoryBus;
+use log::trace;
+
+// Bits do registrador de flags
+const CF: u8 = 0x01;
+const NF: u8 = 0x02;
+const PF: u8 = 0x04;
+const VF: u8 = PF;
+const XF: u8 = 0x08;
+const HF: u8 = 0x10;
+const YF: u8 = 0x20;
+const ZF: u8 = 0x40;
+const SF: u8 = 0x80;
+
+/// Instruções sem prefixo (T-states)
+const CC_OP: [u8; 256] = [
+     4, 10,  7,  6,  4,  4,  7,  4,  4, 11,  7,  6,  4,  4,  7,  4,
+     8, 10,  7,  6,  4,  4,  7,  4, 12, 11,  7,  6,  4,  4,  7,  4,
+     7, 10, 16,  6,  4,  4,  7,  4,  7, 11, 16,  6,  4,  4,  7,  4,
+     7, 10, 13,  6, 11, 11, 10,  4,  7, 11, 13,  6,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     7,  7,  7,  7,  7,  7,  4,  7,  4,  4,  4,  4,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     4,  4,  4,  4,  4,  4,  7,  4,  4,  4,  4,  4,  4,  4,  7,  4,
+     5, 10, 10, 10, 10, 11,  7, 11,  5, 10, 10,  0, 10, 17,  7, 11,
+     5, 10, 10, 11, 10, 11,  7, 11,  5,  4, 10, 11, 10,  0,  7, 11,
+     5, 10, 10, 19, 10, 11,  7, 11,  5,  4, 10,  4, 10,  0,  7, 11,
+     5, 10, 10,  4, 10, 11,  7, 11,  5,  6, 10,  4, 10,  0,  7, 11,
+];
+
+/// Instruções com prefixo CB (inclui o prefixo)
+const CC_CB: [u8; 256] = [
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 12,  8,  8,  8,  8,  8,  8,  8, 12,  8,
+     8,  8,  8,  8,  8,  8, 12,  8,  8,  8,  8,  8,  8,  8, 12,  8,
+     8,  8,  8,  8,  8,  8, 12,  8,  8,  8,  8,  8,  8,  8, 12,  8,
+     8,  8,  8,  8,  8,  8, 12,  8,  8,  8,  8,  8,  8,  8, 12,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+     8,  8,  8,  8,  8,  8, 15,  8,  8,  8,  8,  8,  8,  8, 15,  8,
+];
+
+/// Instruções com prefixo ED (inclui o prefixo; opcodes inválidos são NOPs de 8 ciclos)
+const CC_ED: [u8; 256] = [
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+    12, 12, 15, 20,  8, 14,  8,  9, 12, 12, 15, 20,  8, 14,  8,  9,
+    12, 12, 15, 20,  8, 14,  8,  9, 12, 12, 15, 20,  8, 14,  8,  9,
+    12, 12, 15, 20,  8, 14,  8, 18, 12, 12, 15, 20,  8, 14,  8, 18,
+    12, 12, 15, 20,  8, 14,  8,  8, 12, 12, 15, 20,  8, 14,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+    16, 16, 16, 16,  8,  8,  8,  8, 16, 16, 16, 16,  8,  8,  8,  8,
+    16, 16, 16, 16,  8,  8,  8,  8, 16, 16, 16, 16,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+     8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,  8,
+];
+
+/// Instruções com prefixo DD/FD (inclui o prefixo). Opcodes que não usam
+/// HL executam como a versão sem prefixo e custam 4 + `CC_OP`.
+const CC_XY: [u8; 256] = [
+     8, 14, 11, 10,  8,  8, 11,  8,  8, 15, 11, 10,  8,  8, 11,  8,
+    12, 14, 11, 10,  8,  8, 11,  8, 16, 15, 11, 10,  8,  8, 11,  8,
+    11, 14, 20, 10,  9,  9, 12,  8, 11, 15, 20, 10,  9,  9, 12,  8,
+    11, 14, 17, 10, 23, 23, 19,  8, 11, 15, 17, 10,  8,  8, 11,  8,
+     8,  8,  8,  8,  9,  9, 19,  8,  8,  8,  8,  8,  9,  9, 19,  8,
+     8,  8,  8,  8,  9,  9, 19,  8,  8,  8,  8,  8,  9,  9, 19,  8,
+     9,  9,  9,  9,  9,  9, 19,  9,  9,  9,  9,  9,  9,  9, 19,  9,
+    19, 19, 19, 19, 19, 19,  8, 19,  8,  8,  8,  8,  9,  9, 19,  8,
+     8,  8,  8,  8,  9,  9, 19,  8,  8,  8,  8,  8,  9,  9, 19,  8,
+     8,  8,  8,  8,  9,  9, 19,  8,  8,  8,  8,  8,  9,  9, 19,  8,
+     8,  8,  8,  8,  9,  9, 19,  8,  8,  8,  8,  8,  9,  9, 19,  8,
+     8,  8,  8,  8,  9,  9, 19,  8,  8,  8,  8,  8,  9,  9, 19,  8,
+     9, 14, 14, 14, 14, 15, 11, 15,  9, 14, 14,  0, 14, 21, 11, 15,
+     9, 14, 14, 15, 14, 15, 11, 15,  9,  8, 14, 15, 14,  4, 11, 15,
+     9, 14, 14, 23, 14, 15, 11, 15,  9,  8, 14,  8, 14,  4, 11, 15,
+     9, 14, 14,  8, 14, 15, 11, 15,  9, 10, 14,  8, 14,  4, 11, 15,
+];
+
+/// Instruções com prefixo DDCB/FDCB (tempo total da instrução)
+const CC_XYCB: [u8; 256] = {
+    let mut table = [23; 256];
+    let mut op = 0x40;
+    while op < 0x80 {
+        // BIT n,(IX+d) não escreve o resultado de volta
+        table[op] = 20;
+        op += 1;
+    }
+    table
+};
+
+/// Ciclos extras: desvio/chamada/retorno condicional tomado, repetição das
+/// instruções de bloco e latência de reconhecimento de interrupção (RST)
+const CC_EX: [u8; 256] = [
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     5,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     5,  0,  0,  0,  0,  0,  0,  0,  5,  0,  0,  0,  0,  0,  0,  0,
+     5,  0,  0,  0,  0,  0,  0,  0,  5,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+     5,  5,  5,  5,  0,  0,  0,  0,  5,  5,  5,  5,  0,  0,  0,  0,
+     6,  0,  0,  0,  7,  0,  0,  2,  6,  0,  0,  0,  7,  0,  0,  2,
+     6,  0,  0,  0,  7,  0,  0,  2,  6,  0,  0,  0,  7,  0,  0,  2,
+     6,  0,  0,  0,  7,  0,  0,  2,  6,  0,  0,  0,  7,  0,  0,  2,
+     6,  0,  0,  0,  7,  0,  0,  2,  6,  0,  0,  0,  7,  0,  0,  2,
+];
+
+/// Tempo de reconhecimento da NMI (push do PC + salto para $0066)
+const CYC_NMI: u32 = 11;
+
+/// Registrador usado no lugar de HL (prefixos DD e FD)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Index {
+    Hl,
+    Ix,
+    Iy,
+}
+
+/// Sinal, zero e bits não documentados 5/3 de um resultado de 8 bits
+fn sz(value: u8) -> u8 {
+    (if value == 0 { ZF } else { value & SF }) | (value & (YF | XF))
+}
+
+/// `sz` mais a paridade (PF = paridade par)
+fn szp(value: u8) -> u8 {
+    sz(value) | if value.count_ones() & 1 == 0 { PF } else { 0 }
+}
+
+/// Processador Zilog Z80
+pub struct Z80 {
+    /// Acumulador
+    pub a: u8,
+    /// Flags: S Z Y H X P/V N C
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    /// Conjunto alternativo de registradores (EX AF,AF' / EXX)
+    pub af_alt: u16,
+    pub bc_alt: u16,
+    pub de_alt: u16,
+    pub hl_alt: u16,
+    /// Registradores índice
+    pub ix: u16,
+    pub iy: u16,
+    /// Stack pointer
+    pub sp: u16,
+    /// Program counter
+    pub pc: u16,
+    /// Registrador interno WZ (MEMPTR)
+    pub wz: u16,
+    /// Vetor de interrupção (byte alto no modo 2)
+    pub i: u8,
+    /// Refresh: bits 0-6 contam os ciclos M1, bit 7 só muda via LD R,A
+    pub r: u8,
+    pub iff1: bool,
+    pub iff2: bool,
+    /// Modo de interrupção (0, 1 ou 2)
+    pub im: u8,
+    /// Parado pela instrução HALT até a próxima interrupção
+    pub halted: bool,
+    /// Estado da linha /INT
+    pub irq_line: bool,
+    /// Byte lido do barramento de dados no reconhecimento da interrupção
+    /// (instrução no modo 0, byte baixo do vetor no modo 2). O Mega Drive
+    /// deixa o barramento em aberto ($FF).
+    pub irq_vector: u8,
+    /// Estado da linha /NMI (sensível à borda de descida)
+    nmi_line: bool,
+    nmi_pending: bool,
+    /// EI acabou de ser executado: interrupções só após a próxima instrução
+    after_ei: bool,
+    /// Total de T-states executados
+    pub cycles: u64,
+
+    /// Ciclos da instrução em andamento
+    cyc: u32,
+}
+
+impl Z80 {
+    /// Cria um Z80 no estado de power-on
+    pub fn new() -> Self {
+        Self {
+            a: 0,
+            f: ZF,
+            b: 0,
+            c: 0,
+            d: 0,
+            e: 0,
+            h: 0,
+            l: 0,
+            af_alt: 0,
+            bc_alt: 0,
+            de_alt: 0,
+            hl_alt: 0,
+            ix: 0,
+            iy: 0,
+            sp: 0,
+            pc: 0,
+            wz: 0,
+            i: 0,
+            r: 0,
+            iff1: false,
+            iff2: false,
+            im: 0,
+            halted: false,
+            irq_line: false,
+            irq_vector: 0xFF,
+            nmi_line: false,
+            nmi_pending: false,
+            after_ei: false,
+            cycles: 0,
+            cyc: 0,
+        }
+    }
+
+    /// Pulso em /RESET. Os registradores de uso geral não são afetados.
+    pub fn reset(&mut self) {
+        self.pc = 0;
+        self.i = 0;
+        self.r = 0;
+        self.im = 0;
+        self.iff1 = false;
+        self.iff2 = false;
+        self.halted = false;
+        self.after_ei = false;
+        self.nmi_pending = false;
+        self.wz = self.pc;
+    }
+
+    /// Define o nível da linha /INT (ativa enquanto `true`)
+    pub fn set_irq_line(&mut self, state: bool) {
+        self.irq_line = state;
+    }
+
+    /// Define o nível da linha /NMI. A NMI é registrada na transição para ativa.
+    pub fn set_nmi_line(&mut self, state: bool) {
+        if state && !self.nmi_line {
+            self.nmi_pending = true;
+        }
+        self.nmi_line = state;
+    }
+
+    /// Executa instruções até consumir pelo menos `cycles` T-states.
+    /// Enquanto o 68000 segura /BUSREQ ou /RESET o Z80 fica parado
+    /// (e em reset, no segundo caso) e o tempo passa sem execução.
+    pub fn execute(&mut self, bus: &mut MemoryBus, cycles: u32) -> u32 {
+        let mut done = 0;
+        while done < cycles {
+            if !bus.z80_running() {
+                if bus.z80_reset {
+                    self.reset();
+                }
+                let idle = cycles - done;
+                self.cycles += idle as u64;
+                return cycles;
+            }
+            bus.z80_cycles = self.cycles;
+            let used = self.step(bus);
+            self.cycles += used as u64;
+            done += used;
+        }
+        done
+    }
+
+    /// Executa uma única instrução (ou atende uma interrupção pendente)
+    /// e retorna o número de T-states gastos.
+    pub fn step(&mut self, bus: &mut MemoryBus) -> u32 {
+        self.cyc = 0;
+
+        if self.nmi_pending {
+            self.nmi_pending = false;
+            self.take_nmi(bus);
+            return self.cyc;
+        }
+
+        if (self.irq_line || bus.z80_irq_line()) && self.iff1 && !self.after_ei {
+            self.take_interrupt(bus);
+            return self.cyc;
+        }
+
+        self.after_ei = false;
+        let op = self.fetch_opcode(bus);
+        self.execute_op(bus, op, Index::Hl);
+
+        // Estados de espera dos acessos ao barramento do 68000
+        self.cyc += std::mem::take(&mut bus.z80_wait);
+        self.cyc
+    }
+
+    // --- Pares de registradores ---
+
+    pub fn af(&self) -> u16 {
+        (self.a as u16) << 8 | self.f as u16
+    }
+
+    pub fn bc(&self) -> u16 {
+        (self.b as u16) << 8 | self.c as u16
+    }
+
+    pub fn de(&self) -> u16 {
+        (self.d as u16) << 8 | self.e as u16
+    }
+
+    pub fn hl(&self) -> u16 {
+        (self.h as u16) << 8 | self.l as u16
+    }
+
+    pub fn set_af(&mut self, value: u16) {
+        self.a = (value >> 8) as u8;
+        self.f = value as u8;
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        self.b = (value >> 8) as u8;
+        self.c = value as u8;
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        self.d = (value >> 8) as u8;
+        self.e = value as u8;
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        self.h = (value >> 8) as u8;
+        self.l = value as u8;
+    }
+
+    /// HL, IX ou IY conforme o prefixo
+    fn index(&self, idx: Index) -> u16 {
+        match idx {
+            Index::Hl => self.hl(),
+            Index::Ix => self.ix,
+            Index::Iy => self.iy,
+        }
+    }
+
+    fn set_index(&mut self, idx: Index, value: u16) {
+        match idx {
+            Index::Hl => self.set_hl(value),
+            Index::Ix => self.ix = value,
+            Index::Iy => self.iy = value,
+        }
+    }
+
+    /// Par de registradores do campo `p` (BC, DE, HL/IX/IY, SP)
+    fn rp(&self, p: u8, idx: Index) -> u16 {
+        match p & 3 {
+            0 => self.bc(),
+            1 => self.de(),
+            2 => self.index(idx),
+            _ => self.sp,
+        }
+    }
+
+    fn set_rp(&mut self, p: u8, value: u16, idx: Index) {
+        match p & 3 {
+            0 => self.set_bc(value),
+            1 => self.set_de(value),
+            2 => self.set_index(idx, value),
+            _ => self.sp = value,
+        }
+    }
+
+    /// Par de registradores de PUSH/POP (AF no lugar de SP)
+    fn rp2(&self, p: u8, idx: Index) -> u16 {
+        if p & 3 == 3 {
+            self.af()
+        } else {
+            self.rp(p, idx)
+        }
+    }
+
+    fn set_rp2(&mut self, p: u8, value: u16, idx: Index) {
+        if p & 3 == 3 {
+            self.set_af(value);
+        } else {
+            self.set_rp(p, value, idx);
+        }
+    }
+
+    /// Registrador de 8 bits do campo `r` (exceto 6 = memória).
+    /// Com prefixo, H e L são substituídos pelas metades de IX/IY.
+    fn reg(&self, r: u8, idx: Index) -> u8 {
+        match r & 7 {
+            0 => self.b,
+            1 => self.c,
+            2 => self.d,
+            3 => self.e,
+            4 => (self.index(idx) >> 8) as u8,
+            5 => self.index(idx) as u8,
+            7 => self.a,
+            _ => unreachable!("(HL) não é registrador"),
+        }
+    }
+
+    fn set_reg(&mut self, r: u8, value: u8, idx: Index) {
+        match r & 7 {
+            0 => self.b = value,
+            1 => self.c = value,
+            2 => self.d = value,
+            3 => self.e = value,
+            4 => {
+                let v = self.index(idx);
+                self.set_index(idx, (v & 0x00FF) | (value as u16) << 8);
+            }
+            5 => {
+                let v = self.index(idx);
+                self.set_index(idx, (v & 0xFF00) | value as u16);
+            }
+            7 => self.a = value,
+            _ => unreachable!("(HL) não é registrador"),
+        }
+    }
+
+    /// Condição do campo `cc` (NZ, Z, NC, C, PO, PE, P, M)
+    fn condition(&self, cc: u8) -> bool {
+        match cc & 7 {
+            0 => self.f & ZF == 0,
+            1 => self.f & ZF != 0,
+            2 => self.f & CF == 0,
+            3 => self.f & CF != 0,
+            4 => self.f & PF == 0,
+            5 => self.f & PF != 0,
+            6 => self.f & SF == 0,
+            _ => self.f & SF != 0,
+        }
+    }
+
+    // --- Acesso ao barramento ---
+
+    fn read_byte(&mut self, bus: &mut MemoryBus, addr: u16) -> u8 {
+        bus.z80_read(addr)
+    }
+
+    fn write_byte(&mut self, bus: &mut MemoryBus, addr: u16, value: u8) {
+        bus.z80_write(addr, value);
+    }
+
+    fn read_word(&mut self, bus: &mut MemoryBus, addr: u16) -> u16 {
+        let low = self.read_byte(bus, addr) as u16;
+        let high = self.read_byte(bus, addr.wrapping_add(1)) as u16;
+        high << 8 | low
+    }
+
+    fn write_word(&mut self, bus: &mut MemoryBus, addr: u16, value: u16) {
+        self.write_byte(bus, addr, value as u8);
+        self.write_byte(bus, addr.wrapping_add(1), (value >> 8) as u8);
+    }
+
+    fn port_in(&mut self, bus: &mut MemoryBus, port: u16) -> u8 {
+        bus.z80_in(port)
+    }
+
+    fn port_out(&mut self, bus: &mut MemoryBus, port: u16, value: u8) {
+        bus.z80_out(port, value);
+    }
+
+    /// Ciclo M1: lê um opcode (ou prefixo) e incrementa R
+    fn fetch_opcode(&mut self, bus: &mut MemoryBus) -> u8 {
+        let op = self.read_byte(bus, self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        self.r = (self.r & 0x80) | (self.r.wrapping_add(1) & 0x7F);
+        op
+    }
+
+    /// Lê um operando imediato
+    fn fetch_byte(&mut self, bus: &mut MemoryBus) -> u8 {
+        let value = self.read_byte(bus, self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        value
+    }
+
+    fn fetch_word(&mut self, bus: &mut MemoryBus) -> u16 {
+        let value = self.read_word(bus, self.pc);
+        self.pc = self.pc.wrapping_add(2);
+        value
+    }
+
+    fn push(&mut self, bus: &mut MemoryBus, value: u16) {
+        self.sp = self.sp.wrapping_sub(2);
+        self.write_word(bus, self.sp, value);
+    }
+
+    fn pop(&mut self, bus: &mut MemoryBus) -> u16 {
+        let value = self.read_word(bus, self.sp);
+        self.sp = self.sp.wrapping_add(2);
+        value
+    }
+
+    /// Endereço do operando de memória: (HL) ou (IX+d)/(IY+d)
+    fn mem_operand(&mut self, bus: &mut MemoryBus, idx: Index) -> u16 {
+        match idx {
+            Index::Hl => self.hl(),
+            _ => {
+                let disp = self.fetch_byte(bus) as i8;
+                let ea = self.index(idx).wrapping_add(disp as u16);
+                self.wz = ea;
+                ea
+            }
+        }
+    }
+
+    // --- Interrupções ---
+
+    fn leave_halt(&mut self) {
+        if self.halted {
+            self.halted = false;
+            self.pc = self.pc.wrapping_add(1);
+        }
+    }
+
+    fn take_nmi(&mut self, bus: &mut MemoryBus) {
+        trace!("Z80 NMI em ${:04X}", self.pc);
+        self.leave_halt();
+        self.iff1 = false;
+        self.push(bus, self.pc);
+        self.pc = 0x0066;
+        self.wz = self.pc;
+        self.cyc += CYC_NMI;
+    }
+
+    fn take_interrupt(&mut self, bus: &mut MemoryBus) {
+        self.leave_halt();
+        self.iff1 = false;
+        self.iff2 = false;
+
+        match self.im {
+            1 => {
+                // RST $38
+                self.push(bus, self.pc);
+                self.pc = 0x0038;
+                self.cyc += (CC_OP[0xFF] + CC_EX[0xFF]) as u32;
+            }
+            2 => {
+                // CALL ($I:vetor)
+                let vector = (self.i as u16) << 8 | self.irq_vector as u16;
+                self.push(bus, self.pc);
+                self.pc = self.read_word(bus, vector);
+                self.cyc += (CC_OP[0xCD] + CC_EX[0xFF]) as u32;
+            }
+            _ => {
+                // Modo 0: a instrução no barramento é tratada como RST
+                self.push(bus, self.pc);
+                self.pc = (self.irq_vector & 0x38) as u16;
+                self.cyc += (CC_OP[0xFF] + CC_EX[0xFF]) as u32;
+            }
+        }
+        trace!("Z80 IRQ modo {} -> ${:04X}", self.im, self.pc);
+        self.wz = self.pc;
+    }
+
+    // --- ALU ---
+
+    fn add8(&mut self, value: u8, carry: u8) {
+        let a = self.a;
+        let wide = a as u16 + value as u16 + carry as u16;
+        let res = wide as u8;
+        self.f = sz(res)
+            | ((a ^ value ^ res) & HF)
+            | ((wide >> 8) as u8 & CF)
+            | (((!(a ^ value) & (a ^ res)) >> 5) & VF);
+        self.a = res;
+    }
+
+    /// Subtração com flags (resultado não armazenado)
+    fn sub8_flags(&mut self, value: u8, carry: u8) -> u8 {
+        let a = self.a;
+        let wide = (a as u16).wrapping_sub(value as u16).wrapping_sub(carry as u16);
+        let res = wide as u8;
+        self.f = NF
+            | sz(res)
+            | ((a ^ value ^ res) & HF)
+            | ((wide >> 8) as u8 & CF)
+            | ((((a ^ value) & (a ^ res)) >> 5) & VF);
+        res
+    }
+
+    /// Operação da ALU do campo `op` (ADD, ADC, SUB, SBC, AND, XOR, OR, CP)
+    fn alu(&mut self, op: u8, value: u8) {
+        match op & 7 {
+            0 => self.add8(value, 0),
+            1 => self.add8(value, self.f & CF),
+            2 => self.a = self.sub8_flags(value, 0),
+            3 => self.a = self.sub8_flags(value, self.f & CF),
+            4 => {
+                self.a &= value;
+                self.f = szp(self.a) | HF;
+            }
+            5 => {
+                self.a ^= value;
+                self.f = szp(self.a);
+            }
+            6 => {
+                self.a |= value;
+                self.f = szp(self.a);
+            }
+            _ => {
+                // CP: bits 5/3 vêm do operando
+                self.sub8_flags(value, 0);
+                self.f = (self.f & !(YF | XF)) | (value & (YF | XF));
+            }
+        }
+    }
+
+    fn inc8(&mut self, value: u8) -> u8 {
+        let res = value.wrapping_add(1);
+        self.f = (self.f & CF)
+            | sz(res)
+            | if res == 0x80 { VF } else { 0 }
+            | if res & 0x0F == 0 { HF } else { 0 };
+        res
+    }
+
+    fn dec8(&mut self, value: u8) -> u8 {
+        let res = value.wrapping_sub(1);
+        self.f = (self.f & CF)
+            | NF
+            | sz(res)
+            | if res == 0x7F { VF } else { 0 }
+            | if res & 0x0F == 0x0F { HF } else { 0 };
+        res
+    }
+
+    fn daa(&mut self) {
+        let a = self.a;
+        let mut res = a;
+        let lo = self.f & HF != 0 || a & 0x0F > 9;
+        let hi = self.f & CF != 0 || a > 0x99;
+        if self.f & NF != 0 {
+            if lo {
+                res = res.wrapping_sub(0x06);
+            }
+            if hi {
+                res = res.wrapping_sub(0x60);
+            }
+        } else {
+            if lo {
+                res = res.wrapping_add(0x06);
+            }
+            if hi {
+                res = res.wrapping_add(0x60);
+            }
+        }
+        self.f = (self.f & (CF | NF)) | (a > 0x99) as u8 | ((a ^ res) & HF) | szp(res);
+        self.a = res;
+    }
+
+    fn add16(&mut self, dst: u16, src: u16) -> u16 {
+        let res = dst as u32 + src as u32;
+        self.wz = dst.wrapping_add(1);
+        self.f = (self.f & (SF | ZF | VF))
+            | (((dst as u32 ^ res ^ src as u32) >> 8) as u8 & HF)
+            | ((res >> 16) as u8 & CF)
+            | ((res >> 8) as u8 & (YF | XF));
+        res as u16
+    }
+
+    fn adc16(&mut self, value: u16) {
+        let hl = self.hl() as u32;
+        let value = value as u32;
+        let res = hl + value + (self.f & CF) as u32;
+        self.wz = (hl as u16).wrapping_add(1);
+        self.f = (((hl ^ res ^ value) >> 8) as u8 & HF)
+            | ((res >> 16) as u8 & CF)
+            | ((res >> 8) as u8 & (SF | YF | XF))
+            | if res & 0xFFFF == 0 { ZF } else { 0 }
+            | ((((value ^ hl ^ 0x8000) & (value ^ res) & 0x8000) >> 13) as u8);
+        self.set_hl(res as u16);
+    }
+
+    fn sbc16(&mut self, value: u16) {
+        let hl = self.hl() as u32;
+        let value = value as u32;
+        let res = hl.wrapping_sub(value).wrapping_sub((self.f & CF) as u32);
+        self.wz = (hl as u16).wrapping_add(1);
+        self.f = (((hl ^ res ^ value) >> 8) as u8 & HF)
+            | NF
+            | ((res >> 16) as u8 & CF)
+            | ((res >> 8) as u8 & (SF | YF | XF))
+            | if res & 0xFFFF == 0 { ZF } else { 0 }
+            | ((((value ^ hl) & (hl ^ res) & 0x8000) >> 13) as u8);
+        self.set_hl(res as u16);
+    }
+
+    /// Rotações e deslocamentos do grupo CB (RLC, RRC, RL, RR, SLA, SRA, SLL, SRL)
+    fn rot(&mut self, op: u8, value: u8) -> u8 {
+        let (res, carry) = match op & 7 {
+            0 => (value.rotate_left(1), value >> 7),
+            1 => (value.rotate_right(1), value & 1),
+            2 => (value << 1 | (self.f & CF), value >> 7),
+            3 => (value >> 1 | (self.f & CF) << 7, value & 1),
+            4 => (value << 1, value >> 7),
+            5 => (value >> 1 | (value & 0x80), value & 1),
+            6 => (value << 1 | 1, value >> 7),
+            _ => (value >> 1, value & 1),
+        };
+        self.f = szp(res) | carry;
+        res
+    }
+
+    /// Flags de BIT n; `xy` fornece os bits 5/3 (registrador, WZ ou endereço)
+    fn bit(&mut self, bit: u8, value: u8, xy: u8) {
+        let res = value & (1 << bit);
+        let sz_bit = if res == 0 { ZF | PF } else { res & SF };
+        self.f = (self.f & CF) | HF | sz_bit | (xy & (YF | XF));
+    }
+
+    // --- Decodificação ---
+
+    /// Instruções sem prefixo, ou com prefixo DD/FD quando `idx` != HL
+    fn execute_op(&mut self, bus: &mut MemoryBus, op: u8, idx: Index) {
+        self.cyc += if idx == Index::Hl { CC_OP[op as usize] } else { CC_XY[op as usize] } as u32;
+
+        let y = (op >> 3) & 7;
+        let z = op & 7;
+        let p = y >> 1;
+
+        match op {
+            0x00 => {}
+
+            // LD rr,nn
+            0x01 | 0x11 | 0x21 | 0x31 => {
+                let value = self.fetch_word(bus);
+                self.set_rp(p, value, idx);
+            }
+
+            // LD (BC),A / LD (DE),A
+            0x02 | 0x12 => {
+                let addr = self.rp(p, idx);
+                self.write_byte(bus, addr, self.a);
+                self.wz = ((addr.wrapping_add(1)) & 0xFF) | (self.a as u16) << 8;
+            }
+
+            // LD A,(BC) / LD A,(DE)
+            0x0A | 0x1A => {
+                let addr = self.rp(p, idx);
+                self.a = self.read_byte(bus, addr);
+                self.wz = addr.wrapping_add(1);
+            }
+
+            // INC rr / DEC rr
+            0x03 | 0x13 | 0x23 | 0x33 => {
+                let value = self.rp(p, idx).wrapping_add(1);
+                self.set_rp(p, value, idx);
+            }
+            0x0B | 0x1B | 0x2B | 0x3B => {
+                let value = self.rp(p, idx).wrapping_sub(1);
+                self.set_rp(p, value, idx);
+            }
+
+            // INC r / DEC r
+            0x04 | 0x0C | 0x14 | 0x1C | 0x24 | 0x2C | 0x34 | 0x3C => {
+                if y == 6 {
+                    let addr = self.mem_operand(bus, idx);
+                    let value = self.read_byte(bus, addr);
+                    let res = self.inc8(value);
+                    self.write_byte(bus, addr, res);
+                } else {
+                    let value = self.reg(y, idx);
+                    let res = self.inc8(value);
+                    self.set_reg(y, res, idx);
+                }
+            }
+            0x05 | 0x0D | 0x15 | 0x1D | 0x25 | 0x2D | 0x35 | 0x3D => {
+                if y == 6 {
+                    let addr = self.mem_operand(bus, idx);
+                    let value = self.read_byte(bus, addr);
+                    let res = self.dec8(value);
+                    self.write_byte(bus, addr, res);
+                } else {
+                    let value = self.reg(y, idx);
+                    let res = self.dec8(value);
+                    self.set_reg(y, res, idx);
+                }
+            }
+
+            // LD r,n
+            0x06 | 0x0E | 0x16 | 0x1E | 0x26 | 0x2E | 0x36 | 0x3E => {
+                if y == 6 {
+                    let addr = self.mem_operand(bus, idx);
+                    let value = self.fetch_byte(bus);
+                    self.write_byte(bus, addr, value);
+                } else {
+                    let value = self.fetch_byte(bus);
+                    self.set_reg(y, value, idx);
+                }
+            }
+
+            // RLCA / RRCA / RLA / RRA
+            0x07 => {
+                self.a = self.a.rotate_left(1);
+                self.f = (self.f & (SF | ZF | PF)) | (self.a & (YF | XF | CF));
+            }
+            0x0F => {
+                self.f = (self.f & (SF | ZF | PF)) | (self.a & CF);
+                self.a = self.a.rotate_right(1);
+                self.f |= self.a & (YF | XF);
+            }
+            0x17 => {
+                let res = self.a << 1 | (self.f & CF);
+                self.f = (self.f & (SF | ZF | PF)) | (self.a >> 7) | (res & (YF | XF));
+                self.a = res;
+            }
+            0x1F => {
+                let res = self.a >> 1 | (self.f & CF) << 7;
+                self.f = (self.f & (SF | ZF | PF)) | (self.a & CF) | (res & (YF | XF));
+                self.a = res;
+            }
+
+            // EX AF,AF'
+            0x08 => {
+                let af = self.af();
+                self.set_af(self.af_alt);
+                self.af_alt = af;
+            }
+
+            // ADD HL,rr
+            0x09 | 0x19 | 0x29 | 0x39 => {
+                let dst = self.index(idx);
+                let src = self.rp(p, idx);
+                let res = self.add16(dst, src);
+                self.set_index(idx, res);
+            }
+
+            // DJNZ e
+            0x10 => {
+                self.b = self.b.wrapping_sub(1);
+                self.jr_cond(bus, self.b != 0, op);
+            }
+
+            // JR e / JR cc,e
+            0x18 => self.jr_cond(bus, true, op),
+            0x20 | 0x28 | 0x30 | 0x38 => {
+                let taken = self.condition(y - 4);
+                self.jr_cond(bus, taken, op);
+            }
+
+            // LD (nn),HL / LD HL,(nn)
+            0x22 => {
+                let addr = self.fetch_word(bus);
+                let value = self.index(idx);
+                self.write_word(bus, addr, value);
+                self.wz = addr.wrapping_add(1);
+            }
+            0x2A => {
+                let addr = self.fetch_word(bus);
+                let value = self.read_word(bus, addr);
+                self.set_index(idx, value);
+                self.wz = addr.wrapping_add(1);
+            }
+
+            // LD (nn),A / LD A,(nn)
+            0x32 => {
+                let addr = self.fetch_word(bus);
+                self.write_byte(bus, addr, self.a);
+                self.wz = (addr.wrapping_add(1) & 0xFF) | (self.a as u16) << 8;
+            }
+            0x3A => {
+                let addr = self.fetch_word(bus);
+                self.a = self.read_byte(bus, addr);
+                self.wz = addr.wrapping_add(1);
+            }
+
+            0x27 => self.daa(),
+            // CPL
+            0x2F => {
+                self.a = !self.a;
+                self.f = (self.f & (SF | ZF | PF | CF)) | HF | NF | (self.a & (YF | XF));
+            }
+            // SCF
+            0x37 => {
+                self.f = (self.f & (SF | ZF | YF | XF | PF)) | CF | (self.a & (YF | XF));
+            }
+            // CCF
+            0x3F => {
+                self.f = ((self.f & (SF | ZF | YF | XF | PF | CF)) | ((self.f & CF) << 4) | (self.a & (YF | XF))) ^ CF;
+            }
+
+            // HALT: repete o próprio opcode até a próxima interrupção
+            0x76 => {
+                self.halted = true;
+                self.pc = self.pc.wrapping_sub(1);
+            }
+
+            // LD r,r'
+            0x40..=0x7F => {
+                if z == 6 {
+                    let addr = self.mem_operand(bus, idx);
+                    let value = self.read_byte(bus, addr);
+                    self.set_reg(y, value, Index::Hl);
+                } else if y == 6 {
+                    let addr = self.mem_operand(bus, idx);
+                    let value = self.reg(z, Index::Hl);
+                    self.write_byte(bus, addr, value);
+                } else {
+                    let value = self.reg(z, idx);
+                    self.set_reg(y, value, idx);
+                }
+            }
+
+            // ALU A,r
+            0x80..=0xBF => {
+                let value = if z == 6 {
+                    let addr = self.mem_operand(bus, idx);
+                    self.read_byte(bus, addr)
+                } else {
+                    self.reg(z, idx)
+                };
+                self.alu(y, value);
+            }
+
+            // RET cc
+            0xC0 | 0xC8 | 0xD0 | 0xD8 | 0xE0 | 0xE8 | 0xF0 | 0xF8 => {
+                if self.condition(y) {
+                    self.pc = self.pop(bus);
+                    self.wz = self.pc;
+                    self.cyc += CC_EX[op as usize] as u32;
+                }
+            }
+
+            // POP rr / PUSH rr
+            0xC1 | 0xD1 | 0xE1 | 0xF1 => {
+                let value = self.pop(bus);
+                self.set_rp2(p, value, idx);
+            }
+            0xC5 | 0xD5 | 0xE5 | 0xF5 => {
+                let value = self.rp2(p, idx);
+                self.push(bus, value);
+            }
+
+            // JP cc,nn / JP nn
+            0xC2 | 0xCA | 0xD2 | 0xDA | 0xE2 | 0xEA | 0xF2 | 0xFA => {
+                let addr = self.fetch_word(bus);
+                self.wz = addr;
+                if self.condition(y) {
+                    self.pc = addr;
+                }
+            }
+            0xC3 => {
+                self.pc = self.fetch_word(bus);
+                self.wz = self.pc;
+            }
+
+            // CALL cc,nn / CALL nn
+            0xC4 | 0xCC | 0xD4 | 0xDC | 0xE4 | 0xEC | 0xF4 | 0xFC => {
+                let addr = self.fetch_word(bus);
+                self.wz = addr;
+                if self.condition(y) {
+                    self.push(bus, self.pc);
+                    self.pc = addr;
+                    self.cyc += CC_EX[op as usize] as u32;
+                }
+            }
+            0xCD => {
+                let addr = self.fetch_word(bus);
+                self.wz = addr;
+                self.push(bus, self.pc);
+                self.pc = addr;
+            }
+
+            // ALU A,n
+            0xC6 | 0xCE | 0xD6 | 0xDE | 0xE6 | 0xEE | 0xF6 | 0xFE => {
+                let value = self.fetch_byte(bus);
+                self.alu(y, value);
+            }
+
+            // RST p
+            0xC7 | 0xCF | 0xD7 | 0xDF | 0xE7 | 0xEF | 0xF7 | 0xFF => {
+                self.push(bus, self.pc);
+                self.pc = (op & 0x38) as u16;
+                self.wz = self.pc;
+            }
+
+            0xC9 => {
+                self.pc = self.pop(bus);
+                self.wz = self.pc;
+            }
+
+            // Prefixos
+            0xCB => {
+                if idx == Index::Hl {
+                    let op = self.fetch_opcode(bus);
+                    self.execute_cb(bus, op);
+                } else {
+                    self.execute_xycb(bus, idx);
+                }
+            }
+            0xDD => {
+                let op = self.fetch_opcode(bus);
+                self.execute_op(bus, op, Index::Ix);
+            }
+            0xFD => {
+                let op = self.fetch_opcode(bus);
+                self.execute_op(bus, op, Index::Iy);
+            }
+            0xED => {
+                let op = self.fetch_opcode(bus);
+                self.execute_ed(bus, op);
+            }
+
+            // OUT (n),A / IN A,(n)
+            0xD3 => {
+                let n = self.fetch_byte(bus);
+                let port = (self.a as u16) << 8 | n as u16;
+                self.port_out(bus, port, self.a);
+                self.wz = (n.wrapping_add(1) as u16) | (self.a as u16) << 8;
+            }
+            0xDB => {
+                let n = self.fetch_byte(bus);
+                let port = (self.a as u16) << 8 | n as u16;
+                self.a = self.port_in(bus, port);
+                self.wz = port.wrapping_add(1);
+            }
+
+            // EXX
+            0xD9 => {
+                let (bc, de, hl) = (self.bc(), self.de(), self.hl());
+                self.set_bc(self.bc_alt);
+                self.set_de(self.de_alt);
+                self.set_hl(self.hl_alt);
+                self.bc_alt = bc;
+                self.de_alt = de;
+                self.hl_alt = hl;
+            }
+
+            // EX (SP),HL
+            0xE3 => {
+                let value = self.read_word(bus, self.sp);
+                let old = self.index(idx);
+                self.write_word(bus, self.sp, old);
+                self.set_index(idx, value);
+                self.wz = value;
+            }
+
+            // JP (HL)
+            0xE9 => self.pc = self.index(idx),
+
+            // EX DE,HL (não é afetado pelos prefixos)
+            0xEB => {
+                let de = self.de();
+                self.set_de(self.hl());
+                self.set_hl(de);
+            }
+
+            // DI / EI
+            0xF3 => {
+                self.iff1 = false;
+                self.iff2 = false;
+            }
+            0xFB => {
+                self.iff1 = true;
+                self.iff2 = true;
+                self.after_ei = true;
+            }
+
+            // LD SP,HL
+            0xF9 => self.sp = self.index(idx),
+        }
+    }
+
+    /// JR/DJNZ: o deslocamento é sempre lido; o desvio tomado custa `CC_EX`
+    fn jr_cond(&mut self, bus: &mut MemoryBus, taken: bool, op: u8) {
+        let disp = self.fetch_byte(bus) as i8;
+        if taken {
+            self.pc = self.pc.wrapping_add(disp as u16);
+            self.wz = self.pc;
+            self.cyc += CC_EX[op as usize] as u32;
+        }
+    }
+
+    /// Prefixo CB: rotações, deslocamentos e operações de bit
+    fn execute_cb(&mut self, bus: &mut MemoryBus, op: u8) {
+        self.cyc += CC_CB[op as usize] as u32;
+
+        let y = (op >> 3) & 7;
+        let z = op & 7;
+        let value = if z == 6 {
+            let addr = self.hl();
+            self.read_byte(bus, addr)
+        } else {
+            self.reg(z, Index::Hl)
+        };
+
+        let res = match op >> 6 {
+            0 => self.rot(y, value),
+            1 => {
+                let xy = if z == 6 { (self.wz >> 8) as u8 } else { value };
+                self.bit(y, value, xy);
+                return;
+            }
+            2 => value & !(1 << y),
+            _ => value | (1 << y),
+        };
+
+        if z == 6 {
+            let addr = self.hl();
+            self.write_byte(bus, addr, res);
+        } else {
+            self.set_reg(z, res, Index::Hl);
+        }
+    }
+
+    /// Prefixos DDCB/FDCB: o operando é sempre (IX+d)/(IY+d). Fora de BIT,
+    /// o resultado também é copiado para o registrador do campo `z`
+    /// (comportamento não documentado).
+    fn execute_xycb(&mut self, bus: &mut MemoryBus, idx: Index) {
+        let addr = self.mem_operand(bus, idx);
+        let op = self.fetch_byte(bus);
+        self.cyc += CC_XYCB[op as usize] as u32;
+
+        let y = (op >> 3) & 7;
+        let z = op & 7;
+        let value = self.read_byte(bus, addr);
+
+        let res = match op >> 6 {
+            0 => self.rot(y, value),
+            1 => {
+                self.bit(y, value, (addr >> 8) as u8);
+                return;
+            }
+            2 => value & !(1 << y),
+            _ => value | (1 << y),
+        };
+
+        self.write_byte(bus, addr, res);
+        if z != 6 {
+            self.set_reg(z, res, Index::Hl);
+        }
+    }
+
+    /// Prefixo ED: instruções estendidas e de bloco
+    fn execute_ed(&mut self, bus: &mut MemoryBus, op: u8) {
+        self.cyc += CC_ED[op as usize] as u32;
+
+        let y = (op >> 3) & 7;
+        let p = y >> 1;
+
+        match op {
+            // IN r,(C) (r = 6 só afeta as flags)
+            0x40 | 0x48 | 0x50 | 0x58 | 0x60 | 0x68 | 0x70 | 0x78 => {
+                let port = self.bc();
+                let value = self.port_in(bus, port);
+                self.wz = port.wrapping_add(1);
+                self.f = (self.f & CF) | szp(value);
+                if y != 6 {
+                    self.set_reg(y, value, Index::Hl);
+                }
+            }
+
+            // OUT (C),r (r = 6 escreve 0)
+            0x41 | 0x49 | 0x51 | 0x59 | 0x61 | 0x69 | 0x71 | 0x79 => {
+                let port = self.bc();
+                let value = if y == 6 { 0 } else { self.reg(y, Index::Hl) };
+                self.port_out(bus, port, value);
+                self.wz = port.wrapping_add(1);
+            }
+
+            // SBC HL,rr / ADC HL,rr
+            0x42 | 0x52 | 0x62 | 0x72 => {
+                let value = self.rp(p, Index::Hl);
+                self.sbc16(value);
+            }
+            0x4A | 0x5A | 0x6A | 0x7A => {
+                let value = self.rp(p, Index::Hl);
+                self.adc16(value);
+            }
+
+            // LD (nn),rr / LD rr,(nn)
+            0x43 | 0x53 | 0x63 | 0x73 => {
+                let addr = self.fetch_word(bus);
+                let value = self.rp(p, Index::Hl);
+                self.write_word(bus, addr, value);
+                self.wz = addr.wrapping_add(1);
+            }
+            0x4B | 0x5B | 0x6B | 0x7B => {
+                let addr = self.fetch_word(bus);
+                let value = self.read_word(bus, addr);
+                self.set_rp(p, value, Index::Hl);
+                self.wz = addr.wrapping_add(1);
+            }
+
+            // NEG (e espelhos)
+            0x44 | 0x4C | 0x54 | 0x5C | 0x64 | 0x6C | 0x74 | 0x7C => {
+                let value = self.a;
+                self.a = 0;
+                self.a = self.sub8_flags(value, 0);
+            }
+
+            // RETN / RETI (ambos restauram IFF1 a partir de IFF2)
+            0x45 | 0x4D | 0x55 | 0x5D | 0x65 | 0x6D | 0x75 | 0x7D => {
+                self.pc = self.pop(bus);
+                self.wz = self.pc;
+                self.iff1 = self.iff2;
+            }
+
+            // IM 0 / IM 1 / IM 2
+            0x46 | 0x4E | 0x66 | 0x6E => self.im = 0,
+            0x56 | 0x76 => self.im = 1,
+            0x5E | 0x7E => self.im = 2,
+
+            // LD I,A / LD R,A / LD A,I / LD A,R
+            0x47 => self.i = self.a,
+            0x4F => self.r = self.a,
+            0x57 => {
+                self.a = self.i;
+                self.f = (self.f & CF) | sz(self.a) | if self.iff2 { PF } else { 0 };
+            }
+            0x5F => {
+                self.a = self.r;
+                self.f = (self.f & CF) | sz(self.a) | if self.iff2 { PF } else { 0 };
+            }
+
+            // RRD / RLD
+            0x67 => {
+                let addr = self.hl();
+                let n = self.read_byte(bus, addr);
+                self.wz = addr.wrapping_add(1);
+                self.write_byte(bus, addr, (n >> 4) | (self.a << 4));
+                self.a = (self.a & 0xF0) | (n & 0x0F);
+                self.f = (self.f & CF) | szp(self.a);
+            }
+            0x6F => {
+                let addr = self.hl();
+                let n = self.read_byte(bus, addr);
+                self.wz = addr.wrapping_add(1);
+                self.write_byte(bus, addr, (n << 4) | (self.a & 0x0F));
+                self.a = (self.a & 0xF0) | (n >> 4);
+                self.f = (self.f & CF) | szp(self.a);
+            }
+
+            // Instruções de bloco
+            0xA0 | 0xA8 | 0xB0 | 0xB8 => {
+                self.ldi_ldd(bus, op & 0x08 != 0);
+                if op & 0x10 != 0 && self.bc() != 0 {
+                    self.repeat_block(op);
+                    self.wz = self.pc.wrapping_add(1);
+                }
+            }
+            0xA1 | 0xA9 | 0xB1 | 0xB9 => {
+                self.cpi_cpd(bus, op & 0x08 != 0);
+                if op & 0x10 != 0 && self.bc() != 0 && self.f & ZF == 0 {
+                    self.repeat_block(op);
+                    self.wz = self.pc.wrapping_add(1);
+                }
+            }
+            0xA2 | 0xAA | 0xB2 | 0xBA => {
+                self.ini_ind(bus, op & 0x08 != 0);
+                if op & 0x10 != 0 && self.b != 0 {
+                    self.repeat_block(op);
+                }
+            }
+            0xA3 | 0xAB | 0xB3 | 0xBB => {
+                self.outi_outd(bus, op & 0x08 != 0);
+                if op & 0x10 != 0 && self.b != 0 {
+                    self.repeat_block(op);
+                }
+            }
+
+            // Demais opcodes ED são NOPs
+            _ => {}
+        }
+    }
+
+    /// Repete a instrução de bloco voltando o PC para o prefixo ED
+    fn repeat_block(&mut self, op: u8) {
+        self.pc = self.pc.wrapping_sub(2);
+        self.cyc += CC_EX[op as usize] as u32;
+    }
+
+    fn step_hl(&mut self, dec: bool) {
+        let hl = self.hl();
+        self.set_hl(if dec { hl.wrapping_sub(1) } else { hl.wrapping_add(1) });
+    }
+
+    fn ldi_ldd(&mut self, bus: &mut MemoryBus, dec: bool) {
+        let value = self.read_byte(bus, self.hl());
+        self.write_byte(bus, self.de(), value);
+        let n = self.a.wrapping_add(value);
+        self.f = (self.f & (SF | ZF | CF)) | (n & XF) | ((n << 4) & YF);
+        self.step_hl(dec);
+        let de = self.de();
+        self.set_de(if dec { de.wrapping_sub(1) } else { de.wrapping_add(1) });
+        self.set_bc(self.bc().wrapping_sub(1));
+        if self.bc() != 0 {
+            self.f |= VF;
+        }
+    }
+
+    fn cpi_cpd(&mut self, bus: &mut MemoryBus, dec: bool) {
+        let value = self.read_byte(bus, self.hl());
+        let mut res = self.a.wrapping_sub(value);
+        self.wz = if dec { self.wz.wrapping_sub(1) } else { self.wz.wrapping_add(1) };
+        self.step_hl(dec);
+        self.set_bc(self.bc().wrapping_sub(1));
+        self.f = (self.f & CF) | (sz(res) & !(YF | XF)) | ((self.a ^ value ^ res) & HF) | NF;
+        if self.f & HF != 0 {
+            res = res.wrapping_sub(1);
+        }
+        self.f |= (res & XF) | ((res << 4) & YF);
+        if self.bc() != 0 {
+            self.f |= VF;
+        }
+    }
+
+    /// Flags comuns de INI/IND/OUTI/OUTD; `k` é a soma auxiliar de 9 bits
+    fn block_io_flags(&mut self, value: u8, k: u16) {
+        self.f = sz(self.b);
+        if value & SF != 0 {
+            self.f |= NF;
+        }
+        if k & 0x100 != 0 {
+            self.f |= HF | CF;
+        }
+        self.f |= szp(((k & 0x07) as u8) ^ self.b) & PF;
+    }
+
+    fn ini_ind(&mut self, bus: &mut MemoryBus, dec: bool) {
+        let port = self.bc();
+        let value = self.port_in(bus, port);
+        self.wz = if dec { port.wrapping_sub(1) } else { port.wrapping_add(1) };
+        self.b = self.b.wrapping_sub(1);
+        self.write_byte(bus, self.hl(), value);
+        self.step_hl(dec);
+        let c = if dec { self.c.wrapping_sub(1) } else { self.c.wrapping_add(1) };
+        self.block_io_flags(value, c as u16 + value as u16);
+    }
+
+    fn outi_outd(&mut self, bus: &mut MemoryBus, dec: bool) {
+        let value = self.read_byte(bus, self.hl());
+        self.b = self.b.wrapping_sub(1);
+        let port = self.bc();
+        self.wz = if dec { port.wrapping_sub(1) } else { port.wrapping_add(1) };
+        self.port_out(bus, port, value);
+        self.step_hl(dec);
+        self.block_io_flags(value, self.l as u16 + value as u16);
+    }
+}
+
+impl Default for Z80 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Carrega `code` em $0000 da Z80 RAM com o Z80 liberado pelo 68000
+    fn setup(code: &[u8]) -> (Z80, MemoryBus) {
+        let mut bus = MemoryBus::new();
+        bus.zram[..code.len()].copy_from_slice(code);
+        bus.z80_reset = false;
+        let mut cpu = Z80::new();
+        cpu.reset();
+        (cpu, bus)
+    }
+
+    #[test]
+    fn test_ld_add_flags_and_cycles() {
+        // LD A,$7F ; ADD A,$01 ; LD B,A
+        let (mut cpu, mut bus) = setup(&[0x3E, 0x7F, 0xC6, 0x01, 0x47]);
+        assert_eq!(cpu.step(&mut bus), 7);
+        assert_eq!(cpu.step(&mut bus), 7);
+        assert_eq!(cpu.step(&mut bus), 4);
+        assert_eq!(cpu.b, 0x80);
+        assert_eq!(cpu.f, SF | HF | VF);
+    }
+
+    #[test]
+    fn test_djnz_and_ldir() {
+        // LD HL,$1000 ; LD DE,$1100 ; LD BC,3 ; LDIR
+        let (mut cpu, mut bus) = setup(&[0x21, 0x00, 0x10, 0x11, 0x00, 0x11, 0x01, 0x03, 0x00, 0xED, 0xB0]);
+        bus.zram[0x1000..0x1003].copy_from_slice(&[1, 2, 3]);
+        for _ in 0..3 {
+            cpu.step(&mut bus);
+        }
+        assert_eq!(cpu.step(&mut bus), 21);
+        assert_eq!(cpu.step(&mut bus), 21);
+        assert_eq!(cpu.step(&mut bus), 16);
+        assert_eq!(&bus.zram[0x1100..0x1103], &[1, 2, 3]);
+        assert_eq!(cpu.bc(), 0);
+        assert_eq!(cpu.f & VF, 0);
+
+        // LD B,2 ; loop: DJNZ loop
+        let (mut cpu, mut bus) = setup(&[0x06, 0x02, 0x10, 0xFE]);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.step(&mut bus), 13);
+        assert_eq!(cpu.step(&mut bus), 8);
+        assert_eq!(cpu.pc, 4);
+    }
+
+    #[test]
+    fn test_index_registers_and_undocumented() {
+        // LD IX,$1000 ; LD (IX+5),$AA ; LD IXH,$12 ; SET 0,(IX+5)->B (DD CB 05 C0)
+        let (mut cpu, mut bus) = setup(&[
+            0xDD, 0x21, 0x00, 0x10, 0xDD, 0x36, 0x05, 0xAA, 0xDD, 0x26, 0x12, 0xDD, 0xCB, 0x05, 0xC0,
+        ]);
+        assert_eq!(cpu.step(&mut bus), 14);
+        assert_eq!(cpu.step(&mut bus), 19);
+        assert_eq!(bus.zram[0x1005], 0xAA);
+        assert_eq!(cpu.step(&mut bus), CC_XY[0x26] as u32);
+        assert_eq!(cpu.ix, 0x1200);
+        cpu.ix = 0x1000;
+        assert_eq!(cpu.step(&mut bus), 23);
+        assert_eq!(bus.zram[0x1005], 0xAB);
+        assert_eq!(cpu.b, 0xAB);
+        assert_eq!(cpu.r & 0x7F, 8);
+    }
+
+    #[test]
+    fn test_im1_and_im2_interrupts() {
+        // IM 1 ; EI ; NOP ; HALT
+        let (mut cpu, mut bus) = setup(&[0xED, 0x56, 0xFB, 0x00, 0x76]);
+        cpu.sp = 0x1F00;
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        cpu.set_irq_line(true);
+        // A interrupção só é aceita após a instrução seguinte ao EI
+        assert_eq!(cpu.step(&mut bus), 4);
+        assert_eq!(cpu.step(&mut bus), 13);
+        assert_eq!(cpu.pc, 0x0038);
+        assert_eq!(bus.zram[0x1EFE], 0x04);
+        assert!(!cpu.iff1);
+
+        // IM 2 com vetor em $1234
+        let (mut cpu, mut bus) = setup(&[0xED, 0x5E, 0xFB, 0x00]);
+        cpu.sp = 0x1F00;
+        cpu.i = 0x10;
+        cpu.irq_vector = 0x20;
+        bus.zram[0x1020] = 0x34;
+        bus.zram[0x1021] = 0x12;
+        for _ in 0..3 {
+            cpu.step(&mut bus);
+        }
+        cpu.set_irq_line(true);
+        assert_eq!(cpu.step(&mut bus), 19);
+        assert_eq!(cpu.pc, 0x1234);
+    }
+
+    #[test]
+    fn test_halted_by_busreq_and_reset() {
+        // INC A em loop
+        let (mut cpu, mut bus) = setup(&[0x3C, 0x18, 0xFD]);
+        cpu.execute(&mut bus, 40);
+        let a = cpu.a;
+        assert!(a > 0);
+
+        bus.z80_busreq = true;
+        assert_eq!(cpu.execute(&mut bus, 100), 100);
+        assert_eq!(cpu.a, a);
+
+        bus.z80_busreq = false;
+        bus.z80_reset = true;
+        cpu.execute(&mut bus, 10);
+        assert_eq!(cpu.pc, 0);
+    }
+
+    #[test]
+    fn test_bank_window_into_68k_space() {
+        use crate::core::memory::Cartridge;
+
+        // ROM de 128KB com $5A em $018123
+        let mut rom = vec![0u8; 0x20000];
+        rom[0x18123] = 0x5A;
+        let mut cart = Cartridge::new();
+        cart.load_from_buffer(&rom).unwrap();
+
+        // LD HL,$6000 ; LD A,1 ; 2x LD (HL),A ; XOR A ; 7x LD (HL),A
+        // (banco $018000: A15 é o primeiro bit escrito) ; LD A,($8123)
+        let mut code = vec![0x21, 0x00, 0x60, 0x3E, 0x01, 0x77, 0x77, 0xAF];
+        code.extend_from_slice(&[0x77; 7]);
+        code.extend_from_slice(&[0x3A, 0x23, 0x81]);
+        let (mut cpu, mut bus) = setup(&code);
+        bus.init(cart).unwrap();
+
+        for _ in 0..12 {
+            cpu.step(&mut bus);
+        }
+        assert_eq!(bus.zbank, 0x018000);
+        let wait = bus.m68k_wait;
+        assert_eq!(cpu.step(&mut bus), 13 + 3);
+        assert_eq!(cpu.a, 0x5A);
+        assert!(bus.m68k_wait > wait);
+
+        // A RAM do 68000 aceita escrita mas não leitura pela janela
+        bus.zbank = 0xFF8000;
+        bus.z80_write(0x8010, 0x77);
+        assert_eq!(bus.wram[0x8010], 0x77);
+        assert_eq!(bus.z80_read(0x8010), 0xFF);
+    }
+
+    #[test]
+    fn test_bit_hl_uses_wz() {
+        // LD A,($2000) -> WZ=$2001 ; BIT 0,(HL)
+        let (mut cpu, mut bus) = setup(&[0x3A, 0x00, 0x20, 0xCB, 0x46]);
+        cpu.set_hl(0x0100);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.step(&mut bus), 12);
+        assert_eq!(cpu.f & (YF | XF), 0x20);
+        assert_ne!(cpu.f & ZF, 0);
+    }
+}
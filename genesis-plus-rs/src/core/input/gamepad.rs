@@ -0,0 +1,195 @@
+//! Controles de 3 e 6 botões do Mega Drive.
+//! Baseado em `input_hw/gamepad.c` do Genesis Plus GX.
+//!
+//! O console seleciona o que o controle devolve pelo pino TH:
+//!
+//! ```text
+//! TH = 1 : ?1CBRLDU    TH = 0 : ?0SA00DU    (controle de 3 botões)
+//! ```
+//!
+//! O controle de 6 botões conta as subidas do TH: na terceira descida
+//! D3-D0 vão a 0 (identificação), na quarta subida devolvem MXYZ e na
+//! quarta descida vão a 1. Sem novas subidas por ~1,5 ms, a contagem volta
+//! a zero e o controle se comporta como um de 3 botões.
+
+use crate::core::input::{InputState, IoDevice, PIN_TH};
+use crate::utils::clock::M68K_DIVIDER;
+
+/// Sem subidas do TH por este tempo, o controle de 6 botões volta ao início
+/// da sequência (~1,5 ms, 25 linhas no Genesis Plus GX)
+const TIMEOUT_MCYCLES: u64 = 80_000;
+
+/// O TH só sobe dentro do controle 172 ciclos da CPU que acessa as portas
+/// (68000 ou, no Master System, Z80) depois que o pino volta a ser entrada
+/// (medido no MK-1650; Decap Attack depende disto)
+const TH_RISE_CYCLES: u64 = 172;
+
+/// Modelo do controle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadType {
+    ThreeButton,
+    SixButton,
+}
+
+/// Controle de 3 ou 6 botões, lido do jogador `player` do `InputState`
+pub struct Gamepad {
+    kind: PadType,
+    player: usize,
+    /// Nível do TH visto pelo controle
+    th: bool,
+    /// Subidas do TH contadas pelo controle de 6 botões (de 2 em 2)
+    counter: u8,
+    /// Clock mestre da última subida contada
+    last_rise: u64,
+    /// Clock mestre em que o TH sobe internamente após virar entrada
+    th_rise_at: u64,
+    /// Divisor do clock mestre da CPU que acessa as portas
+    cpu_divider: u64,
+}
+
+impl Gamepad {
+    pub fn new(kind: PadType, player: usize) -> Self {
+        Self {
+            kind,
+            player,
+            // TH em nível alto por padrão (Samurai Shodown, Power Instinct)
+            th: true,
+            counter: 0,
+            last_rise: 0,
+            th_rise_at: 0,
+            cpu_divider: M68K_DIVIDER,
+        }
+    }
+
+    pub fn kind(&self) -> PadType {
+        self.kind
+    }
+
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    /// Volta ao início da sequência se o TH ficou parado tempo demais
+    fn check_timeout(&mut self, mcycles: u64) {
+        if self.counter != 0 && mcycles.saturating_sub(self.last_rise) > TIMEOUT_MCYCLES {
+            self.counter = 0;
+        }
+    }
+}
+
+impl IoDevice for Gamepad {
+    fn read(&mut self, input: &InputState, mcycles: u64) -> u8 {
+        self.check_timeout(mcycles);
+        let pad = input.pads[self.player];
+        // D7 não está conectado, D6 devolve o TH
+        let mut data = if self.th { 0x7F } else { 0x3F };
+        let mut step = self.counter | self.th as u8;
+        if mcycles < self.th_rise_at {
+            step &= !1;
+        }
+
+        // Botões ativos em 0: C/B ou START/A em D5-D4, direcionais ou
+        // botões extras em D3-D0
+        let start_a = ((pad >> 2) & 0x30) as u8;
+        data &= match step {
+            // Terceira descida: D3-D0 forçados a 0
+            4 => !(start_a | 0x0F),
+            // Quarta subida: ?1CBMXYZ
+            7 => !((pad & 0x30) as u8 | ((pad >> 8) & 0x0F) as u8),
+            // Quarta descida: D3-D0 forçados a 1
+            6 => !start_a,
+            _ if step & 1 != 0 => !((pad & 0x3F) as u8),
+            _ => !((pad & 0x03) as u8 | start_a | 0x0C),
+        };
+        data
+    }
+
+    fn write(&mut self, data: u8, mask: u8, mcycles: u64) {
+        if mask & PIN_TH == 0 {
+            // TH como entrada: o resistor de pull-up leva o pino a 1, com atraso
+            if !self.th {
+                self.th_rise_at = mcycles + TH_RISE_CYCLES * self.cpu_divider;
+            }
+            self.th = true;
+            return;
+        }
+
+        let th = data & PIN_TH != 0;
+        self.th_rise_at = 0;
+        if self.kind == PadType::SixButton && th && !self.th {
+            self.check_timeout(mcycles);
+            if self.counter < 8 {
+                self.counter += 2;
+                self.last_rise = mcycles;
+            }
+        }
+        self.th = th;
+    }
+
+    fn set_cpu_divider(&mut self, divider: u64) {
+        self.cpu_divider = divider;
+    }
+
+    fn reset(&mut self) {
+        self.th = true;
+        self.counter = 0;
+        self.last_rise = 0;
+        self.th_rise_at = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::{INPUT_A, INPUT_MODE, INPUT_START, INPUT_UP, INPUT_X};
+
+    /// Sequência de leituras alternando o TH, como nas rotinas dos jogos
+    fn th_cycle(pad: &mut Gamepad, input: &InputState, mcycles: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        for i in 0..8 {
+            let th = if i % 2 == 0 { 0x40 } else { 0x00 };
+            pad.write(th, 0x40, mcycles + i * 100);
+            out.push(pad.read(input, mcycles + i * 100));
+        }
+        out
+    }
+
+    #[test]
+    fn test_six_button_sequence_and_timeout() {
+        let mut input = InputState::default();
+        input.pads[1] = INPUT_UP | INPUT_A | INPUT_START | INPUT_X | INPUT_MODE;
+
+        let mut pad = Gamepad::new(PadType::SixButton, 1);
+        let reads = th_cycle(&mut pad, &input, 0);
+        assert_eq!(reads[0], 0x7E); // ?1CBRLDU
+        assert_eq!(reads[1], 0x02); // ?0SA00DU
+        assert_eq!(reads[5], 0x00); // terceira descida: D3-D0 = 0
+        assert_eq!(reads[6], 0x73); // quarta subida: ?1CBMXYZ
+        assert_eq!(reads[7], 0x0F); // quarta descida: D3-D0 = 1
+
+        // A sequência só recomeça depois do timeout
+        let reads = th_cycle(&mut pad, &input, 2_000);
+        assert_eq!(reads[6], 0x7E);
+        // (o TH estava baixo: a primeira escrita já é uma subida)
+        let reads = th_cycle(&mut pad, &input, 2_000 + 2 * TIMEOUT_MCYCLES);
+        assert_eq!(reads[4], 0x73);
+
+        // O controle de 3 botões ignora a contagem
+        let mut pad = Gamepad::new(PadType::ThreeButton, 1);
+        let reads = th_cycle(&mut pad, &input, 0);
+        assert_eq!(reads[5], 0x02);
+        assert_eq!(reads[6], 0x7E);
+    }
+
+    #[test]
+    fn test_th_rise_latency() {
+        let mut input = InputState::default();
+        input.pads[0] = INPUT_A;
+        let mut pad = Gamepad::new(PadType::ThreeButton, 0);
+        pad.write(0x00, 0x40, 0);
+        // TH volta a ser entrada: os botões de TH = 0 continuam visíveis
+        pad.write(0x00, 0x00, 1_000);
+        assert_eq!(pad.read(&input, 1_000) & 0x3F, 0x23);
+        assert_eq!(pad.read(&input, 1_000 + TH_RISE_CYCLES * M68K_DIVIDER) & 0x3F, 0x3F);
+    }
+}
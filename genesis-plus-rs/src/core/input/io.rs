@@ -0,0 +1,323 @@
+//! Chip de I/O do Mega Drive ($A10000-$A1001F).
+//! Baseado em `io_ctrl.c` do Genesis Plus GX.
+//!
+//! O registrador de versão identifica região, padrão de vídeo e a presença
+//! do Mega-CD. Cada uma das três portas tem um registrador de dados e um de
+//! controle: os pinos marcados como saída no controle devolvem o valor
+//! escrito, os de entrada vêm do dispositivo conectado. Com o bit 7 do
+//! controle ligado, uma descida do TH configurado como entrada gera a
+//! interrupção externa (/HL) no VDP.
+//!
+//! No modo Master System as mesmas portas aparecem em $DC/$DD, com a
+//! direção e os níveis de saída de TR e TH escolhidos pela porta $3F.
+
+use log::trace;
+
+use crate::core::input::{Beam, InputState, IoDevice, Port, PIN_TH, PIN_TR, PORT_COUNT};
+use crate::core::system::Region;
+use crate::utils::clock::M68K_DIVIDER;
+
+/// Registrador de versão, bit 7: console de exportação
+const VERSION_OVERSEAS: u8 = 0x80;
+/// Registrador de versão, bit 6: vídeo PAL
+const VERSION_PAL: u8 = 0x40;
+/// Registrador de versão, bit 5: nenhuma unidade Mega-CD conectada
+const VERSION_NO_CD: u8 = 0x20;
+
+/// Controle de porta, bit 7: interrupção externa pelo TH
+const CTRL_HL: u8 = 0x80;
+
+/// Porta sem dispositivo: pinos em nível alto pelos resistores de pull-up
+const UNCONNECTED: u8 = 0x7F;
+
+/// Chip de I/O e os dispositivos conectados às portas
+pub struct IoChip {
+    /// Controles do frontend, lidos pelos dispositivos
+    pub input: InputState,
+    /// Registradores, indexados por (endereço >> 1) & $0F
+    reg: [u8; 16],
+    /// Controle 1, controle 2 e expansão
+    devices: [Option<Box<dyn IoDevice>>; PORT_COUNT],
+    /// Último nível do TH de cada porta, para detectar as descidas
+    th: [bool; PORT_COUNT],
+    /// Controle de I/O do Master System (porta $3F)
+    sms_ctrl: u8,
+    /// Divisor do clock mestre da CPU que acessa as portas
+    cpu_divider: u64,
+}
+
+impl IoChip {
+    pub fn new() -> Self {
+        let mut io = Self {
+            input: InputState::default(),
+            reg: [0; 16],
+            devices: [None, None, None],
+            th: [true; PORT_COUNT],
+            sms_ctrl: 0xFF,
+            cpu_divider: M68K_DIVIDER,
+        };
+        io.power_on(Region::Usa, false, false);
+        io
+    }
+
+    /// Define o registrador de versão e reseta as portas
+    pub fn power_on(&mut self, region: Region, tmss: bool, mega_cd: bool) {
+        let mut version = tmss as u8;
+        if region != Region::Japan {
+            version |= VERSION_OVERSEAS;
+        }
+        if region.is_pal() {
+            version |= VERSION_PAL;
+        }
+        if !mega_cd {
+            version |= VERSION_NO_CD;
+        }
+        self.reg[0] = version;
+        self.reset();
+    }
+
+    /// Reset: portas como entrada, buffers seriais vazios. O registrador de
+    /// versão não muda.
+    pub fn reset(&mut self) {
+        self.reg[1..].copy_from_slice(&[
+            0x00, 0x00, 0x00, // Dados
+            0x00, 0x00, 0x00, // Controle
+            0xFF, 0x00, 0x00, // Porta A: TxData, RxData, S-Ctrl
+            0xFF, 0x00, 0x00, // Porta B
+            0xFB, 0x00, 0x00, // Porta C
+        ]);
+        for device in self.devices.iter_mut().flatten() {
+            device.reset();
+        }
+        self.th = [true; PORT_COUNT];
+        self.sms_ctrl = 0xFF;
+    }
+
+    /// Registrador de versão ($A10001)
+    pub fn version(&self) -> u8 {
+        self.reg[0]
+    }
+
+    /// Conecta um dispositivo à porta, substituindo o anterior. `None`
+    /// desconecta.
+    pub fn connect(&mut self, port: Port, device: Option<Box<dyn IoDevice>>) {
+        let port = port as usize;
+        self.devices[port] = device;
+        if let Some(device) = &mut self.devices[port] {
+            device.set_cpu_divider(self.cpu_divider);
+            device.reset();
+        }
+        self.th[port] = true;
+    }
+
+    /// Define a CPU que acessa as portas pelo divisor do seu clock
+    /// (`M68K_DIVIDER` ou, no Master System, `Z80_DIVIDER`)
+    pub fn set_cpu_divider(&mut self, divider: u64) {
+        self.cpu_divider = divider;
+        for device in self.devices.iter_mut().flatten() {
+            device.set_cpu_divider(divider);
+        }
+    }
+
+    /// Dispositivo conectado à porta
+    pub fn device(&self, port: Port) -> Option<&dyn IoDevice> {
+        self.devices[port as usize].as_deref()
+    }
+
+    /// Níveis de entrada vindos do dispositivo da porta
+    fn pins(&mut self, port: usize, mcycles: u64) -> u8 {
+        let input = &self.input;
+        self.devices[port].as_mut().map_or(UNCONNECTED, |device| device.read(input, mcycles))
+    }
+
+    /// Lê o registrador `offset`
+    pub fn read(&mut self, offset: usize, mcycles: u64) -> u8 {
+        match offset {
+            // D7 devolve o valor escrito; os pinos de saída, o registrador
+            1..=3 => {
+                let mask = 0x80 | self.reg[offset + 3];
+                let data = self.pins(offset - 1, mcycles);
+                (self.reg[offset] & mask) | (data & !mask)
+            }
+            _ => self.reg[offset],
+        }
+    }
+
+    /// Escreve no registrador `offset`
+    pub fn write(&mut self, offset: usize, value: u8, mcycles: u64) {
+        match offset {
+            1..=3 => {
+                self.reg[offset] = value;
+                if let Some(device) = &mut self.devices[offset - 1] {
+                    device.write(value, self.reg[offset + 3], mcycles);
+                }
+            }
+            4..=6 => {
+                if value != self.reg[offset] {
+                    self.reg[offset] = value;
+                    if let Some(device) = &mut self.devices[offset - 4] {
+                        device.write(self.reg[offset - 3], value, mcycles);
+                    }
+                }
+            }
+            // TxData
+            7 | 10 | 13 => self.reg[offset] = value,
+            // S-Ctrl: bits 2-0 só de leitura
+            9 | 12 | 15 => self.reg[offset] = value & 0xF8,
+            _ => trace!("I/O: escrita no registrador só de leitura {} <- ${:02X}", offset, value),
+        }
+    }
+
+    /// Início de uma linha do VDP: clock mestre em que uma pistola de luz
+    /// vai levar o TH a 0 nesta linha
+    pub fn start_line(&mut self, beam: &Beam) -> Option<u64> {
+        let input = &self.input;
+        self.devices.iter_mut().flatten().filter_map(|device| device.start_line(input, beam)).min()
+    }
+
+    /// Porta $3F do Master System: D3-D0 dão a direção do TR e do TH das
+    /// portas A e B (1 = entrada), D7-D4 os níveis de saída. Devolve `true`
+    /// se o TH de saída de alguma porta subiu, o que trava o contador HV.
+    pub fn write_sms_control(&mut self, value: u8, mcycles: u64) -> bool {
+        let rise = !self.sms_ctrl & value & 0xA0 != 0;
+        self.sms_ctrl = value;
+        for port in 0..2 {
+            let dir = value >> (port * 2);
+            let level = value >> (port * 2 + 4);
+            // Traduz para os registradores de dados e controle; no Master
+            // System toda descida do TH de entrada trava o contador HV
+            let mut ctrl = CTRL_HL;
+            if dir & 0x01 == 0 {
+                ctrl |= PIN_TR;
+            }
+            if dir & 0x02 == 0 {
+                ctrl |= PIN_TH;
+            }
+            let data = (level & 0x03) << 5;
+            self.reg[1 + port] = data;
+            self.reg[4 + port] = ctrl;
+            if let Some(device) = &mut self.devices[port] {
+                device.write(data, ctrl, mcycles);
+            }
+        }
+        rise
+    }
+
+    /// Portas $DC (`port` par) e $DD do Master System
+    pub fn read_sms_port(&mut self, port: u16, mcycles: u64) -> u8 {
+        let a = self.read(1, mcycles);
+        let b = self.read(2, mcycles);
+        if port & 1 == 0 {
+            // Porta A e os direcionais para cima e para baixo da porta B
+            (a & 0x3F) | (b << 6)
+        } else {
+            // Resto da porta B, RESET (D4, ativo em 0) e os TH das duas portas
+            ((b >> 2) & 0x0F) | 0x30 | (a & PIN_TH) | ((b & PIN_TH) << 1)
+        }
+    }
+
+    /// Amostra o TH de entrada das portas. Devolve `true` se alguma porta
+    /// com a interrupção externa ligada viu o TH descer.
+    pub fn poll_th(&mut self, mcycles: u64) -> bool {
+        let mut interrupt = false;
+        for port in 0..PORT_COUNT {
+            let ctrl = self.reg[4 + port];
+            if ctrl & PIN_TH != 0 {
+                // TH como saída: não há borda externa
+                self.th[port] = true;
+                continue;
+            }
+            let level = self.pins(port, mcycles) & PIN_TH != 0;
+            if self.th[port] && !level && ctrl & CTRL_HL != 0 {
+                interrupt = true;
+            }
+            self.th[port] = level;
+        }
+        interrupt
+    }
+}
+
+impl Default for IoChip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::{Gamepad, PadType, INPUT_A};
+    use crate::utils::clock::Z80_DIVIDER;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::sync::Arc;
+
+    /// Dispositivo de teste: coloca `level` nos pinos
+    struct Probe {
+        level: Arc<AtomicU8>,
+    }
+
+    impl IoDevice for Probe {
+        fn read(&mut self, _input: &InputState, _mcycles: u64) -> u8 {
+            self.level.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn test_version_and_port_direction() {
+        let mut io = IoChip::new();
+        io.power_on(Region::Japan, false, true);
+        assert_eq!(io.version(), 0x00);
+        io.power_on(Region::Europe, true, false);
+        assert_eq!(io.version(), 0xE1);
+        assert_eq!(io.read(0, 0), 0xE1);
+        assert_eq!(io.read(7, 0), 0xFF);
+
+        // Porta vazia: entradas em nível alto, D7 devolve o valor escrito
+        io.write(1, 0x80, 0);
+        assert_eq!(io.read(1, 0), 0xFF);
+        io.write(1, 0x00, 0);
+        assert_eq!(io.read(1, 0), 0x7F);
+
+        let level = Arc::new(AtomicU8::new(0x3C));
+        io.connect(Port::B, Some(Box::new(Probe { level: level.clone() })));
+        // TH e D0 como saída
+        io.write(5, 0x41, 0);
+        io.write(2, 0x41, 0);
+        assert_eq!(io.read(2, 0), 0x7D);
+        io.write(9, 0xFF, 0);
+        assert_eq!(io.read(9, 0), 0xF8);
+    }
+
+    #[test]
+    fn test_th_external_interrupt() {
+        let mut io = IoChip::new();
+        let level = Arc::new(AtomicU8::new(0x7F));
+        io.connect(Port::A, Some(Box::new(Probe { level: level.clone() })));
+        assert!(!io.poll_th(0));
+
+        // Sem o bit 7 do controle, a descida não gera interrupção
+        level.store(0x3F, Ordering::Relaxed);
+        assert!(!io.poll_th(0));
+        level.store(0x7F, Ordering::Relaxed);
+        io.write(4, 0x80, 0);
+        assert!(!io.poll_th(0));
+        level.store(0x3F, Ordering::Relaxed);
+        assert!(io.poll_th(0));
+        // Só na borda
+        assert!(!io.poll_th(0));
+    }
+    #[test]
+    fn test_sms_th_rise_latency() {
+        let mut io = IoChip::new();
+        io.input.pads[0] = INPUT_A;
+        io.connect(Port::A, Some(Box::new(Gamepad::new(PadType::ThreeButton, 0))));
+        io.set_cpu_divider(Z80_DIVIDER);
+
+        // TH da porta A como saída em 0 e depois de volta a entrada: o
+        // pull-up só chega ao controle 172 ciclos de Z80 depois
+        io.write_sms_control(0xDD, 0);
+        io.write_sms_control(0xFF, 1_000);
+        assert_eq!(io.read_sms_port(0xDC, 1_000 + 172 * M68K_DIVIDER) & 0x3F, 0x23);
+        assert_eq!(io.read_sms_port(0xDC, 1_000 + 172 * Z80_DIVIDER) & 0x3F, 0x3F);
+    }
+}
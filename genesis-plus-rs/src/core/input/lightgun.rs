@@ -0,0 +1,245 @@
+//! Pistolas de luz: Sega Menacer, Konami Justifier e Sega Light Phaser.
+//! Baseado em `input_hw/lightgun.c` do Genesis Plus GX.
+//!
+//! O frontend informa a mira em pixels da parte visível da tela
+//! (`InputState::analog`). No início de cada linha a pistola calcula em que
+//! clock mestre o feixe passa pela mira; a partir desse instante o sensor
+//! mantém o TH em 0 até o fim da linha. O chip de I/O vê a descida e trava o
+//! contador HV no pixel certo (no Mega Drive, só com o /HL da porta ligado,
+//! o que também gera a interrupção externa). A calibração desloca o ponto
+//! em que o sensor dispara.
+
+use crate::core::input::{InputState, IoDevice, INPUT_A, PIN_TH, PIN_TL, PIN_TR};
+use crate::core::vdp::{hvc, Viewport};
+use crate::utils::clock::MCYCLES_PER_LINE;
+
+/// Linhas acima e abaixo da mira em que o Light Phaser ainda vê o feixe
+const PHASER_LINES: i32 = 5;
+
+/// Calibração da mira: `x` em passos do contador H (2 pixels), `y` em linhas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GunOffset {
+    pub x: i16,
+    pub y: i16,
+}
+
+impl GunOffset {
+    /// Valores padrão do Genesis Plus GX
+    pub const MENACER: GunOffset = GunOffset { x: 64, y: 0 };
+    pub const JUSTIFIER: GunOffset = GunOffset { x: 0, y: 0 };
+    pub const LIGHT_PHASER: GunOffset = GunOffset { x: 20, y: 0 };
+}
+
+/// Posição do feixe no início de uma linha
+#[derive(Debug, Clone, Copy)]
+pub struct Beam {
+    /// Linha do quadro e clock mestre em que ela começa
+    pub line: u16,
+    pub mcycles: u64,
+    /// Modo de 40 células
+    pub h40: bool,
+    /// Parte visível da imagem
+    pub viewport: Viewport,
+}
+
+impl Beam {
+    /// Clock mestre em que o feixe passa pela mira `[x, y]`, se ela está na
+    /// tela e a até `lines` linhas desta
+    fn crossing(&self, [x, y]: [i16; 2], offset: GunOffset, lines: i32) -> Option<u64> {
+        let viewport = self.viewport;
+        if x < 0 || y < 0 || x as usize >= viewport.width || y as u16 >= viewport.height {
+            return None;
+        }
+        let target = i32::from(viewport.y) + i32::from(y) + i32::from(offset.y);
+        if (i32::from(self.line) - target).abs() > lines {
+            return None;
+        }
+        let pixel = viewport.x as i64 + i64::from(x);
+        let steps = (pixel / 2 + i64::from(offset.x)).max(0) as u64;
+        Some(self.mcycles + hvc::hcounter_mcycles(self.h40, steps))
+    }
+}
+
+/// Sensor óptico: TH em 0 do instante em que vê o feixe até o fim da linha
+#[derive(Debug, Clone, Copy, Default)]
+struct Sensor {
+    lit: Option<(u64, u64)>,
+}
+
+impl Sensor {
+    fn aim(&mut self, beam: &Beam, crossing: Option<u64>) -> Option<u64> {
+        self.lit = crossing.map(|at| (at, beam.mcycles + MCYCLES_PER_LINE));
+        crossing
+    }
+
+    fn th(&self, mcycles: u64) -> u8 {
+        match self.lit {
+            Some((from, to)) if (from..to).contains(&mcycles) => 0,
+            _ => PIN_TH,
+        }
+    }
+}
+
+/// Sega Menacer do jogador `player`: receptor infravermelho na porta que
+/// devolve os botões da pistola em D3-D0 (ativos em 1)
+pub struct Menacer {
+    player: usize,
+    offset: GunOffset,
+    sensor: Sensor,
+}
+
+impl Menacer {
+    pub fn new(player: usize, offset: GunOffset) -> Self {
+        Self { player, offset, sensor: Sensor::default() }
+    }
+}
+
+impl IoDevice for Menacer {
+    fn read(&mut self, input: &InputState, mcycles: u64) -> u8 {
+        // D0 = B, D1 = gatilho (A), D2 = C, D3 = START; TL e TR em 0
+        let data = (input.pads[self.player] >> 4) as u8;
+        (data & 0x09) | ((data >> 1) & 0x02) | ((data << 1) & 0x04) | self.sensor.th(mcycles)
+    }
+
+    fn start_line(&mut self, input: &InputState, beam: &Beam) -> Option<u64> {
+        // Os jogos ampliam a posição informada pelo receptor
+        let [x, y] = input.analog[self.player];
+        let x = (i32::from(x) * 289 / 320) as i16;
+        self.sensor.aim(beam, beam.crossing([x, y], self.offset, 0))
+    }
+
+    fn reset(&mut self) {
+        self.sensor = Sensor::default();
+    }
+}
+
+/// Par de Konami Justifiers, dos jogadores `player` (azul) e `player + 1`
+/// (rosa). O TR escolhe a pistola que responde; com o TH de saída em 1 a
+/// porta devolve a identificação.
+pub struct Justifier {
+    player: usize,
+    offset: GunOffset,
+    /// Pinos de saída escritos pelo console
+    state: u8,
+    sensor: Sensor,
+}
+
+impl Justifier {
+    pub fn new(player: usize, offset: GunOffset) -> Self {
+        Self { player, offset, state: PIN_TH, sensor: Sensor::default() }
+    }
+
+    /// Jogador da pistola selecionada pelo TR
+    fn gun(&self) -> usize {
+        self.player + ((self.state & PIN_TR) >> 5) as usize
+    }
+}
+
+impl IoDevice for Justifier {
+    fn read(&mut self, input: &InputState, mcycles: u64) -> u8 {
+        if self.state & PIN_TH != 0 {
+            return PIN_TR | PIN_TL;
+        }
+        // Gatilho (A) em D0 e START em D1, ativos em 0; D3-D2 em 0
+        let buttons = (!input.pads[self.gun()] >> 6) as u8 & 0x03;
+        buttons | PIN_TR | PIN_TL | self.sensor.th(mcycles)
+    }
+
+    fn write(&mut self, data: u8, mask: u8, _mcycles: u64) {
+        // Pinos de entrada ficam em 0 (Lethal Enforcers II)
+        self.state = data & mask;
+    }
+
+    fn start_line(&mut self, input: &InputState, beam: &Beam) -> Option<u64> {
+        let aim = input.analog[self.gun()];
+        self.sensor.aim(beam, beam.crossing(aim, self.offset, 0))
+    }
+
+    fn reset(&mut self) {
+        self.state = PIN_TH;
+        self.sensor = Sensor::default();
+    }
+}
+
+/// Sega Light Phaser do Master System: gatilho no TL, sensor no TH
+pub struct LightPhaser {
+    player: usize,
+    offset: GunOffset,
+    sensor: Sensor,
+}
+
+impl LightPhaser {
+    pub fn new(player: usize, offset: GunOffset) -> Self {
+        Self { player, offset, sensor: Sensor::default() }
+    }
+}
+
+impl IoDevice for LightPhaser {
+    fn read(&mut self, input: &InputState, mcycles: u64) -> u8 {
+        let trigger = if input.pads[self.player] & INPUT_A != 0 { PIN_TL } else { 0 };
+        (0x3F & !trigger) | self.sensor.th(mcycles)
+    }
+
+    fn start_line(&mut self, input: &InputState, beam: &Beam) -> Option<u64> {
+        // A lente vê o brilho de várias linhas em torno da mira
+        let aim = input.analog[self.player];
+        self.sensor.aim(beam, beam.crossing(aim, self.offset, PHASER_LINES))
+    }
+
+    fn reset(&mut self) {
+        self.sensor = Sensor::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::INPUT_START;
+
+    fn beam(line: u16, h40: bool) -> Beam {
+        let width = if h40 { 320 } else { 256 };
+        Beam { line, mcycles: 10 * MCYCLES_PER_LINE, h40, viewport: Viewport { x: 0, width, y: 0, height: 224 } }
+    }
+
+    #[test]
+    fn test_gun_crossing_matches_hcounter() {
+        let mut input = InputState::default();
+        input.analog[1] = [200, 100];
+        input.pads[1] = INPUT_A;
+        let mut gun = Justifier::new(0, GunOffset { x: 4, y: 2 });
+
+        // Pistola rosa (TR = 1); TH como entrada
+        gun.write(PIN_TR, PIN_TR, 0);
+        assert_eq!(gun.start_line(&input, &beam(101, true)), None);
+        let at = gun.start_line(&input, &beam(102, true)).unwrap();
+        let start = 10 * MCYCLES_PER_LINE;
+        assert_eq!(hvc::hcounter(true, at - start), 100 + 4);
+        assert_eq!(hvc::hcounter(true, at - start - 1), 100 + 3);
+
+        // TH em 0 só depois do cruzamento; gatilho ativo em 0
+        assert_eq!(gun.read(&input, at - 1), 0x72);
+        assert_eq!(gun.read(&input, at), 0x32);
+        assert_eq!(gun.read(&input, start + MCYCLES_PER_LINE), 0x72);
+
+        // TH de saída em 1: identificação
+        gun.write(PIN_TH, PIN_TH | PIN_TR, 0);
+        assert_eq!(gun.read(&input, at), 0x30);
+    }
+
+    #[test]
+    fn test_menacer_and_phaser_buttons() {
+        let mut input = InputState::default();
+        input.pads[0] = INPUT_A | INPUT_START;
+        let mut menacer = Menacer::new(0, GunOffset::MENACER);
+        assert_eq!(menacer.read(&input, 0), 0x4A);
+
+        // O Phaser vê o feixe em várias linhas perto da mira
+        input.analog[0] = [100, 50];
+        let mut phaser = LightPhaser::new(0, GunOffset::LIGHT_PHASER);
+        assert!(phaser.start_line(&input, &beam(45, false)).is_some());
+        assert!(phaser.start_line(&input, &beam(56, false)).is_none());
+        let at = phaser.start_line(&input, &beam(55, false)).unwrap();
+        assert_eq!(hvc::hcounter(false, at - 10 * MCYCLES_PER_LINE), 50 + 20);
+        assert_eq!(phaser.read(&input, at), 0x2F);
+    }
+}
@@ -0,0 +1,150 @@
+//! Portas de controle e periféricos de entrada.
+//! Baseado em `io_ctrl.c` e `input_hw/` do Genesis Plus GX.
+
+pub mod gamepad;
+pub mod io;
+pub mod lightgun;
+pub mod mouse;
+pub mod multitap;
+
+pub use gamepad::{Gamepad, PadType};
+pub use io::IoChip;
+pub use lightgun::{Beam, GunOffset, Justifier, LightPhaser, Menacer};
+pub use mouse::Mouse;
+pub use multitap::{FourWayPlay, FourWayPlaySelect, TeamPlayer};
+
+/// Pino TH (D6) de uma porta de controle
+pub const PIN_TH: u8 = 0x40;
+/// Pino TR (D5)
+pub const PIN_TR: u8 = 0x20;
+/// Pino TL (D4)
+pub const PIN_TL: u8 = 0x10;
+
+/// Portas do chip de I/O: controle 1, controle 2 e expansão
+pub const PORT_COUNT: usize = 3;
+
+/// Porta de controle em que o frontend conecta um periférico (a de
+/// expansão fica vazia)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Port {
+    /// Controle 1
+    A,
+    /// Controle 2
+    B,
+}
+
+/// Jogadores lógicos (dois adaptadores de 4 jogadores)
+pub const MAX_PLAYERS: usize = 8;
+
+/// Botões de um controle em `InputState::pads` (1 = pressionado)
+pub const INPUT_MODE: u16 = 0x0800;
+pub const INPUT_X: u16 = 0x0400;
+pub const INPUT_Y: u16 = 0x0200;
+pub const INPUT_Z: u16 = 0x0100;
+pub const INPUT_START: u16 = 0x0080;
+pub const INPUT_A: u16 = 0x0040;
+pub const INPUT_C: u16 = 0x0020;
+pub const INPUT_B: u16 = 0x0010;
+pub const INPUT_RIGHT: u16 = 0x0008;
+pub const INPUT_LEFT: u16 = 0x0004;
+pub const INPUT_DOWN: u16 = 0x0002;
+pub const INPUT_UP: u16 = 0x0001;
+
+/// Botões do Mega Mouse (START é `INPUT_START`)
+pub const INPUT_MOUSE_CENTER: u16 = 0x0040;
+pub const INPUT_MOUSE_RIGHT: u16 = 0x0020;
+pub const INPUT_MOUSE_LEFT: u16 = 0x0010;
+
+/// Estado dos controles fornecido pelo frontend a cada quadro
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    /// Botões de cada jogador (ver `INPUT_*`)
+    pub pads: [u16; MAX_PLAYERS],
+    /// Eixos de cada jogador: movimento do mouse desde o quadro anterior
+    /// (X para a direita, Y para baixo) ou mira da pistola de luz em pixels
+    /// da parte visível da tela
+    pub analog: [[i16; 2]; MAX_PLAYERS],
+}
+
+/// Periférico escolhido pelo frontend para uma porta de controle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortDevice {
+    None,
+    Pad(PadType),
+    /// Sega Team Player com quatro controles
+    TeamPlayer(PadType),
+    /// EA 4-Way Play com quatro controles: ocupa as duas portas
+    FourWayPlay(PadType),
+    /// Sega Mega Mouse
+    Mouse,
+    /// Sega Menacer (porta 2 nos jogos do Mega Drive)
+    Menacer(GunOffset),
+    /// Par de Konami Justifiers (porta 2)
+    Justifier(GunOffset),
+    /// Sega Light Phaser do Master System
+    LightPhaser(GunOffset),
+}
+
+impl Default for PortDevice {
+    fn default() -> Self {
+        PortDevice::Pad(PadType::ThreeButton)
+    }
+}
+
+impl PortDevice {
+    /// Jogadores lógicos ocupados pelo periférico
+    pub fn players(self) -> usize {
+        match self {
+            PortDevice::None => 0,
+            PortDevice::Pad(_) | PortDevice::Mouse | PortDevice::Menacer(_) | PortDevice::LightPhaser(_) => 1,
+            PortDevice::Justifier(_) => 2,
+            PortDevice::TeamPlayer(_) | PortDevice::FourWayPlay(_) => 4,
+        }
+    }
+}
+
+/// Cria os dispositivos das portas 1 e 2, numerando os jogadores lógicos em
+/// sequência. O 4-Way Play na porta 1 ocupa também a porta 2.
+pub fn create_devices(ports: [PortDevice; 2]) -> [Option<Box<dyn IoDevice>>; 2] {
+    if let PortDevice::FourWayPlay(kind) = ports[0] {
+        let (port_a, port_b) = FourWayPlay::new(kind, 0);
+        return [Some(Box::new(port_a)), Some(Box::new(port_b))];
+    }
+    let mut player = 0;
+    ports.map(|device| {
+        let first = player;
+        player += device.players();
+        match device {
+            PortDevice::None | PortDevice::FourWayPlay(_) => None,
+            PortDevice::Pad(kind) => Some(Box::new(Gamepad::new(kind, first)) as Box<dyn IoDevice>),
+            PortDevice::TeamPlayer(kind) => Some(Box::new(TeamPlayer::new(kind, first)) as Box<dyn IoDevice>),
+            PortDevice::Mouse => Some(Box::new(Mouse::new(first)) as Box<dyn IoDevice>),
+            PortDevice::Menacer(offset) => Some(Box::new(Menacer::new(first, offset)) as Box<dyn IoDevice>),
+            PortDevice::Justifier(offset) => Some(Box::new(Justifier::new(first, offset)) as Box<dyn IoDevice>),
+            PortDevice::LightPhaser(offset) => Some(Box::new(LightPhaser::new(first, offset)) as Box<dyn IoDevice>),
+        }
+    })
+}
+
+/// Periférico conectado a uma porta de controle. Os níveis dos pinos usam
+/// o layout do registrador de dados: D6 = TH, D5 = TR, D4 = TL, D3-D0.
+pub trait IoDevice: Send {
+    /// Níveis que o periférico coloca nos pinos
+    fn read(&mut self, input: &InputState, mcycles: u64) -> u8;
+
+    /// Níveis escritos pelo console; só os pinos em `mask` são saídas
+    fn write(&mut self, _data: u8, _mask: u8, _mcycles: u64) {}
+
+    /// Início de uma linha do VDP. Uma pistola de luz devolve o clock mestre
+    /// em que seu sensor vê o feixe nesta linha.
+    fn start_line(&mut self, _input: &InputState, _beam: &Beam) -> Option<u64> {
+        None
+    }
+
+    /// Divisor do clock mestre da CPU que acessa as portas: 68000 no modo
+    /// Mega Drive, Z80 no Master System
+    fn set_cpu_divider(&mut self, _divider: u64) {}
+
+    /// Reset do console ou conexão à porta
+    fn reset(&mut self) {}
+}
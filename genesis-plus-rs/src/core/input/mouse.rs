@@ -0,0 +1,155 @@
+//! Sega Mega Mouse.
+//! Baseado em `input_hw/mouse.c` do Genesis Plus GX.
+//!
+//! A leitura começa com a descida do TH. A cada mudança do TR o mouse
+//! avança um nibble e confirma pelo TL (igual ao TR quando pronto):
+//! identificação, sinais e estouro dos eixos, botões (START, meio, direito,
+//! esquerdo) e os 8 bits baixos de X e de Y. Os deslocamentos são
+//! travados no início da leitura.
+
+use crate::core::input::{InputState, IoDevice, PIN_TH, PIN_TR};
+
+/// Leituras com o TL ainda ocupado após cada mudança do TR (Cannon Fodder,
+/// Shanghai II e outros dependem do atraso)
+const HANDSHAKE_WAIT: u8 = 2;
+
+/// Mega Mouse do jogador `player`: botões em `InputState::pads` (ver
+/// `INPUT_MOUSE_*`) e movimento em `InputState::analog`
+pub struct Mouse {
+    player: usize,
+    /// Níveis de TH e TR escritos pelo console
+    state: u8,
+    /// Posição na sequência de leitura
+    counter: u8,
+    /// Leituras restantes até o TL confirmar a mudança do TR
+    wait: u8,
+    /// O movimento ainda não foi travado nesta leitura
+    pending: bool,
+    /// Deslocamentos travados (9 bits com sinal, Y para cima) e estouro
+    dx: i16,
+    dy: i16,
+    overflow: u8,
+}
+
+impl Mouse {
+    pub fn new(player: usize) -> Self {
+        Self {
+            player,
+            state: PIN_TH | PIN_TR,
+            counter: 0,
+            wait: 0,
+            pending: false,
+            dx: 0,
+            dy: 0,
+            overflow: 0,
+        }
+    }
+
+    /// Trava o movimento do quadro. O frontend fornece Y para baixo; o
+    /// mouse informa Y para cima.
+    fn latch(&mut self, input: &InputState) {
+        let [x, y] = input.analog[self.player];
+        let clamp = |delta: i16| delta.clamp(-256, 255);
+        let y = y.saturating_neg();
+        self.overflow = (clamp(x) != x) as u8 | ((clamp(y) != y) as u8) << 1;
+        self.dx = clamp(x);
+        self.dy = clamp(y);
+    }
+}
+
+impl IoDevice for Mouse {
+    fn read(&mut self, input: &InputState, _mcycles: u64) -> u8 {
+        if self.pending {
+            self.latch(input);
+            self.pending = false;
+        }
+        let nibble = match self.counter {
+            1 => 0x0B,
+            2 | 3 => 0x0F,
+            // Estouro de Y, de X, sinal de Y, de X
+            4 => self.overflow << 2 | ((self.dy < 0) as u8) << 1 | (self.dx < 0) as u8,
+            // Botões ativos em 1
+            5 => (input.pads[self.player] >> 4) as u8 & 0x0F,
+            6 => (self.dx >> 4) as u8 & 0x0F,
+            7 => self.dx as u8 & 0x0F,
+            8 => (self.dy >> 4) as u8 & 0x0F,
+            9 => self.dy as u8 & 0x0F,
+            _ => 0x00,
+        };
+
+        // TL = TR com o handshake concluído, !TR enquanto ocupado
+        let tr = if self.wait > 0 {
+            self.wait -= 1;
+            !self.state & PIN_TR
+        } else {
+            self.state & PIN_TR
+        };
+        nibble | tr >> 1
+    }
+
+    fn write(&mut self, data: u8, mask: u8, _mcycles: u64) {
+        let data = (self.state & !mask) | (data & mask);
+        if (self.state ^ data) & PIN_TR != 0 {
+            if (1..9).contains(&self.counter) {
+                self.counter += 1;
+            }
+            self.wait = HANDSHAKE_WAIT;
+        }
+        // Descida do TH inicia a leitura, subida a encerra
+        if (self.state ^ data) & PIN_TH != 0 {
+            self.counter = (self.state & PIN_TH != 0) as u8;
+            self.pending = self.counter == 1;
+        }
+        self.state = data;
+    }
+
+    fn reset(&mut self) {
+        self.state = PIN_TH | PIN_TR;
+        self.counter = 0;
+        self.wait = 0;
+        self.pending = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::{INPUT_MOUSE_LEFT, INPUT_START};
+
+    /// Leitura completa como a rotina de um jogo: TH baixo e oito mudanças
+    /// do TR, esperando o TL confirmar cada uma
+    fn acquire(mouse: &mut Mouse, input: &InputState) -> Vec<u8> {
+        mouse.write(0x60, 0x60, 0);
+        mouse.write(0x20, 0x60, 0);
+        let mut nibbles = vec![mouse.read(input, 0) & 0x0F];
+        let mut tr = 0x20;
+        for _ in 0..8 {
+            tr ^= 0x20;
+            mouse.write(tr, 0x60, 0);
+            let mut data = mouse.read(input, 0);
+            while data & 0x10 != tr >> 1 {
+                data = mouse.read(input, 0);
+            }
+            nibbles.push(data & 0x0F);
+        }
+        mouse.write(0x60, 0x60, 0);
+        nibbles
+    }
+
+    #[test]
+    fn test_mouse_packet() {
+        let mut input = InputState::default();
+        input.pads[0] = INPUT_START | INPUT_MOUSE_LEFT;
+        input.analog[0] = [-3, 20];
+
+        let mut mouse = Mouse::new(0);
+        // Identificação, sinais (X e Y negativos), botões, X = $FD, Y = $EC
+        assert_eq!(acquire(&mut mouse, &input), [0x0B, 0x0F, 0x0F, 0x03, 0x09, 0x0F, 0x0D, 0x0E, 0x0C]);
+
+        // Estouro: o deslocamento fica no limite de 9 bits
+        input.analog[0] = [400, 0];
+        let nibbles = acquire(&mut mouse, &input);
+        assert_eq!(nibbles[3], 0x04);
+        assert_eq!((nibbles[5], nibbles[6]), (0x0F, 0x0F));
+    }
+}
@@ -0,0 +1,207 @@
+//! Adaptadores de 4 jogadores: Sega Team Player e EA 4-Way Play.
+//! Baseado em `input_hw/teamplayer.c` e `input_hw/gamepad.c` do Genesis
+//! Plus GX.
+//!
+//! O Team Player ocupa uma porta e entrega os controles em nibbles: com o TH
+//! baixo, cada mudança do TR avança a sequência (identificação, tipo de cada
+//! controle e então RLDU, SACB e, nos de 6 botões, MXYZ de cada um), e o TL
+//! confirma copiando o TR. O 4-Way Play usa as duas portas: as escritas na
+//! porta 2 escolhem qual dos quatro controles responde na porta 1.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use crate::core::input::{Gamepad, InputState, IoDevice, PadType, PIN_TH, PIN_TR};
+
+/// Tipo de controle informado pelo Team Player
+fn pad_id(kind: PadType) -> u8 {
+    match kind {
+        PadType::ThreeButton => 0x00,
+        PadType::SixButton => 0x01,
+    }
+}
+
+/// Sega Team Player com quatro controles, dos jogadores `player` a
+/// `player + 3`
+pub struct TeamPlayer {
+    kind: PadType,
+    player: usize,
+    /// Níveis de TH e TR escritos pelo console
+    state: u8,
+    /// Posição na sequência de aquisição
+    counter: usize,
+    /// Nibbles lidos após o cabeçalho: (controle, deslocamento dos botões)
+    table: Vec<(usize, u32)>,
+}
+
+impl TeamPlayer {
+    pub fn new(kind: PadType, player: usize) -> Self {
+        let shifts: &[u32] = match kind {
+            PadType::ThreeButton => &[0, 4],
+            PadType::SixButton => &[0, 4, 8],
+        };
+        let table = (0..4).flat_map(|pad| shifts.iter().map(move |&shift| (pad, shift))).collect();
+        Self {
+            kind,
+            player,
+            state: PIN_TH | PIN_TR,
+            counter: 0,
+            table,
+        }
+    }
+}
+
+impl IoDevice for TeamPlayer {
+    fn read(&mut self, input: &InputState, _mcycles: u64) -> u8 {
+        // O TL acompanha o TR
+        let tl = (self.state & PIN_TR) >> 1;
+        let nibble = match self.counter {
+            0 => 0x03,
+            1 => 0x0F,
+            2 | 3 => 0x00,
+            4..=7 => pad_id(self.kind),
+            n => match self.table.get(n - 8) {
+                Some(&(pad, shift)) => !(input.pads[self.player + pad] >> shift) as u8 & 0x0F,
+                None => 0x0F,
+            },
+        };
+        tl | nibble
+    }
+
+    fn write(&mut self, data: u8, mask: u8, _mcycles: u64) {
+        let state = (self.state & !mask) | (data & mask);
+        if state & PIN_TH != 0 {
+            self.counter = 0;
+        } else if (self.state ^ state) & (PIN_TH | PIN_TR) != 0 {
+            self.counter += 1;
+        }
+        self.state = state;
+    }
+
+    fn reset(&mut self) {
+        self.state = PIN_TH | PIN_TR;
+        self.counter = 0;
+    }
+}
+
+/// Lado da porta 1 do EA 4-Way Play: o controle escolhido responde
+pub struct FourWayPlay {
+    pads: [Gamepad; 4],
+    /// TH, TR e TL travados pela porta 2
+    latch: Arc<AtomicU8>,
+}
+
+/// Lado da porta 2 do EA 4-Way Play: seleção do controle
+pub struct FourWayPlaySelect {
+    latch: Arc<AtomicU8>,
+}
+
+impl FourWayPlay {
+    /// Cria as duas metades do adaptador, com os controles dos jogadores
+    /// `player` a `player + 3`
+    pub fn new(kind: PadType, player: usize) -> (Self, FourWayPlaySelect) {
+        let latch = Arc::new(AtomicU8::new(0));
+        let pads = std::array::from_fn(|i| Gamepad::new(kind, player + i));
+        (Self { pads, latch: Arc::clone(&latch) }, FourWayPlaySelect { latch })
+    }
+}
+
+impl IoDevice for FourWayPlay {
+    fn read(&mut self, input: &InputState, mcycles: u64) -> u8 {
+        let latch = self.latch.load(Ordering::Relaxed);
+        // TH travado em 1: identificação do adaptador (xxxxx00)
+        if latch & 0x04 != 0 {
+            return 0x7C;
+        }
+        self.pads[latch as usize].read(input, mcycles)
+    }
+
+    fn write(&mut self, data: u8, mask: u8, mcycles: u64) {
+        let latch = self.latch.load(Ordering::Relaxed);
+        self.pads[(latch & 0x03) as usize].write(data, mask, mcycles);
+    }
+
+    fn set_cpu_divider(&mut self, divider: u64) {
+        for pad in &mut self.pads {
+            pad.set_cpu_divider(divider);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.latch.store(0, Ordering::Relaxed);
+        for pad in &mut self.pads {
+            pad.reset();
+        }
+    }
+}
+
+impl IoDevice for FourWayPlaySelect {
+    fn read(&mut self, _input: &InputState, _mcycles: u64) -> u8 {
+        0x7F
+    }
+
+    fn write(&mut self, data: u8, mask: u8, _mcycles: u64) {
+        // Pinos que não são saída ficam em 1; com UP e DOWN em 0, o
+        // adaptador trava TH, TR e TL
+        let data = data | !mask;
+        if data & 0x03 == 0 {
+            self.latch.store((data >> 4) & 0x07, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::input::{INPUT_B, INPUT_MODE, INPUT_RIGHT, INPUT_START, INPUT_UP};
+
+    #[test]
+    fn test_team_player_sequence() {
+        let mut input = InputState::default();
+        input.pads[4] = INPUT_UP;
+        input.pads[5] = INPUT_START | INPUT_B;
+        input.pads[7] = INPUT_RIGHT | INPUT_MODE;
+
+        let mut tap = TeamPlayer::new(PadType::SixButton, 4);
+        // TH e TR como saída; TH baixo inicia a aquisição
+        tap.write(0x60, 0x60, 0);
+        assert_eq!(tap.read(&input, 0), 0x13);
+        tap.write(0x20, 0x60, 0);
+        let mut reads = vec![tap.read(&input, 0)];
+        let mut tr = 0x20;
+        for _ in 0..19 {
+            tr ^= 0x20;
+            tap.write(tr, 0x60, 0);
+            reads.push(tap.read(&input, 0));
+        }
+        // Cabeçalho, tipos (6 botões) e o TL espelhando o TR
+        assert_eq!(reads[..7], [0x1F, 0x00, 0x10, 0x01, 0x11, 0x01, 0x11]);
+        // Jogador 5: RLDU; jogador 6: SACB; jogador 8: RLDU e MXYZ
+        assert_eq!(reads[7] & 0x0F, 0x0E);
+        assert_eq!(reads[11] & 0x0F, 0x06);
+        assert_eq!(reads[16] & 0x0F, 0x07);
+        assert_eq!(reads[18] & 0x0F, 0x07);
+
+        // TH alto reinicia a sequência
+        tap.write(0x60, 0x60, 0);
+        assert_eq!(tap.read(&input, 0) & 0x0F, 0x03);
+    }
+
+    #[test]
+    fn test_four_way_play_select() {
+        let mut input = InputState::default();
+        input.pads[2] = INPUT_UP;
+        let (mut port_a, mut port_b) = FourWayPlay::new(PadType::ThreeButton, 0);
+        port_a.write(0x40, 0x40, 0);
+
+        // TH travado em 1: identificação
+        port_b.write(0x40, 0x7F, 0);
+        assert_eq!(port_a.read(&input, 0), 0x7C);
+        // Sem UP e DOWN em 0, nada é travado
+        port_b.write(0x23, 0x7F, 0);
+        assert_eq!(port_a.read(&input, 0), 0x7C);
+        // Controle 3 (TR = 1, TL = 0)
+        port_b.write(0x20, 0x7F, 0);
+        assert_eq!(port_a.read(&input, 0), 0x7E);
+    }
+}
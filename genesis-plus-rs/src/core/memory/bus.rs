@@ -2,26 +2,62 @@
 //! Este é o núcleo do sistema de memória, chamado pela CPU.
 
 use std::sync::{Arc, Mutex};
-use crate::core::memory::map::create_rom_handlers;
-use crate::core::memory::{ADDRESS_MASK, MemoryError, MemoryResult};
+use log::{trace, warn};
+use crate::core::memory::map::{create_boot_rom_handler, create_rom_handlers};
+use crate::core::audio::{FmChip, PsgType, SN76489, YM2413};
+use crate::core::cartridge::chips::Yx5200;
+use crate::core::input::{Beam, IoChip};
+use crate::core::memory::{ADDRESS_MASK, MemoryResult};
 use crate::core::memory::cart::Cartridge;
+use crate::core::memory::sms::{SmsCartridge, SmsSlot, MEMCTRL_BIOS_BOOT, MEMCTRL_CART, MEMCTRL_CART_BOOT, MEMCTRL_IO, MEMCTRL_RAM};
+use crate::core::system::Region;
 use crate::core::memory::map::{MemoryMap, MemoryHandler, MemRegion};
-use crate::core::memory::sram::SaveRam;
-use log::{trace, warn};
+use crate::core::vdp::fifo::DmaType;
+use crate::core::vdp::{VdpModel, VdpRam, VDP};
+use crate::utils::clock::{CartridgeChip, M68K_DIVIDER, Z80_DIVIDER};
+
+/// Ciclos de 68000 perdidos a cada acesso do Z80 ao barramento do 68000
+/// (média medida em hardware: ~72-78 clocks mestres)
+const ZBANK_M68K_WAIT: u32 = 10;
+
+/// T-states de espera médios do Z80 ao acessar o barramento do 68000
+const ZBANK_Z80_WAIT: u32 = 3;
 
 /// Barramento de memória principal
 pub struct MemoryBus {
     pub cart: Option<Arc<Mutex<Cartridge>>>,
+    pub yx5200: Option<Yx5200>, // Tocador de MP3 da placa KAISER WAVE (/TIME, $A130xx)
+    pub cart_deadlines: Vec<(CartridgeChip, Option<u64>)>, // Prazos pedidos pelos chips do cartucho (None cancela)
     pub map: MemoryMap,
+    pub wram: [u8; 65536],    // 64KB RAM de trabalho do 68000
     pub zram: [u8; 8192],     // 8KB Z80 RAM
-    pub ioports: [u8; 256],   // Portas I/O
+    pub io: IoChip,           // Chip de I/O e portas de controle ($A10000)
     pub vram: [u16; 65536],   // 128KB VRAM (64K words)
     pub cram: [u16; 64],      // 128 bytes CRAM (64 words)
     pub vsram: [u16; 40],     // 80 bytes VSRAM (40 words)
+    pub vdp: VDP,             // Registradores e portas do VDP
+    pub fm: FmChip,           // Chip FM ($A04000 no 68000, $4000 no Z80)
+    pub psg: SN76489,         // PSG ($C00011 no 68000, $7F11 no Z80, portas $40-$7F no SMS)
+    pub ym2413: YM2413,       // FM Sound Unit do SMS (portas $F0-$F2)
+    pub fm_unit: bool,        // YM2413 presente
+    pub audio_control: u8,    // Controle de áudio ($F2) do SMS japonês
+    pub region: Region,       // Região do console
     
     pub genesis_mode: bool,   // true = Genesis, false = Master System
-    pub tmss_enabled: bool,   // Proteção TMSS
-    pub tmss_reg: u8,
+    pub tmss_enabled: bool,   // Proteção TMSS (modelo 1 VA6+ e modelo 2)
+    pub tmss_reg: [u8; 4],    // Registrador TMSS ($A14000): "SEGA" libera o VDP
+    pub boot_rom: Option<Arc<[u8]>>, // ROM de boot do TMSS fornecida pelo frontend
+    pub sms_slots: [Option<SmsCartridge>; 4], // Slots do SMS (ver `SmsSlot`)
+    pub memory_control: u8,   // Controle de memória ($3E) do SMS
+    
+    pub z80_busreq: bool,     // /BUSREQ do Z80 solicitado pelo 68000 ($A11100)
+    pub z80_reset: bool,      // /RESET do Z80 ativo ($A11200)
+    pub open_bus: u16,        // Última palavra buscada pelo 68000 (barramento em aberto)
+    pub m68k_wait: u32,       // Ciclos de espera do 68000 ainda não contabilizados
+    pub m68k_lockup: bool,    // Acesso sem /DTACK: o 68000 trava até o próximo reset
+    pub zbank: u32,           // Base da janela de 32KB do Z80 no espaço do 68000 ($6000)
+    pub z80_wait: u32,        // T-states de espera do Z80 ainda não contabilizados
+    pub z80_cycles: u64,      // T-states do Z80 no início da instrução corrente
     
     pub cycles: u64,          // Ciclos totais executados
 }
@@ -31,16 +67,38 @@ impl MemoryBus {
     pub fn new() -> Self {
         Self {
             cart: None,
-            map: MemoryMap::new(),
+            yx5200: None,
+            cart_deadlines: Vec::new(),
+            map: Self::default_map(),
+            wram: [0; 65536],
             zram: [0; 8192],
-            ioports: [0; 256],
+            io: IoChip::new(),
             vram: [0; 65536],
             cram: [0; 64],
             vsram: [0; 40],
+            vdp: VDP::new(),
+            fm: FmChip::default(),
+            psg: SN76489::new(PsgType::Integrated),
+            ym2413: YM2413::new(),
+            fm_unit: false,
+            audio_control: 0,
+            region: Region::Usa,
             
             genesis_mode: true,
             tmss_enabled: false,
-            tmss_reg: 0,
+            tmss_reg: [0; 4],
+            boot_rom: None,
+            sms_slots: [None, None, None, None],
+            memory_control: MEMCTRL_CART_BOOT,
+            
+            z80_busreq: false,
+            z80_reset: true,
+            open_bus: 0,
+            m68k_wait: 0,
+            m68k_lockup: false,
+            zbank: 0,
+            z80_wait: 0,
+            z80_cycles: 0,
             
             cycles: 0,
         }
@@ -53,6 +111,25 @@ impl MemoryBus {
         self.setup_memory_map(cart_arc)
     }
     
+    /// Mapa de memória sem cartucho: apenas as regiões internas do console
+    fn default_map() -> MemoryMap {
+        let mut map = MemoryMap::new();
+        
+        // Z80 RAM (0xA00000 - 0xA01FFF)
+        map.map_region(0xA00000, 0xA01FFF, MemoryHandler::internal(MemRegion::Zram));
+        
+        // I/O (0xA10000 - 0xA1001F)
+        map.map_region(0xA10000, 0xA1001F, MemoryHandler::internal(MemRegion::Io));
+        
+        // VDP (0xC00000 - 0xC0001F)
+        map.map_region(0xC00000, 0xC0001F, MemoryHandler::internal(MemRegion::Vdp));
+        
+        // RAM de trabalho (0xE00000 - 0xFFFFFF, espelhada a cada 64KB)
+        map.map_region(0xE00000, 0xFFFFFF, MemoryHandler::internal(MemRegion::Wram));
+        
+        map
+    }
+    
     /// Configura o mapa de memória baseado no cartucho
     fn setup_memory_map(&mut self, cart: Arc<Mutex<Cartridge>>) -> MemoryResult<()> {
         // Mapeia ROM (0x000000 - 0x3FFFFF) em 4 blocos de 1MB
@@ -63,38 +140,8 @@ impl MemoryBus {
         self.map.map_region(0x200000, 0x2FFFFF, rom_handler2);
         self.map.map_region(0x300000, 0x3FFFFF, rom_handler3);
         
-        // Mapeia ROM (0x000000 - 0x3FFFFF)
-        // (Implementação completa requer create_rom_handlers)
-        
-        // Mapeia Z80 RAM (0xA00000 - 0xA01FFF)
-        let zram_handler = MemoryHandler {
-            read_byte: |addr| self.read_zram(addr),
-            read_word: |addr| self.read_zram_word(addr),
-            write_byte: |addr, val| self.write_zram(addr, val),
-            write_word: |addr, val| self.write_zram_word(addr, val),
-            region: MemRegion::Zram,
-        };
-        self.map.map_region(0xA00000, 0xA01FFF, zram_handler);
-        
-        // Mapeia I/O (0xA10000 - 0xA1001F)
-        let io_handler = MemoryHandler {
-            read_byte: |addr| self.read_io(addr),
-            read_word: |addr| self.read_io_word(addr),
-            write_byte: |addr, val| self.write_io(addr, val),
-            write_word: |addr, val| self.write_io_word(addr, val),
-            region: MemRegion::Io,
-        };
-        self.map.map_region(0xA10000, 0xA1001F, io_handler);
-        
-        // Mapeia VDP (0xC00000 - 0xC0001F)
-        let vdp_handler = MemoryHandler {
-            read_byte: |addr| self.read_vdp(addr),
-            read_word: |addr| self.read_vdp_word(addr),
-            write_byte: |addr, val| self.write_vdp(addr, val),
-            write_word: |addr, val| self.write_vdp_word(addr, val),
-            region: MemRegion::Vdp,
-        };
-        self.map.map_region(0xC00000, 0xC0001F, vdp_handler);
+        // Z80 RAM, I/O, VDP e RAM de trabalho são tratados pelo próprio
+        // barramento (ver `default_map`), pois dependem do estado interno.
         
         Ok(())
     }
@@ -102,138 +149,827 @@ impl MemoryBus {
     // --- Funções principais de acesso à memória (chamadas pela CPU) ---
     
     /// Lê um byte (8-bit) do endereço especificado
-    pub fn read_byte(&self, addr: u32) -> u8 {
+    pub fn read_byte(&mut self, addr: u32) -> u8 {
         let masked_addr = addr & ADDRESS_MASK;
-        let handler = self.map.get_handler(masked_addr);
-        (handler.read_byte)(masked_addr)
+        let region = self.map.get_handler(masked_addr).region;
+        match region {
+            MemRegion::Wram => self.wram[(masked_addr & 0xFFFF) as usize],
+            MemRegion::Zram => self.read_zram(masked_addr),
+            MemRegion::Io => self.read_io(masked_addr),
+            MemRegion::Vdp if self.vdp_locked() => self.m68k_lockup_read(masked_addr) as u8,
+            MemRegion::Vdp => self.read_vdp(masked_addr),
+            _ => (self.map.get_handler(masked_addr).read_byte)(masked_addr),
+        }
     }
     
     /// Lê uma palavra (16-bit) do endereço especificado
-    pub fn read_word(&self, addr: u32) -> u16 {
+    pub fn read_word(&mut self, addr: u32) -> u16 {
         let masked_addr = addr & ADDRESS_MASK;
-        let handler = self.map.get_handler(masked_addr);
+        let region = self.map.get_handler(masked_addr).region;
         
         // Endereços ímpares são permitidos no 68000 mas mais lentos
         if masked_addr & 1 == 1 {
-            let low = (handler.read_byte)(masked_addr) as u16;
-            let high = (handler.read_byte)(masked_addr.wrapping_add(1)) as u16;
+            let low = self.read_byte(masked_addr) as u16;
+            let high = self.read_byte(masked_addr.wrapping_add(1)) as u16;
             (high << 8) | low
         } else {
-            (handler.read_word)(masked_addr)
+            match region {
+                MemRegion::Wram => {
+                    let offset = (masked_addr & 0xFFFF) as usize;
+                    (self.wram[offset] as u16) << 8 | self.wram[offset + 1] as u16
+                }
+                MemRegion::Zram => self.read_zram_word(masked_addr),
+                MemRegion::Io => self.read_io_word(masked_addr),
+                MemRegion::Vdp if self.vdp_locked() => self.m68k_lockup_read(masked_addr),
+                MemRegion::Vdp => self.read_vdp_word(masked_addr),
+                _ => (self.map.get_handler(masked_addr).read_word)(masked_addr),
+            }
         }
     }
     
     /// Escreve um byte no endereço especificado
     pub fn write_byte(&mut self, addr: u32, value: u8) {
         let masked_addr = addr & ADDRESS_MASK;
-        let handler = self.map.get_handler(masked_addr);
-        (handler.write_byte)(masked_addr, value);
+        let region = self.map.get_handler(masked_addr).region;
+        match region {
+            MemRegion::Wram => self.wram[(masked_addr & 0xFFFF) as usize] = value,
+            MemRegion::Zram => self.write_zram(masked_addr, value),
+            MemRegion::Io => self.write_io(masked_addr, value),
+            MemRegion::Vdp if self.vdp_locked() => self.m68k_lockup_write(masked_addr),
+            MemRegion::Vdp => self.write_vdp(masked_addr, value),
+            _ => (self.map.get_handler(masked_addr).write_byte)(masked_addr, value),
+        }
     }
     
     /// Escreve uma palavra no endereço especificado
     pub fn write_word(&mut self, addr: u32, value: u16) {
         let masked_addr = addr & ADDRESS_MASK;
-        let handler = self.map.get_handler(masked_addr);
+        let region = self.map.get_handler(masked_addr).region;
         
         if masked_addr & 1 == 1 {
             // Escrita não alinhada
-            (handler.write_byte)(masked_addr, value as u8);
-            (handler.write_byte)(masked_addr.wrapping_add(1), (value >> 8) as u8);
+            self.write_byte(masked_addr, value as u8);
+            self.write_byte(masked_addr.wrapping_add(1), (value >> 8) as u8);
         } else {
-            (handler.write_word)(masked_addr, value);
+            match region {
+                MemRegion::Wram => {
+                    let offset = (masked_addr & 0xFFFF) as usize;
+                    self.wram[offset] = (value >> 8) as u8;
+                    self.wram[offset + 1] = value as u8;
+                }
+                MemRegion::Zram => self.write_zram_word(masked_addr, value),
+                MemRegion::Io => self.write_io_word(masked_addr, value),
+                MemRegion::Vdp if self.vdp_locked() => self.m68k_lockup_write(masked_addr),
+                MemRegion::Vdp => self.write_vdp_word(masked_addr, value),
+                _ => (self.map.get_handler(masked_addr).write_word)(masked_addr, value),
+            }
         }
     }
     
     // --- Handlers específicos para cada região ---
     
-    /// Lê da Z80 RAM
-    fn read_zram(&self, addr: u32) -> u8 {
-        let offset = (addr & 0x1FFF) as usize;
-        if offset < 8192 {
-            self.zram[offset]
+    /// Byte do barramento em aberto (prefetch do 68000) para o endereço dado
+    fn open_bus_byte(&self, addr: u32) -> u8 {
+        if addr & 1 == 0 {
+            (self.open_bus >> 8) as u8
         } else {
-            0xFF
+            self.open_bus as u8
         }
     }
     
-    fn read_zram_word(&self, addr: u32) -> u16 {
-        let offset = (addr & 0x1FFF) as usize;
-        if offset < 8191 {
-            let low = self.zram[offset] as u16;
-            let high = self.zram[offset + 1] as u16;
-            (high << 8) | low
-        } else {
-            0xFFFF
+    /// O 68000 só acessa o espaço do Z80 com /BUSREQ concedido e /RESET liberado
+    pub fn z80_bus_granted(&self) -> bool {
+        self.z80_busreq && !self.z80_reset
+    }
+    
+    /// O Z80 executa apenas com /BUSREQ e /RESET liberados
+    pub fn z80_running(&self) -> bool {
+        !self.z80_busreq && !self.z80_reset
+    }
+    
+    /// Lê do espaço do Z80 ($A00000-$A0FFFF) pelo lado do 68000
+    fn read_zram(&mut self, addr: u32) -> u8 {
+        if !self.z80_bus_granted() {
+            return self.open_bus_byte(addr);
+        }
+        
+        // Latência de acesso ao barramento do Z80
+        self.m68k_wait += 1;
+        
+        match (addr >> 13) & 3 {
+            2 => {
+                let mcycles = self.vdp_mcycles();
+                self.fm.read(mcycles, addr as u8 & 3)
+            }
+            3 => {
+                if addr & 0xFF00 == 0x7F00 {
+                    // VDP pelo barramento do Z80: trava o 68000 no hardware real
+                    warn!("68000 lendo o VDP através do barramento do Z80 (${:06X})", addr);
+                }
+                0xFF
+            }
+            _ => self.zram[(addr & 0x1FFF) as usize],
         }
     }
     
+    /// Leituras de palavra no espaço do Z80 repetem o byte nos dois lados
+    fn read_zram_word(&mut self, addr: u32) -> u16 {
+        if !self.z80_bus_granted() {
+            return self.open_bus;
+        }
+        let value = self.read_zram(addr) as u16;
+        value << 8 | value
+    }
+    
     fn write_zram(&mut self, addr: u32, value: u8) {
-        let offset = (addr & 0x1FFF) as usize;
-        if offset < 8192 {
-            self.zram[offset] = value;
+        if !self.z80_bus_granted() {
+            return;
+        }
+        
+        self.m68k_wait += 1;
+        
+        match (addr >> 13) & 3 {
+            2 => {
+                let mcycles = self.vdp_mcycles();
+                self.fm.write(mcycles, addr as u8 & 3, value);
+            }
+            3 => match (addr >> 8) & 0x7F {
+                0x60 => self.write_zbank(value),
+                0x7F => warn!("68000 escrevendo no VDP através do barramento do Z80 (${:06X})", addr),
+                _ => {}
+            },
+            _ => self.zram[(addr & 0x1FFF) as usize] = value,
         }
     }
     
+    /// Escritas de palavra no espaço do Z80 usam apenas o byte alto
     fn write_zram_word(&mut self, addr: u32, value: u16) {
-        let offset = (addr & 0x1FFF) as usize;
-        if offset < 8191 {
-            self.zram[offset] = value as u8;
-            self.zram[offset + 1] = (value >> 8) as u8;
-        }
+        self.write_zram(addr, (value >> 8) as u8);
     }
     
-    /// Lê de I/O
-    fn read_io(&self, addr: u32) -> u8 {
-        let offset = (addr & 0x1F) as usize;
-        if offset < 256 {
-            self.ioports[offset]
-        } else {
-            0xFF
+    /// Lê de I/O ($A10000-$A1FFFF)
+    fn read_io(&mut self, addr: u32) -> u8 {
+        match (addr >> 8) & 0xFF {
+            // Chip de I/O (o mesmo registrador nos endereços par e ímpar)
+            0x00 if addr & 0xE0 == 0 => self.read_io_port(addr),
+            // Z80 BUSACK: bit 0 = 0 quando o 68000 tem o barramento do Z80
+            0x11 if addr & 1 == 0 => {
+                (self.open_bus_byte(addr) & 0xFE) | !self.z80_bus_granted() as u8
+            }
+            // Seleção da ROM de boot: bit 0 = 1 com o cartucho mapeado
+            0x41 if self.tmss_enabled && addr & 1 != 0 => {
+                (self.open_bus_byte(addr) & 0xFE) | self.cart_mapped() as u8
+            }
+            _ => self.open_bus_byte(addr),
         }
     }
     
-    fn read_io_word(&self, addr: u32) -> u16 {
-        let offset = (addr & 0x1F) as usize;
-        if offset < 255 {
-            let low = self.ioports[offset] as u16;
-            let high = self.ioports[offset + 1] as u16;
-            (high << 8) | low
-        } else {
-            0xFFFF
+    fn read_io_word(&mut self, addr: u32) -> u16 {
+        match (addr >> 8) & 0xFF {
+            0x00 if addr & 0xE0 == 0 => {
+                let data = self.read_io_port(addr);
+                u16::from_be_bytes([data, data])
+            }
+            0x11 => (self.open_bus & 0xFEFF) | (!self.z80_bus_granted() as u16) << 8,
+            0x41 if self.tmss_enabled => (self.open_bus & 0xFFFE) | self.cart_mapped() as u16,
+            _ => self.open_bus,
         }
     }
     
     fn write_io(&mut self, addr: u32, value: u8) {
-        let offset = (addr & 0x1F) as usize;
-        if offset < 256 {
-            self.ioports[offset] = value;
+        match (addr >> 8) & 0xFF {
+            // O chip de I/O fica na metade baixa do barramento
+            0x00 if addr & 0xE1 == 0x01 => self.write_io_port(addr, value),
+            // Registradores do Z80 respondem apenas em endereços pares
+            0x11 if addr & 1 == 0 => self.write_z80_busreq(value & 1 != 0),
+            0x12 if addr & 1 == 0 => self.write_z80_reset(value & 1 != 0),
+            0x30 if addr & 1 == 0 => self.write_time(value),
+            0x41 if self.tmss_enabled && addr & 1 != 0 => self.write_bankswitch(value & 1 != 0),
+            _ => {}
         }
     }
     
     fn write_io_word(&mut self, addr: u32, value: u16) {
-        let offset = (addr & 0x1F) as usize;
-        if offset < 255 {
-            self.ioports[offset] = value as u8;
-            self.ioports[offset + 1] = (value >> 8) as u8;
+        match (addr >> 8) & 0xFF {
+            0x00 if addr & 0xE0 == 0 => self.write_io_port(addr, value as u8),
+            0x11 => self.write_z80_busreq(value & 0x100 != 0),
+            0x12 => self.write_z80_reset(value & 0x100 != 0),
+            0x30 => self.write_time(value as u8),
+            // O registrador TMSS só aceita escritas de palavra
+            0x40 if self.tmss_enabled => self.write_tmss(addr, value),
+            _ => {}
+        }
+    }
+    
+    /// Escrita em /TIME ($A130xx, só /UWR): a placa KAISER WAVE liga o D0
+    /// à linha RX do YX5200
+    fn write_time(&mut self, value: u8) {
+        if let Some(yx5200) = &mut self.yx5200 {
+            yx5200.write(value & 1);
+        }
+    }
+    
+    /// Registrador do chip de I/O em $A10000-$A1001F
+    fn read_io_port(&mut self, addr: u32) -> u8 {
+        self.io.read(((addr >> 1) & 0x0F) as usize, self.vdp_mcycles())
+    }
+    
+    fn write_io_port(&mut self, addr: u32, value: u8) {
+        let mcycles = self.vdp_mcycles();
+        self.io.write(((addr >> 1) & 0x0F) as usize, value, mcycles);
+        self.update_io(mcycles);
+    }
+    
+    /// Descida do TH numa porta com /HL ligado: trava o contador HV e gera
+    /// a interrupção externa
+    pub fn update_io(&mut self, mcycles: u64) {
+        if self.io.poll_th(mcycles) {
+            self.vdp.latch_hv_external(mcycles);
         }
     }
     
-    /// Lê do VDP (implementação simplificada)
-    fn read_vdp(&self, addr: u32) -> u8 {
-        // Implementação real é complexa
-        0
+    /// $A11100: 1 = solicita o barramento do Z80 (para o Z80), 0 = devolve
+    fn write_z80_busreq(&mut self, request: bool) {
+        trace!("Z80 /BUSREQ {}", if request { "ativo" } else { "liberado" });
+        self.z80_busreq = request;
+    }
+    
+    /// $A11200: 0 = mantém o Z80 em reset, 1 = libera
+    fn write_z80_reset(&mut self, release: bool) {
+        trace!("Z80 /RESET {}", if release { "liberado" } else { "ativo" });
+        self.z80_reset = !release;
+    }
+    
+    // --- TMSS ---
+    
+    /// Power-on com TMSS: o VDP fica travado até a escrita de "SEGA" e a
+    /// ROM de boot, se houver, ocupa $000000-$00FFFF no lugar do cartucho
+    pub fn tmss_power_on(&mut self) {
+        self.tmss_reg = [0; 4];
+        self.map_boot_rom(self.tmss_enabled);
+    }
+    
+    /// Sem "SEGA" no registrador TMSS, o VDP não responde
+    fn vdp_locked(&self) -> bool {
+        self.tmss_enabled && &self.tmss_reg != b"SEGA"
     }
     
-    fn read_vdp_word(&self, addr: u32) -> u16 {
-        0
+    /// $A14000-$A14003: registrador TMSS
+    fn write_tmss(&mut self, addr: u32, value: u16) {
+        let offset = (addr & 2) as usize;
+        self.tmss_reg[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+        trace!("TMSS: VDP {}", if self.vdp_locked() { "travado" } else { "liberado" });
+    }
+    
+    /// O cartucho ocupa o início do mapa (sempre, sem ROM de boot)
+    fn cart_mapped(&self) -> bool {
+        self.map.get_handler(0).region != MemRegion::Bios
+    }
+    
+    /// $A14101: 0 = ROM de boot em $000000, 1 = cartucho
+    fn write_bankswitch(&mut self, cartridge: bool) {
+        if self.boot_rom.is_some() {
+            self.map_boot_rom(!cartridge);
+        }
+    }
+    
+    /// Mapeia a ROM de boot (se houver) ou o cartucho em $000000-$00FFFF
+    fn map_boot_rom(&mut self, boot: bool) {
+        let handler = match (&self.boot_rom, &self.cart) {
+            (Some(rom), _) if boot => create_boot_rom_handler(Arc::clone(rom)),
+            (_, Some(cart)) => create_rom_handlers(Arc::clone(cart)).0,
+            _ => MemoryHandler::unmapped(),
+        };
+        self.map.map_region(0x000000, 0x00FFFF, handler);
+    }
+    
+    /// Acesso do 68000 que nunca recebe /DTACK: a CPU trava. A leitura
+    /// devolve o que estava no barramento (prefetch).
+    fn m68k_lockup_read(&mut self, addr: u32) -> u16 {
+        warn!("68000 travado: leitura em ${:06X}", addr);
+        self.m68k_lockup = true;
+        self.open_bus
+    }
+    
+    fn m68k_lockup_write(&mut self, addr: u32) {
+        warn!("68000 travado: escrita em ${:06X}", addr);
+        self.m68k_lockup = true;
+    }
+    
+    /// Acesso do Z80 pela janela que nunca é respondido: o Z80 fica parado
+    /// até o 68000 liberá-lo de novo
+    fn z80_lockup(&mut self, addr: u32) {
+        warn!("Z80 travado: acesso à janela do 68000 em ${:06X}", addr);
+        self.z80_busreq = false;
+        self.z80_reset = true;
+    }
+    
+    // --- Master System ---
+    
+    /// Conecta a BIOS e o cartucho aos slots do SMS. O cartucho só é ligado
+    /// pela BIOS (ou direto, no reset, quando não há BIOS).
+    pub fn sms_power_on(&mut self, bios: Option<Arc<[u8]>>) {
+        let rom: Option<Arc<[u8]>> = self.cart.as_ref().map(|cart| Arc::from(cart.lock().unwrap().rom.as_slice()));
+        self.sms_slots[SmsSlot::Cartridge as usize] = rom.filter(|rom| !rom.is_empty()).map(SmsCartridge::new);
+        self.sms_slots[SmsSlot::Bios as usize] = bios.map(SmsCartridge::new);
+    }
+    
+    /// Reset dos slots: a BIOS, se houver, volta a ocupar o barramento
+    pub fn sms_reset(&mut self) {
+        for cart in self.sms_slots.iter_mut().flatten() {
+            cart.reset();
+        }
+        // Portas de controle como entrada
+        self.io.write_sms_control(0xFF, self.z80_cycles * Z80_DIVIDER);
+        if self.sms_slots[SmsSlot::Bios as usize].is_some() {
+            self.write_memory_control(MEMCTRL_BIOS_BOOT);
+        } else {
+            self.write_memory_control(MEMCTRL_CART_BOOT);
+            // Valor normalmente deixado na RAM pela BIOS do Master System
+            if self.vdp.model != VdpModel::GameGear {
+                self.zram[0] = MEMCTRL_CART_BOOT;
+            }
+        }
+    }
+    
+    /// $3E: liga e desliga os slots (bits ativos em 0)
+    pub fn write_memory_control(&mut self, value: u8) {
+        let bios = SmsSlot::Bios as usize;
+        let cart = SmsSlot::Cartridge as usize;
+        // Sem BIOS, só um jogo que faz o papel dela desligaria o próprio
+        // slot: o cartucho passa a responder como BIOS
+        if value & MEMCTRL_CART != 0 && self.sms_slots[bios].is_none() {
+            self.sms_slots[bios] = self.sms_slots[cart].take();
+        }
+        self.memory_control = value;
+        for slot in SmsSlot::ALL {
+            if let Some(media) = &mut self.sms_slots[slot as usize] {
+                media.set_enabled(value & slot.disable_bit() == 0);
+            }
+        }
+        trace!("SMS: controle de memória ${:02X}", value);
+    }
+    
+    /// Slot ligado que responde em `addr` ($0000-$BFFF)
+    fn sms_slot(&self, addr: u16) -> Option<&SmsCartridge> {
+        self.sms_slots.iter().flatten().find(|media| media.enabled() && media.decodes(addr))
+    }
+    
+    /// Mapa de memória do Z80 no Master System
+    fn sms_read(&self, addr: u16) -> u8 {
+        if addr >= 0xC000 {
+            if self.memory_control & MEMCTRL_RAM != 0 {
+                return 0xFF;
+            }
+            return self.zram[(addr & 0x1FFF) as usize];
+        }
+        if let Some(media) = self.sms_slot(addr) {
+            return media.read(addr);
+        }
+        // A BIOS de 1KB do Game Gear deixa o cartucho visível acima de $0400
+        let [bios, cart, ..] = &self.sms_slots;
+        match (bios, cart) {
+            (Some(bios), Some(cart)) if bios.enabled() => cart.read(addr),
+            _ => 0xFF,
+        }
     }
     
+    fn sms_write(&mut self, addr: u16, value: u8) {
+        if addr < 0xC000 {
+            trace!("SMS: escrita ignorada na ROM ${:04X} <- ${:02X}", addr, value);
+            return;
+        }
+        if self.memory_control & MEMCTRL_RAM == 0 {
+            self.zram[(addr & 0x1FFF) as usize] = value;
+        }
+        if addr >= 0xFFFC {
+            for media in self.sms_slots.iter_mut().flatten().filter(|media| media.enabled()) {
+                media.write_mapper(addr, value);
+            }
+        }
+    }
+    
+    // --- Acesso pelo lado do Z80 ---
+    
+    /// Lê um byte do mapa de memória do Z80
+    pub fn z80_read(&mut self, addr: u16) -> u8 {
+        if !self.genesis_mode {
+            return self.sms_read(addr);
+        }
+        match addr >> 13 {
+            // 8KB de RAM espelhados em $0000-$3FFF
+            0 | 1 => self.zram[(addr & 0x1FFF) as usize],
+            2 => self.fm.read(self.z80_cycles * Z80_DIVIDER, addr as u8 & 3),
+            3 => {
+                if addr & 0xFF00 == 0x7F00 {
+                    // VDP pelo barramento do 68000
+                    self.request_68k_bus();
+                    self.read_vdp(0xC00000 | (addr as u32 & 0xFF))
+                } else {
+                    0xFF
+                }
+            }
+            // $8000-$FFFF: janela de 32KB no espaço do 68000
+            _ => {
+                self.request_68k_bus();
+                self.zbank_read(self.zbank | (addr as u32 & 0x7FFF))
+            }
+        }
+    }
+    
+    /// Escreve um byte no mapa de memória do Z80
+    pub fn z80_write(&mut self, addr: u16, value: u8) {
+        if !self.genesis_mode {
+            return self.sms_write(addr, value);
+        }
+        match addr >> 13 {
+            0 | 1 => self.zram[(addr & 0x1FFF) as usize] = value,
+            2 => self.fm.write(self.z80_cycles * Z80_DIVIDER, addr as u8 & 3, value),
+            3 => match (addr >> 8) & 0xFF {
+                0x60 => self.write_zbank(value),
+                // PSG, sincronizado com o Z80
+                0x7F if addr & 0x18 == 0x10 => {
+                    self.request_68k_bus();
+                    if addr & 1 != 0 {
+                        self.psg.write(self.z80_cycles * Z80_DIVIDER, value);
+                    }
+                }
+                0x7F => {
+                    self.request_68k_bus();
+                    self.write_vdp(0xC00000 | (addr as u32 & 0xFF), value);
+                }
+                _ => {}
+            },
+            _ => {
+                self.request_68k_bus();
+                self.zbank_write(self.zbank | (addr as u32 & 0x7FFF), value);
+            }
+        }
+    }
+    
+    /// Registrador de banco ($6000): deslocamento serial de 9 bits, um bit
+    /// por escrita, que forma A15-A23 do endereço da janela
+    fn write_zbank(&mut self, value: u8) {
+        self.zbank = ((self.zbank >> 1) | ((value as u32 & 1) << 23)) & 0xFF8000;
+    }
+    
+    /// Acesso do Z80 ao barramento do 68000: o 68000 fica parado enquanto o
+    /// Z80 usa o barramento e o Z80 também recebe estados de espera
+    fn request_68k_bus(&mut self) {
+        self.m68k_wait += ZBANK_M68K_WAIT;
+        self.z80_wait += ZBANK_Z80_WAIT;
+    }
+    
+    /// Leitura na janela do Z80, despachada pelo mapa de memória do 68000
+    fn zbank_read(&mut self, addr: u32) -> u8 {
+        let region = self.map.get_handler(addr).region;
+        match region {
+            // O Z80 só pode escrever na RAM do 68000, não ler
+            MemRegion::Wram => 0xFF,
+            MemRegion::Zram => {
+                warn!("Z80 lendo o próprio barramento pela janela do 68000 (${:06X})", addr);
+                0xFF
+            }
+            MemRegion::Io => match (addr >> 8) & 0xFF {
+                0x00 if addr & 0xE0 == 0 => self.read_io_port(addr),
+                _ => 0xFF,
+            },
+            MemRegion::Vdp if self.vdp_locked() => {
+                self.z80_lockup(addr);
+                0xFF
+            }
+            MemRegion::Vdp => self.read_vdp(addr),
+            _ => (self.map.get_handler(addr).read_byte)(addr),
+        }
+    }
+    
+    /// Escrita na janela do Z80, despachada pelo mapa de memória do 68000
+    fn zbank_write(&mut self, addr: u32, value: u8) {
+        let region = self.map.get_handler(addr).region;
+        match region {
+            MemRegion::Wram => self.wram[(addr & 0xFFFF) as usize] = value,
+            MemRegion::Zram => {
+                warn!("Z80 escrevendo no próprio barramento pela janela do 68000 (${:06X})", addr);
+            }
+            MemRegion::Io => self.write_io(addr, value),
+            MemRegion::Vdp if self.vdp_locked() => self.z80_lockup(addr),
+            MemRegion::Vdp => self.write_vdp(addr, value),
+            _ => (self.map.get_handler(addr).write_byte)(addr, value),
+        }
+    }
+    
+    /// Leitura de porta de I/O do Z80 (não conectadas no Mega Drive).
+    /// No Master System o VDP responde em $80-$BF (dados nas portas pares)
+    /// e a FM Sound Unit em $F0-$F2.
+    pub fn z80_in(&mut self, port: u16) -> u8 {
+        if self.genesis_mode {
+            return 0xFF;
+        }
+        let mcycles = self.z80_cycles * Z80_DIVIDER;
+        match port & 0xC1 {
+            0x40 => self.vdp.read_vcounter(mcycles),
+            0x41 => self.vdp.read_hcounter(mcycles),
+            0x80 => {
+                let (vdp, ram) = self.vdp_ports();
+                vdp.z80_read_data(ram)
+            }
+            0x81 => self.vdp.z80_read_status(),
+            // O 315-5297 do SMS japonês decodifica todas as portas: $F2
+            // devolve o controle de áudio, usado para detectar o chip FM, e só
+            // $DC/$DD respondem com os controles
+            0xC0 | 0xC1 if self.region == Region::Japan && port & 0xFF == 0xF2 => self.audio_control & 0x03,
+            0xC0 | 0xC1 if self.region == Region::Japan && !matches!(port & 0xFF, 0xDC | 0xDD) => 0xFF,
+            // Unidade FM externa: apenas A2 é decodificado
+            0xC0 | 0xC1 if self.fm_unit && port & 4 == 0 => self.ym2413.read(),
+            // Portas de controle ($DC/$DD), desligáveis pelo controle de memória
+            0xC0 | 0xC1 if self.memory_control & MEMCTRL_IO == 0 => self.io.read_sms_port(port, mcycles),
+            _ => 0xFF,
+        }
+    }
+    
+    /// Escrita em porta de I/O do Z80 (não conectadas no Mega Drive)
+    pub fn z80_out(&mut self, port: u16, value: u8) {
+        if self.genesis_mode {
+            return;
+        }
+        let mcycles = self.z80_cycles * Z80_DIVIDER;
+        match port & 0xC1 {
+            // Registrador de estéreo do PSG do Game Gear
+            0x00 if port & 0xFF == 0x06 && self.vdp.model == VdpModel::GameGear => {
+                self.psg.write_stereo(mcycles, value)
+            }
+            // Controle de memória; as portas $00-$06 do Game Gear são outros registradores
+            0x00 if port & 1 == 0 && !(self.vdp.model == VdpModel::GameGear && port & 0xFF < 7) => {
+                self.write_memory_control(value)
+            }
+            // Controle de I/O ($3F)
+            0x01 if !(self.vdp.model == VdpModel::GameGear && port & 0xFF < 7) => {
+                if self.io.write_sms_control(value, mcycles) {
+                    self.vdp.latch_hv_external(mcycles);
+                }
+                self.update_io(mcycles);
+            }
+            0x40 | 0x41 => self.psg.write(mcycles, value),
+            0x80 | 0x81 => {
+                self.vdp.sync(mcycles);
+                let (vdp, ram) = self.vdp_ports();
+                if port & 1 == 0 {
+                    vdp.z80_write_data(ram, value);
+                } else {
+                    vdp.z80_write_control(ram, value);
+                }
+            }
+            0xC0 | 0xC1 if self.fm_unit && self.region == Region::Japan => match port & 0xFF {
+                0xF0 | 0xF1 => self.ym2413.write(mcycles, port as u8, value),
+                // D1-D0: 00 = só PSG (power-on), 01 = só FM, 10 = nenhum, 11 = ambos
+                0xF2 => {
+                    let psg_enabled = value.wrapping_add(1) & 0x02 == 0;
+                    self.psg.write_stereo(mcycles, if psg_enabled { 0xFF } else { 0x00 });
+                    self.ym2413.write(mcycles, 0x02, value);
+                    self.audio_control = value;
+                }
+                _ => trace!("Z80: escrita na porta ${:02X} <- ${:02X}", port & 0xFF, value),
+            },
+            0xC0 | 0xC1 if self.fm_unit && port & 4 == 0 => self.ym2413.write(mcycles, port as u8, value),
+            _ => trace!("Z80: escrita na porta ${:02X} <- ${:02X}", port & 0xFF, value),
+        }
+    }
+    
+    /// Revisão do PSG: o SG-1000 usa o chip discreto da TI
+    pub fn psg_type(&self) -> PsgType {
+        if self.vdp.model == VdpModel::Tms9918 {
+            PsgType::Discrete
+        } else {
+            PsgType::Integrated
+        }
+    }
+    
+    /// Nível de interrupção pedido ao 68000
+    pub fn m68k_irq_level(&self) -> u8 {
+        self.vdp.irq_level()
+    }
+    
+    /// Ciclo de reconhecimento de interrupção do 68000
+    pub fn m68k_int_ack(&mut self, level: u8) {
+        self.vdp.int_ack(level);
+    }
+    
+    /// Estado da linha /INT do Z80
+    pub fn z80_irq_line(&self) -> bool {
+        self.vdp.z80_irq()
+    }
+    
+    /// Separa o VDP das memórias de vídeo para um acesso às portas
+    pub fn vdp_ports(&mut self) -> (&mut VDP, VdpRam<'_>) {
+        let ram = VdpRam {
+            vram: &mut self.vram,
+            cram: &mut self.cram,
+            vsram: &mut self.vsram,
+        };
+        (&mut self.vdp, ram)
+    }
+    
+    /// Clock mestre corrente, usado pelas leituras de status do VDP
+    fn vdp_mcycles(&self) -> u64 {
+        self.cycles * M68K_DIVIDER
+    }
+    
+    /// Lê um byte do VDP ($C00000-$C0001F)
+    fn read_vdp(&mut self, addr: u32) -> u8 {
+        match addr & 0x1C {
+            0x00 => {
+                let data = self.read_vdp_word(addr & !1);
+                if addr & 1 == 0 { (data >> 8) as u8 } else { data as u8 }
+            }
+            0x04 => {
+                let mcycles = self.vdp_mcycles();
+                let status = self.vdp.read_status(mcycles);
+                if addr & 1 == 0 {
+                    // Bits 7-2 vêm do barramento em aberto
+                    ((status >> 8) as u8 & 0x03) | (self.open_bus_byte(addr) & 0xFC)
+                } else {
+                    status as u8
+                }
+            }
+            0x08 | 0x0C => {
+                let hv = self.vdp.read_hv(self.vdp_mcycles());
+                if addr & 1 == 0 { (hv >> 8) as u8 } else { hv as u8 }
+            }
+            _ => self.open_bus_byte(addr),
+        }
+    }
+    
+    /// Lê uma palavra do VDP
+    fn read_vdp_word(&mut self, addr: u32) -> u16 {
+        match addr & 0x1C {
+            0x00 => {
+                let (vdp, ram) = self.vdp_ports();
+                vdp.read_data(ram)
+            }
+            0x04 => {
+                let mcycles = self.vdp_mcycles();
+                // Bits 15-10 vêm do barramento em aberto
+                self.vdp.read_status(mcycles) & 0x03FF | (self.open_bus & 0xFC00)
+            }
+            0x08 | 0x0C => self.vdp.read_hv(self.vdp_mcycles()),
+            _ => self.open_bus,
+        }
+    }
+    
+    /// Escreve um byte no VDP: o byte é replicado nas duas metades do barramento
     fn write_vdp(&mut self, addr: u32, value: u8) {
-        // Será implementado no módulo VDP
+        match addr & 0x1C {
+            0x00 | 0x04 => self.write_vdp_word(addr & !1, u16::from_le_bytes([value, value])),
+            // PSG: só os endereços ímpares
+            0x10 | 0x14 => {
+                if addr & 1 != 0 {
+                    self.psg.write(self.vdp_mcycles(), value);
+                }
+            }
+            _ => trace!("Escrita ignorada no VDP ${:06X} <- ${:02X}", addr, value),
+        }
     }
     
+    /// Escreve uma palavra no VDP
     fn write_vdp_word(&mut self, addr: u32, value: u16) {
-        // Será implementado no módulo VDP
+        match addr & 0x1C {
+            0x00 => {
+                let mcycles = self.vdp_mcycles();
+                let (vdp, ram) = self.vdp_ports();
+                let stall = vdp.write_data(ram, value, mcycles);
+                self.m68k_stall_until(stall);
+                if self.vdp.take_dma_start() {
+                    self.vdp_dma_update(mcycles);
+                }
+            }
+            0x04 => {
+                let mcycles = self.vdp_mcycles();
+                self.vdp.sync(mcycles);
+                self.vdp.write_control(value);
+                if self.vdp.take_dma_start() {
+                    self.vdp_dma_update(mcycles);
+                }
+            }
+            0x10 | 0x14 => self.psg.write(self.vdp_mcycles(), value as u8),
+            _ => trace!("Escrita ignorada no VDP ${:06X} <- ${:04X}", addr, value),
+        }
+    }
+    
+    /// Para o 68000 até o clock mestre `mcycles` (0 = sem espera)
+    fn m68k_stall_until(&mut self, mcycles: u64) {
+        let now = self.vdp_mcycles();
+        if mcycles > now {
+            self.m68k_wait += (mcycles - now).div_ceil(M68K_DIVIDER) as u32;
+        }
+    }
+    
+    /// Início de uma linha do VDP: atualiza o VBLANK e continua um DMA
+    /// em andamento
+    pub fn vdp_start_line(&mut self, line: u16, mcycles: u64) {
+        self.vdp.start_line(line, mcycles);
+        if self.vdp.dma_length() > 0 {
+            self.vdp_dma_update(mcycles);
+        }
+    }
+    
+    /// Clock mestre em que uma pistola de luz vê o feixe na linha atual
+    pub fn light_gun_crossing(&mut self, line: u16, mcycles: u64) -> Option<u64> {
+        let beam = Beam {
+            line,
+            mcycles,
+            h40: self.vdp.h40(),
+            viewport: self.vdp.viewport(),
+        };
+        self.io.start_line(&beam)
+    }
+    
+    /// Registra um prazo (clock mestre absoluto) para um chip do cartucho,
+    /// substituindo o anterior
+    pub fn schedule_cartridge(&mut self, chip: CartridgeChip, at: u64) {
+        self.cart_deadlines.push((chip, Some(at)));
+    }
+    
+    /// Cancela o prazo pendente de um chip do cartucho
+    pub fn cancel_cartridge(&mut self, chip: CartridgeChip) {
+        self.cart_deadlines.push((chip, None));
+    }
+    
+    /// Prazo de um chip do cartucho atingido no clock mestre `mcycles`
+    pub fn cartridge_event(&mut self, chip: CartridgeChip, mcycles: u64) {
+        // SVP, Paprium e MegaSD ainda não compilam (ver `core::cartridge`):
+        // até lá o prazo só é consumido
+        match chip {
+            CartridgeChip::Svp | CartridgeChip::Paprium | CartridgeChip::MegaSd => {
+                trace!("Prazo de {:?} atingido em {}", chip, mcycles);
+            }
+        }
+    }
+    
+    /// Executa a fatia de DMA que cabe a partir do clock mestre `mcycles`
+    fn vdp_dma_update(&mut self, mcycles: u64) {
+        let length = self.vdp.dma_begin(mcycles);
+    
+        // O 68000 fica parado enquanto o DMA usa o seu barramento
+        if self.vdp.dma_type().uses_68k_bus() {
+            self.m68k_stall_until(self.vdp.dma_end());
+        }
+    
+        if length > 0 {
+            match self.vdp.dma_type() {
+                DmaType::M68kToCram | DmaType::M68kToVram => self.vdp_dma_68k(length),
+                DmaType::Fill => {
+                    let (vdp, ram) = self.vdp_ports();
+                    vdp.dma_fill(ram, length);
+                }
+                DmaType::Copy => {
+                    let (vdp, ram) = self.vdp_ports();
+                    vdp.dma_copy(ram, length);
+                }
+            }
+    
+            if let Some(data) = self.vdp.dma_finish() {
+                self.vdp.write_control(data);
+            }
+        }
+    }
+    
+    /// DMA do barramento do 68000 para VRAM, CRAM ou VSRAM
+    fn vdp_dma_68k(&mut self, length: u32) {
+        let mut source = self.vdp.dma_source();
+        for _ in 0..length {
+            let data = self.dma_read_word(source);
+            source = self.vdp.dma_next_source(source);
+            let (vdp, ram) = self.vdp_ports();
+            vdp.bus_write(ram, data);
+        }
+        self.vdp.set_dma_source(source);
+    }
+    
+    /// Palavra lida pelo DMA do VDP no barramento do 68000
+    fn dma_read_word(&mut self, source: u32) -> u16 {
+        let wram = |bus: &Self| {
+            let offset = (source & 0xFFFE) as usize;
+            u16::from_be_bytes([bus.wram[offset], bus.wram[offset + 1]])
+        };
+        match source >> 21 {
+            // $000000-$7FFFFF: cartucho e expansões
+            0..=3 => (self.map.get_handler(source).read_word)(source),
+            // $A00000-$BFFFFF: área do Z80 e chip de I/O
+            5 => {
+                if source <= 0xA0FFFF {
+                    // $FFFF só quando o 68000 detém o barramento do Z80
+                    if self.z80_bus_granted() { 0xFFFF } else { wram(self) }
+                } else if source <= 0xA1001F {
+                    // O chip de I/O tem precedência sobre a RAM
+                    let data = self.read_io(source | 1) as u16;
+                    (data << 8) | data
+                } else {
+                    wram(self)
+                }
+            }
+            // Demais endereços acessam a RAM de trabalho
+            _ => wram(self),
+        }
     }
     
     /// Avança o contador de ciclos
@@ -243,15 +979,38 @@ impl MemoryBus {
     
     /// Reseta o barramento
     pub fn reset(&mut self) {
+        self.wram = [0; 65536];
         self.zram = [0; 8192];
-        self.ioports = [0; 256];
+        self.io.reset();
         self.vram = [0; 65536];
         self.cram = [0; 64];
         self.vsram = [0; 40];
+        self.vdp.reset(false);
+        self.fm = FmChip::new(self.fm.backend(), self.fm.ladder_effect());
+        self.psg = SN76489::new(self.psg_type());
+        self.ym2413 = YM2413::new();
+        self.audio_control = 0;
+        self.z80_busreq = false;
+        self.z80_reset = self.genesis_mode;
+        self.m68k_wait = 0;
+        self.m68k_lockup = false;
+        self.zbank = 0;
+        self.z80_wait = 0;
+        self.z80_cycles = 0;
         self.cycles = 0;
         
-        if let Some(cart) = &mut self.cart {
-            // Reseta o cartucho também
+        if let Some(cart) = &self.cart {
+            cart.lock().unwrap().reset();
+        }
+        if let Some(yx5200) = &mut self.yx5200 {
+            yx5200.reset();
         }
+        self.cart_deadlines.clear();
+    }
+}
+
+impl Default for MemoryBus {
+    fn default() -> Self {
+        Self::new()
     }
 }
\ No newline at end of file
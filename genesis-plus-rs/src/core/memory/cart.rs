@@ -1,11 +1,11 @@
 //! Estruturas e funções para gerenciamento de cartuchos.
 //! Baseado em `cart.h` e `cart.c` do Genesis Plus GX.
 
-use crate::core::memory::{MAX_ROM_SIZE, MAX_SRAM_SIZE, MemoryError, MemoryResult};
+use crate::core::memory::{MAX_ROM_SIZE, MemoryError, MemoryResult};
 use std::path::Path;
 use std::fs::File;
 use std::io::Read;
-use log::{info, warn, error};
+use log::info;
 
 /// Tipo de mapeador de cartucho
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -39,6 +39,8 @@ pub struct Cartridge {
     pub has_eeprom: bool,
     pub is_pal: bool,
     pub region: u8,
+    pub has_fm: bool,        // Jogo do SMS com suporte à FM Sound Unit
+    pub has_yx5200: bool,    // Placa KAISER WAVE com o tocador de MP3 YX5200
     pub header: [u8; 0x200], // Cabeçalho ROM
 }
 
@@ -64,6 +66,8 @@ impl Cartridge {
             has_eeprom: false,
             is_pal: false,
             region: 0,
+            has_fm: false,
+            has_yx5200: false,
             header: [0; 0x200],
         }
     }
@@ -101,6 +105,7 @@ impl Cartridge {
         self.detect_mapper()?;
         self.detect_sram();
         self.detect_region();
+        self.detect_yx5200();
         
         info!("Cartucho carregado: {} bytes, Mapper: {:?}, SRAM: {}", 
               self.rom_size, self.mapper, self.has_sram);
@@ -177,6 +182,25 @@ impl Cartridge {
         }
     }
     
+    /// Detecta a placa KAISER WAVE (The Secret Of The Four Winds) pelo
+    /// cabeçalho e pelo checksum
+    fn detect_yx5200(&mut self) {
+        let header = &self.header;
+        let checksum = u16::from_be_bytes([header[0x18E], header[0x18F]]);
+        let real = self.rom.get(0x200..).unwrap_or_default().chunks(2).fold(0u16, |sum, word| {
+            sum.wrapping_add(u16::from_be_bytes([word[0], word.get(1).copied().unwrap_or(0)]))
+        });
+        self.has_yx5200 = &header[0x180..0x182] == b"GM"
+            && header[0x182..0x18E].windows(11).any(|product| product == b"00000000-00")
+            && checksum == 0x45C1
+            && real == 0xC613;
+    }
+    
+    /// Reset do console: os registradores do mapeador voltam ao início
+    pub fn reset(&mut self) {
+        self.reset_banks();
+    }
+    
     /// Reseta os bancos para estado inicial
     fn reset_banks(&mut self) {
         self.bank_regs = [0; 8];
@@ -218,17 +242,13 @@ impl Cartridge {
     /// Atualiza o mapeamento de um banco específico
     fn update_bank(&mut self, bank: usize) {
         match self.mapper {
-            MapperType::Sega => {
-                if bank < 6 {
-                    let bank_num = self.bank_regs[bank] as u32;
-                    self.bank_start[bank] = (bank_num & 0x3F) * 0x10000;
-                }
+            MapperType::Sega if bank < 6 => {
+                let bank_num = self.bank_regs[bank] as u32;
+                self.bank_start[bank] = (bank_num & 0x3F) * 0x10000;
             }
-            MapperType::Codemasters => {
-                if bank == 0 {
-                    let bank_num = self.bank_regs[0] as u32;
-                    self.bank_start[0] = (bank_num & 0x3) * 0x4000;
-                }
+            MapperType::Codemasters if bank == 0 => {
+                let bank_num = self.bank_regs[0] as u32;
+                self.bank_start[0] = (bank_num & 0x3) * 0x4000;
             }
             _ => {}
         }
@@ -276,4 +296,10 @@ impl Cartridge {
             }
         }
     }
+}
+
+impl Default for Cartridge {
+    fn default() -> Self {
+        Self::new()
+    }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 //! Tabelas de mapeamento e handlers de memória.
 //! Baseado em `mem68k.c` e `memory.h` do Genesis Plus GX.
 
-use crate::core::memory::{ADDRESS_MASK, MemoryError};
+use crate::core::memory::ADDRESS_MASK;
 use crate::core::memory::cart::Cartridge;
 use std::sync::{Arc, Mutex};
 
@@ -9,9 +9,11 @@ use std::sync::{Arc, Mutex};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MemRegion {
     Rom,        // Cartucho ROM
+    Bios,       // ROM de boot do console (TMSS)
     Sram,       // Save RAM
     Zram,       // RAM do Z80 (8KB)
     Zrom,       // ROM do Z80 (cartucho)
+    Wram,       // RAM de trabalho do 68000 (64KB)
     Io,         // I/O (VDP, PSG, etc.)
     Vdp,        // Vídeo Display Processor
     Psg,        // Programmable Sound Generator
@@ -19,22 +21,34 @@ pub enum MemRegion {
 }
 
 /// Handler para acesso à memória (usando trait objects para flexibilidade)
+///
+/// As closures são compartilhadas (`Arc`) para que a mesma região possa ser
+/// mapeada em várias páginas de 64KB sem duplicar o estado capturado.
+#[derive(Clone)]
 pub struct MemoryHandler {
-    pub read_byte: Box<dyn Fn(u32) -> u8 + Send + Sync>,
-    pub read_word: Box<dyn Fn(u32) -> u16 + Send + Sync>,
-    pub write_byte: Box<dyn Fn(u32, u8) + Send + Sync>,
-    pub write_word: Box<dyn Fn(u32, u16) + Send + Sync>,
+    pub read_byte: Arc<dyn Fn(u32) -> u8 + Send + Sync>,
+    pub read_word: Arc<dyn Fn(u32) -> u16 + Send + Sync>,
+    pub write_byte: Arc<dyn Fn(u32, u8) + Send + Sync>,
+    pub write_word: Arc<dyn Fn(u32, u16) + Send + Sync>,
     pub region: MemRegion,
 }
 
-impl Clone for MemoryHandler {
-    fn clone(&self) -> Self {
+impl MemoryHandler {
+    /// Handler de região sem acesso externo (leitura em aberto, escrita ignorada)
+    pub fn unmapped() -> Self {
+        Self::internal(MemRegion::Unmapped)
+    }
+
+    /// Handler para regiões tratadas diretamente pelo `MemoryBus`
+    /// (RAM de trabalho, Z80 RAM, I/O, VDP). As closures nunca são chamadas
+    /// para essas regiões: o barramento despacha pelo campo `region`.
+    pub fn internal(region: MemRegion) -> Self {
         Self {
-            read_byte: self.read_byte.clone(),
-            read_word: self.read_word.clone(),
-            write_byte: self.write_byte.clone(),
-            write_word: self.write_word.clone(),
-            region: self.region,
+            read_byte: Arc::new(|_| 0xFF),
+            read_word: Arc::new(|_| 0xFFFF),
+            write_byte: Arc::new(|_, _| {}),
+            write_word: Arc::new(|_, _| {}),
+            region,
         }
     }
 }
@@ -47,16 +61,8 @@ pub struct MemoryMap {
 impl MemoryMap {
     /// Cria um novo mapa de memória vazio
     pub fn new() -> Self {
-        let unmapped_handler = MemoryHandler {
-            read_byte: Box::new(|_| 0xFF),
-            read_word: Box::new(|_| 0xFFFF),
-            write_byte: Box::new(|_, _| {}),
-            write_word: Box::new(|_, _| {}),
-            region: MemRegion::Unmapped,
-        };
-        
         Self {
-            handlers: [unmapped_handler; 256],
+            handlers: std::array::from_fn(|_| MemoryHandler::unmapped()),
         }
     }
     
@@ -79,6 +85,12 @@ impl MemoryMap {
     }
 }
 
+impl Default for MemoryMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Cria handlers para a ROM do cartucho
 pub fn create_rom_handlers(cart: Arc<Mutex<Cartridge>>) 
     -> (MemoryHandler, MemoryHandler, MemoryHandler, MemoryHandler) 
@@ -91,9 +103,10 @@ pub fn create_rom_handlers(cart: Arc<Mutex<Cartridge>>)
     
     let cart2 = Arc::clone(&cart);
     let read_word = move |addr: u32| {
+        // O 68000 é big-endian: o byte par é a metade alta da palavra
         let cart = cart2.lock().unwrap();
-        let low = cart.read_rom(addr);
-        let high = cart.read_rom(addr.wrapping_add(1));
+        let high = cart.read_rom(addr);
+        let low = cart.read_rom(addr.wrapping_add(1));
         (high as u16) << 8 | low as u16
     };
     
@@ -101,12 +114,31 @@ pub fn create_rom_handlers(cart: Arc<Mutex<Cartridge>>)
     let write_word = |_: u32, _: u16| {};
     
     let handler = MemoryHandler {
-        read_byte: Box::new(read_byte),
-        read_word: Box::new(read_word),
-        write_byte: Box::new(write_byte),
-        write_word: Box::new(write_word),
+        read_byte: Arc::new(read_byte),
+        read_word: Arc::new(read_word),
+        write_byte: Arc::new(write_byte),
+        write_word: Arc::new(write_word),
         region: MemRegion::Rom,
     };
     
     (handler.clone(), handler.clone(), handler.clone(), handler)
-}
\ No newline at end of file
+}
+
+/// Cria o handler da ROM de boot, espelhada por toda a página
+pub fn create_boot_rom_handler(rom: Arc<[u8]>) -> MemoryHandler {
+    let rom1 = Arc::clone(&rom);
+    let read_byte = move |addr: u32| rom1[addr as usize % rom1.len()];
+    let read_word = move |addr: u32| {
+        let high = rom[addr as usize % rom.len()];
+        let low = rom[(addr as usize + 1) % rom.len()];
+        (high as u16) << 8 | low as u16
+    };
+
+    MemoryHandler {
+        read_byte: Arc::new(read_byte),
+        read_word: Arc::new(read_word),
+        write_byte: Arc::new(|_, _| {}),
+        write_word: Arc::new(|_, _| {}),
+        region: MemRegion::Bios,
+    }
+}
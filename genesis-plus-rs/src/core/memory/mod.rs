@@ -5,12 +5,14 @@
 pub mod bus;
 pub mod cart;
 pub mod map;
+pub mod sms;
 pub mod sram;
 
 // Re-exportações para facilitar o uso
 pub use bus::MemoryBus;
 pub use cart::{Cartridge, MapperType};
 pub use map::{MemRegion, MemoryHandler};
+pub use sms::{SmsCartridge, SmsSlot};
 pub use sram::SaveRam;
 
 /// Máscara de endereço válido para o barramento Genesis (24-bit = 16 MB)
@@ -0,0 +1,139 @@
+//! Slots de memória do Master System e do Game Gear.
+//! Baseado em `sms_cart.c` e `memz80.c` do Genesis Plus GX.
+//!
+//! A BIOS, o cartucho, o cartão e a expansão ocupam $0000-$BFFF em slots
+//! separados, ligados e desligados pela porta de controle de memória ($3E).
+//! A RAM de trabalho fica em $C000-$FFFF e os registradores do mapeador da
+//! Sega em $FFFC-$FFFF.
+
+use std::sync::Arc;
+
+/// Porta $3E, bit 7: slot de expansão desligado
+pub const MEMCTRL_EXPANSION: u8 = 0x80;
+/// Porta $3E, bit 6: slot de cartucho desligado
+pub const MEMCTRL_CART: u8 = 0x40;
+/// Porta $3E, bit 5: slot de cartão desligado
+pub const MEMCTRL_CARD: u8 = 0x20;
+/// Porta $3E, bit 4: RAM de trabalho desligada
+pub const MEMCTRL_RAM: u8 = 0x10;
+/// Porta $3E, bit 3: BIOS desligada
+pub const MEMCTRL_BIOS: u8 = 0x08;
+/// Porta $3E, bit 2: chip de I/O desligado
+pub const MEMCTRL_IO: u8 = 0x04;
+
+/// Controle de memória no power-on com a BIOS: só a BIOS e a RAM ligadas
+pub const MEMCTRL_BIOS_BOOT: u8 = 0xE0;
+/// Valor deixado pela BIOS ao passar para o cartucho
+pub const MEMCTRL_CART_BOOT: u8 = 0xA8;
+
+/// Slot de memória do Master System
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmsSlot {
+    Bios,
+    Cartridge,
+    Card,
+    Expansion,
+}
+
+impl SmsSlot {
+    /// Slots em ordem de prioridade no barramento
+    pub const ALL: [SmsSlot; 4] = [SmsSlot::Bios, SmsSlot::Cartridge, SmsSlot::Card, SmsSlot::Expansion];
+
+    /// Bit da porta $3E que desliga o slot
+    pub fn disable_bit(self) -> u8 {
+        match self {
+            SmsSlot::Bios => MEMCTRL_BIOS,
+            SmsSlot::Cartridge => MEMCTRL_CART,
+            SmsSlot::Card => MEMCTRL_CARD,
+            SmsSlot::Expansion => MEMCTRL_EXPANSION,
+        }
+    }
+}
+
+/// ROM conectada a um slot, paginada pelo mapeador da Sega quando maior
+/// que 48KB
+pub struct SmsCartridge {
+    rom: Arc<[u8]>,
+    /// Registradores do mapeador ($FFFC-$FFFF)
+    pub fcr: [u8; 4],
+    mapper: bool,
+    enabled: bool,
+}
+
+impl SmsCartridge {
+    /// Conecta a ROM a um slot. O slot começa desligado: só a porta $3E
+    /// (escrita pela BIOS ou no power-on sem BIOS) o liga.
+    pub fn new(rom: Arc<[u8]>) -> Self {
+        Self {
+            mapper: rom.len() > 0xC000,
+            rom,
+            fcr: [0, 0, 1, 2],
+            enabled: false,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Paginação inicial do mapeador
+    pub fn reset(&mut self) {
+        self.fcr = [0, 0, 1, 2];
+    }
+
+    /// A ROM decodifica o endereço? A BIOS de 1KB do Game Gear só
+    /// responde em $0000-$03FF, deixando o cartucho visível acima.
+    pub fn decodes(&self, addr: u16) -> bool {
+        addr < 0xC000 && (self.rom.len() > 0x400 || addr < 0x400)
+    }
+
+    /// Lê um byte em $0000-$BFFF
+    pub fn read(&self, addr: u16) -> u8 {
+        // O primeiro 1KB nunca é paginado (vetores de interrupção)
+        let offset = if !self.mapper || addr < 0x400 {
+            addr as usize
+        } else {
+            self.fcr[1 + (addr >> 14) as usize] as usize * 0x4000 + (addr & 0x3FFF) as usize
+        };
+        self.rom[offset % self.rom.len()]
+    }
+
+    /// Escrita em $FFFC-$FFFF
+    pub fn write_mapper(&mut self, addr: u16, value: u8) {
+        if self.mapper {
+            self.fcr[(addr & 3) as usize] = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sega_mapper_paging() {
+        let rom: Vec<u8> = (0..0x40000).map(|i| (i / 0x4000) as u8).collect();
+        let mut cart = SmsCartridge::new(Arc::from(rom));
+        assert!(!cart.enabled());
+        assert_eq!(cart.read(0x8000), 2);
+
+        cart.write_mapper(0xFFFD, 5);
+        cart.write_mapper(0xFFFF, 0x1F);
+        // O primeiro 1KB continua fixo; páginas além da ROM são espelhadas
+        assert_eq!(cart.read(0x03FF), 0);
+        assert_eq!(cart.read(0x0400), 5);
+        assert_eq!(cart.read(0xBFFF), 0x0F);
+
+        cart.reset();
+        assert_eq!(cart.read(0x0400), 0);
+
+        // ROMs de até 48KB não têm mapeador
+        let mut small = SmsCartridge::new(Arc::from(vec![0xAA; 0x8000]));
+        small.write_mapper(0xFFFF, 1);
+        assert_eq!(small.fcr, [0, 0, 1, 2]);
+    }
+}
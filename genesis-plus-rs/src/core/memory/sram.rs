@@ -4,7 +4,7 @@
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::Path;
-use log::{info, warn, error};
+use log::{info, error};
 
 /// Save RAM com suporte a persistência
 pub struct SaveRam {
@@ -53,8 +53,8 @@ impl SaveRam {
     /// Salva automaticamente se suja
     pub fn auto_save(&mut self) {
         if self.dirty && self.enabled && !self.write_protect {
-            if let Some(ref path) = self.file_path {
-                if let Err(e) = self.save_to_file(path) {
+            if let Some(path) = self.file_path.clone() {
+                if let Err(e) = self.save_to_file(&path) {
                     error!("Falha ao salvar Save RAM: {}", e);
                 }
             }
@@ -0,0 +1,9 @@
+//! Núcleo do emulador: CPUs, memória, vídeo, áudio e cartuchos.
+
+pub mod audio;
+pub mod cartridge;
+pub mod cpu;
+pub mod input;
+pub mod memory;
+pub mod system;
+pub mod vdp;
@@ -0,0 +1,719 @@
+//! Sistema Genesis/Mega Drive completo e agendamento de um quadro.
+//! Baseado em `system.c` (`system_frame_gen`) do Genesis Plus GX.
+//!
+//! Um quadro é executado linha a linha: em cada linha o 68000 e o Z80 rodam
+//! até o fim da linha (medido em clocks mestres), depois são tratados os
+//! eventos de linha do VDP e o áudio é gerado até o mesmo ponto.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::core::audio::{FmBackend, FmChip, FmUnit, Sound, BLIP_CART, BLIP_FM, BLIP_PSG, SN76489, YM2413};
+use crate::core::cartridge::chips::Yx5200;
+use crate::core::cpu::{M68K, Z80};
+use crate::core::input::{create_devices, InputState, Port, PortDevice};
+use crate::core::memory::{Cartridge, MemoryBus, MemoryResult};
+use crate::core::vdp::renderer::{PixelFormat, Renderer};
+use crate::core::vdp::VdpModel;
+use crate::utils::clock::{
+    ClockEvent, MasterClock, MCLOCK_NTSC, MCLOCK_PAL, MCYCLES_PER_LINE, M68K_DIVIDER, YM2413_DIVIDER,
+    YM2612_DIVIDER, Z80_DIVIDER,
+};
+
+/// Dimensões máximas do framebuffer (H40, V30 no entrelaçado 2)
+pub const FRAMEBUFFER_WIDTH: usize = 320;
+pub const FRAMEBUFFER_HEIGHT: usize = 480;
+
+/// Taxa de amostragem padrão do áudio de saída
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// Região do console
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// Japão (NTSC, doméstico)
+    Japan,
+    /// América do Norte (NTSC, exportação)
+    Usa,
+    /// Europa (PAL, exportação)
+    Europe,
+}
+
+impl Region {
+    pub fn is_pal(self) -> bool {
+        self == Region::Europe
+    }
+
+    /// Linhas por quadro (262 NTSC, 313 PAL)
+    pub fn lines_per_frame(self) -> u16 {
+        if self.is_pal() { 313 } else { 262 }
+    }
+
+    /// Frequência do clock mestre
+    pub fn master_clock(self) -> u32 {
+        if self.is_pal() { MCLOCK_PAL } else { MCLOCK_NTSC }
+    }
+}
+
+/// Console emulado: define o mapa de memória do Z80, o modelo do VDP e a
+/// BIOS usada
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Console {
+    #[default]
+    MegaDrive,
+    MasterSystem,
+    GameGear,
+}
+
+impl Console {
+    fn vdp_model(self) -> VdpModel {
+        match self {
+            Console::MegaDrive => VdpModel::MegaDrive,
+            Console::MasterSystem => VdpModel::MasterSystem2,
+            Console::GameGear => VdpModel::GameGear,
+        }
+    }
+}
+
+/// Saída do entrelaçado 2 (Sonic 2 em dois jogadores, Combat Cars)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterlaceOutput {
+    /// Quadro de 448 linhas com os dois campos intercalados
+    Weave,
+    /// Uma imagem de 224 linhas por campo (ver `Frame::odd_field`)
+    Field,
+}
+
+/// Quadro emulado pronto para o frontend
+pub struct Frame<'a> {
+    /// Pixels no formato `format`; a linha `y` começa no byte `y * pitch`
+    pub video: &'a [u8],
+    pub width: usize,
+    pub height: usize,
+    pub pitch: usize,
+    pub format: PixelFormat,
+    /// Campo ímpar de um quadro entrelaçado (meia linha abaixo do par)
+    pub odd_field: bool,
+    /// Amostras estéreo intercaladas (esquerda, direita)
+    pub audio: &'a [i16],
+    pub sample_rate: u32,
+}
+
+/// Sistema Genesis/Mega Drive
+pub struct GenesisSystem {
+    pub m68k: M68K,
+    pub z80: Z80,
+    pub bus: MemoryBus,
+    pub region: Region,
+    /// Console emulado (vale a partir do próximo power-on)
+    pub console: Console,
+    /// Taxa de amostragem do áudio gerado (qualquer taxa do frontend; vale
+    /// a partir do próximo quadro)
+    pub sample_rate: u32,
+    /// Quadros executados desde o power-on
+    pub frame_count: u64,
+    /// Clock mestre e eventos agendados pelos dispositivos
+    pub clock: MasterClock,
+    /// Saída do entrelaçado 2
+    pub interlace_output: InterlaceOutput,
+    /// Síntese e mistura do áudio de todas as fontes
+    pub sound: Sound,
+
+    /// Linha corrente do quadro
+    line: u16,
+    /// Última amostra do chip FM, base da próxima variação
+    fm_last: (i32, i32),
+    /// Última amostra do YM2413
+    ym2413_last: i32,
+    /// Núcleo e modelo do chip FM escolhidos para a sessão
+    fm_backend: FmBackend,
+    ladder_effect: bool,
+    /// Presença da FM Sound Unit no Master System
+    fm_unit: FmUnit,
+    /// Console com TMSS
+    tmss: bool,
+    /// BIOS fornecida pelo frontend para cada console (ver `Console`)
+    bios: [Option<Arc<[u8]>>; 3],
+    /// Periféricos das portas de controle 1 e 2
+    ports: [PortDevice; 2],
+    /// Pasta da ROM, onde ficam as faixas de áudio do cartucho
+    content_dir: PathBuf,
+
+    renderer: Renderer,
+    /// Largura da última linha renderizada (160, 256 ou 320)
+    width: usize,
+    framebuffer: Vec<u8>,
+    audio_buffer: Vec<i16>,
+}
+
+impl GenesisSystem {
+    /// Cria o sistema sem cartucho (região americana)
+    pub fn new() -> Self {
+        Self {
+            m68k: M68K::new(),
+            z80: Z80::new(),
+            bus: MemoryBus::new(),
+            region: Region::Usa,
+            console: Console::MegaDrive,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            frame_count: 0,
+            clock: MasterClock::ntsc(),
+            interlace_output: InterlaceOutput::Weave,
+            sound: Sound::new(DEFAULT_SAMPLE_RATE, Region::Usa.master_clock()),
+            line: 0,
+            fm_last: (0, 0),
+            ym2413_last: 0,
+            fm_backend: FmBackend::default(),
+            ladder_effect: true,
+            fm_unit: FmUnit::default(),
+            tmss: false,
+            bios: [None, None, None],
+            ports: [PortDevice::default(); 2],
+            content_dir: PathBuf::new(),
+            renderer: Renderer::new(PixelFormat::Xrgb8888),
+            width: FRAMEBUFFER_WIDTH,
+            framebuffer: vec![0; FRAMEBUFFER_WIDTH * FRAMEBUFFER_HEIGHT * 4],
+            audio_buffer: Vec::new(),
+        }
+    }
+
+    /// Seleciona o formato de pixel do framebuffer
+    pub fn set_pixel_format(&mut self, format: PixelFormat) {
+        self.renderer.set_format(format);
+        self.framebuffer = vec![0; FRAMEBUFFER_WIDTH * FRAMEBUFFER_HEIGHT * format.bytes_per_pixel()];
+    }
+
+    /// Mostra os pontos causados por escritas na CRAM durante o display
+    /// ativo (opção de precisão, desligada por padrão)
+    pub fn set_cram_dots(&mut self, enabled: bool) {
+        self.renderer.set_cram_dots(enabled);
+    }
+
+    /// Seleciona o núcleo do chip FM e o modelo emulado: YM2612 discreto do
+    /// modelo 1, com o "ladder effect" do DAC, ou YM3438 integrado
+    pub fn set_fm_chip(&mut self, backend: FmBackend, ladder_effect: bool) {
+        self.fm_backend = backend;
+        self.ladder_effect = ladder_effect;
+        self.bus.fm.configure(self.clock.now(), backend, ladder_effect);
+    }
+
+    /// Liga ou desliga a FM Sound Unit (YM2413) do Master System. Em
+    /// `FmUnit::Auto` o chip só está presente nos jogos com suporte a FM.
+    /// Vale a partir do próximo power-on.
+    pub fn set_fm_unit(&mut self, fm_unit: FmUnit) {
+        self.fm_unit = fm_unit;
+    }
+
+    /// Emula o TMSS dos modelos 1 VA6+ e 2: o VDP só responde depois que o
+    /// jogo escreve "SEGA" em $A14000. Vale a partir do próximo power-on.
+    pub fn set_tmss(&mut self, enabled: bool) {
+        self.tmss = enabled;
+    }
+
+    /// BIOS opcional de um console, executada antes do cartucho. No Mega
+    /// Drive é a ROM de boot do TMSS (2KB), usada só com o TMSS ligado, até a
+    /// escrita em $A14101; no Master System e no Game Gear ela liga o slot
+    /// do cartucho pela porta $3E. Vale a partir do próximo power-on.
+    pub fn set_bios(&mut self, console: Console, rom: Option<&[u8]>) {
+        self.bios[console as usize] = rom.filter(|rom| !rom.is_empty()).map(Arc::from);
+    }
+
+    /// Conecta um periférico à porta de controle `port`. Pode ser trocado
+    /// com o jogo rodando. O EA 4-Way Play ocupa as duas portas.
+    pub fn set_port_device(&mut self, port: Port, device: PortDevice) {
+        if let PortDevice::FourWayPlay(_) = device {
+            self.ports = [device, PortDevice::None];
+        } else {
+            if let PortDevice::FourWayPlay(_) = self.ports[0] {
+                self.ports[0] = PortDevice::None;
+            }
+            self.ports[port as usize] = device;
+        }
+        self.connect_ports();
+    }
+
+    pub fn port_device(&self, port: Port) -> PortDevice {
+        self.ports[port as usize]
+    }
+
+    /// Botões dos até 8 jogadores, numerados em sequência pelas portas
+    pub fn input(&mut self) -> &mut InputState {
+        &mut self.bus.io.input
+    }
+
+    /// Recria os periféricos, distribuindo os jogadores lógicos
+    fn connect_ports(&mut self) {
+        let [port_a, port_b] = create_devices(self.ports);
+        self.bus.io.connect(Port::A, port_a);
+        self.bus.io.connect(Port::B, port_b);
+    }
+
+    /// Pasta da ROM carregada a seguir, onde o cartucho procura arquivos
+    /// extras (as faixas MP3 da placa KAISER WAVE)
+    pub fn set_content_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.content_dir = dir.into();
+    }
+
+    /// Carrega uma ROM e liga o console
+    pub fn load_rom(&mut self, data: &[u8]) -> MemoryResult<()> {
+        let mut cart = Cartridge::new();
+        cart.load_from_buffer(data)?;
+        self.bus = MemoryBus::new();
+        if cart.has_yx5200 {
+            self.bus.yx5200 = Some(Yx5200::new(&self.content_dir));
+        }
+        self.bus.init(cart)?;
+        self.power_on();
+        Ok(())
+    }
+
+    /// Liga o console: zera a contagem de tempo e reseta as CPUs
+    pub fn power_on(&mut self) {
+        self.m68k = M68K::new();
+        self.z80 = Z80::new();
+        self.clock = MasterClock::new(self.region.master_clock());
+        self.bus.genesis_mode = self.console == Console::MegaDrive;
+        self.bus.vdp.model = self.console.vdp_model();
+        self.bus.vdp.reset(self.region.is_pal());
+        self.bus.fm = FmChip::new(self.fm_backend, self.ladder_effect);
+        self.bus.psg = SN76489::new(self.bus.psg_type());
+        self.bus.ym2413 = YM2413::new();
+        self.bus.audio_control = 0;
+        self.bus.region = self.region;
+        self.bus.tmss_enabled = self.tmss && self.bus.genesis_mode;
+        self.bus.boot_rom = self.bios[Console::MegaDrive as usize].clone();
+        self.bus.tmss_power_on();
+        // Nenhuma unidade Mega-CD é emulada
+        self.bus.io.power_on(self.region, self.bus.tmss_enabled, false);
+        self.bus.io.set_cpu_divider(if self.bus.genesis_mode { M68K_DIVIDER } else { Z80_DIVIDER });
+        self.connect_ports();
+        let bios = self.bios[self.console as usize].clone();
+        let sms_bios = bios.is_some();
+        if !self.bus.genesis_mode {
+            self.bus.sms_power_on(bios);
+        }
+        self.bus.fm_unit = match self.fm_unit {
+            FmUnit::Off => false,
+            FmUnit::On => true,
+            FmUnit::Auto => self.bus.cart.as_ref().is_some_and(|cart| cart.lock().unwrap().has_fm),
+        };
+        self.sound.set_rate(self.sample_rate, self.region.master_clock());
+        self.sound.enable(BLIP_CART, self.bus.yx5200.is_some());
+        self.line = 0;
+        self.frame_count = 0;
+        self.reset();
+        // Sem BIOS, a pilha fica onde a BIOS a deixaria
+        if !self.bus.genesis_mode && !sms_bios {
+            self.z80.sp = 0xDFF0;
+        }
+    }
+
+    /// Botão RESET: reinicia as CPUs sem apagar as memórias
+    pub fn reset(&mut self) {
+        self.bus.z80_busreq = false;
+        // Fora do Mega Drive, o Z80 é a CPU principal
+        self.bus.z80_reset = self.bus.genesis_mode;
+        self.bus.zbank = 0;
+        self.bus.io.reset();
+        if !self.bus.genesis_mode {
+            self.bus.sms_reset();
+        }
+        self.bus.fm.reset(self.clock.now());
+        self.bus.ym2413.reset(self.clock.now());
+        self.sound.reset(self.clock.now());
+        if let Some(yx5200) = &mut self.bus.yx5200 {
+            yx5200.reset();
+        }
+        self.fm_last = (0, 0);
+        self.ym2413_last = 0;
+        self.z80.reset();
+        self.m68k.reset(&mut self.bus);
+    }
+
+    /// Executa um quadro completo e devolve vídeo e áudio gerados
+    pub fn run_frame(&mut self) -> Frame<'_> {
+        self.audio_buffer.clear();
+        if self.sound.sample_rate() != self.sample_rate {
+            self.sound.set_rate(self.sample_rate, self.region.master_clock());
+            self.sound.reset(self.clock.now());
+        }
+
+        let lines = self.region.lines_per_frame();
+        for line in 0..lines {
+            self.line = line;
+            self.run_line();
+        }
+        self.line = 0;
+        self.frame_count += 1;
+
+        let samples = self.sound.end_frame(self.clock.now());
+        if let Some(yx5200) = &mut self.bus.yx5200 {
+            yx5200.update(&mut self.sound, samples);
+        }
+        self.sound.read(&mut self.audio_buffer);
+
+        let format = self.renderer.format();
+        let height = self.bus.vdp.viewport().height as usize;
+        Frame {
+            video: &self.framebuffer,
+            width: self.width,
+            height: if self.weave() { height * 2 } else { height },
+            pitch: FRAMEBUFFER_WIDTH * format.bytes_per_pixel(),
+            format,
+            odd_field: self.bus.vdp.odd_field(),
+            audio: &self.audio_buffer,
+            sample_rate: self.sample_rate,
+        }
+    }
+
+    /// Linha corrente do quadro em execução
+    pub fn line(&self) -> u16 {
+        self.line
+    }
+
+    /// Executa uma linha: CPUs, eventos do VDP e áudio
+    fn run_line(&mut self) {
+        let line_end = (self.clock.now() / MCYCLES_PER_LINE + 1) * MCYCLES_PER_LINE;
+        let line = self.line as usize;
+
+        self.bus.vdp_start_line(self.line, self.clock.now());
+
+        // Pistolas de luz: a fatia termina quando o feixe passa pela mira
+        if let Some(at) = self.bus.light_gun_crossing(self.line, self.clock.now()) {
+            self.clock.schedule(ClockEvent::LightGun, at);
+        }
+
+        // A interrupção vertical ocorre alguns ciclos após o início da linha
+        if self.line == self.bus.vdp.vint_line() {
+            let at = self.clock.now() + self.bus.vdp.vint_mcycle();
+            self.clock.schedule(ClockEvent::Vint, at);
+        }
+
+        // A linha é fatiada nos prazos registrados pelos dispositivos
+        loop {
+            self.sync_cartridge_deadlines();
+            let slice_end = match self.clock.next_deadline() {
+                Some(deadline) if deadline < line_end => deadline,
+                _ => line_end,
+            };
+            self.run_cpus(slice_end);
+            self.clock.advance_to(slice_end);
+            self.bus.update_io(slice_end);
+
+            while let Some(event) = self.clock.pop_due() {
+                self.handle_event(event);
+            }
+            if slice_end == line_end {
+                break;
+            }
+        }
+
+        let viewport = self.bus.vdp.viewport();
+        if (viewport.y..viewport.y + viewport.height).contains(&self.line) {
+            let mut row = line - viewport.y as usize;
+            if self.weave() {
+                row = row * 2 + self.bus.vdp.odd_field() as usize;
+            }
+            self.render_line(line, row);
+        }
+
+        self.update_audio(line_end);
+    }
+
+    /// Campos do entrelaçado 2 intercalados num quadro de altura dobrada
+    fn weave(&self) -> bool {
+        self.interlace_output == InterlaceOutput::Weave && self.bus.vdp.interlace_mode2()
+    }
+
+    /// Executa o 68000 e o Z80 até o M-cycle `mcycles`
+    fn run_cpus(&mut self, mcycles: u64) {
+        let target = mcycles / M68K_DIVIDER;
+        if self.bus.genesis_mode && self.m68k.cycles < target {
+            self.m68k.execute(&mut self.bus, (target - self.m68k.cycles) as u32);
+        }
+
+        let target = mcycles / Z80_DIVIDER;
+        if self.z80.cycles < target {
+            self.z80.execute(&mut self.bus, (target - self.z80.cycles) as u32);
+        }
+    }
+
+    /// Trata um evento cujo prazo foi atingido
+    fn handle_event(&mut self, event: ClockEvent) {
+        match event {
+            ClockEvent::Vint => self.bus.vdp.trigger_vint(),
+            // O TH da pistola já foi amostrado no fim da fatia
+            ClockEvent::LightGun => {}
+            ClockEvent::Cartridge(chip) => self.bus.cartridge_event(chip, self.clock.now()),
+        }
+    }
+
+    /// Transfere para a fila os prazos pedidos pelos chips do cartucho
+    fn sync_cartridge_deadlines(&mut self) {
+        for (chip, deadline) in self.bus.cart_deadlines.drain(..) {
+            match deadline {
+                Some(at) => self.clock.schedule(ClockEvent::Cartridge(chip), at),
+                None => self.clock.cancel(ClockEvent::Cartridge(chip)),
+            }
+        }
+    }
+
+    /// Desenha a linha ativa `line` na linha `row` do framebuffer
+    fn render_line(&mut self, line: usize, row: usize) {
+        let pitch = FRAMEBUFFER_WIDTH * self.renderer.format().bytes_per_pixel();
+        let out = &mut self.framebuffer[row * pitch..(row + 1) * pitch];
+        let (vdp, ram) = self.bus.vdp_ports();
+        self.width = self.renderer.render_line(vdp, ram, line as u16, out);
+    }
+
+    /// Soma ao buffer de síntese as variações de nível dos chips até o
+    /// clock mestre `mcycles`
+    fn update_audio(&mut self, mcycles: u64) {
+        self.bus.fm.sync(mcycles);
+        self.bus.psg.sync(mcycles);
+        if self.bus.fm_unit {
+            self.bus.ym2413.sync(mcycles);
+        }
+
+        let (mut time, fm) = self.bus.fm.drain();
+        for (left, right) in fm {
+            self.sound.add_delta(BLIP_FM, time, left - self.fm_last.0, right - self.fm_last.1);
+            self.fm_last = (left, right);
+            time += YM2612_DIVIDER;
+        }
+        let (mut time, opll) = self.bus.ym2413.drain();
+        for sample in opll {
+            let delta = sample - self.ym2413_last;
+            self.sound.add_delta(BLIP_FM, time, delta, delta);
+            self.ym2413_last = sample;
+            time += YM2413_DIVIDER;
+        }
+        for (time, left, right) in self.bus.psg.drain() {
+            self.sound.add_delta(BLIP_PSG, time, left, right);
+        }
+    }
+}
+
+impl Default for GenesisSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cpu::m68k::RunState;
+    use crate::core::input::{GunOffset, PadType, INPUT_A, INPUT_UP, INPUT_Z};
+    use crate::core::memory::SmsSlot;
+    use crate::utils::clock::CartridgeChip;
+
+    #[test]
+    fn test_frame_timing_ntsc_and_pal() {
+        let mut system = GenesisSystem::new();
+        system.power_on();
+
+        let frame = system.run_frame();
+        // 262 * 3420 / 53.693175 MHz * 44100 Hz ~= 735 amostras
+        assert!((734..=736).contains(&(frame.audio.len() / 2)));
+        // O VDP liga em modo 4 (192 linhas)
+        assert_eq!(frame.height, 192);
+        let target = 262 * MCYCLES_PER_LINE / M68K_DIVIDER;
+        assert!(system.m68k.cycles >= target && system.m68k.cycles < target + 200);
+        assert_eq!(system.z80.cycles, 262 * MCYCLES_PER_LINE / Z80_DIVIDER);
+
+        let mut system = GenesisSystem::new();
+        system.region = Region::Europe;
+        system.power_on();
+        let frame = system.run_frame();
+        // 313 * 3420 / 53.203424 MHz * 44100 Hz ~= 887 amostras
+        assert!((886..=888).contains(&(frame.audio.len() / 2)));
+        assert_eq!(system.z80.cycles, 313 * MCYCLES_PER_LINE / Z80_DIVIDER);
+    }
+
+    #[test]
+    fn test_z80_vblank_interrupt() {
+        let mut system = GenesisSystem::new();
+        system.power_on();
+
+        // IM 1 ; EI ; loop: JR loop
+        let code = [0xED, 0x56, 0xFB, 0x18, 0xFE];
+        system.bus.zram[..code.len()].copy_from_slice(&code);
+        // RST $38: INC A ; LD B,0 ; DJNZ $ (espera o fim da linha) ; EI ; RET
+        let handler = [0x3C, 0x06, 0x00, 0x10, 0xFE, 0xFB, 0xC9];
+        system.bus.zram[0x38..0x38 + handler.len()].copy_from_slice(&handler);
+        system.z80.sp = 0x2000;
+        system.bus.z80_reset = false;
+
+        system.run_frame();
+        assert_eq!(system.z80.a, 1);
+        system.run_frame();
+        assert_eq!(system.z80.a, 2);
+    }
+
+    #[test]
+    fn test_tmss_boot_rom_and_vdp_lockup() {
+        // MOVE.W #$8144,$C00004 ; BRA.S *
+        let vdp_write = [0x33, 0xFC, 0x81, 0x44, 0x00, 0xC0, 0x00, 0x04, 0x60, 0xFE];
+        let mut rom = vec![0u8; 0x400];
+        rom[0..8].copy_from_slice(&[0x00, 0xFF, 0xFE, 0x00, 0x00, 0x00, 0x02, 0x00]);
+        rom[0x112..0x11C].copy_from_slice(&vdp_write);
+        rom[0x200..0x20A].copy_from_slice(&vdp_write);
+
+        // Sem "SEGA" o acesso ao VDP trava o 68000
+        let mut system = GenesisSystem::new();
+        system.set_tmss(true);
+        system.load_rom(&rom).unwrap();
+        system.run_frame();
+        assert_eq!(system.m68k.run_state, RunState::Halted);
+        assert!(!system.bus.vdp.display_enabled());
+
+        // A ROM de boot (espelhada) escreve "SEGA" e passa para o
+        // cartucho, que continua no endereço seguinte
+        let mut boot = vec![0u8; 0x800];
+        boot[0..8].copy_from_slice(&[0x00, 0xFF, 0xFE, 0x00, 0x00, 0x00, 0x01, 0x00]);
+        boot[0x100..0x112].copy_from_slice(&[
+            0x23, 0xFC, 0x53, 0x45, 0x47, 0x41, 0x00, 0xA1, 0x40, 0x00, // MOVE.L #'SEGA',$A14000
+            0x13, 0xFC, 0x00, 0x01, 0x00, 0xA1, 0x41, 0x01, // MOVE.B #1,$A14101
+        ]);
+        system.set_bios(Console::MegaDrive, Some(&boot));
+        system.power_on();
+        assert_eq!(system.m68k.pc, 0x100);
+        assert_eq!(system.bus.read_byte(0x4902), 0x53);
+        system.run_frame();
+        assert_eq!(system.m68k.run_state, RunState::Running);
+        assert!(system.bus.vdp.display_enabled());
+        assert_eq!(system.bus.read_byte(0x102), 0x00);
+        assert_eq!(system.bus.read_byte(0xA14101) & 1, 1);
+    }
+
+    #[test]
+    fn test_sms_bios_enables_cartridge() {
+        // LD A,$42 ; LD ($C001),A ; HALT, logo após o código da BIOS
+        let mut rom = vec![0u8; 0x8000];
+        rom[4..10].copy_from_slice(&[0x3E, 0x42, 0x32, 0x01, 0xC0, 0x76]);
+
+        // Sem BIOS, o cartucho já começa ligado
+        let mut system = GenesisSystem::new();
+        system.console = Console::MasterSystem;
+        system.load_rom(&rom).unwrap();
+        assert_eq!(system.bus.zram[0], 0xA8);
+        assert_eq!(system.z80.sp, 0xDFF0);
+        assert_eq!(system.bus.z80_read(0x0004), 0x3E);
+
+        // LD A,$A8 ; OUT ($3E),A: a BIOS liga o cartucho e se desliga
+        system.set_bios(Console::MasterSystem, Some(&[0x3E, 0xA8, 0xD3, 0x3E]));
+        system.power_on();
+        let cart = SmsSlot::Cartridge as usize;
+        assert!(!system.bus.sms_slots[cart].as_ref().unwrap().enabled());
+        assert_eq!(system.bus.z80_read(0x0004), 0x3E);
+        assert_eq!(system.bus.z80_read(0x0005), 0xA8);
+
+        system.run_frame();
+        assert!(system.bus.sms_slots[cart].as_ref().unwrap().enabled());
+        assert_eq!(system.bus.memory_control, 0xA8);
+        assert_eq!(system.bus.zram[1], 0x42);
+    }
+
+    #[test]
+    fn test_kaiser_wave_yx5200() {
+        // The Secret Of The Four Winds: cabeçalho e checksums da placa
+        let mut rom = vec![0u8; 0x400];
+        rom[0..8].copy_from_slice(&[0x00, 0xFF, 0xFE, 0x00, 0x00, 0x00, 0x02, 0x00]);
+        rom[0x180..0x190].copy_from_slice(b"GM 00000000-00\x45\xC1");
+        rom[0x200..0x202].copy_from_slice(&[0x60, 0xFE]); // BRA.S *
+        let sum = rom[0x200..]
+            .chunks(2)
+            .fold(0u16, |sum, word| sum.wrapping_add(u16::from_be_bytes([word[0], word[1]])));
+        rom[0x3FE..].copy_from_slice(&0xC613u16.wrapping_sub(sum).to_be_bytes());
+
+        let mut system = GenesisSystem::new();
+        system.set_content_dir(std::env::temp_dir());
+        system.load_rom(&rom).unwrap();
+
+        // Volume 7 pela linha RX; as escritas em endereços ímpares não contam
+        for byte in [0x7E, 0xFF, 0x06, 0x06, 0x00, 0x00, 0x07, 0x00, 0x00, 0xEF] {
+            let bits = std::iter::once(0).chain((0..8).map(|bit| (byte >> bit) & 1)).chain([1]);
+            for bit in bits {
+                system.bus.write_byte(0xA13001, bit ^ 1);
+                system.bus.write_byte(0xA13000, bit);
+            }
+        }
+        assert_eq!(system.bus.yx5200.as_ref().unwrap().volume(), 7);
+
+        // O buffer do cartucho entra na mistura a cada quadro
+        let frame = system.run_frame();
+        assert!((734..=736).contains(&(frame.audio.len() / 2)));
+        system.reset();
+        assert_eq!(system.bus.yx5200.as_ref().unwrap().volume(), 30);
+    }
+
+    #[test]
+    fn test_pads_through_io_ports() {
+        let mut system = GenesisSystem::new();
+        system.set_port_device(Port::B, PortDevice::Pad(PadType::SixButton));
+        system.power_on();
+        system.input().pads[0] = INPUT_A | INPUT_UP;
+        system.input().pads[1] = INPUT_Z;
+
+        // TH como saída em nível baixo: ?0SA00DU
+        system.bus.write_byte(0xA10009, 0x40);
+        system.bus.write_byte(0xA10003, 0x00);
+        assert_eq!(system.bus.read_byte(0xA10003), 0x22);
+
+        // Jogador 2 no controle de 6 botões: a escrita no controle é a
+        // primeira descida; ?1CBMXYZ na quarta subida
+        system.bus.write_byte(0xA1000B, 0x40);
+        for th in [0x40, 0x00, 0x40, 0x00, 0x40] {
+            system.bus.write_byte(0xA10005, th);
+        }
+        assert_eq!(system.bus.read_byte(0xA10005), 0x7E);
+
+        // Porta desconectada com o jogo rodando
+        system.set_port_device(Port::A, PortDevice::None);
+        assert_eq!(system.bus.read_byte(0xA10003), 0x3F);
+    }
+
+    #[test]
+    fn test_light_phaser_latches_hcounter() {
+        // HALT: o Z80 só espera o quadro passar
+        let mut system = GenesisSystem::new();
+        system.console = Console::MasterSystem;
+        system.set_port_device(Port::A, PortDevice::LightPhaser(GunOffset::LIGHT_PHASER));
+        system.load_rom(&[0x76; 0x8000]).unwrap();
+        system.input().pads[0] = INPUT_A;
+        system.input().analog[0] = [100, 50];
+
+        // Gatilho no TL da porta A ($DC, D4 ativo em 0)
+        assert_eq!(system.bus.z80_in(0xDC), 0xEF);
+
+        // O sensor trava o contador H no pixel da mira mais a calibração
+        system.run_frame();
+        assert_eq!(system.bus.z80_in(0x7F), 50 + 20);
+    }
+    #[test]
+    fn test_cartridge_deadlines() {
+        let mut system = GenesisSystem::new();
+        system.power_on();
+        let svp = ClockEvent::Cartridge(CartridgeChip::Svp);
+        let megasd = ClockEvent::Cartridge(CartridgeChip::MegaSd);
+
+        // Prazos dentro e além da linha; o do MegaSD é cancelado antes de vencer
+        system.bus.schedule_cartridge(CartridgeChip::Svp, 1000);
+        system.bus.schedule_cartridge(CartridgeChip::Paprium, 5 * MCYCLES_PER_LINE);
+        system.bus.schedule_cartridge(CartridgeChip::MegaSd, 2000);
+        system.bus.cancel_cartridge(CartridgeChip::MegaSd);
+        system.run_line();
+        assert_eq!(system.clock.deadline(svp), None);
+        assert_eq!(system.clock.deadline(megasd), None);
+        assert_eq!(system.clock.next_deadline(), Some(5 * MCYCLES_PER_LINE));
+
+        // O prazo é atendido na fatia em que vence
+        system.line = 1;
+        system.run_line();
+        system.line = 2;
+        system.bus.schedule_cartridge(CartridgeChip::Svp, system.clock.now() + 10);
+        system.run_line();
+        assert_eq!(system.clock.deadline(svp), None);
+        assert_eq!(system.clock.now(), 3 * MCYCLES_PER_LINE);
+    }
+}
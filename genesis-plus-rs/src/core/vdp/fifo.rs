@@ -0,0 +1,343 @@
+//! FIFO de escrita do VDP e temporização de DMA.
+//! Baseado em `vdp_ctrl.c` (`vdp_68k_data_w_m5`, `vdp_dma_update` e
+//! `vdp_dma_*`) do Genesis Plus GX.
+//!
+//! Durante o display ativo o VDP só acessa a VRAM em slots fixos da linha.
+//! Cada escrita na porta de dados ocupa uma entrada da FIFO (4 entradas) até
+//! o slot em que é efetivamente aplicada; com a FIFO cheia o 68000 espera.
+//! O DMA segue a mesma banda: um número fixo de acessos por linha.
+
+use super::{read_vram_byte, write_vram_byte, VdpRam, STATUS_DMA, STATUS_VBLANK, VDP};
+use crate::utils::clock::MCYCLES_PER_LINE;
+
+/// Slots de acesso externo em H32 (clocks mestres desde o início da linha)
+const FIFO_TIMING_H32: [u64; 28] = [
+    230, 510, 810, 970, 1130, 1450, 1610, 1770, 2090, 2250, 2410, 2730, 2890, 3050, 3350, 3370,
+    MCYCLES_PER_LINE + 230, MCYCLES_PER_LINE + 510, MCYCLES_PER_LINE + 810, MCYCLES_PER_LINE + 970,
+    MCYCLES_PER_LINE + 1130, MCYCLES_PER_LINE + 1450, MCYCLES_PER_LINE + 1610, MCYCLES_PER_LINE + 1770,
+    MCYCLES_PER_LINE + 2090, MCYCLES_PER_LINE + 2250, MCYCLES_PER_LINE + 2410, MCYCLES_PER_LINE + 2730,
+];
+
+/// Slots de acesso externo em H40
+const FIFO_TIMING_H40: [u64; 30] = [
+    352, 820, 948, 1076, 1332, 1460, 1588, 1844, 1972, 2100, 2356, 2484, 2612, 2868, 2996, 3124, 3364, 3380,
+    MCYCLES_PER_LINE + 352, MCYCLES_PER_LINE + 820, MCYCLES_PER_LINE + 948, MCYCLES_PER_LINE + 1076,
+    MCYCLES_PER_LINE + 1332, MCYCLES_PER_LINE + 1460, MCYCLES_PER_LINE + 1588, MCYCLES_PER_LINE + 1844,
+    MCYCLES_PER_LINE + 1972, MCYCLES_PER_LINE + 2100, MCYCLES_PER_LINE + 2356, MCYCLES_PER_LINE + 2484,
+];
+
+/// Bytes transferidos por linha: [display ativo, blanking][H32, H40]
+///
+/// Para 68k > CRAM/VSRAM a contagem é em palavras; 68k > VRAM e cópia
+/// gastam dois acessos por unidade.
+const DMA_TIMING: [[u64; 2]; 2] = [[16, 18], [166, 204]];
+
+/// Tipo de DMA em andamento
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmaType {
+    /// 68000 > CRAM ou VSRAM
+    M68kToCram,
+    /// 68000 > VRAM
+    M68kToVram,
+    /// Preenchimento de VRAM/CRAM/VSRAM
+    Fill,
+    /// Cópia VRAM > VRAM
+    Copy,
+}
+
+impl DmaType {
+    /// O DMA lê o barramento do 68000 (que fica parado até o fim)
+    pub fn uses_68k_bus(self) -> bool {
+        matches!(self, DmaType::M68kToCram | DmaType::M68kToVram)
+    }
+}
+
+/// FIFO de escrita de 4 entradas
+#[derive(Debug, Clone)]
+pub struct Fifo {
+    entries: [u16; 4],
+    /// Próxima entrada a ser escrita (também a mais antiga)
+    idx: usize,
+    /// Clock mestre em que cada entrada é aplicada
+    cycles: [u64; 4],
+    /// Destino VRAM: cada palavra ocupa dois slots de byte
+    pub byte_access: bool,
+}
+
+impl Fifo {
+    pub fn new() -> Self {
+        Self {
+            entries: [0; 4],
+            idx: 0,
+            cycles: [0; 4],
+            byte_access: false,
+        }
+    }
+
+    /// Grava uma palavra na próxima entrada
+    pub fn push(&mut self, data: u16) {
+        self.entries[self.idx] = data;
+        self.idx = (self.idx + 1) & 3;
+    }
+
+    /// Última palavra escrita
+    pub fn last(&self) -> u16 {
+        self.entries[(self.idx + 3) & 3]
+    }
+
+    /// Próxima entrada disponível (a mais antiga)
+    pub fn next(&self) -> u16 {
+        self.entries[self.idx]
+    }
+
+    /// Todas as entradas já foram aplicadas em `mcycles`
+    pub fn is_empty(&self, mcycles: u64) -> bool {
+        mcycles >= self.cycles[(self.idx + 3) & 3]
+    }
+
+    /// A entrada mais antiga ainda não foi aplicada em `mcycles`
+    pub fn is_full(&self, mcycles: u64) -> bool {
+        mcycles < self.cycles[self.idx]
+    }
+
+    /// Reserva o slot de acesso da próxima escrita feita em `mcycles`
+    /// durante o display ativo. Retorna até quando a CPU fica parada
+    /// esperando uma entrada livre (0 se não houver espera).
+    pub fn schedule(&mut self, mcycles: u64, line_start: u64, h40: bool) -> u64 {
+        let mut stall = 0;
+        let mut cycles = mcycles;
+
+        let last = self.cycles[(self.idx + 3) & 3];
+        if cycles < last {
+            // FIFO cheia: espera a entrada mais antiga ser aplicada
+            // (Chaos Engine / Soldiers of Fortune, Double Clutch, Titan Overdrive)
+            if cycles < self.cycles[self.idx] {
+                stall = self.cycles[self.idx].div_ceil(7) * 7;
+            }
+            // A nova entrada só é processada depois da última
+            cycles = last;
+        }
+
+        let timing: &[u64] = if h40 { &FIFO_TIMING_H40 } else { &FIFO_TIMING_H32 };
+        let offset = cycles.saturating_sub(line_start);
+        let slot = timing.iter().position(|&t| offset < t).unwrap_or(timing.len() - 1);
+        let slot = (slot + self.byte_access as usize).min(timing.len() - 1);
+        self.cycles[self.idx] = line_start + timing[slot];
+
+        stall
+    }
+}
+
+impl Default for Fifo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VDP {
+    /// Inicia ou continua o DMA em `mcycles`, processando os bytes que cabem
+    /// até o fim da linha (ou do VBLANK). Retorna quantas unidades devem ser
+    /// transferidas agora; o fim desta fatia fica em `dma_end`.
+    pub fn dma_begin(&mut self, mut mcycles: u64) -> u32 {
+        self.clock = mcycles;
+        let blank = self.status & STATUS_VBLANK != 0 || !self.display_enabled();
+        let mut rate = DMA_TIMING[blank as usize][self.h40() as usize];
+
+        match self.dma_type {
+            // Um acesso por byte: palavras para a VRAM e cópia gastam dois
+            DmaType::M68kToVram | DmaType::Copy => rate >>= 1,
+            // Com o display desligado um slot extra é perdido a cada refresh
+            DmaType::M68kToCram => {
+                if rate == 166 {
+                    rate = 161;
+                } else if rate == 204 {
+                    rate = 198;
+                }
+            }
+            // O preenchimento começa depois da escrita inicial na porta de dados
+            DmaType::Fill => mcycles += 2 * (MCYCLES_PER_LINE / rate),
+        }
+
+        let limit = if self.status & STATUS_VBLANK != 0 {
+            // Até o fim do VBLANK: a largura da tela não muda durante o DMA
+            let lines_left = (self.lines_per_frame - 1).saturating_sub(self.line) as u64;
+            self.line_start + lines_left * MCYCLES_PER_LINE
+        } else {
+            self.line_start + MCYCLES_PER_LINE
+        };
+        let mut dma_cycles = limit.saturating_sub(mcycles);
+        let mut bytes = dma_cycles * rate / MCYCLES_PER_LINE;
+
+        if (self.dma_length as u64) < bytes {
+            bytes = self.dma_length as u64;
+            dma_cycles = bytes * MCYCLES_PER_LINE / rate;
+        }
+        self.dma_end = mcycles + dma_cycles;
+
+        // O flag de DMA só é visível quando o 68000 pode lê-lo
+        if !self.dma_type.uses_68k_bus() {
+            self.status |= STATUS_DMA;
+        }
+
+        self.dma_length -= bytes as u32;
+        bytes as u32
+    }
+
+    /// Conclui uma fatia de DMA. Com o DMA terminado atualiza os registradores
+    /// de origem e tamanho e devolve a palavra de controle adiada, se houver.
+    pub fn dma_finish(&mut self) -> Option<u16> {
+        if self.dma_length > 0 {
+            return None;
+        }
+
+        // A origem é incrementada mesmo no preenchimento
+        let src = u16::from_le_bytes([self.reg[21], self.reg[22]]);
+        let len = u16::from_le_bytes([self.reg[19], self.reg[20]]);
+        let end = src.wrapping_add(len);
+        self.reg[21] = end as u8;
+        self.reg[22] = (end >> 8) as u8;
+
+        // O contador de tamanho é decrementado até zero
+        self.reg[19] = 0;
+        self.reg[20] = 0;
+
+        self.cached_write.take()
+    }
+
+    /// Endereço de origem no barramento do 68000
+    pub fn dma_source(&self) -> u32 {
+        ((self.reg[23] as u32 & 0x7F) << 17) | ((self.dma_src as u32) << 1)
+    }
+
+    /// Avança a origem no barramento do 68000 dentro da janela de 128KB
+    pub fn dma_next_source(&self, source: u32) -> u32 {
+        ((self.reg[23] as u32 & 0x7F) << 17) | (source.wrapping_add(2) & 0x1FFFF)
+    }
+
+    /// Atualiza a origem após uma fatia de DMA do 68000
+    pub fn set_dma_source(&mut self, source: u32) {
+        self.dma_src = (source >> 1) as u16;
+    }
+
+    /// Preenchimento: repete o último dado escrito na porta de dados
+    pub fn dma_fill(&mut self, mut ram: VdpRam<'_>, length: u32) {
+        match self.code & 0x0F {
+            0x01 => {
+                // Só o byte alto é usado, escrito no endereço adjacente
+                let data = (self.fifo.last() >> 8) as u8;
+                for _ in 0..length {
+                    write_vram_byte(ram.vram, self.addr ^ 1, data);
+                    self.addr = self.addr.wrapping_add(self.reg[15] as u16);
+                }
+            }
+            0x03 => {
+                // CRAM e VSRAM usam a próxima entrada da FIFO
+                let data = self.fifo.next();
+                let color = ((data & 0xE00) >> 3) | ((data & 0x0E0) >> 2) | ((data & 0x00E) >> 1);
+                for _ in 0..length {
+                    self.write_cram(&mut ram, ((self.addr >> 1) & 0x3F) as usize, color);
+                    self.addr = self.addr.wrapping_add(self.reg[15] as u16);
+                }
+            }
+            0x05 => {
+                let data = self.fifo.next();
+                for _ in 0..length {
+                    if let Some(word) = ram.vsram.get_mut(((self.addr >> 1) & 0x3F) as usize) {
+                        *word = data & 0x7FF;
+                    }
+                    self.addr = self.addr.wrapping_add(self.reg[15] as u16);
+                }
+            }
+            _ => {
+                // Destino inválido não escreve nada, mas o endereço avança
+                // (Williams Greatest Hits após soft reset)
+                let step = (self.reg[15] as u32 * length) as u16;
+                self.addr = self.addr.wrapping_add(step);
+            }
+        }
+    }
+
+    /// Cópia VRAM > VRAM, byte a byte entre endereços adjacentes
+    pub fn dma_copy(&mut self, ram: VdpRam<'_>, length: u32) {
+        // Sem CD4 o VDP trava (só um reset resolve)
+        if self.code & 0x10 == 0 {
+            return;
+        }
+        let mut source = self.dma_src;
+        for _ in 0..length {
+            let data = read_vram_byte(ram.vram, source ^ 1);
+            write_vram_byte(ram.vram, self.addr ^ 1, data);
+            source = source.wrapping_add(1);
+            self.addr = self.addr.wrapping_add(self.reg[15] as u16);
+        }
+        self.dma_src = source;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::MemoryBus;
+
+    fn write_regs(bus: &mut MemoryBus, words: &[u16]) {
+        for &w in words {
+            bus.write_word(0xC00004, w);
+        }
+    }
+
+    #[test]
+    fn test_dma_fill_byte_order() {
+        let mut bus = MemoryBus::new();
+        bus.vdp_start_line(0, 0);
+        // Display desligado, DMA habilitado, incremento 1, 4 bytes, preenchimento
+        write_regs(&mut bus, &[0x8114, 0x8F01, 0x9304, 0x9400, 0x9780]);
+        write_regs(&mut bus, &[0x4000, 0x0080]);
+        assert_ne!(bus.read_word(0xC00004) & STATUS_DMA, 0);
+
+        // A escrita inicial é normal; depois só o byte alto é repetido,
+        // sempre no endereço adjacente
+        bus.write_word(0xC00000, 0x1234);
+        assert_eq!(&bus.vram[..3], &[0x1234, 0x1212, 0x0012]);
+        assert_eq!(bus.vdp.dma_length(), 0);
+    }
+
+    #[test]
+    fn test_dma_68k_bandwidth_and_stall() {
+        let mut bus = MemoryBus::new();
+        bus.vdp_start_line(0, 0);
+        for (i, byte) in bus.wram.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+
+        // H40, display ligado: 9 palavras por linha para a VRAM
+        write_regs(&mut bus, &[0x8154, 0x8C81, 0x8F02]);
+        // 100 palavras de $FF0000
+        write_regs(&mut bus, &[0x9364, 0x9400, 0x9500, 0x9680, 0x977F]);
+        write_regs(&mut bus, &[0x4000, 0x0080]);
+
+        assert_eq!(bus.vdp.dma_length(), 91);
+        assert_eq!(bus.m68k_wait, MCYCLES_PER_LINE.div_ceil(7) as u32);
+        assert_eq!(bus.vram[8], 0x1011);
+        assert_eq!(bus.vram[9], 0);
+
+        // A linha seguinte continua de onde parou
+        bus.vdp_start_line(1, MCYCLES_PER_LINE);
+        assert_eq!(bus.vdp.dma_length(), 82);
+        assert_eq!(bus.vram[9], 0x1213);
+    }
+
+    #[test]
+    fn test_fifo_full_stall() {
+        let mut fifo = Fifo::new();
+        fifo.byte_access = true;
+        // Quatro escritas seguidas no início da linha ocupam a FIFO
+        for _ in 0..4 {
+            assert_eq!(fifo.schedule(0, 0, false), 0);
+            fifo.push(0);
+        }
+        assert!(fifo.is_full(0));
+        // A quinta espera a entrada mais antiga (slot 510 em H32)
+        assert_eq!(fifo.schedule(0, 0, false), 511);
+        assert!(fifo.is_empty(MCYCLES_PER_LINE * 2));
+    }
+}
@@ -0,0 +1,86 @@
+//! Contadores H e V do VDP.
+//! Baseado em `hvc.h` e `vdp_hvc_r` do Genesis Plus GX.
+//!
+//! O contador H avança a cada dois pixels e salta no meio do HBLANK para
+//! caber em 8 bits. O contador V salta durante o VBLANK num ponto que
+//! depende da altura da tela e do padrão de vídeo (NTSC ou PAL).
+
+use crate::utils::clock::MCYCLES_PER_LINE;
+
+/// Contador H no início da linha, último valor antes do salto, primeiro
+/// valor depois do salto e número de valores por linha
+const H32_COUNTER: (u16, u16, u16, u64) = (0x85, 0x93, 0xE9, 171);
+const H40_COUNTER: (u16, u16, u16, u64) = (0xA5, 0xB6, 0xE4, 211);
+
+/// Contador H após `cycles` clocks mestres desde o início da linha
+pub fn hcounter(h40: bool, cycles: u64) -> u8 {
+    let (start, last, next, steps) = if h40 { H40_COUNTER } else { H32_COUNTER };
+    let step = (cycles.min(MCYCLES_PER_LINE - 1) * steps / MCYCLES_PER_LINE) as u16;
+    let mut value = start + step;
+    if value > last {
+        value += next - last - 1;
+    }
+    value as u8
+}
+
+/// Clocks mestres desde o início da linha até o contador H avançar `steps`
+/// valores a partir de 0 (o primeiro pixel da linha ativa), passando pelo
+/// salto e voltando ao início da linha se preciso
+pub fn hcounter_mcycles(h40: bool, steps: u64) -> u64 {
+    let (start, last, next, per_line) = if h40 { H40_COUNTER } else { H32_COUNTER };
+    let zero = u64::from(last - start + 1 + 0x100 - next);
+    let step = (zero + steps) % per_line;
+    (step * MCYCLES_PER_LINE).div_ceil(per_line)
+}
+
+/// Contador V (9 bits) da linha `line` do quadro
+pub fn vcounter(line: u16, active_lines: u16, lines_per_frame: u16) -> u16 {
+    let pal = lines_per_frame > 262;
+    // Último valor antes do salto; 240 linhas em NTSC não saltam
+    let last = match (active_lines, pal) {
+        (192, false) => 0xDA,
+        (192, true) => 0xF2,
+        (224, false) => 0xEA,
+        (224, true) => 0x102,
+        (_, false) => return line & 0x1FF,
+        (_, true) => 0x10A,
+    };
+    if line <= last {
+        line
+    } else {
+        // Os valores restantes terminam em $1FF na última linha
+        line + 0x200 - lines_per_frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_jumps() {
+        // H32: $85-$93, $E9-$FF, $00-$84
+        assert_eq!(hcounter(false, 0), 0x85);
+        assert_eq!(hcounter(false, 14 * 20), 0x93);
+        assert_eq!(hcounter(false, 15 * 20), 0xE9);
+        assert_eq!(hcounter(false, MCYCLES_PER_LINE - 1), 0x84);
+        // H40: $A5-$B6, $E4-$FF, $00-$A4
+        assert_eq!(hcounter(true, 0), 0xA5);
+        assert_eq!(hcounter(true, 18 * MCYCLES_PER_LINE / 211 + 1), 0xE4);
+        assert_eq!(hcounter(true, MCYCLES_PER_LINE - 1), 0xA4);
+        // Inverso: passos a partir do contador 0
+        assert_eq!(hcounter(false, hcounter_mcycles(false, 0x93)), 0x93);
+        assert_eq!(hcounter(false, hcounter_mcycles(false, 0x94)), 0xE9);
+        assert_eq!(hcounter(true, hcounter_mcycles(true, 0x10)), 0x10);
+
+        // NTSC V28: $00-$EA, $1E5-$1FF
+        assert_eq!(vcounter(0xEA, 224, 262), 0xEA);
+        assert_eq!(vcounter(0xEB, 224, 262), 0x1E5);
+        assert_eq!(vcounter(261, 224, 262), 0x1FF);
+        // PAL V30: $00-$10A, $1D2-$1FF; NTSC V30 conta sem saltar
+        assert_eq!(vcounter(0x10B, 240, 313), 0x1D2);
+        assert_eq!(vcounter(0x105, 240, 262), 0x105);
+        // Master System PAL 192 linhas: $00-$F2, $1BA-$1FF
+        assert_eq!(vcounter(0xF3, 192, 313), 0x1BA);
+    }
+}
@@ -0,0 +1,446 @@
+//! Renderizador dos modos herdados: modo 4 (Master System e Game Gear) e
+//! modos do TMS9918 (SG-1000, SC-3000 e Master System em compatibilidade).
+//! Baseado em `vdp_render.c` (`render_bg_m4`, `render_bg_m0`-`m3`,
+//! `render_obj_m4` e `render_obj_tms`) do Genesis Plus GX.
+//!
+//! As linhas têm sempre 256 pixels e são montadas como índices de cor: no
+//! modo 4 as 32 entradas da CRAM (sprites na segunda paleta), nos modos TMS
+//! as 16 cores fixas do TMS9918.
+
+use super::renderer::MAX_WIDTH;
+use super::{read_vram_byte, VideoMode, VDP, STATUS_SPRITE_COLLISION, STATUS_SPRITE_OVERFLOW};
+
+/// Largura da linha nos modos herdados
+pub const LEGACY_WIDTH: usize = 256;
+
+/// Cores fixas do TMS9918 (a cor 0 é transparente e mostra o fundo)
+pub const TMS_PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0x00, 0x00, 0x00),
+    (0x21, 0xC8, 0x42),
+    (0x5E, 0xDC, 0x78),
+    (0x54, 0x55, 0xED),
+    (0x7D, 0x76, 0xFC),
+    (0xD4, 0x52, 0x4D),
+    (0x42, 0xEB, 0xF5),
+    (0xFC, 0x55, 0x54),
+    (0xFF, 0x79, 0x78),
+    (0xD4, 0xC1, 0x54),
+    (0xE6, 0xCE, 0x80),
+    (0x21, 0xB0, 0x3B),
+    (0xC9, 0x5B, 0xBA),
+    (0xCC, 0xCC, 0xCC),
+    (0xFF, 0xFF, 0xFF),
+];
+
+/// Bit de prioridade do plano sobre os sprites no buffer do modo 4
+const PRIORITY: u8 = 0x80;
+
+/// Linha do modo 4 em índices de CRAM (0-31)
+pub(super) fn render_mode4(vdp: &mut VDP, vram: &[u16; 65536], line: u16, pixels: &mut [u8; MAX_WIDTH]) {
+    let reg = vdp.reg;
+    let backdrop = 0x10 | (reg[7] & 0x0F);
+    if !vdp.display_enabled() {
+        pixels[..LEGACY_WIDTH].fill(backdrop);
+        return;
+    }
+
+    // Nos modos de 224 e 240 linhas a tabela de nomes tem 32 linhas de células
+    let extended = vdp.active_lines() > 192;
+    let (name_base, plane_h) = if extended {
+        (((reg[2] as u16 & 0x0C) << 10) | 0x0700, 256)
+    } else {
+        ((reg[2] as u16 & 0x0E) << 10, 224)
+    };
+
+    // Trava da rolagem horizontal nas duas primeiras linhas de células e da
+    // vertical nas oito últimas colunas
+    let hscroll = if reg[0] & 0x40 != 0 && line < 16 { 0 } else { reg[8] as u16 };
+    let scrolled = (line + reg[9] as u16) % plane_h;
+
+    let mut plane = [0u8; LEGACY_WIDTH];
+    for (x, pixel) in plane.iter_mut().enumerate() {
+        let x = x as u16;
+        let y = if reg[0] & 0x80 != 0 && x >= 192 { line } else { scrolled };
+        let px = x.wrapping_sub(hscroll) & 0xFF;
+        let addr = name_base + ((y >> 3) * 32 + (px >> 3)) * 2;
+        let entry = u16::from_le_bytes([
+            read_vram_byte(vram, addr & 0x3FFF),
+            read_vram_byte(vram, (addr + 1) & 0x3FFF),
+        ]);
+
+        let tx = if entry & 0x0200 != 0 { 7 - (px & 7) } else { px & 7 };
+        let ty = if entry & 0x0400 != 0 { 7 - (y & 7) } else { y & 7 };
+        let color = mode4_pattern_pixel(vram, entry & 0x1FF, tx, ty);
+        let palette = ((entry >> 7) & 0x10) as u8;
+        // A prioridade só vale para os pixels opacos do plano
+        let priority = if entry & 0x1000 != 0 && color != 0 { PRIORITY } else { 0 };
+        *pixel = priority | palette | color;
+    }
+
+    let sprites = render_mode4_sprites(vdp, vram, line, extended);
+
+    let blank_left = reg[0] & 0x20 != 0;
+    for x in 0..LEGACY_WIDTH {
+        pixels[x] = if blank_left && x < 8 {
+            backdrop
+        } else if sprites[x] != 0 && plane[x] & PRIORITY == 0 {
+            sprites[x]
+        } else {
+            plane[x] & 0x1F
+        };
+    }
+}
+
+/// Sprites do modo 4: 8 por linha, sempre na segunda paleta
+fn render_mode4_sprites(vdp: &mut VDP, vram: &[u16; 65536], line: u16, extended: bool) -> [u8; LEGACY_WIDTH] {
+    let reg = vdp.reg;
+    let mut sprites = [0u8; LEGACY_WIDTH];
+
+    let sat = (reg[5] as u16 & 0x7E) << 7;
+    let tall = reg[1] & 0x02 != 0;
+    let zoom = (reg[1] & 0x01) as u16;
+    let height = (if tall { 16 } else { 8 }) << zoom;
+    let wrap = vdp.active_lines() as i32 + 16;
+
+    let mut count = 0;
+    let mut overflow = false;
+    let mut collision = false;
+    for i in 0..64u16 {
+        let y = read_vram_byte(vram, sat + i);
+        // Y = $D0 encerra a lista, exceto nos modos estendidos
+        if y == 0xD0 && !extended {
+            break;
+        }
+        let mut top = y as i32 + 1;
+        if top > wrap {
+            top -= 256;
+        }
+        let row = line as i32 - top;
+        if row < 0 || row >= height {
+            continue;
+        }
+        if count == 8 {
+            overflow = true;
+            break;
+        }
+        count += 1;
+
+        let mut x = read_vram_byte(vram, sat + 0x80 + i * 2) as i32;
+        if reg[0] & 0x08 != 0 {
+            x -= 8;
+        }
+        let mut name = read_vram_byte(vram, sat + 0x81 + i * 2) as u16;
+        if reg[6] & 0x04 != 0 {
+            name |= 0x100;
+        }
+        if tall {
+            name &= !1;
+        }
+
+        let row = (row as u16) >> zoom;
+        let tile = name + (row >> 3);
+        for px in 0..(8i32 << zoom) {
+            let sx = x + px;
+            if !(0..LEGACY_WIDTH as i32).contains(&sx) {
+                continue;
+            }
+            let color = mode4_pattern_pixel(vram, tile, (px >> zoom) as u16, row & 7);
+            if color == 0 {
+                continue;
+            }
+            let slot = &mut sprites[sx as usize];
+            if *slot != 0 {
+                collision = true;
+            } else {
+                *slot = 0x10 | color;
+            }
+        }
+    }
+
+    if overflow {
+        vdp.status |= STATUS_SPRITE_OVERFLOW;
+    }
+    if collision {
+        vdp.status |= STATUS_SPRITE_COLLISION;
+    }
+    sprites
+}
+
+/// Cor (0-15) do pixel (x, y) de um padrão de 4 planos de bits
+fn mode4_pattern_pixel(vram: &[u16; 65536], tile: u16, x: u16, y: u16) -> u8 {
+    let addr = (tile << 5) + (y << 2);
+    (0..4).fold(0, |color, plane| {
+        let bits = read_vram_byte(vram, (addr + plane) & 0x3FFF);
+        color | (((bits >> (7 - x)) & 1) << plane)
+    })
+}
+
+/// Linha de um modo TMS9918 em índices da paleta fixa (0-15)
+pub(super) fn render_tms(vdp: &mut VDP, vram: &[u16; 65536], line: u16, mode: VideoMode, pixels: &mut [u8; MAX_WIDTH]) {
+    let reg = vdp.reg;
+    let backdrop = reg[7] & 0x0F;
+    pixels[..LEGACY_WIDTH].fill(backdrop);
+    if !vdp.display_enabled() {
+        return;
+    }
+
+    let rb = |addr: u16| read_vram_byte(vram, addr & 0x3FFF);
+    let name_base = (reg[2] as u16 & 0x0F) << 10;
+    let pattern_base = (reg[4] as u16 & 0x07) << 11;
+    let row = line >> 3;
+    let opaque = |color: u8| if color == 0 { backdrop } else { color };
+
+    match mode {
+        VideoMode::Text => {
+            // 40 colunas de 6 pixels entre bordas de 8 pixels
+            let (fg, bg) = (opaque(reg[7] >> 4), backdrop);
+            for col in 0..40u16 {
+                let name = rb(name_base + row * 40 + col) as u16;
+                let pattern = rb(pattern_base + name * 8 + (line & 7));
+                for px in 0..6 {
+                    let color = if pattern & (0x80 >> px) != 0 { fg } else { bg };
+                    pixels[8 + col as usize * 6 + px] = color;
+                }
+            }
+            // O modo texto não tem sprites
+            return;
+        }
+        VideoMode::Multicolor => {
+            for col in 0..32u16 {
+                let name = rb(name_base + row * 32 + col) as u16;
+                let colors = rb(pattern_base + name * 8 + (row & 3) * 2 + ((line >> 2) & 1));
+                let x = col as usize * 8;
+                pixels[x..x + 4].fill(opaque(colors >> 4));
+                pixels[x + 4..x + 8].fill(opaque(colors & 0x0F));
+            }
+        }
+        _ => {
+            for col in 0..32u16 {
+                let name = rb(name_base + row * 32 + col) as u16;
+                let (pattern, colors) = if mode == VideoMode::Graphics2 {
+                    // Um terço da tela para cada bloco de 256 padrões; os bits
+                    // baixos de #3 e #4 mascaram o número do padrão
+                    let name = name | ((row & 0x18) << 5);
+                    let pattern_base = (reg[4] as u16 & 0x04) << 11;
+                    let pattern_mask = ((reg[4] as u16 & 0x03) << 8) | 0xFF;
+                    let color_base = (reg[3] as u16 & 0x80) << 6;
+                    let color_mask = ((reg[3] as u16 & 0x7F) << 3) | 0x07;
+                    (
+                        rb(pattern_base + ((name & pattern_mask) << 3) + (line & 7)),
+                        rb(color_base + ((name & color_mask) << 3) + (line & 7)),
+                    )
+                } else {
+                    (rb(pattern_base + name * 8 + (line & 7)), rb(((reg[3] as u16) << 6) + (name >> 3)))
+                };
+                for px in 0..8 {
+                    let color = if pattern & (0x80 >> px) != 0 { colors >> 4 } else { colors & 0x0F };
+                    pixels[col as usize * 8 + px] = opaque(color);
+                }
+            }
+        }
+    }
+
+    render_tms_sprites(vdp, vram, line, pixels);
+}
+
+/// Sprites do TMS9918: 32 na tabela, 4 por linha, uma cor por sprite
+fn render_tms_sprites(vdp: &mut VDP, vram: &[u16; 65536], line: u16, pixels: &mut [u8; MAX_WIDTH]) {
+    let reg = vdp.reg;
+    let rb = |addr: u16| read_vram_byte(vram, addr & 0x3FFF);
+
+    let sat = (reg[5] as u16 & 0x7F) << 7;
+    let pattern_base = (reg[6] as u16 & 0x07) << 11;
+    let size = if reg[1] & 0x02 != 0 { 16 } else { 8 };
+    let zoom = (reg[1] & 0x01) as i32;
+
+    let mut drawn = [false; LEGACY_WIDTH];
+    let mut count = 0;
+    let mut overflow = false;
+    let mut collision = false;
+    let mut number = 0;
+    for i in 0..32u16 {
+        number = i as u8;
+        let y = rb(sat + i * 4);
+        if y == 0xD0 {
+            break;
+        }
+        let mut top = y as i32 + 1;
+        if top > 0xE0 {
+            top -= 256;
+        }
+        let row = line as i32 - top;
+        if row < 0 || row >= size << zoom {
+            continue;
+        }
+        if count == 4 {
+            overflow = true;
+            break;
+        }
+        count += 1;
+
+        let mut x = rb(sat + i * 4 + 1) as i32;
+        let mut name = rb(sat + i * 4 + 2) as u16;
+        let attr = rb(sat + i * 4 + 3);
+        // Early clock desloca o sprite 32 pixels para a esquerda
+        if attr & 0x80 != 0 {
+            x -= 32;
+        }
+        if size == 16 {
+            name &= 0xFC;
+        }
+
+        let row = (row >> zoom) as u16;
+        let color = attr & 0x0F;
+        for px in 0..(size << zoom) {
+            let sx = x + px;
+            if !(0..LEGACY_WIDTH as i32).contains(&sx) {
+                continue;
+            }
+            // Sprites 16x16: a metade direita fica 16 bytes adiante
+            let cx = (px >> zoom) as u16;
+            let bits = rb(pattern_base + name * 8 + row + if cx >= 8 { 16 } else { 0 });
+            if bits & (0x80 >> (cx & 7)) == 0 {
+                continue;
+            }
+            let sx = sx as usize;
+            if drawn[sx] {
+                collision = true;
+            } else {
+                drawn[sx] = true;
+                if color != 0 {
+                    pixels[sx] = color;
+                }
+            }
+        }
+    }
+
+    vdp.sprite_number = number;
+    if overflow {
+        vdp.status |= STATUS_SPRITE_OVERFLOW;
+    }
+    if collision {
+        vdp.status |= STATUS_SPRITE_COLLISION;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::vdp::renderer::{PixelFormat, Renderer};
+    use crate::core::vdp::{VdpModel, VdpRam};
+
+    struct Memories {
+        vram: Box<[u16; 65536]>,
+        cram: [u16; 64],
+        vsram: [u16; 40],
+    }
+
+    impl Memories {
+        fn new() -> Self {
+            Self { vram: Box::new([0; 65536]), cram: [0; 64], vsram: [0; 40] }
+        }
+
+        fn ram(&mut self) -> VdpRam<'_> {
+            VdpRam { vram: &mut self.vram, cram: &mut self.cram, vsram: &mut self.vsram }
+        }
+    }
+
+    fn vdp_with(model: VdpModel, mem: &mut Memories, regs: &[(u8, u8)]) -> VDP {
+        let mut vdp = VDP::new();
+        vdp.model = model;
+        vdp.reset(false);
+        for &(r, d) in regs {
+            vdp.z80_write_control(mem.ram(), d);
+            vdp.z80_write_control(mem.ram(), 0x80 | r);
+        }
+        vdp
+    }
+
+    /// Escreve `data` a partir de `addr` (código 1 = VRAM, 3 = CRAM)
+    fn write(vdp: &mut VDP, mem: &mut Memories, code: u8, addr: u16, data: &[u8]) {
+        vdp.z80_write_control(mem.ram(), addr as u8);
+        vdp.z80_write_control(mem.ram(), (code << 6) | (addr >> 8) as u8);
+        for &byte in data {
+            vdp.z80_write_data(mem.ram(), byte);
+        }
+    }
+
+    fn render(vdp: &mut VDP, mem: &mut Memories, line: u16) -> Vec<u32> {
+        let mut renderer = Renderer::new(PixelFormat::Xrgb8888);
+        let mut out = vec![0u8; MAX_WIDTH * 4];
+        let width = renderer.render_line(vdp, mem.ram(), line, &mut out);
+        out.chunks(4).take(width).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+    }
+
+    #[test]
+    fn test_mode4_game_gear_viewport_and_sprites() {
+        let mut mem = Memories::new();
+        // Modo 4, display ligado, tabela de nomes em $3800, SAT em $3F00
+        let mut vdp = vdp_with(VdpModel::GameGear, &mut mem, &[(0, 0x04), (1, 0x40), (2, 0xFF), (5, 0xFF), (6, 0xFB)]);
+        assert_eq!(vdp.video_mode(), VideoMode::Mode4);
+
+        // Cores de 12 bits: plano cor 1 = vermelho, sprite cor 2 = azul
+        write(&mut vdp, &mut mem, 3, 0x02, &[0x0F, 0x00]);
+        write(&mut vdp, &mut mem, 3, 0x24, &[0x00, 0x0F]);
+        assert_eq!(mem.cram[1], 0x00F);
+
+        // Padrão 1 sólido na cor 1, padrão 2 sólido na cor 2 (plano 1)
+        write(&mut vdp, &mut mem, 1, 0x20, &[0xFF, 0x00, 0x00, 0x00].repeat(8));
+        write(&mut vdp, &mut mem, 1, 0x40, &[0x00, 0xFF, 0x00, 0x00].repeat(8));
+        // Célula (6, 3): primeira coluna visível da linha 24 do Game Gear
+        write(&mut vdp, &mut mem, 1, 0x3800 + (3 * 32 + 6) * 2, &[0x01, 0x00]);
+        // Sprite 0 em (64, 24) com o padrão 2, lista encerrada pelo sprite 1
+        write(&mut vdp, &mut mem, 1, 0x3F00, &[23, 0xD0]);
+        write(&mut vdp, &mut mem, 1, 0x3F80, &[64, 2]);
+
+        let viewport = vdp.viewport();
+        assert_eq!((viewport.x, viewport.y, viewport.width, viewport.height), (48, 24, 160, 144));
+
+        let pixels = render(&mut vdp, &mut mem, 24);
+        assert_eq!(pixels.len(), 160);
+        let red = PixelFormat::Xrgb8888.encode(255, 0, 0);
+        let blue = PixelFormat::Xrgb8888.encode(0, 0, 255);
+        assert_eq!(pixels[0], red);
+        assert_eq!(pixels[8], 0);
+        assert_eq!(pixels[16], blue);
+
+        // Prioridade do plano sobre o sprite
+        write(&mut vdp, &mut mem, 1, 0x3800 + (3 * 32 + 8) * 2, &[0x01, 0x10]);
+        assert_eq!(render(&mut vdp, &mut mem, 24)[16], red);
+    }
+
+    #[test]
+    fn test_tms_graphics1_and_sprite_limit() {
+        let mut mem = Memories::new();
+        // Graphics I: nomes em $0000, cores em $0400, padrões em $0800, SAT em $1000,
+        // padrões de sprites em $1800, fundo cor 4
+        let regs = [(0, 0x00), (1, 0x40), (2, 0x00), (3, 0x10), (4, 0x01), (5, 0x20), (6, 0x03), (7, 0x04)];
+        let mut vdp = vdp_with(VdpModel::Tms9918, &mut mem, &regs);
+        assert_eq!(vdp.video_mode(), VideoMode::Graphics1);
+
+        // Padrão 8: metade esquerda acesa; grupo 1 de cores: frente 6, fundo transparente
+        write(&mut vdp, &mut mem, 1, 0x0840, &[0xF0; 8]);
+        write(&mut vdp, &mut mem, 1, 0x0401, &[0x60]);
+        write(&mut vdp, &mut mem, 1, 0x0000, &[8]);
+
+        // Cinco sprites 8x8 sólidos na linha 0, cor 15
+        write(&mut vdp, &mut mem, 1, 0x1800, &[0xFF; 8]);
+        for i in 0..5u16 {
+            write(&mut vdp, &mut mem, 1, 0x1000 + i * 4, &[0xFF, 100 + i as u8 * 8, 0, 15]);
+        }
+        write(&mut vdp, &mut mem, 1, 0x1014, &[0xD0]);
+
+        let pixels = render(&mut vdp, &mut mem, 0);
+        let color = |i: usize| PixelFormat::Xrgb8888.encode(TMS_PALETTE[i].0, TMS_PALETTE[i].1, TMS_PALETTE[i].2);
+        assert_eq!(pixels.len(), 256);
+        assert_eq!(pixels[0], color(6));
+        assert_eq!(pixels[4], color(4));
+        assert_eq!(pixels[100], color(15));
+        // O quinto sprite não é desenhado e seu número vai para o status
+        assert_eq!(pixels[132], color(4));
+        let status = vdp.z80_read_status();
+        assert_eq!(status & 0x40, 0x40);
+        assert_eq!(status & 0x1F, 4);
+    }
+}
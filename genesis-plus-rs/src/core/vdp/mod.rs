@@ -0,0 +1,1028 @@
+//! VDP (Video Display Processor) do Mega Drive - Sega 315-5313.
+//! Baseado em `vdp_ctrl.c` do Genesis Plus GX.
+//!
+//! Este módulo contém os 24 registradores, a máquina de estados das portas
+//! de controle e de dados e o registrador de status. A VRAM, CRAM e VSRAM
+//! ficam no `MemoryBus` e são emprestadas ao VDP a cada acesso (`VdpRam`).
+//!
+//! O mesmo módulo emula os VDPs anteriores (Master System, Game Gear e o
+//! TMS9918 do SG-1000/SC-3000), selecionados por `VdpModel` e acessados
+//! pelas portas de 8 bits do Z80.
+
+pub mod fifo;
+pub mod hvc;
+pub mod legacy;
+pub mod renderer;
+
+use log::trace;
+
+use crate::utils::clock::{MCYCLES_PER_LINE, VDP_DIVIDER_H32, VDP_DIVIDER_H40};
+use fifo::{DmaType, Fifo};
+
+/// Número de registradores do VDP em modo 5
+pub const VDP_REGISTERS: usize = 24;
+
+// Bits do registrador de status
+pub const STATUS_FIFO_EMPTY: u16 = 0x200;
+pub const STATUS_FIFO_FULL: u16 = 0x100;
+pub const STATUS_VINT_PENDING: u16 = 0x80;
+pub const STATUS_SPRITE_OVERFLOW: u16 = 0x40;
+pub const STATUS_SPRITE_COLLISION: u16 = 0x20;
+pub const STATUS_ODD_FRAME: u16 = 0x10;
+pub const STATUS_VBLANK: u16 = 0x08;
+pub const STATUS_HBLANK: u16 = 0x04;
+pub const STATUS_DMA: u16 = 0x02;
+pub const STATUS_PAL: u16 = 0x01;
+
+/// Janela do flag HBLANK em clocks mestres desde o início da linha (H32, H40)
+const HBLANK_H32_START_MCYCLE: u64 = 280;
+const HBLANK_H32_END_MCYCLE: u64 = 860;
+const HBLANK_H40_START_MCYCLE: u64 = 228;
+const HBLANK_H40_END_MCYCLE: u64 = 872;
+
+/// Clock mestre do primeiro pixel ativo desde o início da linha (contador
+/// H em $00) em H32 e H40
+const ACTIVE_H32_MCYCLE: u64 = 760;
+const ACTIVE_H40_MCYCLE: u64 = 745;
+
+/// Clock mestre da interrupção vertical desde o início da linha (H32, H40)
+const VINT_H32_MCYCLE: u64 = 770;
+const VINT_H40_MCYCLE: u64 = 788;
+
+/// Tamanho da VSRAM em palavras de 11 bits
+const VSRAM_WORDS: usize = 40;
+
+/// Dimensões da tela visível do Game Gear
+const GG_WIDTH: usize = 160;
+const GG_HEIGHT: u16 = 144;
+
+/// Chip de vídeo emulado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VdpModel {
+    /// Mega Drive (315-5313): modos 5 e 4
+    MegaDrive,
+    /// Master System (315-5124): modo 4 com 192 linhas e modos TMS9918
+    MasterSystem,
+    /// Master System II (315-5246): modo 4 com 224 e 240 linhas
+    MasterSystem2,
+    /// Game Gear (315-5378): CRAM de 12 bits e tela de 160x144
+    GameGear,
+    /// TMS9918A do SG-1000 e SC-3000: apenas os modos TMS
+    Tms9918,
+}
+
+/// Modo de vídeo selecionado pelos bits M1-M5
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoMode {
+    /// Modo 5 do Mega Drive
+    Mode5,
+    /// Modo 4 do Master System
+    Mode4,
+    /// TMS9918 modo 0: 32x24 padrões, cores por grupo de 8 padrões
+    Graphics1,
+    /// TMS9918 modo 2: tela dividida em três tabelas de padrões e cores
+    Graphics2,
+    /// TMS9918 modo 3: blocos de 4x4 pixels
+    Multicolor,
+    /// TMS9918 modo 1: 40x24 caracteres de 6 pixels
+    Text,
+}
+
+/// Região da linha renderizada entregue ao frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// Primeiro pixel e largura visíveis da linha
+    pub x: usize,
+    pub width: usize,
+    /// Primeira linha e altura visíveis do quadro
+    pub y: u16,
+    pub height: u16,
+}
+
+/// Destino de uma escrita feita durante a linha visível
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RasterTarget {
+    Register(u8),
+    Cram(u8),
+}
+
+/// Escrita em registrador ou CRAM que passa a valer a partir do pixel `x`
+/// da linha corrente (efeitos de raster)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RasterWrite {
+    pub x: u16,
+    pub target: RasterTarget,
+    pub old: u16,
+    pub new: u16,
+}
+
+/// Memórias internas do VDP, emprestadas do barramento durante um acesso
+pub struct VdpRam<'a> {
+    /// VRAM em palavras big-endian (endereço de byte >> 1)
+    pub vram: &'a mut [u16; 65536],
+    /// CRAM em formato 9 bits (BBBGGGRRR)
+    pub cram: &'a mut [u16; 64],
+    /// VSRAM em palavras de 11 bits
+    pub vsram: &'a mut [u16; 40],
+}
+
+/// Estado do VDP
+pub struct VDP {
+    /// Registradores $00-$17
+    pub reg: [u8; VDP_REGISTERS],
+    /// Flags internos do status (FIFO e HBLANK são calculados na leitura)
+    pub status: u16,
+    /// Clock mestre no início da linha corrente
+    pub line_start: u64,
+    /// Linha corrente e total de linhas do quadro
+    pub line: u16,
+    pub lines_per_frame: u16,
+    /// Chip emulado (definido antes do reset)
+    pub model: VdpModel,
+
+    /// Primeira palavra de comando recebida, aguardando a segunda
+    pending: bool,
+    /// Código de acesso CD0-CD5
+    code: u8,
+    /// Registrador de endereço
+    addr: u16,
+    /// Bits A15-A14 do endereço, vindos da segunda palavra de comando
+    addr_latch: u16,
+    /// FIFO de escrita
+    fifo: Fifo,
+
+    /// DMA em andamento
+    dma_type: DmaType,
+    /// Unidades restantes (bytes ou palavras, conforme o tipo)
+    dma_length: u32,
+    /// Origem em palavras (68000) ou bytes (cópia)
+    dma_src: u16,
+    /// Preenchimento armado, aguardando a escrita na porta de dados
+    fill_pending: bool,
+    /// Clock mestre em que termina a fatia corrente do DMA
+    dma_end: u64,
+    /// DMA armado e ainda não iniciado pelo barramento
+    dma_start: bool,
+    /// Segunda palavra de um `move.l` no controle recebida durante o DMA
+    cached_write: Option<u16>,
+
+    /// Buffer de leitura antecipada das portas de 8 bits
+    read_buffer: u8,
+    /// Byte par de uma escrita na CRAM de 12 bits do Game Gear
+    cram_latch: u8,
+    /// Quinto sprite da linha (ou último verificado) nos bits 4-0 do status
+    sprite_number: u8,
+
+    /// Clock mestre do último acesso às portas
+    clock: u64,
+    /// Contador de linhas da interrupção horizontal (recarregado com #10)
+    hint_counter: u8,
+    /// Interrupções aguardando reconhecimento
+    hint_pending: bool,
+    vint_pending: bool,
+    ext_pending: bool,
+    /// Pulso de /INT do Z80 no Mega Drive (uma linha a partir da VINT)
+    z80_vint: bool,
+    /// Contador HV travado pelo registrador #0 ou pelo pino TH
+    hv_latch: Option<u16>,
+    /// Escritas feitas durante a parte visível da linha corrente
+    raster: Vec<RasterWrite>,
+}
+
+/// Nome usado pelos chips de cartucho (Paprium)
+pub type Vdp = VDP;
+
+impl VDP {
+    pub fn new() -> Self {
+        let mut vdp = Self {
+            reg: [0; VDP_REGISTERS],
+            status: 0,
+            line_start: 0,
+            line: 0,
+            lines_per_frame: 262,
+            model: VdpModel::MegaDrive,
+            pending: false,
+            code: 0,
+            addr: 0,
+            addr_latch: 0,
+            fifo: Fifo::new(),
+            dma_type: DmaType::M68kToVram,
+            dma_length: 0,
+            dma_src: 0,
+            fill_pending: false,
+            dma_end: 0,
+            dma_start: false,
+            cached_write: None,
+            read_buffer: 0,
+            cram_latch: 0,
+            sprite_number: 0,
+            clock: 0,
+            hint_counter: 0,
+            hint_pending: false,
+            vint_pending: false,
+            ext_pending: false,
+            z80_vint: false,
+            hv_latch: None,
+            raster: Vec::new(),
+        };
+        vdp.reset(false);
+        vdp
+    }
+
+    /// Reseta o VDP. O bit PAL do status reflete o pino de vídeo do console.
+    pub fn reset(&mut self, pal: bool) {
+        self.reg = [0; VDP_REGISTERS];
+        // H-INT desabilitada na partida (verificado em MD1 VA4 com 315-5313)
+        self.reg[10] = 0xFF;
+        self.status = if pal { STATUS_PAL } else { 0 };
+        self.line_start = 0;
+        self.line = 0;
+        self.lines_per_frame = if pal { 313 } else { 262 };
+        self.pending = false;
+        self.code = 0;
+        self.addr = 0;
+        self.addr_latch = 0;
+        self.fifo = Fifo::new();
+        self.dma_type = DmaType::M68kToVram;
+        self.dma_length = 0;
+        self.dma_src = 0;
+        self.fill_pending = false;
+        self.dma_end = 0;
+        self.dma_start = false;
+        self.cached_write = None;
+        self.read_buffer = 0;
+        self.cram_latch = 0;
+        self.sprite_number = 0;
+        self.clock = 0;
+        self.hint_counter = self.reg[10];
+        self.hint_pending = false;
+        self.vint_pending = false;
+        self.ext_pending = false;
+        self.z80_vint = false;
+        self.hv_latch = None;
+        self.raster.clear();
+    }
+
+    /// Modo 5 (Mega Drive) habilitado
+    pub fn mode5(&self) -> bool {
+        self.model == VdpModel::MegaDrive && self.reg[1] & 0x04 != 0
+    }
+
+    /// Modo de vídeo corrente
+    pub fn video_mode(&self) -> VideoMode {
+        if self.mode5() {
+            VideoMode::Mode5
+        } else if self.reg[0] & 0x04 != 0 && self.model != VdpModel::Tms9918 {
+            VideoMode::Mode4
+        } else if self.reg[1] & 0x10 != 0 {
+            VideoMode::Text
+        } else if self.reg[1] & 0x08 != 0 {
+            VideoMode::Multicolor
+        } else if self.reg[0] & 0x02 != 0 {
+            VideoMode::Graphics2
+        } else {
+            VideoMode::Graphics1
+        }
+    }
+
+    /// Display habilitado
+    pub fn display_enabled(&self) -> bool {
+        self.reg[1] & 0x40 != 0
+    }
+
+    /// Modo de 40 células (320 pixels)
+    pub fn h40(&self) -> bool {
+        self.reg[12] & 0x01 != 0
+    }
+
+    /// Entrelaçado habilitado (modos 1 e 2, bit LSM0 do #12)
+    pub fn interlaced(&self) -> bool {
+        self.mode5() && self.reg[12] & 0x02 != 0
+    }
+
+    /// Entrelaçado 2: resolução dobrada (448 linhas) com padrões de 8x16
+    pub fn interlace_mode2(&self) -> bool {
+        self.mode5() && self.reg[12] & 0x06 == 0x06
+    }
+
+    /// Campo ímpar do quadro entrelaçado corrente
+    pub fn odd_field(&self) -> bool {
+        self.status & STATUS_ODD_FRAME != 0
+    }
+
+    /// Código de acesso corrente (CD0-CD5)
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+
+    /// Registrador de endereço corrente
+    pub fn addr(&self) -> u16 {
+        self.addr
+    }
+
+    /// Linhas ativas: 240 (V30) ou 224 (V28) em modo 5; 192 nos demais,
+    /// exceto os modos estendidos do modo 4 no Master System II e Game Gear
+    pub fn active_lines(&self) -> u16 {
+        match self.video_mode() {
+            VideoMode::Mode5 => {
+                if self.reg[1] & 0x08 != 0 { 240 } else { 224 }
+            }
+            VideoMode::Mode4
+                if matches!(self.model, VdpModel::MasterSystem2 | VdpModel::GameGear) && self.reg[0] & 0x02 != 0 =>
+            {
+                // M2 com M1 = 224 linhas, M2 com M3 = 240 linhas
+                if self.reg[1] & 0x10 != 0 {
+                    224
+                } else if self.reg[1] & 0x08 != 0 {
+                    240
+                } else {
+                    192
+                }
+            }
+            _ => 192,
+        }
+    }
+
+    /// Parte visível das linhas renderizadas. O Game Gear mostra só o centro
+    /// de 160x144 da imagem de 256 pixels.
+    pub fn viewport(&self) -> Viewport {
+        let lines = self.active_lines();
+        if self.model == VdpModel::GameGear && !self.mode5() {
+            return Viewport { x: 48, width: GG_WIDTH, y: (lines - GG_HEIGHT) / 2, height: GG_HEIGHT };
+        }
+        let width = if self.mode5() && self.h40() { 320 } else { 256 };
+        Viewport { x: 0, width, y: 0, height: lines }
+    }
+
+    /// Início de uma linha no clock mestre `mcycles`: atualiza o VBLANK e o
+    /// contador de linhas da interrupção horizontal
+    pub fn start_line(&mut self, line: u16, mcycles: u64) {
+        self.line = line;
+        self.line_start = mcycles;
+        self.clock = mcycles;
+        self.z80_vint = false;
+        self.raster.clear();
+
+        // Cada quadro entrelaçado alterna o campo par e o ímpar
+        if line == 0 {
+            if self.interlaced() {
+                self.status ^= STATUS_ODD_FRAME;
+            } else {
+                self.status &= !STATUS_ODD_FRAME;
+            }
+        }
+
+        // O contador é decrementado nas linhas ativas e na primeira linha do
+        // VBLANK; no resto do VBLANK fica recarregado
+        let active = self.active_lines();
+        if line <= active {
+            if self.hint_counter == 0 {
+                self.hint_counter = self.reg[10];
+                self.hint_pending = true;
+            } else {
+                self.hint_counter -= 1;
+            }
+        } else {
+            self.hint_counter = self.reg[10];
+        }
+
+        if line == active {
+            self.status |= STATUS_VBLANK;
+        } else if line == self.lines_per_frame - 1 {
+            self.status &= !STATUS_VBLANK;
+        }
+    }
+
+    /// Sincroniza o VDP com o clock mestre antes de um acesso às portas
+    pub fn sync(&mut self, mcycles: u64) {
+        self.clock = mcycles;
+    }
+
+    /// Escritas da linha corrente, em ordem, para o renderizador
+    pub fn take_raster_writes(&mut self) -> Vec<RasterWrite> {
+        std::mem::take(&mut self.raster)
+    }
+
+    /// Registra uma escrita feita durante uma linha visível. Escritas antes
+    /// do primeiro pixel valem para a linha inteira e não são registradas.
+    fn log_raster(&mut self, target: RasterTarget, old: u16, new: u16) {
+        if self.line >= self.active_lines() {
+            return;
+        }
+        let (start, divider, width) = if self.h40() {
+            (ACTIVE_H40_MCYCLE, VDP_DIVIDER_H40, 320)
+        } else {
+            (ACTIVE_H32_MCYCLE, VDP_DIVIDER_H32, 256)
+        };
+        let cycles = self.clock.saturating_sub(self.line_start);
+        let x = (cycles.saturating_sub(start) / divider).min(width) as u16;
+        if x > 0 {
+            self.raster.push(RasterWrite { x, target, old, new });
+        }
+    }
+
+    /// Escreve uma cor na CRAM. Toda escrita é registrada, mesmo sem mudar
+    /// a cor, por causa dos pontos que aparecem na tela.
+    fn write_cram(&mut self, ram: &mut VdpRam<'_>, index: usize, color: u16) {
+        let old = ram.cram[index];
+        ram.cram[index] = color;
+        self.log_raster(RasterTarget::Cram(index as u8), old, color);
+    }
+
+    // --- Interrupções ---
+
+    /// Linha da interrupção vertical: primeira linha do VBLANK no modo 5,
+    /// a seguinte nos modos herdados
+    pub fn vint_line(&self) -> u16 {
+        if self.mode5() { self.active_lines() } else { self.active_lines() + 1 }
+    }
+
+    /// Clock mestre da interrupção vertical desde o início da sua linha
+    pub fn vint_mcycle(&self) -> u64 {
+        if self.h40() { VINT_H40_MCYCLE } else { VINT_H32_MCYCLE }
+    }
+
+    /// Dispara a interrupção vertical (flag F do status e /INT do Z80)
+    pub fn trigger_vint(&mut self) {
+        self.vint_pending = true;
+        self.z80_vint = true;
+        self.status |= STATUS_VINT_PENDING;
+    }
+
+    /// Nível de interrupção apresentado ao 68000 (6 = VINT, 4 = HINT, 2 = externa)
+    pub fn irq_level(&self) -> u8 {
+        if self.vint_pending && self.reg[1] & 0x20 != 0 {
+            6
+        } else if self.hint_pending && self.reg[0] & 0x10 != 0 {
+            4
+        } else if self.ext_pending && self.reg[11] & 0x08 != 0 {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// Ciclo de reconhecimento do 68000: limpa a interrupção atendida
+    pub fn int_ack(&mut self, level: u8) {
+        match level {
+            6 => {
+                self.vint_pending = false;
+                self.status &= !STATUS_VINT_PENDING;
+            }
+            4 => self.hint_pending = false,
+            2 => self.ext_pending = false,
+            _ => {}
+        }
+    }
+
+    /// Linha /INT do Z80: pulso da VINT no Mega Drive, VINT ou HINT
+    /// habilitadas e não lidas nos demais chips
+    pub fn z80_irq(&self) -> bool {
+        if self.model == VdpModel::MegaDrive {
+            self.z80_vint
+        } else {
+            (self.vint_pending && self.reg[1] & 0x20 != 0) || (self.hint_pending && self.reg[0] & 0x10 != 0)
+        }
+    }
+
+    // --- Contador HV ---
+
+    /// Contador HV ao vivo no clock mestre `mcycles`
+    fn hv_counter(&self, mcycles: u64) -> u16 {
+        let mut v = hvc::vcounter(self.line, self.active_lines(), self.lines_per_frame);
+        if self.interlaced() {
+            // O entrelaçado 2 conta meias linhas; o bit 8 aparece no bit 0
+            if self.interlace_mode2() {
+                v <<= 1;
+            }
+            v = (v & !1) | ((v >> 8) & 1);
+        }
+        let h = hvc::hcounter(self.h40(), mcycles.saturating_sub(self.line_start));
+        ((v & 0xFF) << 8) | h as u16
+    }
+
+    /// Leitura do contador HV ($C00008), travado se o registrador #0 pedir
+    pub fn read_hv(&mut self, mcycles: u64) -> u16 {
+        self.clock = mcycles;
+        self.hv_latch.unwrap_or_else(|| self.hv_counter(mcycles))
+    }
+
+    /// Contador V de 8 bits (porta $7E do Master System). O TH só trava o
+    /// contador H: o V continua contando.
+    pub fn read_vcounter(&mut self, mcycles: u64) -> u8 {
+        self.clock = mcycles;
+        (self.hv_counter(mcycles) >> 8) as u8
+    }
+
+    /// Contador H de 8 bits (porta $7F): o último valor travado pelo TH
+    pub fn read_hcounter(&mut self, mcycles: u64) -> u8 {
+        self.hv_latch.unwrap_or_else(|| self.hv_counter(mcycles)) as u8
+    }
+
+    /// Borda no pino TH de uma porta de controle (pistolas de luz): trava o
+    /// contador HV e, no modo 5, gera a interrupção externa se habilitada
+    pub fn latch_hv_external(&mut self, mcycles: u64) {
+        if self.model != VdpModel::MegaDrive {
+            self.hv_latch = Some(self.hv_counter(mcycles));
+            return;
+        }
+        if self.reg[0] & 0x02 != 0 {
+            self.hv_latch = Some(self.hv_counter(mcycles));
+        }
+        if self.reg[11] & 0x08 != 0 {
+            self.ext_pending = true;
+        }
+    }
+
+    /// Tipo do DMA em andamento
+    pub fn dma_type(&self) -> DmaType {
+        self.dma_type
+    }
+
+    /// Unidades de DMA ainda não transferidas
+    pub fn dma_length(&self) -> u32 {
+        self.dma_length
+    }
+
+    /// Clock mestre em que termina a fatia corrente do DMA
+    pub fn dma_end(&self) -> u64 {
+        self.dma_end
+    }
+
+    /// Consome o pedido de início de DMA feito pelas portas
+    pub fn take_dma_start(&mut self) -> bool {
+        std::mem::take(&mut self.dma_start)
+    }
+
+    /// Carrega o contador de DMA dos registradores #19-#20 (0 = 64K)
+    fn load_dma_length(&mut self) {
+        self.dma_length = u16::from_le_bytes([self.reg[19], self.reg[20]]) as u32;
+        if self.dma_length == 0 {
+            self.dma_length = 0x10000;
+        }
+    }
+
+    // --- Porta de controle ---
+
+    /// Escrita de uma palavra na porta de controle ($C00004)
+    pub fn write_control(&mut self, data: u16) {
+        if !self.pending {
+            // O 68000 fica parado durante o DMA do seu barramento: a segunda
+            // palavra de um `move.l` é aplicada no fim (Formula One)
+            if self.dma_length > 0 && self.dma_type.uses_68k_bus() {
+                self.cached_write = Some(data);
+                return;
+            }
+
+            // Primeira palavra: A13-A0 e CD1-CD0
+            self.addr = self.addr_latch | (data & 0x3FFF);
+            self.code = (self.code & 0x3C) | ((data >> 14) as u8 & 0x03);
+
+            if data & 0xC000 == 0x8000 {
+                // Escrita de registrador
+                self.write_register(((data >> 8) & 0x1F) as usize, data as u8);
+            } else {
+                // A segunda palavra só existe em modo 5
+                self.pending = self.mode5();
+            }
+        } else {
+            // Segunda palavra: A15-A14 e CD5-CD2
+            self.pending = false;
+            self.addr_latch = (data & 3) << 14;
+            self.addr = self.addr_latch | (self.addr & 0x3FFF);
+            self.code = (self.code & 0x03) | ((data >> 2) as u8 & 0x3C);
+
+            // CD5 com DMA habilitado no registrador #1
+            if self.code & 0x20 != 0 && self.reg[1] & 0x10 != 0 {
+                match self.reg[23] >> 6 {
+                    2 => {
+                        // Preenchimento: começa na próxima escrita de dados
+                        self.dma_type = DmaType::Fill;
+                        self.fill_pending = true;
+                        self.status |= STATUS_DMA;
+                        self.dma_end = u64::MAX;
+                    }
+                    3 => {
+                        self.dma_type = DmaType::Copy;
+                        self.load_dma_length();
+                        self.dma_src = u16::from_le_bytes([self.reg[21], self.reg[22]]);
+                        self.dma_start = true;
+                    }
+                    _ => {
+                        self.dma_type = if self.code & 0x06 != 0 {
+                            DmaType::M68kToCram
+                        } else {
+                            DmaType::M68kToVram
+                        };
+                        self.load_dma_length();
+                        self.dma_src = u16::from_le_bytes([self.reg[21], self.reg[22]]);
+                        self.dma_start = true;
+                    }
+                }
+            }
+        }
+
+        // Cada escrita na VRAM ocupa dois slots de byte; os códigos inválidos
+        // se comportam como VRAM
+        self.fifo.byte_access = self.code & 0x06 == 0;
+    }
+
+    /// Escrita de registrador
+    pub fn write_register(&mut self, r: usize, d: u8) {
+        // Registradores #11 a #23 não podem ser alterados em modo 4
+        // (Captain Planet & Avengers, Bass Master Classic Pro Edition)
+        if !self.mode5() && r > 10 {
+            return;
+        }
+        if r >= VDP_REGISTERS {
+            trace!("VDP: escrita no registrador inválido #{} <- ${:02X}", r, d);
+            return;
+        }
+
+        // Bit 1 do #0 trava o contador HV no valor corrente
+        if r == 0 && self.model == VdpModel::MegaDrive && (d ^ self.reg[0]) & 0x02 != 0 {
+            self.hv_latch = if d & 0x02 != 0 { Some(self.hv_counter(self.clock)) } else { None };
+        }
+        if d != self.reg[r] {
+            self.log_raster(RasterTarget::Register(r as u8), self.reg[r] as u16, d as u16);
+        }
+        self.reg[r] = d;
+    }
+
+    /// Leitura do registrador de status ($C00004) no clock mestre `mcycles`
+    pub fn read_status(&mut self, mcycles: u64) -> u16 {
+        self.clock = mcycles;
+        // DMA concluído: limpa o flag de ocupado
+        if self.status & STATUS_DMA != 0 && self.dma_length == 0 && mcycles >= self.dma_end {
+            self.status &= !STATUS_DMA;
+        }
+
+        let mut temp = self.status;
+
+        // A leitura cancela um comando pela metade e limpa SOVR/SCOL
+        self.pending = false;
+        self.status &= !(STATUS_SPRITE_OVERFLOW | STATUS_SPRITE_COLLISION);
+
+        if self.fifo.is_empty(mcycles) {
+            temp |= STATUS_FIFO_EMPTY;
+        } else if self.fifo.is_full(mcycles) {
+            temp |= STATUS_FIFO_FULL;
+        }
+
+        // VBLANK fica ativo com o display desligado
+        if !self.display_enabled() {
+            temp |= STATUS_VBLANK;
+        }
+
+        let (start, end) = if self.h40() {
+            (HBLANK_H40_START_MCYCLE, HBLANK_H40_END_MCYCLE)
+        } else {
+            (HBLANK_H32_START_MCYCLE, HBLANK_H32_END_MCYCLE)
+        };
+        let cycles = mcycles.saturating_sub(self.line_start) % MCYCLES_PER_LINE;
+        if cycles >= start && cycles < end {
+            temp |= STATUS_HBLANK;
+        }
+
+        temp
+    }
+
+    // --- Porta de dados ---
+
+    /// Escrita de uma palavra na porta de dados ($C00000) no clock mestre
+    /// `mcycles`. Retorna até quando a CPU fica parada com a FIFO cheia
+    /// (0 se não houver espera).
+    pub fn write_data(&mut self, ram: VdpRam<'_>, data: u16, mcycles: u64) -> u64 {
+        self.pending = false;
+        self.clock = mcycles;
+
+        // Fora do blanking a escrita espera um slot de acesso
+        let mut stall = 0;
+        if self.status & STATUS_VBLANK == 0 && self.display_enabled() {
+            stall = self.fifo.schedule(mcycles, self.line_start, self.h40());
+        }
+
+        self.bus_write(ram, data);
+
+        // Preenchimento armado: começa agora
+        if self.fill_pending {
+            self.fill_pending = false;
+            self.load_dma_length();
+            self.dma_start = true;
+        }
+
+        stall
+    }
+
+    /// Aplica uma palavra no destino corrente (porta de dados e DMA do 68000)
+    pub fn bus_write(&mut self, mut ram: VdpRam<'_>, data: u16) {
+        self.fifo.push(data);
+
+        match self.code & 0x0F {
+            0x01 => {
+                // VRAM: dados trocados de byte quando A0 está setado
+                let data = if self.addr & 1 != 0 { data.swap_bytes() } else { data };
+                ram.vram[(self.addr >> 1) as usize] = data;
+            }
+            0x03 => {
+                // CRAM: BBB0GGG0RRR0 -> BBBGGGRRR
+                let index = ((self.addr >> 1) & 0x3F) as usize;
+                let color = ((data & 0xE00) >> 3) | ((data & 0x0E0) >> 2) | ((data & 0x00E) >> 1);
+                self.write_cram(&mut ram, index, color);
+            }
+            0x05 => {
+                let index = ((self.addr >> 1) & 0x3F) as usize;
+                if index < VSRAM_WORDS {
+                    ram.vsram[index] = data & 0x7FF;
+                }
+            }
+            code => trace!("VDP: escrita com código inválido {:02X} em ${:04X}", code, self.addr),
+        }
+
+        self.addr = self.addr.wrapping_add(self.reg[15] as u16);
+    }
+
+    /// Leitura de uma palavra da porta de dados ($C00000)
+    pub fn read_data(&mut self, ram: VdpRam<'_>) -> u16 {
+        self.pending = false;
+
+        // Bits não usados vêm da próxima entrada da FIFO
+        let next = self.fifo.next();
+        let data = match self.code & 0x1F {
+            0x00 => ram.vram[(self.addr >> 1) as usize],
+            0x04 => {
+                // Fora dos 40 words a leitura volta ao endereço 0
+                let mut index = ((self.addr >> 1) & 0x3F) as usize;
+                if index >= VSRAM_WORDS {
+                    index = 0;
+                }
+                (ram.vsram[index] & 0x7FF) | (next & !0x7FF)
+            }
+            0x08 => {
+                // CRAM: BBBGGGRRR -> BBB0GGG0RRR0
+                let color = ram.cram[((self.addr >> 1) & 0x3F) as usize];
+                let data = ((color & 0x1C0) << 3) | ((color & 0x038) << 2) | ((color & 0x007) << 1);
+                data | (next & !0xEEE)
+            }
+            0x0C => {
+                // Leitura de 8 bits não documentada do byte adjacente da VRAM
+                let word = ram.vram[(self.addr >> 1) as usize];
+                let byte = if self.addr & 1 != 0 { word >> 8 } else { word & 0xFF };
+                byte | (next & !0xFF)
+            }
+            code => {
+                // No hardware real trava o VDP até um reset
+                trace!("VDP: leitura com código inválido {:02X} em ${:04X}", code, self.addr);
+                0
+            }
+        };
+
+        self.addr = self.addr.wrapping_add(self.reg[15] as u16);
+        data
+    }
+
+    // --- Portas de 8 bits (modo 4 e TMS9918) ---
+
+    /// Escrita de um byte na porta de controle ($BF no Master System)
+    pub fn z80_write_control(&mut self, ram: VdpRam<'_>, data: u8) {
+        if !self.pending {
+            // Primeiro byte: A7-A0
+            self.addr = (self.addr & 0x3F00) | data as u16;
+            self.pending = true;
+            return;
+        }
+
+        // Segundo byte: A13-A8 e código de acesso
+        self.pending = false;
+        self.code = data >> 6;
+        self.addr = (((data as u16) << 8) | (self.addr & 0xFF)) & 0x3FFF;
+
+        let tms = self.model == VdpModel::Tms9918;
+        if self.code == 0 {
+            // Leitura: o primeiro byte é buscado antecipadamente
+            self.read_buffer = read_vram_byte(ram.vram, self.addr);
+            self.addr = (self.addr + 1) & 0x3FFF;
+        } else if self.code == 2 || (self.code == 3 && tms) {
+            // O TMS9918 não tem CRAM: o código 3 também escreve registradores
+            let r = data & if tms { 0x07 } else { 0x0F };
+            self.write_register(r as usize, self.addr as u8);
+        }
+    }
+
+    /// Escrita de um byte na porta de dados ($BE no Master System)
+    pub fn z80_write_data(&mut self, mut ram: VdpRam<'_>, data: u8) {
+        self.pending = false;
+
+        if self.code == 3 && self.model != VdpModel::Tms9918 {
+            if self.model == VdpModel::GameGear {
+                // CRAM de 12 bits (----BBBBGGGGRRRR) escrita no byte ímpar
+                if self.addr & 1 == 0 {
+                    self.cram_latch = data;
+                } else {
+                    let index = ((self.addr >> 1) & 0x1F) as usize;
+                    let color = u16::from_le_bytes([self.cram_latch, data]) & 0xFFF;
+                    self.write_cram(&mut ram, index, color);
+                }
+            } else {
+                // CRAM de 6 bits (--BBGGRR)
+                self.write_cram(&mut ram, (self.addr & 0x1F) as usize, data as u16 & 0x3F);
+            }
+        } else {
+            write_vram_byte(ram.vram, self.addr, data);
+        }
+
+        // A escrita também carrega o buffer de leitura
+        self.read_buffer = data;
+        self.addr = (self.addr + 1) & 0x3FFF;
+    }
+
+    /// Leitura de um byte da porta de dados: devolve o buffer e busca o próximo
+    pub fn z80_read_data(&mut self, ram: VdpRam<'_>) -> u8 {
+        self.pending = false;
+        let data = self.read_buffer;
+        self.read_buffer = read_vram_byte(ram.vram, self.addr);
+        self.addr = (self.addr + 1) & 0x3FFF;
+        data
+    }
+
+    /// Leitura do status de 8 bits: quadro, estouro e colisão de sprites e
+    /// o número do quinto sprite
+    pub fn z80_read_status(&mut self) -> u8 {
+        let status = (self.status as u8 & 0xE0) | (self.sprite_number & 0x1F);
+        self.pending = false;
+        self.status &= !(STATUS_VINT_PENDING | STATUS_SPRITE_OVERFLOW | STATUS_SPRITE_COLLISION);
+        // A leitura reconhece as interrupções pendentes
+        self.vint_pending = false;
+        self.hint_pending = false;
+        status
+    }
+}
+
+impl Default for VDP {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Lê um byte da VRAM pelo endereço de byte (big-endian)
+fn read_vram_byte(vram: &[u16; 65536], addr: u16) -> u8 {
+    let word = vram[(addr >> 1) as usize];
+    if addr & 1 == 0 { (word >> 8) as u8 } else { word as u8 }
+}
+
+/// Escreve um byte na VRAM pelo endereço de byte (big-endian)
+fn write_vram_byte(vram: &mut [u16; 65536], addr: u16, data: u8) {
+    let word = &mut vram[(addr >> 1) as usize];
+    *word = if addr & 1 == 0 {
+        (*word & 0x00FF) | ((data as u16) << 8)
+    } else {
+        (*word & 0xFF00) | data as u16
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Memories {
+        vram: Box<[u16; 65536]>,
+        cram: [u16; 64],
+        vsram: [u16; 40],
+    }
+
+    impl Memories {
+        fn new() -> Self {
+            Self { vram: Box::new([0; 65536]), cram: [0; 64], vsram: [0; 40] }
+        }
+
+        fn ram(&mut self) -> VdpRam<'_> {
+            VdpRam { vram: &mut self.vram, cram: &mut self.cram, vsram: &mut self.vsram }
+        }
+    }
+
+    fn mode5_vdp() -> VDP {
+        let mut vdp = VDP::new();
+        vdp.write_control(0x8144); // display on, modo 5
+        vdp.write_control(0x8F02); // auto-incremento de 2
+        vdp
+    }
+
+    #[test]
+    fn test_vram_write_read_with_increment() {
+        let mut vdp = mode5_vdp();
+        let mut mem = Memories::new();
+
+        // Escrita em VRAM $C000
+        vdp.write_control(0x4000);
+        vdp.write_control(0x0003);
+        vdp.write_data(mem.ram(), 0x1234, 0);
+        vdp.write_data(mem.ram(), 0x5678, 0);
+        assert_eq!(mem.vram[0x6000], 0x1234);
+        assert_eq!(mem.vram[0x6001], 0x5678);
+        assert_eq!(vdp.addr(), 0xC004);
+
+        // Endereço ímpar troca os bytes
+        vdp.write_control(0x4001);
+        vdp.write_control(0x0000);
+        vdp.write_data(mem.ram(), 0xAABB, 0);
+        assert_eq!(mem.vram[0], 0xBBAA);
+
+        // Leitura de VRAM $C002
+        vdp.write_control(0x0002);
+        vdp.write_control(0x0003);
+        assert_eq!(vdp.read_data(mem.ram()), 0x5678);
+    }
+
+    #[test]
+    fn test_cram_and_vsram() {
+        let mut vdp = mode5_vdp();
+        let mut mem = Memories::new();
+
+        // CRAM cor 1
+        vdp.write_control(0xC002);
+        vdp.write_control(0x0000);
+        vdp.write_data(mem.ram(), 0x0E4A, 0);
+        assert_eq!(mem.cram[1], 0b111_010_101);
+
+        vdp.write_control(0x0002);
+        vdp.write_control(0x0020);
+        assert_eq!(vdp.read_data(mem.ram()) & 0xEEE, 0x0E4A);
+
+        // VSRAM: 11 bits, leituras fora da faixa voltam ao índice 0
+        vdp.write_control(0x4000);
+        vdp.write_control(0x0010);
+        vdp.write_data(mem.ram(), 0xFFFF, 0);
+        assert_eq!(mem.vsram[0], 0x7FF);
+
+        vdp.write_control(0x0050);
+        vdp.write_control(0x0010);
+        assert_eq!(vdp.read_data(mem.ram()) & 0x7FF, 0x7FF);
+    }
+
+    #[test]
+    fn test_status_and_pending_latch() {
+        let mut vdp = mode5_vdp();
+        vdp.status |= STATUS_SPRITE_COLLISION;
+
+        // Leitura do status cancela a primeira palavra de comando
+        vdp.write_control(0x4000);
+        let status = vdp.read_status(0);
+        assert_ne!(status & STATUS_FIFO_EMPTY, 0);
+        assert_ne!(status & STATUS_SPRITE_COLLISION, 0);
+        assert_eq!(vdp.read_status(0) & STATUS_SPRITE_COLLISION, 0);
+
+        // A palavra seguinte volta a ser tratada como escrita de registrador
+        vdp.write_control(0x8C81);
+        assert!(vdp.h40());
+
+        // HBLANK a partir do ciclo 228 da linha em H40
+        assert_eq!(vdp.read_status(100) & STATUS_HBLANK, 0);
+        assert_ne!(vdp.read_status(300) & STATUS_HBLANK, 0);
+        assert_eq!(vdp.read_status(1000) & STATUS_HBLANK, 0);
+
+        // Registradores acima de #10 são ignorados em modo 4
+        vdp.write_control(0x8104);
+        vdp.write_control(0x8100);
+        vdp.write_control(0x8C00);
+        assert!(vdp.h40());
+    }
+
+    #[test]
+    fn test_interrupts_and_hv_latch() {
+        let mut vdp = mode5_vdp();
+        // HINT a cada 3 linhas, VINT habilitada
+        vdp.write_control(0x8014);
+        vdp.write_control(0x8A02);
+        vdp.write_control(0x8164);
+        // O contador só é recarregado com o novo valor durante o VBLANK
+        vdp.start_line(261, 0);
+        for line in 0..2 {
+            vdp.start_line(line, line as u64 * MCYCLES_PER_LINE);
+        }
+        assert_eq!(vdp.irq_level(), 0);
+        vdp.start_line(2, 2 * MCYCLES_PER_LINE);
+        assert_eq!(vdp.irq_level(), 4);
+        vdp.int_ack(4);
+        assert_eq!(vdp.irq_level(), 0);
+
+        // A VINT tem prioridade e o reconhecimento limpa o flag F
+        vdp.start_line(224, 224 * MCYCLES_PER_LINE);
+        vdp.trigger_vint();
+        assert_eq!(vdp.irq_level(), 6);
+        assert_ne!(vdp.read_status(0) & STATUS_VINT_PENDING, 0);
+        vdp.int_ack(6);
+        assert_eq!(vdp.read_status(0) & STATUS_VINT_PENDING, 0);
+        assert_eq!(vdp.irq_level(), 0);
+
+        // Contador HV ao vivo e travado pelo bit 1 do #0 (H32)
+        let start = 100 * MCYCLES_PER_LINE;
+        vdp.start_line(100, start);
+        assert_eq!(vdp.read_hv(start), 0x6485);
+        vdp.sync(start + 16 * 20);
+        vdp.write_control(0x8016);
+        assert_eq!(vdp.read_hv(start + 2000) & 0xFF00, 0x6400);
+        assert_eq!(vdp.read_hv(start + 2000), vdp.read_hv(start + 3000));
+        vdp.write_control(0x8014);
+        assert_ne!(vdp.read_hv(start + 2000), vdp.read_hv(start + 3000));
+    }
+}
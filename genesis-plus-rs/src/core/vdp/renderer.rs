@@ -0,0 +1,667 @@
+//! Renderizador de linhas do modo 5 (Mega Drive).
+//! Baseado em `vdp_render.c` do Genesis Plus GX.
+//!
+//! Cada linha é montada em três buffers de índices de cor (plano B, plano A
+//! ou janela, e sprites) e depois composta segundo as prioridades e o modo
+//! sombra/brilho, sendo convertida para o formato de pixel do host. Os modos
+//! 4 e TMS9918 são montados em `legacy` e passam pela mesma conversão.
+
+use super::legacy::{self, TMS_PALETTE};
+use super::{
+    RasterTarget, VdpModel, VdpRam, VideoMode, STATUS_ODD_FRAME, STATUS_SPRITE_COLLISION, STATUS_SPRITE_OVERFLOW, VDP,
+};
+
+/// Largura máxima de uma linha (H40)
+pub const MAX_WIDTH: usize = 320;
+
+/// Bit de prioridade nos buffers de camada (bits 5-0 = paleta e cor)
+const PRIORITY: u8 = 0x80;
+
+/// Níveis de brilho da composição
+const NORMAL: usize = 0;
+const SHADOW: usize = 1;
+const HIGHLIGHT: usize = 2;
+
+/// Formato de pixel entregue ao frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 32 bits, 0x00RRGGBB
+    Xrgb8888,
+    /// 16 bits, RRRRRGGGGGGBBBBB
+    Rgb565,
+    /// 16 bits, 0RRRRRGGGGGBBBBB
+    Xrgb1555,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Xrgb8888 => 4,
+            PixelFormat::Rgb565 | PixelFormat::Xrgb1555 => 2,
+        }
+    }
+
+    /// Codifica uma cor de 8 bits por componente
+    pub fn encode(self, r: u8, g: u8, b: u8) -> u32 {
+        let (r, g, b) = (r as u32, g as u32, b as u32);
+        match self {
+            PixelFormat::Xrgb8888 => (r << 16) | (g << 8) | b,
+            PixelFormat::Rgb565 => ((r >> 3) << 11) | ((g >> 2) << 5) | (b >> 3),
+            PixelFormat::Xrgb1555 => ((r >> 3) << 10) | ((g >> 3) << 5) | (b >> 3),
+        }
+    }
+
+    /// Grava o pixel `x` de uma linha em bytes little-endian
+    fn store(self, out: &mut [u8], x: usize, color: u32) {
+        let bpp = self.bytes_per_pixel();
+        let bytes = color.to_le_bytes();
+        out[x * bpp..(x + 1) * bpp].copy_from_slice(&bytes[..bpp]);
+    }
+}
+
+/// Nível de 8 bits para cada intensidade do DAC (0-14: sombra, normal e brilho)
+fn level(intensity: u16) -> u8 {
+    ((intensity as u32 * 255 + 7) / 14) as u8
+}
+
+/// Renderizador de linhas do VDP
+pub struct Renderer {
+    format: PixelFormat,
+    plane_a: [u8; MAX_WIDTH],
+    plane_b: [u8; MAX_WIDTH],
+    sprites: [u8; MAX_WIDTH],
+    /// Linha composta em índices de `colors`
+    pixels: [u8; MAX_WIDTH],
+    /// Cores da CRAM convertidas: normal, sombra e brilho
+    colors: [u32; 64 * 3],
+    /// A linha anterior atingiu o limite de pixels de sprites
+    sprite_dot_overflow: bool,
+    /// Mostra a cor escrita na CRAM no pixel em que a escrita ocorreu
+    cram_dots: bool,
+}
+
+impl Renderer {
+    pub fn new(format: PixelFormat) -> Self {
+        Self {
+            format,
+            plane_a: [0; MAX_WIDTH],
+            plane_b: [0; MAX_WIDTH],
+            sprites: [0; MAX_WIDTH],
+            pixels: [0; MAX_WIDTH],
+            colors: [0; 64 * 3],
+            sprite_dot_overflow: false,
+            cram_dots: false,
+        }
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: PixelFormat) {
+        self.format = format;
+    }
+
+    /// Habilita os pontos de CRAM do modo 5 (opção de precisão)
+    pub fn set_cram_dots(&mut self, enabled: bool) {
+        self.cram_dots = enabled;
+    }
+
+    /// Renderiza a linha ativa `line` em `out` (largura * bytes por pixel),
+    /// recortada pela área visível do VDP. Retorna a largura em pixels.
+    ///
+    /// Escritas em registradores e na CRAM feitas durante a linha valem a
+    /// partir do pixel em que ocorreram: o estado do início da linha é
+    /// restaurado e a linha é montada em trechos, reaplicando cada escrita.
+    pub fn render_line(&mut self, vdp: &mut VDP, mut ram: VdpRam<'_>, line: u16, out: &mut [u8]) -> usize {
+        let writes = vdp.take_raster_writes();
+        for write in writes.iter().rev() {
+            apply_write(vdp, &mut ram, write.target, write.old);
+        }
+
+        let dot_overflow = self.sprite_dot_overflow;
+        let mut start = 0;
+        for write in &writes {
+            let x = write.x as usize;
+            if x > start {
+                self.sprite_dot_overflow = dot_overflow;
+                self.render_segment(vdp, &ram, line, out, start, x);
+                start = x;
+            }
+            apply_write(vdp, &mut ram, write.target, write.new);
+        }
+        self.sprite_dot_overflow = dot_overflow;
+        let width = self.render_segment(vdp, &ram, line, out, start, MAX_WIDTH);
+
+        // A cor sendo escrita aparece no pixel corrente durante o display ativo
+        if self.cram_dots && vdp.mode5() && vdp.display_enabled() {
+            for write in &writes {
+                if let RasterTarget::Cram(_) = write.target {
+                    if (write.x as usize) < width {
+                        self.format.store(out, write.x as usize, self.cram_color(write.new));
+                    }
+                }
+            }
+        }
+        width
+    }
+
+    /// Monta a linha com o estado corrente e converte os pixels de `from` a
+    /// `to` (coordenadas da linha inteira) que caem na área visível
+    fn render_segment(&mut self, vdp: &mut VDP, ram: &VdpRam<'_>, line: u16, out: &mut [u8], from: usize, to: usize) -> usize {
+        match vdp.video_mode() {
+            VideoMode::Mode5 => {
+                self.update_colors(ram.cram);
+                self.render_mode5(vdp, ram, line);
+            }
+            VideoMode::Mode4 => {
+                self.update_mode4_colors(vdp.model, ram.cram);
+                legacy::render_mode4(vdp, ram.vram, line, &mut self.pixels);
+            }
+            mode => {
+                for (i, &(r, g, b)) in TMS_PALETTE.iter().enumerate() {
+                    self.colors[i] = self.format.encode(r, g, b);
+                }
+                legacy::render_tms(vdp, ram.vram, line, mode, &mut self.pixels);
+            }
+        }
+
+        let viewport = vdp.viewport();
+        let to = to.min(viewport.x + viewport.width);
+        for x in from.max(viewport.x)..to {
+            let color = self.colors[self.pixels[x] as usize];
+            self.format.store(out, x - viewport.x, color);
+        }
+        viewport.width
+    }
+
+    /// Linha do modo 5 em `pixels` (nível de brilho * 64 + índice de CRAM)
+    fn render_mode5(&mut self, vdp: &mut VDP, ram: &VdpRam<'_>, line: u16) {
+        let width = if vdp.h40() { 320 } else { 256 };
+
+        let backdrop = vdp.reg[7] & 0x3F;
+        if !vdp.display_enabled() {
+            self.pixels[..width].fill(backdrop);
+            self.sprite_dot_overflow = false;
+            return;
+        }
+
+        // No entrelaçado 2 cada campo mostra metade das 448 linhas, com
+        // padrões de 8x16
+        let cell = if vdp.interlace_mode2() {
+            let odd = (vdp.status & STATUS_ODD_FRAME != 0) as u16;
+            Cell { y: line * 2 + odd, height: 16 }
+        } else {
+            Cell { y: line, height: 8 }
+        };
+
+        self.render_planes(vdp, ram, line, cell, width);
+        self.render_sprites(vdp, ram, cell, width);
+
+        let shadow_highlight = vdp.reg[12] & 0x08 != 0;
+        let blank_left = vdp.reg[0] & 0x20 != 0;
+        for x in 0..width {
+            let (index, mode) = if blank_left && x < 8 {
+                (backdrop, NORMAL)
+            } else {
+                compose(self.plane_b[x], self.plane_a[x], self.sprites[x], backdrop, shadow_highlight)
+            };
+            self.pixels[x] = (mode * 64) as u8 + index;
+        }
+    }
+
+    /// Cor normal de uma entrada da CRAM (BBBGGGRRR) no formato do host
+    fn cram_color(&self, color: u16) -> u32 {
+        let (r, g, b) = (color & 7, (color >> 3) & 7, (color >> 6) & 7);
+        self.format.encode(level(r * 2), level(g * 2), level(b * 2))
+    }
+
+    /// Converte as 64 cores da CRAM (BBBGGGRRR) para o formato do host
+    fn update_colors(&mut self, cram: &[u16; 64]) {
+        for (i, &color) in cram.iter().enumerate() {
+            let r = color & 7;
+            let g = (color >> 3) & 7;
+            let b = (color >> 6) & 7;
+            self.colors[NORMAL * 64 + i] = self.cram_color(color);
+            self.colors[SHADOW * 64 + i] = self.format.encode(level(r), level(g), level(b));
+            self.colors[HIGHLIGHT * 64 + i] = self.format.encode(level(r + 7), level(g + 7), level(b + 7));
+        }
+    }
+
+    /// Converte as 32 cores do modo 4: 6 bits (--BBGGRR) ou 12 bits no Game
+    /// Gear (----BBBBGGGGRRRR)
+    fn update_mode4_colors(&mut self, model: VdpModel, cram: &[u16; 64]) {
+        for (i, &color) in cram[..32].iter().enumerate() {
+            self.colors[i] = if model == VdpModel::GameGear {
+                let c = |shift: u16| ((color >> shift) & 0x0F) as u8 * 17;
+                self.format.encode(c(0), c(4), c(8))
+            } else {
+                let c = |shift: u16| ((color >> shift) & 0x03) as u8 * 85;
+                self.format.encode(c(0), c(2), c(4))
+            };
+        }
+    }
+
+    /// Planos A e B (com rolagem) e janela
+    fn render_planes(&mut self, vdp: &VDP, ram: &VdpRam<'_>, line: u16, cell: Cell, width: usize) {
+        let reg = &vdp.reg;
+
+        // Tamanho do plano em células; combinações acima de 4096 células são reduzidas
+        const SIZES: [u16; 4] = [32, 64, 32, 128];
+        let plane_w = SIZES[(reg[16] & 3) as usize];
+        let mut plane_h = SIZES[((reg[16] >> 4) & 3) as usize];
+        if plane_w == 128 {
+            plane_h = 32;
+        } else if plane_w == 64 && plane_h == 128 {
+            plane_h = 64;
+        }
+
+        // Rolagem horizontal: tela inteira, por célula, por linha (modo 1: 8 primeiras linhas)
+        let hscroll_base = (reg[13] as u16 & 0x3F) << 10;
+        let offset = match reg[11] & 3 {
+            0 => 0,
+            1 => (line & 7) * 4,
+            2 => (line & !7) * 4,
+            _ => line * 4,
+        };
+        let hscroll_a = ram.vram[(hscroll_base.wrapping_add(offset) >> 1) as usize] & 0x3FF;
+        let hscroll_b = ram.vram[(hscroll_base.wrapping_add(offset + 2) >> 1) as usize] & 0x3FF;
+
+        let plane_a_base = (reg[2] as u16 & 0x38) << 10;
+        let plane_b_base = (reg[4] as u16 & 0x07) << 13;
+        let column_vscroll = reg[11] & 0x04 != 0;
+
+        // Janela: faixa vertical ocupa a linha inteira, senão divide na horizontal
+        let (window_base, window_w) = if vdp.h40() {
+            ((reg[3] as u16 & 0x3C) << 10, 64)
+        } else {
+            ((reg[3] as u16 & 0x3E) << 10, 32)
+        };
+        let window_v = (reg[18] as u16 & 0x1F) * 8;
+        let window_line = if reg[18] & 0x80 != 0 { line >= window_v } else { line < window_v };
+        let window_h = (reg[17] as usize & 0x1F) * 16;
+        let window_right = reg[17] & 0x80 != 0;
+
+        for x in 0..width {
+            // Rolagem vertical global ou por coluna de 2 células
+            let column = if column_vscroll { (x >> 4).min(19) } else { 0 };
+            let vscroll_a = ram.vsram[column * 2];
+            let vscroll_b = ram.vsram[column * 2 + 1];
+
+            self.plane_b[x] = plane_pixel(ram.vram, plane_b_base, plane_w, plane_h, x as u16, cell, hscroll_b, vscroll_b);
+
+            let in_window = window_line || if window_right { x >= window_h } else { x < window_h };
+            self.plane_a[x] = if in_window {
+                let row = cell.y / cell.height;
+                let entry = ram.vram[((window_base >> 1) + row * window_w + (x as u16 >> 3)) as usize & 0x7FFF];
+                tile_pixel(ram.vram, entry, x as u16 & 7, cell.y % cell.height, cell.height)
+            } else {
+                plane_pixel(ram.vram, plane_a_base, plane_w, plane_h, x as u16, cell, hscroll_a, vscroll_a)
+            };
+        }
+    }
+
+    /// Sprites da linha com limites por linha, mascaramento e colisão
+    fn render_sprites(&mut self, vdp: &mut VDP, ram: &VdpRam<'_>, cell: Cell, width: usize) {
+        self.sprites[..width].fill(0);
+
+        let (sat, max_sprites, max_per_line) = if vdp.h40() {
+            ((vdp.reg[5] as u16 & 0x7E) << 9, 80, 20)
+        } else {
+            ((vdp.reg[5] as u16 & 0x7F) << 9, 64, 16)
+        };
+
+        let mut count = 0;
+        let mut pixels_left = width as i32;
+        let mut dot_seen = false;
+        let mut masked = false;
+        let mut overflow = false;
+        let mut collision = false;
+        let line = cell.y as i32;
+        let cell_h = cell.height as i32;
+        // No entrelaçado 2 a coordenada Y tem um bit a mais
+        let (y_mask, y_offset) = if cell.height == 16 { (0x3FF, 256) } else { (0x1FF, 128) };
+
+        let mut link = 0u16;
+        for _ in 0..max_sprites {
+            let base = ((sat >> 1) + link * 4) as usize & 0x7FFF;
+            let ypos = (ram.vram[base] & y_mask) as i32 - y_offset;
+            let size = (ram.vram[base + 1] >> 8) & 0x0F;
+            let next = ram.vram[base + 1] & 0x7F;
+            let attr = ram.vram[base + 2];
+            let xpos = ram.vram[base + 3] & 0x1FF;
+
+            let cells_w = (size >> 2) as i32 + 1;
+            let cells_h = (size & 3) as i32 + 1;
+
+            if line >= ypos && line < ypos + cells_h * cell_h {
+                if count == max_per_line {
+                    overflow = true;
+                    break;
+                }
+                count += 1;
+
+                // X = 0 esconde os sprites seguintes, desde que um sprite anterior
+                // da linha (ou o estouro da linha anterior) o tenha habilitado
+                if xpos == 0 {
+                    if dot_seen || self.sprite_dot_overflow {
+                        masked = true;
+                    }
+                } else {
+                    dot_seen = true;
+                }
+
+                let mut row = line - ypos;
+                if attr & 0x1000 != 0 {
+                    row = cells_h * cell_h - 1 - row;
+                }
+                let tile_row = (row / cell_h) as u16;
+
+                for cell in 0..cells_w {
+                    if pixels_left <= 0 {
+                        break;
+                    }
+                    let tile_col = if attr & 0x0800 != 0 { cells_w - 1 - cell } else { cell } as u16;
+                    let tile = (attr & 0x7FF).wrapping_add(tile_col * cells_h as u16 + tile_row) & 0x7FF;
+                    let draw = pixels_left.min(8);
+                    pixels_left -= 8;
+
+                    if masked {
+                        continue;
+                    }
+                    for px in 0..draw {
+                        let sx = xpos as i32 - 128 + cell * 8 + px;
+                        if sx < 0 || sx >= width as i32 {
+                            continue;
+                        }
+                        let tx = if attr & 0x0800 != 0 { 7 - px as u16 } else { px as u16 };
+                        let color = pattern_pixel(ram.vram, tile, tx, (row % cell_h) as u16, cell_h as u16);
+                        if color == 0 {
+                            continue;
+                        }
+                        let slot = &mut self.sprites[sx as usize];
+                        if *slot & 0x0F != 0 {
+                            collision = true;
+                        } else {
+                            *slot = ((attr >> 8) as u8 & PRIORITY) | ((attr >> 9) as u8 & 0x30) | color;
+                        }
+                    }
+                }
+                if pixels_left <= 0 {
+                    overflow = true;
+                    break;
+                }
+            }
+
+            link = next;
+            if link == 0 || link >= max_sprites {
+                break;
+            }
+        }
+
+        self.sprite_dot_overflow = pixels_left <= 0;
+        if overflow {
+            vdp.status |= STATUS_SPRITE_OVERFLOW;
+        }
+        if collision {
+            vdp.status |= STATUS_SPRITE_COLLISION;
+        }
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new(PixelFormat::Xrgb8888)
+    }
+}
+
+/// Aplica o valor de uma escrita de raster sem os efeitos colaterais da porta
+fn apply_write(vdp: &mut VDP, ram: &mut VdpRam<'_>, target: RasterTarget, value: u16) {
+    match target {
+        RasterTarget::Register(r) => vdp.reg[r as usize] = value as u8,
+        RasterTarget::Cram(index) => ram.cram[index as usize] = value,
+    }
+}
+
+/// Compõe um pixel: retorna o índice de cor e o nível de brilho
+fn compose(b: u8, a: u8, s: u8, backdrop: u8, shadow_highlight: bool) -> (u8, usize) {
+    let opaque = |p: u8| p & 0x0F != 0;
+    let high = |p: u8| p & PRIORITY != 0;
+
+    // Camada de plano visível: B baixa < A baixa < B alta < A alta
+    let mut plane = backdrop;
+    let mut plane_high = false;
+    for (p, hi) in [(b, false), (a, false), (b, true), (a, true)] {
+        if opaque(p) && high(p) == hi {
+            plane = p & 0x3F;
+            plane_high = hi;
+        }
+    }
+
+    // Sprite de prioridade baixa fica abaixo de planos opacos de prioridade alta
+    let sprite_visible = opaque(s) && (high(s) || !plane_high);
+
+    if !shadow_highlight {
+        return if sprite_visible { (s & 0x3F, NORMAL) } else { (plane, NORMAL) };
+    }
+
+    // Sombra quando nenhum dos planos tem prioridade
+    let base = if high(a) || high(b) { NORMAL } else { SHADOW };
+    if !sprite_visible {
+        return (plane, base);
+    }
+    match s & 0x3F {
+        // Paleta 3, cor 14: operador de brilho
+        0x3E => (plane, if base == SHADOW { NORMAL } else { HIGHLIGHT }),
+        // Paleta 3, cor 15: operador de sombra
+        0x3F => (plane, SHADOW),
+        // Cor 14 das demais paletas nunca é sombreada
+        color @ (0x0E | 0x1E | 0x2E) => (color, NORMAL),
+        color => (color, if high(s) { NORMAL } else { base }),
+    }
+}
+
+/// Linha vertical renderizada e altura das células: 8 normalmente, 16 no
+/// entrelaçado 2 (em que `y` conta as 448 linhas dos dois campos)
+#[derive(Debug, Clone, Copy)]
+struct Cell {
+    y: u16,
+    height: u16,
+}
+
+/// Pixel de um plano rolável em (x, y) da tela
+#[allow(clippy::too_many_arguments)]
+fn plane_pixel(vram: &[u16; 65536], base: u16, plane_w: u16, plane_h: u16, x: u16, cell: Cell, hscroll: u16, vscroll: u16) -> u8 {
+    let px = x.wrapping_sub(hscroll) & (plane_w * 8 - 1);
+    let py = cell.y.wrapping_add(vscroll) & (plane_h * cell.height - 1);
+    let index = (py / cell.height) * plane_w + (px >> 3);
+    let entry = vram[((base >> 1) + index) as usize & 0x7FFF];
+    tile_pixel(vram, entry, px & 7, py % cell.height, cell.height)
+}
+
+/// Pixel de uma entrada de tabela de nomes: prioridade, paleta e cor.
+/// A prioridade é mantida mesmo em pixels transparentes (sombra/brilho).
+fn tile_pixel(vram: &[u16; 65536], entry: u16, x: u16, y: u16, height: u16) -> u8 {
+    let x = if entry & 0x0800 != 0 { 7 - x } else { x };
+    let y = if entry & 0x1000 != 0 { height - 1 - y } else { y };
+    let color = pattern_pixel(vram, entry & 0x7FF, x, y, height);
+    ((entry >> 8) as u8 & PRIORITY) | ((entry >> 9) as u8 & 0x30) | color
+}
+
+/// Cor (0-15) do pixel (x, y) de um padrão de 4 bits com 8 ou 16 linhas.
+/// Os padrões de 8x16 ocupam 64 bytes, com 1024 padrões na VRAM.
+fn pattern_pixel(vram: &[u16; 65536], tile: u16, x: u16, y: u16, height: u16) -> u8 {
+    let start = if height == 16 { (tile & 0x3FF) << 5 } else { tile << 4 };
+    let word = vram[(start + (y << 1) + (x >> 2)) as usize & 0x7FFF];
+    ((word >> ((3 - (x & 3)) * 4)) & 0x0F) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Memories {
+        vram: Box<[u16; 65536]>,
+        cram: [u16; 64],
+        vsram: [u16; 40],
+    }
+
+    impl Memories {
+        fn new() -> Self {
+            Self { vram: Box::new([0; 65536]), cram: [0; 64], vsram: [0; 40] }
+        }
+
+        fn ram(&mut self) -> VdpRam<'_> {
+            VdpRam { vram: &mut self.vram, cram: &mut self.cram, vsram: &mut self.vsram }
+        }
+
+        /// Padrão sólido de uma cor
+        fn solid_tile(&mut self, tile: usize, color: u16) {
+            let word = color * 0x1111;
+            self.vram[tile * 16..tile * 16 + 16].fill(word);
+        }
+    }
+
+    fn render(vdp: &mut VDP, mem: &mut Memories, line: u16) -> Vec<u32> {
+        let mut renderer = Renderer::new(PixelFormat::Xrgb8888);
+        let mut out = vec![0u8; MAX_WIDTH * 4];
+        let width = renderer.render_line(vdp, mem.ram(), line, &mut out);
+        out.chunks(4).take(width).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect()
+    }
+
+    fn mode5_vdp() -> VDP {
+        let mut vdp = VDP::new();
+        for r in [0x8144, 0x8230, 0x8407, 0x8578, 0x8C81, 0x8D3F, 0x9001] {
+            vdp.write_control(r);
+        }
+        vdp
+    }
+
+    #[test]
+    fn test_planes_priority_and_scroll() {
+        let mut vdp = mode5_vdp();
+        let mut mem = Memories::new();
+        mem.cram[1] = 0x007; // vermelho
+        mem.cram[0x12] = 0x1C0; // azul
+
+        mem.solid_tile(1, 1);
+        mem.solid_tile(2, 2);
+        // Plano A ($C000): célula 1 com o padrão 1; plano B ($E000): tudo com o padrão 2, paleta 1
+        mem.vram[0x6001] = 0x0001;
+        mem.vram[0x7000..0x7000 + 64 * 32].fill(0x2002);
+
+        let pixels = render(&mut vdp, &mut mem, 0);
+        let red = PixelFormat::Xrgb8888.encode(255, 0, 0);
+        let blue = PixelFormat::Xrgb8888.encode(0, 0, 255);
+        assert_eq!(pixels.len(), 320);
+        assert_eq!(pixels[0], blue);
+        assert_eq!(pixels[8], red);
+
+        // Plano B com prioridade fica acima do plano A
+        mem.vram[0x7001] = 0xA002;
+        assert_eq!(render(&mut vdp, &mut mem, 0)[8], blue);
+
+        // Rolagem horizontal de 8 pixels no plano A ($FC00)
+        mem.vram[0x7001] = 0x2002;
+        mem.vram[0x7E00] = 8;
+        let pixels = render(&mut vdp, &mut mem, 0);
+        assert_eq!(pixels[8], blue);
+        assert_eq!(pixels[16], red);
+    }
+
+    #[test]
+    fn test_sprites_limit_and_collision() {
+        let mut vdp = mode5_vdp();
+        let mut mem = Memories::new();
+        mem.cram[0x11] = 0x038;
+        mem.solid_tile(3, 1);
+
+        // 21 sprites 1x1 na linha 0 (SAT em $F000): o último estoura o limite de 20
+        for i in 0..21u16 {
+            let base = 0x7800 + i as usize * 4;
+            mem.vram[base] = 128;
+            mem.vram[base + 1] = if i < 20 { i + 1 } else { 0 };
+            mem.vram[base + 2] = 0x2003;
+            mem.vram[base + 3] = 128 + i * 4;
+        }
+        let pixels = render(&mut vdp, &mut mem, 0);
+        assert_eq!(pixels[0], PixelFormat::Xrgb8888.encode(0, 255, 0));
+        let status = vdp.read_status(0);
+        assert_ne!(status & STATUS_SPRITE_OVERFLOW, 0);
+        assert_ne!(status & STATUS_SPRITE_COLLISION, 0);
+    }
+
+    #[test]
+    fn test_shadow_highlight_operators() {
+        // Planos sem prioridade: fundo em sombra
+        assert_eq!(compose(0x01, 0x00, 0x00, 0, true), (0x01, SHADOW));
+        // Plano com prioridade: normal
+        assert_eq!(compose(0x81, 0x00, 0x00, 0, true), (0x01, NORMAL));
+        // Operador de brilho sobre fundo em sombra volta ao normal
+        assert_eq!(compose(0x01, 0x00, 0x3E, 0, true), (0x01, NORMAL));
+        // Operador de sombra
+        assert_eq!(compose(0x80, 0x01, 0x3F, 0, true), (0x01, SHADOW));
+        // Operadores atrás de um plano opaco com prioridade não têm efeito
+        assert_eq!(compose(0x81, 0x00, 0x3F, 0, true), (0x01, NORMAL));
+        // Sprite com prioridade nunca fica em sombra
+        assert_eq!(compose(0x01, 0x00, 0x92, 0, true), (0x12, NORMAL));
+        // Sem o modo, sprites de baixa prioridade ficam atrás de planos com prioridade
+        assert_eq!(compose(0x81, 0x00, 0x12, 0, false), (0x01, NORMAL));
+    }
+
+    #[test]
+    fn test_interlace_mode2_fields() {
+        let mut vdp = mode5_vdp();
+        vdp.write_control(0x8C87); // H40, entrelaçado 2
+        let mut mem = Memories::new();
+        mem.cram[1] = 0x007;
+        mem.cram[2] = 0x1C0;
+
+        // Padrão 8x16 número 1: linhas pares na cor 1, ímpares na cor 2
+        for row in 0..16 {
+            let color = if row % 2 == 0 { 0x1111 } else { 0x2222 };
+            mem.vram[32 + row * 2..32 + row * 2 + 2].fill(color);
+        }
+        mem.vram[0x7000..0x7000 + 64 * 32].fill(0x0001);
+
+        // Cada início de quadro alterna o campo
+        vdp.start_line(0, 0);
+        assert!(vdp.odd_field());
+        assert_eq!(render(&mut vdp, &mut mem, 3)[0], PixelFormat::Xrgb8888.encode(0, 0, 255));
+        vdp.start_line(0, 0);
+        assert!(!vdp.odd_field());
+        assert_eq!(render(&mut vdp, &mut mem, 3)[0], PixelFormat::Xrgb8888.encode(255, 0, 0));
+    }
+
+    #[test]
+    fn test_mid_line_cram_write_and_dots() {
+        let mut vdp = mode5_vdp();
+        let mut mem = Memories::new();
+        mem.cram[0] = 0x007;
+        vdp.start_line(10, 0);
+
+        // Cor 0 trocada no pixel 100 (H40: 8 clocks mestres por pixel) e
+        // escrita na cor 5 no pixel 200
+        vdp.write_control(0xC000);
+        vdp.write_control(0x0000);
+        vdp.write_data(mem.ram(), 0x0E00, 745 + 100 * 8);
+        vdp.write_control(0xC00A);
+        vdp.write_control(0x0000);
+        vdp.write_data(mem.ram(), 0x00E0, 745 + 200 * 8);
+
+        let mut renderer = Renderer::new(PixelFormat::Xrgb8888);
+        renderer.set_cram_dots(true);
+        let mut out = vec![0u8; MAX_WIDTH * 4];
+        renderer.render_line(&mut vdp, mem.ram(), 10, &mut out);
+        let pixels: Vec<u32> = out.chunks(4).map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]])).collect();
+
+        let red = PixelFormat::Xrgb8888.encode(255, 0, 0);
+        let blue = PixelFormat::Xrgb8888.encode(0, 0, 255);
+        assert_eq!(pixels[99], red);
+        assert_eq!(pixels[100], blue);
+        assert_eq!(pixels[200], PixelFormat::Xrgb8888.encode(0, 255, 0));
+        assert_eq!(pixels[201], blue);
+        // A linha seguinte usa o estado final
+        assert_eq!(render(&mut vdp, &mut mem, 11)[0], blue);
+    }
+}
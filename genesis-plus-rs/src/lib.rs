@@ -0,0 +1,21 @@
+// Este é o ponto de entrada principal da biblioteca.
+
+// Módulos principais do projeto.
+pub mod core;
+pub mod utils;
+pub mod simd;
+pub mod gpu;
+
+// Re-exportações para facilitar o uso.
+pub use core::system::GenesisSystem;
+pub use core::cpu::{M68K, Z80};
+pub use core::vdp::VDP;
+pub use core::audio::{YM2612, SN76489};
+
+/// Versão do emulador.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Função conveniente para criar uma nova instância do sistema.
+pub fn create_system() -> GenesisSystem {
+    GenesisSystem::new()
+}
@@ -0,0 +1,201 @@
+//! Clock mestre do sistema e fila de eventos temporizados.
+//! Baseado nas constantes de `system.h` do Genesis Plus GX.
+//!
+//! Todo o tempo é medido em ciclos do clock mestre (M-cycles). Cada chip
+//! roda a uma fração inteira desse clock, e os dispositivos registram
+//! prazos na fila em vez de estimar o tempo a partir de `update(cycles)`:
+//! a execução é fatiada exatamente em cada prazo.
+
+/// Clock mestre NTSC (Hz)
+pub const MCLOCK_NTSC: u32 = 53_693_175;
+
+/// Clock mestre PAL (Hz)
+pub const MCLOCK_PAL: u32 = 53_203_424;
+
+/// Número de M-cycles executados por linha
+pub const MCYCLES_PER_LINE: u64 = 3420;
+
+/// Divisores inteiros do clock mestre
+pub const M68K_DIVIDER: u64 = 7;
+pub const Z80_DIVIDER: u64 = 15;
+/// Clock de pixel do VDP em H32 e H40
+pub const VDP_DIVIDER_H32: u64 = 10;
+pub const VDP_DIVIDER_H40: u64 = 8;
+/// Uma amostra do YM2612 a cada 144 ciclos do 68000
+pub const YM2612_DIVIDER: u64 = M68K_DIVIDER * 144;
+/// Uma amostra do PSG a cada 16 ciclos do Z80
+pub const PSG_DIVIDER: u64 = Z80_DIVIDER * 16;
+/// Uma amostra do YM2413 a cada 72 ciclos do Z80
+pub const YM2413_DIVIDER: u64 = Z80_DIVIDER * 72;
+
+/// Eventos que podem ser agendados no clock mestre
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockEvent {
+    /// Interrupção vertical do VDP
+    Vint,
+    /// Feixe passando pela mira de uma pistola de luz
+    LightGun,
+    /// Prazo registrado por um chip do cartucho
+    Cartridge(CartridgeChip),
+}
+
+/// Chips de cartucho que podem registrar prazos na fila
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CartridgeChip {
+    /// Fatia de execução do SSP1601 (Virtua Racing)
+    Svp,
+    /// Microcontrolador do cartucho Paprium
+    Paprium,
+    /// Reprodução de CD/áudio do MegaSD
+    MegaSd,
+}
+
+/// Clock mestre e fila de prazos pendentes
+#[derive(Debug, Clone)]
+pub struct MasterClock {
+    frequency: u32,
+    mcycles: u64,
+    /// Prazos pendentes em ordem crescente (no máximo um por evento)
+    events: Vec<(u64, ClockEvent)>,
+}
+
+impl MasterClock {
+    pub fn new(frequency: u32) -> Self {
+        Self {
+            frequency,
+            mcycles: 0,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn ntsc() -> Self {
+        Self::new(MCLOCK_NTSC)
+    }
+
+    pub fn pal() -> Self {
+        Self::new(MCLOCK_PAL)
+    }
+
+    /// Frequência do clock mestre (Hz)
+    pub fn frequency(&self) -> u32 {
+        self.frequency
+    }
+
+    /// M-cycles decorridos desde o power-on
+    pub fn now(&self) -> u64 {
+        self.mcycles
+    }
+
+    /// Ciclos decorridos no domínio de um divisor (ex.: `Z80_DIVIDER`)
+    pub fn cycles(&self, divider: u64) -> u64 {
+        self.mcycles / divider
+    }
+
+    /// Converte ciclos de um chip para M-cycles
+    pub fn to_mcycles(cycles: u64, divider: u64) -> u64 {
+        cycles * divider
+    }
+
+    /// Avança o clock até `mcycles` (nunca volta no tempo)
+    pub fn advance_to(&mut self, mcycles: u64) {
+        self.mcycles = self.mcycles.max(mcycles);
+    }
+
+    /// Zera o tempo e descarta os eventos pendentes
+    pub fn reset(&mut self) {
+        self.mcycles = 0;
+        self.events.clear();
+    }
+
+    /// Agenda `event` para o M-cycle absoluto `at`, substituindo um prazo
+    /// anterior do mesmo evento
+    pub fn schedule(&mut self, event: ClockEvent, at: u64) {
+        self.cancel(event);
+        let pos = self.events.partition_point(|&(deadline, _)| deadline <= at);
+        self.events.insert(pos, (at, event));
+    }
+
+    /// Agenda `event` para daqui a `delay` M-cycles
+    pub fn schedule_in(&mut self, event: ClockEvent, delay: u64) {
+        self.schedule(event, self.mcycles + delay);
+    }
+
+    /// Remove o prazo pendente de `event`, se houver
+    pub fn cancel(&mut self, event: ClockEvent) {
+        self.events.retain(|&(_, e)| e != event);
+    }
+
+    /// Prazo pendente de `event`
+    pub fn deadline(&self, event: ClockEvent) -> Option<u64> {
+        self.events.iter().find(|&&(_, e)| e == event).map(|&(deadline, _)| deadline)
+    }
+
+    /// Prazo mais próximo entre todos os eventos pendentes
+    pub fn next_deadline(&self) -> Option<u64> {
+        self.events.first().map(|&(deadline, _)| deadline)
+    }
+
+    /// Retira o próximo evento cujo prazo já foi atingido
+    pub fn pop_due(&mut self) -> Option<ClockEvent> {
+        match self.events.first() {
+            Some(&(deadline, event)) if deadline <= self.mcycles => {
+                self.events.remove(0);
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for MasterClock {
+    fn default() -> Self {
+        Self::ntsc()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dividers() {
+        let mut clock = MasterClock::ntsc();
+        clock.advance_to(MCYCLES_PER_LINE * 262);
+        // 68000 a ~7.67 MHz e Z80 a ~3.58 MHz: 488 e 228 ciclos por linha
+        assert_eq!(clock.cycles(M68K_DIVIDER), 488 * 262 + 262 * 4 / 7);
+        assert_eq!(clock.cycles(Z80_DIVIDER), 228 * 262);
+        // ~53267 Hz para o YM2612 e ~223721 Hz para o PSG
+        assert_eq!(MCLOCK_NTSC as u64 / YM2612_DIVIDER, 53267);
+        assert_eq!(MCLOCK_NTSC as u64 / PSG_DIVIDER, 223721);
+    }
+
+    #[test]
+    fn test_event_queue() {
+        let svp = ClockEvent::Cartridge(CartridgeChip::Svp);
+        let paprium = ClockEvent::Cartridge(CartridgeChip::Paprium);
+        let megasd = ClockEvent::Cartridge(CartridgeChip::MegaSd);
+
+        let mut clock = MasterClock::ntsc();
+        clock.schedule(megasd, 300);
+        clock.schedule(svp, 100);
+        clock.schedule(paprium, 200);
+        clock.schedule(ClockEvent::Vint, 150);
+        assert_eq!(clock.next_deadline(), Some(100));
+
+        // Reagendar substitui o prazo anterior
+        clock.schedule_in(svp, 250);
+        assert_eq!(clock.deadline(svp), Some(250));
+        assert_eq!(clock.next_deadline(), Some(150));
+
+        clock.cancel(megasd);
+        assert_eq!(clock.deadline(megasd), None);
+        assert_eq!(clock.pop_due(), None);
+
+        clock.advance_to(260);
+        assert_eq!(clock.pop_due(), Some(ClockEvent::Vint));
+        assert_eq!(clock.pop_due(), Some(paprium));
+        assert_eq!(clock.pop_due(), Some(svp));
+        assert_eq!(clock.pop_due(), None);
+        assert_eq!(clock.next_deadline(), None);
+    }
+}
@@ -0,0 +1,3 @@
+//! Utilitários compartilhados pelo núcleo: temporização, log e save states.
+
+pub mod clock;